@@ -0,0 +1,157 @@
+//! Extensions to Bevy's [App] for working with Koto
+
+use crate::{
+    entity::{koto_entity_channel, KotoEntityReceiver},
+    runtime::{KotoRuntime, KotoRuntimePlugin},
+};
+use bevy::{
+    app::{AppLabel, MainSchedulePlugin},
+    ecs::{
+        event::{event_update_condition, event_update_system, EventRegistry, EventUpdates},
+        schedule::ScheduleLabel,
+        world::EntityWorldMut,
+    },
+    prelude::*,
+    time::TimePlugin,
+};
+use koto::prelude::*;
+
+/// Extension methods for Bevy's [App] that simplify contributing to Koto's prelude
+///
+/// Registrations are queued, and then applied to the [KotoRuntime] in the
+/// [KotoUpdate::Register](crate::runtime::KotoUpdate::Register) system set, which runs before any
+/// scripts are compiled. This means that the methods can be called before or after the
+/// [KotoRuntimePlugin](crate::runtime::KotoRuntimePlugin) has been added.
+pub trait KotoAppExt {
+    /// Adds a module to Koto's prelude with the given name
+    ///
+    /// The module is made by calling `make_module`, e.g. `koto_random::make_module`.
+    fn register_koto_module(&mut self, name: &str, make_module: fn() -> KMap) -> &mut Self;
+
+    /// Adds a native function to Koto's prelude with the given name
+    fn add_koto_fn(&mut self, name: &str, f: impl KotoFunction) -> &mut Self;
+
+    /// Adds a channel for entity events of type `T` that are sent from Koto to Bevy
+    ///
+    /// The channel's [KotoEntitySender](crate::entity::KotoEntitySender) and
+    /// [KotoEntityReceiver] are inserted as resources, and a system is added to the [Update]
+    /// schedule that calls `handler` for each received event, along with the event's entity.
+    /// Events for entities that no longer exist are dropped.
+    fn add_koto_entity_event<T>(
+        &mut self,
+        handler: impl Fn(EntityWorldMut, T) + Send + Sync + 'static,
+    ) -> &mut Self
+    where
+        T: Send + Sync + 'static;
+
+    /// Adds an independent Koto runtime that runs in its own sub-app
+    ///
+    /// The sub-app has its own [World], containing a separate [KotoRuntime] along with its own
+    /// schedules, channels, and events. Scripts running in the sub-app can't access the main
+    /// app's entities or resources, which allows untrusted scripts to be isolated from the main
+    /// scene.
+    ///
+    /// The sub-app is set up with Bevy's main schedules, its own [Time], and a default
+    /// [KotoRuntimePlugin]. `configure` is then called to add further plugins and systems, e.g.
+    /// `|sub_app| { sub_app.add_plugins(KotoRandomPlugin); }`.
+    ///
+    /// The sub-app is updated after the main app on each frame. Data can be passed from the main
+    /// app's world with [SubApp::set_extract], and scripts can be loaded by sending
+    /// [LoadScript](crate::runtime::LoadScript) events to the sub-app's world, e.g.
+    /// `app.sub_app_mut(label).world_mut().send_event(LoadScript::inline(...))`.
+    /// The sub-app doesn't have an asset server, so scripts need to be loaded with
+    /// [LoadScript::inline](crate::runtime::LoadScript::inline).
+    fn add_koto_sub_app(
+        &mut self,
+        label: impl AppLabel,
+        configure: impl FnOnce(&mut SubApp),
+    ) -> &mut Self;
+}
+
+impl KotoAppExt for App {
+    fn register_koto_module(&mut self, name: &str, make_module: fn() -> KMap) -> &mut Self {
+        let name = name.to_string();
+        queue_registration(self, move |koto| {
+            koto.prelude().insert(name.as_str(), make_module());
+        })
+    }
+
+    fn add_koto_fn(&mut self, name: &str, f: impl KotoFunction) -> &mut Self {
+        let name = name.to_string();
+        queue_registration(self, move |koto| koto.prelude().add_fn(&name, f))
+    }
+
+    fn add_koto_entity_event<T>(
+        &mut self,
+        handler: impl Fn(EntityWorldMut, T) + Send + Sync + 'static,
+    ) -> &mut Self
+    where
+        T: Send + Sync + 'static,
+    {
+        let (sender, receiver) = koto_entity_channel::<T>();
+
+        self.insert_resource(sender)
+            .insert_resource(receiver)
+            .add_systems(Update, move |world: &mut World| {
+                let channel = world.resource::<KotoEntityReceiver<T>>().clone();
+                let _span =
+                    info_span!("koto_channel", channel = std::any::type_name::<T>()).entered();
+                while let Some(event) = channel.receive() {
+                    if let Ok(entity) = world.get_entity_mut(event.entity.get()) {
+                        handler(entity, event.event);
+                    }
+                }
+            })
+    }
+
+    fn add_koto_sub_app(
+        &mut self,
+        label: impl AppLabel,
+        configure: impl FnOnce(&mut SubApp),
+    ) -> &mut Self {
+        let mut sub_app = SubApp::new();
+        sub_app.update_schedule = Some(Main.intern());
+        sub_app
+            .init_resource::<AppTypeRegistry>()
+            .init_resource::<EventRegistry>()
+            .add_plugins((MainSchedulePlugin, TimePlugin))
+            .add_systems(
+                First,
+                event_update_system
+                    .in_set(EventUpdates)
+                    .run_if(event_update_condition),
+            )
+            .add_plugins(KotoRuntimePlugin::default());
+
+        configure(&mut sub_app);
+
+        self.insert_sub_app(label, sub_app);
+        self
+    }
+}
+
+fn queue_registration(
+    app: &mut App,
+    register: impl FnOnce(&KotoRuntime) + Send + Sync + 'static,
+) -> &mut App {
+    app.world_mut()
+        .get_resource_or_init::<KotoRegistrations>()
+        .0
+        .push(Box::new(register));
+    app
+}
+
+type KotoRegistration = Box<dyn FnOnce(&KotoRuntime) + Send + Sync>;
+
+// Registrations that are waiting to be applied to the Koto runtime
+#[derive(Resource, Default)]
+pub(crate) struct KotoRegistrations(Vec<KotoRegistration>);
+
+pub(crate) fn apply_koto_registrations(
+    koto: Res<KotoRuntime>,
+    mut registrations: ResMut<KotoRegistrations>,
+) {
+    for register in registrations.0.drain(..) {
+        register(&koto);
+    }
+}
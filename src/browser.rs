@@ -0,0 +1,406 @@
+//! Browsing through a folder of scripts
+
+use crate::{prelude::*, runtime::KotoPreludeScript};
+use bevy::{asset::LoadedFolder, prelude::*};
+use std::path::{Path, PathBuf};
+
+/// Loads a folder of scripts, and allows switching between them at runtime
+///
+/// The scripts in the top level of the folder are added to the [ScriptPlaylist] resource once the
+/// folder has been loaded, and the initial script is then loaded. Scripts in subfolders aren't
+/// added to the playlist, which allows them to be used as modules. The runtime's prelude script
+/// (see [KotoRuntimePlugin::with_prelude_script]) is also excluded from the playlist.
+///
+/// By default, Tab loads the next script, Shift+Tab loads the previous script, and R reloads the
+/// current script. The bindings can be changed with [KotoScriptBrowserPlugin::with_keys].
+///
+/// Bevy's `AssetPlugin` needs to be added before this plugin.
+pub struct KotoScriptBrowserPlugin {
+    /// The path of the scripts folder, relative to the assets folder
+    pub folder: PathBuf,
+    /// The order in which scripts appear in the playlist
+    pub sort_order: ScriptSortOrder,
+    /// The key bindings used to switch between scripts
+    pub keys: ScriptBrowserKeys,
+    /// The name of the script that should be loaded first, without its extension
+    ///
+    /// If `None`, or if the script isn't found, then the first script in the playlist is loaded.
+    pub initial_script: Option<String>,
+}
+
+impl Default for KotoScriptBrowserPlugin {
+    fn default() -> Self {
+        Self {
+            folder: "scripts".into(),
+            sort_order: default(),
+            keys: default(),
+            initial_script: None,
+        }
+    }
+}
+
+impl KotoScriptBrowserPlugin {
+    /// Sets the path of the scripts folder, relative to the assets folder
+    #[must_use]
+    pub fn with_folder(mut self, folder: impl Into<PathBuf>) -> Self {
+        self.folder = folder.into();
+        self
+    }
+
+    /// Sets the order in which scripts appear in the playlist
+    #[must_use]
+    pub fn with_sort_order(mut self, sort_order: ScriptSortOrder) -> Self {
+        self.sort_order = sort_order;
+        self
+    }
+
+    /// Sets the key bindings used to switch between scripts
+    #[must_use]
+    pub fn with_keys(mut self, keys: ScriptBrowserKeys) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    /// Sets the name of the script that should be loaded first, e.g. `"scrolling_squares"`
+    #[must_use]
+    pub fn with_initial_script(mut self, name: impl Into<String>) -> Self {
+        self.initial_script = Some(name.into());
+        self
+    }
+}
+
+impl Plugin for KotoScriptBrowserPlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+        assert!(app.is_plugin_added::<AssetPlugin>());
+
+        let folder = app
+            .world()
+            .resource::<AssetServer>()
+            .load_folder(self.folder.clone());
+
+        app.insert_resource(ScriptPlaylist {
+            folder_path: self.folder.clone(),
+            folder,
+            sort_order: self.sort_order,
+            initial_script: self.initial_script.clone(),
+            scripts: Vec::new(),
+            current: None,
+            pending: None,
+        })
+        .insert_resource(self.keys.clone())
+        .add_systems(
+            Update,
+            (
+                populate_playlist,
+                process_keypresses.run_if(resource_exists::<ButtonInput<KeyCode>>),
+                load_pending_script,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// The order in which scripts appear in the [ScriptPlaylist]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScriptSortOrder {
+    /// Scripts are sorted by path in ascending order
+    #[default]
+    Ascending,
+    /// Scripts are sorted by path in descending order
+    Descending,
+}
+
+/// The key bindings used by the [KotoScriptBrowserPlugin]
+///
+/// Bindings can be disabled by setting them to `None`.
+#[derive(Resource, Clone, Debug)]
+pub struct ScriptBrowserKeys {
+    /// Loads the next script in the playlist
+    pub next: Option<KeyBinding>,
+    /// Loads the previous script in the playlist
+    pub previous: Option<KeyBinding>,
+    /// Reloads the current script
+    pub reload: Option<KeyBinding>,
+}
+
+impl Default for ScriptBrowserKeys {
+    fn default() -> Self {
+        Self {
+            next: Some(KeyBinding::new(KeyCode::Tab)),
+            previous: Some(KeyBinding::new(KeyCode::Tab).with_shift()),
+            reload: Some(KeyBinding::new(KeyCode::KeyR)),
+        }
+    }
+}
+
+/// A key, along with whether or not Shift needs to be held
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyBinding {
+    /// The key that triggers the binding
+    pub key: KeyCode,
+    /// True if Shift needs to be held when the key is pressed
+    pub shift: bool,
+}
+
+impl KeyBinding {
+    /// Makes a binding for the given key, without Shift
+    pub fn new(key: KeyCode) -> Self {
+        Self { key, shift: false }
+    }
+
+    /// Requires Shift to be held for the binding to be triggered
+    #[must_use]
+    pub fn with_shift(mut self) -> Self {
+        self.shift = true;
+        self
+    }
+
+    fn just_pressed(&self, input: &ButtonInput<KeyCode>) -> bool {
+        input.just_pressed(self.key)
+            && input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) == self.shift
+    }
+}
+
+/// The scripts that are available in the [KotoScriptBrowserPlugin]'s folder
+///
+/// Selecting a script requests that it should be loaded, with the script then being loaded during
+/// Bevy's [Update] schedule.
+#[derive(Resource)]
+pub struct ScriptPlaylist {
+    folder_path: PathBuf,
+    folder: Handle<LoadedFolder>,
+    sort_order: ScriptSortOrder,
+    initial_script: Option<String>,
+    scripts: Vec<PlaylistScript>,
+    current: Option<usize>,
+    pending: Option<usize>,
+}
+
+struct PlaylistScript {
+    handle: Handle<KotoScript>,
+    path: PathBuf,
+    metadata: ScriptMetadata,
+}
+
+impl ScriptPlaylist {
+    /// The number of scripts in the playlist
+    pub fn len(&self) -> usize {
+        self.scripts.len()
+    }
+
+    /// True if the playlist is empty, e.g. if the folder hasn't finished loading
+    pub fn is_empty(&self) -> bool {
+        self.scripts.is_empty()
+    }
+
+    /// The paths of the scripts in the playlist, relative to the assets folder
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.scripts.iter().map(|script| script.path.as_path())
+    }
+
+    /// The metadata of the script at the given index, see [ScriptMetadata]
+    pub fn metadata(&self, index: usize) -> Option<&ScriptMetadata> {
+        self.scripts.get(index).map(|script| &script.metadata)
+    }
+
+    /// The title of the script at the given index
+    ///
+    /// The title is taken from the script's metadata, falling back to the script's file name.
+    pub fn title(&self, index: usize) -> Option<String> {
+        self.scripts.get(index).map(|script| {
+            script.metadata.title.clone().unwrap_or_else(|| {
+                script
+                    .path
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+        })
+    }
+
+    /// The indices of the scripts that have the given tag in their metadata
+    pub fn with_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = usize> + 'a {
+        self.scripts
+            .iter()
+            .enumerate()
+            .filter(move |(_, script)| script.metadata.has_tag(tag))
+            .map(|(index, _)| index)
+    }
+
+    /// The index of the script that was most recently selected
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    /// The path of the script that was most recently selected
+    pub fn current_path(&self) -> Option<&Path> {
+        self.current
+            .and_then(|index| self.scripts.get(index))
+            .map(|script| script.path.as_path())
+    }
+
+    /// Selects the script at the given index
+    ///
+    /// Returns false if the index is out of range.
+    pub fn select(&mut self, index: usize) -> bool {
+        if index < self.scripts.len() {
+            self.current = Some(index);
+            self.pending = Some(index);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Selects the script with the given name, without its extension
+    ///
+    /// Returns false if no matching script is found.
+    pub fn select_by_name(&mut self, name: &str) -> bool {
+        match self.find(name) {
+            Some(index) => self.select(index),
+            None => false,
+        }
+    }
+
+    /// Selects the next script, wrapping around to the first script
+    pub fn next(&mut self) {
+        if !self.scripts.is_empty() {
+            let index = self
+                .current
+                .map_or(0, |index| (index + 1) % self.scripts.len());
+            self.select(index);
+        }
+    }
+
+    /// Selects the previous script, wrapping around to the last script
+    pub fn previous(&mut self) {
+        if !self.scripts.is_empty() {
+            let index = self.current.map_or(0, |index| {
+                index.checked_sub(1).unwrap_or(self.scripts.len() - 1)
+            });
+            self.select(index);
+        }
+    }
+
+    /// Reloads the current script
+    pub fn reload(&mut self) {
+        if let Some(index) = self.current {
+            self.select(index);
+        }
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.scripts
+            .iter()
+            .position(|script| script.path.file_stem().is_some_and(|stem| stem == name))
+    }
+}
+
+fn populate_playlist(
+    mut playlist: ResMut<ScriptPlaylist>,
+    mut folder_events: EventReader<AssetEvent<LoadedFolder>>,
+    loaded_folders: Res<Assets<LoadedFolder>>,
+    scripts: Res<Assets<KotoScript>>,
+    prelude_script: Option<Res<KotoPreludeScript>>,
+) {
+    let is_loaded = folder_events
+        .read()
+        .any(|event| event.is_loaded_with_dependencies(&playlist.folder));
+    if !is_loaded {
+        return;
+    }
+
+    let Some(folder) = loaded_folders.get(&playlist.folder) else {
+        error!("Missing script folder");
+        return;
+    };
+
+    let previous_path = playlist.current_path().map(ToOwned::to_owned);
+    let mut new_scripts = Vec::new();
+
+    for handle in folder.handles.iter() {
+        let Ok(script_id) = handle.id().try_typed::<KotoScript>() else {
+            continue;
+        };
+        if prelude_script
+            .as_ref()
+            .is_some_and(|prelude| prelude.0.id() == script_id)
+        {
+            continue;
+        }
+        let Some(script) = scripts.get(script_id) else {
+            error!("Script missing (id: {script_id})");
+            continue;
+        };
+
+        // Only top-level scripts are added to the playlist
+        if script.path.parent() == Some(&playlist.folder_path) {
+            new_scripts.push(PlaylistScript {
+                handle: handle.clone().typed::<KotoScript>(),
+                path: script.path.clone(),
+                metadata: script.metadata.clone(),
+            });
+        }
+    }
+
+    new_scripts.sort_by(|a, b| a.path.cmp(&b.path));
+    if playlist.sort_order == ScriptSortOrder::Descending {
+        new_scripts.reverse();
+    }
+
+    for script in new_scripts.iter() {
+        info!("Found script: {}", script.path.to_string_lossy());
+    }
+
+    playlist.scripts = new_scripts;
+
+    // Keep the current script selected if the folder was reloaded
+    playlist.current = previous_path.and_then(|previous| {
+        playlist
+            .scripts
+            .iter()
+            .position(|script| script.path == previous)
+    });
+
+    if playlist.current.is_none() {
+        let initial = playlist
+            .initial_script
+            .take()
+            .and_then(|name| playlist.find(&name))
+            .unwrap_or(0);
+        playlist.select(initial);
+    }
+}
+
+fn process_keypresses(
+    input: Res<ButtonInput<KeyCode>>,
+    keys: Res<ScriptBrowserKeys>,
+    mut playlist: ResMut<ScriptPlaylist>,
+) {
+    let pressed = |binding: &Option<KeyBinding>| {
+        binding
+            .as_ref()
+            .is_some_and(|binding| binding.just_pressed(&input))
+    };
+
+    if pressed(&keys.next) {
+        playlist.next();
+    } else if pressed(&keys.previous) {
+        playlist.previous();
+    } else if pressed(&keys.reload) {
+        playlist.reload();
+    }
+}
+
+fn load_pending_script(
+    mut playlist: ResMut<ScriptPlaylist>,
+    mut load_script: EventWriter<LoadScript>,
+) {
+    if let Some(index) = playlist.pending.take() {
+        if let Some(script) = playlist.scripts.get(index) {
+            load_script.send(LoadScript::load(script.handle.clone()));
+        }
+    }
+}
@@ -1,98 +1,676 @@
 //! Support for modifying properties of a Bevy camera
 
-use crate::prelude::*;
-use bevy::{prelude::*, render::camera::ScalingMode, window::WindowResized};
+use crate::{geometry::koto_to_bevy_vec3, prelude::*};
+use bevy::{
+    prelude::*,
+    render::camera::ScalingMode,
+    transform::TransformSystem,
+    utils::HashMap,
+    window::{PrimaryWindow, WindowResized},
+};
 use cloned::cloned;
-use koto::prelude::*;
+use koto::{prelude::*, runtime::Result as KotoResult};
+use parking_lot::RwLock;
+use std::sync::Arc;
 
-/// Exposes a `set_zoom` function to Koto that modifies the zoom of a 2D camera
+/// Exposes camera controls to Koto scripts
 ///
-/// The camera needs to have the [KotoCamera] component attached to it for the
-pub struct KotoCameraPlugin;
+/// Cameras need to have the [KotoCamera] component attached to them to be controlled by scripts.
+///
+/// The plugin adds a `set_zoom` function to Koto's prelude that modifies the zoom of the main
+/// camera, along with a `camera` module containing the following functions:
+/// - `camera.set_zoom(zoom)`: Sets the zoom of the main camera.
+/// - `camera.set_world_height(height)`: Scales the main camera to show the given height of the
+///   world, overriding the plugin's scaling.
+/// - `camera.get(name)`: Returns a `Camera` map for the camera with the given name, with the
+///   same functions as the `camera` module, along with a `name` function.
+///
+/// Cameras are named via [KotoCamera::named], with [KotoCamera::default] being named
+/// [MAIN_CAMERA]. Scripts can then control several cameras independently, e.g. a background
+/// layer and a zoomed-in foreground:
+///
+/// ```koto
+/// camera.get('background').set_zoom 2
+/// camera.set_zoom 0.5
+/// ```
+///
+/// Positions can be converted between window coordinates (in logical pixels, with the origin at
+/// the top-left of the window) and world coordinates with the `screen_to_world(position)` and
+/// `world_to_screen(position)` functions, which are added to the prelude for the main camera, and
+/// to each camera's functions, e.g.
+///
+/// ```koto
+/// top_left = screen_to_world 0, 0
+/// pixel = camera.get('background').world_to_screen geometry.vec2 0.5, 0.5
+/// ```
+///
+/// `screen_to_world` is intended for 2D cameras, and `world_to_screen` also accepts 3D positions.
+/// Both functions return `null` if the position can't be converted, e.g. if the camera hasn't
+/// been rendered yet. The conversions use the camera's properties from the start of the frame.
+///
+/// `camera.shake(amplitude, duration, frequency)` shakes a camera with a noise-driven offset that
+/// decays to zero over the given duration in seconds. The amplitude is in world units, and the
+/// frequency (in Hz, defaulting to 15) sets how quickly the offset changes, see [CameraShake].
+///
+/// The orthographic projections of 2D cameras are scaled to show a fixed extent of the world,
+/// which by default is a [-1, 1] range along the window's shorter side. The scaling can be
+/// configured with [KotoCameraPlugin::with_scaling] and [KotoCameraPlugin::with_world_extent],
+/// and scripts can show a fixed height of the world with `camera.set_world_height(height)`, which
+/// is reset when a script is loaded.
+///
+/// Other plugins can add their own camera functions with [KotoCameraFunctions].
+#[derive(Clone, Debug)]
+pub struct KotoCameraPlugin {
+    /// How the world extent is fitted to the window
+    pub scaling: KotoCameraScaling,
+    /// The size of the world that's shown by 2D cameras along the scaled axis, in world units
+    ///
+    /// The default extent is 2.0.
+    pub world_extent: f32,
+}
+
+impl Default for KotoCameraPlugin {
+    fn default() -> Self {
+        Self {
+            scaling: KotoCameraScaling::default(),
+            world_extent: 2.0,
+        }
+    }
+}
+
+impl KotoCameraPlugin {
+    /// Sets how the world extent is fitted to the window
+    #[must_use]
+    pub fn with_scaling(mut self, scaling: KotoCameraScaling) -> Self {
+        self.scaling = scaling;
+        self
+    }
+
+    /// Sets the size of the world that's shown by 2D cameras along the scaled axis
+    #[must_use]
+    pub fn with_world_extent(mut self, world_extent: f32) -> Self {
+        self.world_extent = world_extent;
+        self
+    }
+}
+
+/// How the world extent of a [KotoCameraPlugin] is fitted to the window
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KotoCameraScaling {
+    /// The world extent fits the window's shorter side
+    #[default]
+    FitShorterSide,
+    /// The world extent fits the window's longer side
+    FitLongerSide,
+    /// The world extent fits the window's height
+    FixedHeight,
+    /// The world extent fits the window's width
+    FixedWidth,
+}
+
+// The plugin's settings, made available to the plugin's systems
+#[derive(Resource)]
+struct KotoCameraSettings {
+    scaling: KotoCameraScaling,
+    world_extent: f32,
+}
 
 impl Plugin for KotoCameraPlugin {
+    #[allow(clippy::result_large_err)]
     fn build(&self, app: &mut App) {
         debug_assert!(app.is_plugin_added::<KotoRuntimePlugin>());
 
         let (update_ortho_projection_sender, update_ortho_projection_receiver) =
-            koto_channel::<UpdateOrthographicProjection>();
+            koto_channel::<KotoCameraEvent<UpdateOrthographicProjection>>();
+
+        let mut camera_functions = KotoCameraFunctions::default();
+        camera_functions.add({
+            cloned!(update_ortho_projection_sender);
+            move |camera, name| {
+                camera.add_fn("set_zoom", {
+                    cloned!(update_ortho_projection_sender, name);
+                    move |ctx| set_zoom(&update_ortho_projection_sender, &name, ctx.args())
+                });
+                camera.add_fn("set_world_height", {
+                    cloned!(update_ortho_projection_sender, name);
+                    move |ctx| match ctx.args() {
+                        [KValue::Number(height)] if *height > 0.0 => {
+                            update_ortho_projection_sender.send(KotoCameraEvent::new(
+                                name.clone(),
+                                UpdateOrthographicProjection::WorldHeight(height.into()),
+                            ));
+                            Ok(KValue::Null)
+                        }
+                        unexpected => unexpected_args("a positive Number", unexpected),
+                    }
+                });
+            }
+        });
+
+        let (shake_sender, shake_receiver) = koto_channel::<KotoCameraEvent<CameraShake>>();
+        camera_functions.add({
+            cloned!(shake_sender);
+            move |camera, name| {
+                camera.add_fn("shake", {
+                    cloned!(shake_sender, name);
+                    move |ctx| {
+                        use KValue::Number;
+
+                        let (amplitude, duration, frequency) = match ctx.args() {
+                            [Number(amplitude), Number(duration)] => {
+                                (amplitude.into(), duration.into(), DEFAULT_SHAKE_FREQUENCY)
+                            }
+                            [Number(amplitude), Number(duration), Number(frequency)] => {
+                                (amplitude.into(), duration.into(), frequency.into())
+                            }
+                            unexpected => {
+                                return unexpected_args(
+                                    "an amplitude, a duration in seconds, \
+                                     and an optional frequency in Hz",
+                                    unexpected,
+                                )
+                            }
+                        };
+                        shake_sender.send(KotoCameraEvent::new(
+                            name.clone(),
+                            CameraShake {
+                                amplitude,
+                                duration,
+                                frequency,
+                            },
+                        ));
+                        Ok(KValue::Null)
+                    }
+                });
+            }
+        });
+
+        let snapshots = CameraSnapshots::default();
+        camera_functions.add({
+            cloned!(snapshots);
+            move |camera, name| {
+                camera.add_fn("screen_to_world", {
+                    cloned!(snapshots, name);
+                    move |ctx| screen_to_world(&snapshots, &name, ctx.args())
+                });
+                camera.add_fn("world_to_screen", {
+                    cloned!(snapshots, name);
+                    move |ctx| world_to_screen(&snapshots, &name, ctx.args())
+                });
+            }
+        });
+
+        app.add_koto_fn("screen_to_world", {
+            cloned!(snapshots);
+            move |ctx| screen_to_world(&snapshots, MAIN_CAMERA, ctx.args())
+        })
+        .add_koto_fn("world_to_screen", {
+            cloned!(snapshots);
+            move |ctx| world_to_screen(&snapshots, MAIN_CAMERA, ctx.args())
+        });
 
-        app.insert_resource(update_ortho_projection_sender)
-            .insert_resource(update_ortho_projection_receiver)
-            .add_systems(Startup, on_startup)
-            .add_systems(KotoSchedule, on_script_loaded.in_set(KotoUpdate::PreUpdate))
-            .add_systems(Update, (on_window_resized, update_orthographic_projection));
+        app.add_koto_fn("set_zoom", {
+            cloned!(update_ortho_projection_sender);
+            move |ctx| {
+                set_zoom(
+                    &update_ortho_projection_sender,
+                    &MAIN_CAMERA.into(),
+                    ctx.args(),
+                )
+            }
+        })
+        .insert_resource(camera_functions)
+        .insert_resource(KotoCameraSettings {
+            scaling: self.scaling,
+            world_extent: self.world_extent,
+        })
+        .insert_resource(snapshots)
+        .insert_resource(update_ortho_projection_sender)
+        .insert_resource(update_ortho_projection_receiver)
+        .insert_resource(shake_sender)
+        .insert_resource(shake_receiver)
+        .add_systems(Startup, on_startup)
+        .add_systems(First, remove_shake_offsets)
+        .add_systems(
+            KotoSchedule,
+            (on_script_loaded, sync_camera_snapshots).in_set(KotoUpdate::PreUpdate),
+        )
+        .add_systems(
+            Update,
+            (
+                on_window_resized,
+                update_orthographic_projection,
+                koto_to_bevy_shake_events,
+            ),
+        )
+        .add_systems(
+            PostUpdate,
+            apply_shake_offsets.before(TransformSystem::TransformPropagate),
+        );
     }
 }
 
+/// The name of the camera that's controlled by the `camera` module's functions
+pub const MAIN_CAMERA: &str = "main";
+
 /// Event for updating the camera's orthographic projection
 #[derive(Clone, Event)]
 pub enum UpdateOrthographicProjection {
     /// Sets the projection's scale
     Scale(f32),
+    /// Scales the projection to show a fixed height of the world, overriding the plugin's scaling
+    WorldHeight(f32),
 }
 
-/// Used to help identify our main camera
+// The world height that's been set for a camera by the script
 #[derive(Component)]
-pub struct KotoCamera;
+struct ScriptWorldHeight(f32);
 
-fn on_startup(
-    koto: Res<KotoRuntime>,
-    update_projection: Res<KotoSender<UpdateOrthographicProjection>>,
-) {
-    koto.prelude().add_fn("set_zoom", {
-        cloned!(update_projection);
+/// Event for shaking a camera
+///
+/// The offset is applied to the camera's transform before transforms are propagated in
+/// [PostUpdate], and is then removed at the start of the next frame, so the camera's [Transform]
+/// is unaffected by the shake during the rest of the frame. The shake uses [KotoTime], so it's
+/// paused along with the script's time.
+///
+/// A new shake replaces a shake that's already in progress.
+#[derive(Clone, Copy, Debug, Event)]
+pub struct CameraShake {
+    /// The maximum offset of the camera, in world units
+    pub amplitude: f32,
+    /// The duration of the shake in seconds
+    pub duration: f32,
+    /// The rate at which the offset changes, in Hz
+    pub frequency: f32,
+}
+
+// The frequency of a shake when it isn't provided by the script
+const DEFAULT_SHAKE_FREQUENCY: f32 = 15.0;
+
+// A shake that's in progress
+#[derive(Component)]
+struct ActiveShake {
+    shake: CameraShake,
+    elapsed: f32,
+    // The offset that's currently applied to the camera's translation
+    offset: Vec3,
+}
+
+/// An event from Koto that's targeted at the [KotoCamera] with the given name
+#[derive(Clone)]
+pub struct KotoCameraEvent<T> {
+    /// The name of the camera that the event is sent to
+    pub camera: KString,
+    /// The event associated with the camera
+    pub event: T,
+}
+
+impl<T> KotoCameraEvent<T> {
+    /// Returns a new camera event for the given camera name and event value
+    pub fn new(camera: KString, event: T) -> Self {
+        Self { camera, event }
+    }
+}
+
+/// Marks a camera that can be controlled by Koto scripts
+///
+/// Each camera is given a name that scripts can use to find it, see [KotoCameraPlugin].
+#[derive(Component, Clone, Debug)]
+pub struct KotoCamera {
+    name: String,
+}
+
+impl KotoCamera {
+    /// Makes a camera component with the given name
+    pub fn named(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+
+    /// The camera's name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Default for KotoCamera {
+    fn default() -> Self {
+        Self::named(MAIN_CAMERA)
+    }
+}
+
+/// A function that adds camera functions to a map, given the camera's name
+pub type AddCameraFunctions = Arc<dyn Fn(&KMap, &KString) + Send + Sync>;
+
+/// The functions that are available for each camera in Koto scripts
+///
+/// The functions are added to the `camera` module for the main camera, and to the maps that are
+/// returned by `camera.get(name)`. Plugins can add their own functions to the resource while
+/// they're being built, with the functions sending [KotoCameraEvent]s to the named camera.
+#[derive(Resource, Clone, Default)]
+pub struct KotoCameraFunctions(Vec<AddCameraFunctions>);
+
+impl KotoCameraFunctions {
+    /// Adds a function that's called with each camera's map and name
+    pub fn add(&mut self, add_functions: impl Fn(&KMap, &KString) + Send + Sync + 'static) {
+        self.0.push(Arc::new(add_functions));
+    }
+
+    fn add_to_map(&self, map: &KMap, name: &KString) {
+        for add_functions in &self.0 {
+            add_functions(map, name);
+        }
+    }
+}
+
+#[allow(clippy::result_large_err)]
+pub(crate) fn on_startup(koto: Res<KotoRuntime>, camera_functions: Res<KotoCameraFunctions>) {
+    let module = KMap::with_type("camera");
+    camera_functions.add_to_map(&module, &MAIN_CAMERA.into());
+
+    module.add_fn("get", {
+        let camera_functions = camera_functions.clone();
         move |ctx| match ctx.args() {
-            [KValue::Number(zoom)] => {
-                update_projection.send(UpdateOrthographicProjection::Scale(zoom.into()));
-                Ok(KValue::Null)
+            [KValue::Str(name)] => {
+                let camera = KMap::with_type("Camera");
+                camera.add_fn("name", {
+                    cloned!(name);
+                    move |_| Ok(name.clone().into())
+                });
+                camera_functions.add_to_map(&camera, name);
+                Ok(camera.into())
             }
-            unexpected => unexpected_args("a Number", unexpected),
+            unexpected => unexpected_args("a camera name", unexpected),
         }
     });
+
+    koto.prelude().insert("camera", module);
+}
+
+#[allow(clippy::result_large_err)]
+fn set_zoom(
+    sender: &KotoSender<KotoCameraEvent<UpdateOrthographicProjection>>,
+    camera: &KString,
+    args: &[KValue],
+) -> KotoResult<KValue> {
+    match args {
+        [KValue::Number(zoom)] => {
+            sender.send(KotoCameraEvent::new(
+                camera.clone(),
+                UpdateOrthographicProjection::Scale(zoom.into()),
+            ));
+            Ok(KValue::Null)
+        }
+        unexpected => unexpected_args("a Number", unexpected),
+    }
+}
+
+// Snapshots of the cameras and their transforms,
+// shared between the plugin's systems and the Koto functions
+#[derive(Resource, Clone, Default)]
+struct CameraSnapshots(Arc<RwLock<HashMap<String, (Camera, GlobalTransform)>>>);
+
+// Matches Koto cameras that have changed since the snapshots were last synced
+type ChangedCamera = (
+    With<KotoCamera>,
+    Or<(
+        Changed<KotoCamera>,
+        Changed<Camera>,
+        Changed<GlobalTransform>,
+    )>,
+);
+
+fn sync_camera_snapshots(
+    cameras: Query<(&KotoCamera, &Camera, &GlobalTransform)>,
+    changed: Query<(), ChangedCamera>,
+    mut removed: RemovedComponents<KotoCamera>,
+    snapshots: Res<CameraSnapshots>,
+) {
+    let is_removed = removed.read().count() > 0;
+    if changed.is_empty() && !is_removed {
+        return;
+    }
+
+    let mut snapshots = snapshots.0.write();
+    snapshots.clear();
+    for (koto_camera, camera, transform) in &cameras {
+        snapshots.insert(koto_camera.name.clone(), (camera.clone(), *transform));
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn screen_to_world(
+    snapshots: &CameraSnapshots,
+    camera: &str,
+    args: &[KValue],
+) -> KotoResult<KValue> {
+    use KValue::{Number, Object};
+
+    let position = match args {
+        [Object(o)] if o.is_a::<KotoVec2>() => o.cast::<KotoVec2>()?.inner().as_vec2(),
+        [Number(x), Number(y)] => Vec2::new(x.into(), y.into()),
+        unexpected => return unexpected_args("a Vec2, or x and y Numbers", unexpected),
+    };
+
+    let snapshots = snapshots.0.read();
+    let result = snapshots
+        .get(camera)
+        .and_then(|(camera, transform)| camera.viewport_to_world_2d(transform, position).ok());
+
+    Ok(result.map_or(KValue::Null, |position| {
+        KotoVec2::new(position.x.into(), position.y.into()).into()
+    }))
+}
+
+#[allow(clippy::result_large_err)]
+fn world_to_screen(
+    snapshots: &CameraSnapshots,
+    camera: &str,
+    args: &[KValue],
+) -> KotoResult<KValue> {
+    use KValue::{Number, Object};
+
+    let position = match args {
+        [Object(o)] if o.is_a::<KotoVec2>() => o.cast::<KotoVec2>()?.inner().as_vec2().extend(0.0),
+        [Object(o)] if o.is_a::<KotoVec3>() => koto_to_bevy_vec3(&*o.cast::<KotoVec3>()?)?,
+        [Number(x), Number(y)] => Vec3::new(x.into(), y.into(), 0.0),
+        [Number(x), Number(y), Number(z)] => Vec3::new(x.into(), y.into(), z.into()),
+        unexpected => {
+            return unexpected_args("a Vec2 or Vec3, or x and y (and z) Numbers", unexpected)
+        }
+    };
+
+    let snapshots = snapshots.0.read();
+    let result = snapshots
+        .get(camera)
+        .and_then(|(camera, transform)| camera.world_to_viewport(transform, position).ok());
+
+    Ok(result.map_or(KValue::Null, |position| {
+        KotoVec2::new(position.x.into(), position.y.into()).into()
+    }))
 }
 
-// Reset the camera's projection when a script is loaded
+type ScriptCameraProjection = (
+    Entity,
+    Option<&'static mut OrthographicProjection>,
+    Has<ScriptWorldHeight>,
+);
+
+// Reset the cameras' projections and stop any shakes when a script is loaded
 fn on_script_loaded(
     mut script_loaded_events: EventReader<ScriptLoaded>,
-    mut camera_query: Query<&mut OrthographicProjection, With<KotoCamera>>,
+    mut camera_query: Query<ScriptCameraProjection, With<KotoCamera>>,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
+    settings: Res<KotoCameraSettings>,
+    mut commands: Commands,
 ) {
     for _ in script_loaded_events.read() {
-        let mut camera = camera_query.single_mut();
-        camera.scale = 1.0;
+        for (entity, projection, has_world_height) in camera_query.iter_mut() {
+            if let Some(mut projection) = projection {
+                projection.scale = 1.0;
+                if has_world_height {
+                    if let Ok(window) = primary_window.get_single() {
+                        projection.scaling_mode =
+                            get_scaling_mode(&settings, None, window.width(), window.height());
+                    }
+                }
+            }
+            commands
+                .entity(entity)
+                .remove::<(ActiveShake, ScriptWorldHeight)>();
+        }
+    }
+}
+
+fn koto_to_bevy_shake_events(
+    channel: Res<KotoReceiver<KotoCameraEvent<CameraShake>>>,
+    camera_query: Query<(Entity, &KotoCamera)>,
+    mut commands: Commands,
+) {
+    let _span = info_span!("koto_channel", channel = "CameraShake").entered();
+    while let Some(KotoCameraEvent { camera, event }) = channel.receive() {
+        let Some((entity, _)) = camera_query
+            .iter()
+            .find(|(_, koto_camera)| koto_camera.name == camera.as_str())
+        else {
+            warn!("Unable to find a camera named '{camera}'");
+            continue;
+        };
+        commands.entity(entity).insert(ActiveShake {
+            shake: event,
+            elapsed: 0.0,
+            offset: Vec3::ZERO,
+        });
+    }
+}
+
+fn apply_shake_offsets(
+    mut camera_query: Query<(&mut Transform, &mut ActiveShake)>,
+    koto_time: Res<KotoTime>,
+) {
+    for (mut transform, mut active) in camera_query.iter_mut() {
+        active.elapsed += koto_time.delta() as f32;
+        let CameraShake {
+            amplitude,
+            duration,
+            frequency,
+        } = active.shake;
+
+        // The shake's strength decays quadratically over its duration
+        let remaining = if duration > 0.0 {
+            (1.0 - active.elapsed / duration).max(0.0)
+        } else {
+            0.0
+        };
+        let t = active.elapsed * frequency;
+        let offset = amplitude
+            * remaining
+            * remaining
+            * (transform.right() * value_noise(t, 0) + transform.up() * value_noise(t, 1));
+
+        transform.translation += offset;
+        active.offset = offset;
+    }
+}
+
+fn remove_shake_offsets(
+    mut camera_query: Query<(Entity, &mut Transform, &mut ActiveShake)>,
+    mut commands: Commands,
+) {
+    for (entity, mut transform, mut active) in camera_query.iter_mut() {
+        transform.translation -= active.offset;
+        active.offset = Vec3::ZERO;
+        if active.elapsed >= active.shake.duration {
+            commands.entity(entity).remove::<ActiveShake>();
+        }
     }
 }
 
+// Smoothly interpolated noise in the range -1..1, with a separate noise sequence for each seed
+fn value_noise(t: f32, seed: u32) -> f32 {
+    let i = t.floor();
+    let f = t - i;
+    let a = hash_to_unit(i as i32 as u32, seed);
+    let b = hash_to_unit((i as i32).wrapping_add(1) as u32, seed);
+    a + (b - a) * f * f * (3.0 - 2.0 * f)
+}
+
+// Hashes an integer to a value in the range -1..1
+fn hash_to_unit(n: u32, seed: u32) -> f32 {
+    let mut x = n.wrapping_mul(0x9e37_79b9) ^ seed.wrapping_mul(0x85eb_ca6b);
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb_352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846c_a68b);
+    x ^= x >> 16;
+    (x as f32 / u32::MAX as f32) * 2.0 - 1.0
+}
+
 fn update_orthographic_projection(
-    channel: Res<KotoReceiver<UpdateOrthographicProjection>>,
-    mut camera_query: Query<&mut OrthographicProjection, With<KotoCamera>>,
+    channel: Res<KotoReceiver<KotoCameraEvent<UpdateOrthographicProjection>>>,
+    mut camera_query: Query<(Entity, &KotoCamera, &mut OrthographicProjection)>,
+    mut commands: Commands,
 ) {
-    let mut camera = camera_query.single_mut();
-    while let Some(event) = channel.receive() {
+    let _span = info_span!("koto_channel", channel = "UpdateOrthographicProjection").entered();
+    while let Some(KotoCameraEvent { camera, event }) = channel.receive() {
+        let Some((entity, _, mut projection)) = camera_query
+            .iter_mut()
+            .find(|(_, koto_camera, _)| koto_camera.name == camera.as_str())
+        else {
+            warn!("Unable to find a camera named '{camera}'");
+            continue;
+        };
         match event {
-            UpdateOrthographicProjection::Scale(scale) => camera.scale = scale,
+            UpdateOrthographicProjection::Scale(scale) => projection.scale = scale,
+            UpdateOrthographicProjection::WorldHeight(height) => {
+                projection.scaling_mode = ScalingMode::FixedVertical {
+                    viewport_height: height,
+                };
+                commands.entity(entity).insert(ScriptWorldHeight(height));
+            }
         }
     }
 }
 
 fn on_window_resized(
     mut window_resized_events: EventReader<WindowResized>,
-    mut camera_query: Query<&mut OrthographicProjection, With<KotoCamera>>,
+    mut camera_query: Query<
+        (&mut OrthographicProjection, Option<&ScriptWorldHeight>),
+        With<KotoCamera>,
+    >,
+    settings: Res<KotoCameraSettings>,
 ) {
-    let mut camera = camera_query.single_mut();
     for event in window_resized_events.read() {
-        camera.scaling_mode = get_scaling_mode(event.width, event.height);
+        for (mut camera, world_height) in camera_query.iter_mut() {
+            camera.scaling_mode = get_scaling_mode(
+                &settings,
+                world_height.map(|world_height| world_height.0),
+                event.width,
+                event.height,
+            );
+        }
     }
 }
 
-fn get_scaling_mode(width: f32, height: f32) -> ScalingMode {
-    if width > height {
-        ScalingMode::FixedVertical {
-            viewport_height: 2.0,
-        }
-    } else {
-        ScalingMode::FixedHorizontal {
-            viewport_width: 2.0,
-        }
+fn get_scaling_mode(
+    settings: &KotoCameraSettings,
+    world_height: Option<f32>,
+    width: f32,
+    height: f32,
+) -> ScalingMode {
+    let fixed_height = |viewport_height| ScalingMode::FixedVertical { viewport_height };
+    let fixed_width = |viewport_width| ScalingMode::FixedHorizontal { viewport_width };
+
+    if let Some(world_height) = world_height {
+        return fixed_height(world_height);
+    }
+
+    let extent = settings.world_extent;
+    match settings.scaling {
+        KotoCameraScaling::FitShorterSide if width > height => fixed_height(extent),
+        KotoCameraScaling::FitShorterSide => fixed_width(extent),
+        KotoCameraScaling::FitLongerSide if width > height => fixed_width(extent),
+        KotoCameraScaling::FitLongerSide => fixed_height(extent),
+        KotoCameraScaling::FixedHeight => fixed_height(extent),
+        KotoCameraScaling::FixedWidth => fixed_width(extent),
     }
 }
@@ -0,0 +1,199 @@
+//! Support for controlling 3D perspective cameras from Koto scripts
+
+use crate::{geometry::koto_to_bevy_vec3, prelude::*};
+use bevy::prelude::*;
+use cloned::cloned;
+use koto::{prelude::*, runtime::Result as KotoResult};
+
+/// Exposes 3D camera controls to Koto scripts
+///
+/// The plugin adds the following functions to the `camera` module, and to the `Camera` maps that
+/// are returned by `camera.get(name)`:
+/// - `camera.set_fov(fov)`: Sets the vertical field of view of the camera's perspective
+///   projection, in radians.
+/// - `camera.set_position(position)`: Moves the camera to the given position, either as a `Vec3`
+///   or as x, y, and z values.
+/// - `camera.look_at(target)`: Rotates the camera to face the given target, either as a `Vec3` or
+///   as x, y, and z values, with the camera's up direction being the Y axis.
+///
+/// e.g.
+///
+/// ```koto
+/// camera.set_position 0, 2, 5
+/// camera.look_at 0, 0, 0
+/// camera.set_fov 0.8
+/// ```
+///
+/// The cameras need to have a [KotoCamera] component along with a perspective [Projection], e.g.
+/// `(Camera3d::default(), KotoCamera::default())`. Cameras that have been modified by a script have
+/// their original transform and field of view restored when a script is loaded.
+pub struct KotoCamera3dPlugin;
+
+impl Plugin for KotoCamera3dPlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+        assert!(app.is_plugin_added::<KotoCameraPlugin>());
+        assert!(app.is_plugin_added::<KotoGeometryPlugin>());
+
+        let (update_camera_sender, update_camera_receiver) =
+            koto_channel::<KotoCameraEvent<UpdateCamera3d>>();
+
+        app.world_mut().resource_mut::<KotoCameraFunctions>().add({
+            cloned!(update_camera_sender);
+            move |camera, name| add_camera_functions(camera, name, &update_camera_sender)
+        });
+
+        app.insert_resource(update_camera_sender)
+            .insert_resource(update_camera_receiver)
+            .add_systems(KotoSchedule, on_script_loaded.in_set(KotoUpdate::PreUpdate))
+            .add_systems(Update, koto_to_bevy_camera_events);
+    }
+}
+
+/// Event for updating a 3D camera
+#[derive(Clone, Debug, Event)]
+pub enum UpdateCamera3d {
+    /// Sets the vertical field of view of the camera's perspective projection, in radians
+    Fov(f32),
+    /// Sets the position of the camera
+    Position(Vec3),
+    /// Rotates the camera to face the given target
+    LookAt(Vec3),
+}
+
+// The camera's transform and field of view before they were changed by a script
+#[derive(Component)]
+struct ScriptCamera3d {
+    transform: Transform,
+    fov: Option<f32>,
+}
+
+#[allow(clippy::result_large_err)]
+fn add_camera_functions(
+    camera: &KMap,
+    name: &KString,
+    sender: &KotoSender<KotoCameraEvent<UpdateCamera3d>>,
+) {
+    camera.add_fn("set_fov", {
+        cloned!(name, sender);
+        move |ctx| match ctx.args() {
+            [KValue::Number(fov)] if *fov > 0.0 => {
+                sender.send(KotoCameraEvent::new(
+                    name.clone(),
+                    UpdateCamera3d::Fov(fov.into()),
+                ));
+                Ok(KValue::Null)
+            }
+            unexpected => unexpected_args("a field of view in radians", unexpected),
+        }
+    });
+
+    camera.add_fn("set_position", {
+        cloned!(name, sender);
+        move |ctx| {
+            let position = koto_to_vec3("camera.set_position", ctx.args())?;
+            sender.send(KotoCameraEvent::new(
+                name.clone(),
+                UpdateCamera3d::Position(position),
+            ));
+            Ok(KValue::Null)
+        }
+    });
+
+    camera.add_fn("look_at", {
+        cloned!(name, sender);
+        move |ctx| {
+            let target = koto_to_vec3("camera.look_at", ctx.args())?;
+            sender.send(KotoCameraEvent::new(
+                name.clone(),
+                UpdateCamera3d::LookAt(target),
+            ));
+            Ok(KValue::Null)
+        }
+    });
+}
+
+// Gets a Vec3 from either a geometry.vec3 or x, y, and z Numbers
+#[allow(clippy::result_large_err)]
+fn koto_to_vec3(fn_name: &str, args: &[KValue]) -> KotoResult<Vec3> {
+    use KValue::{Number, Object};
+
+    match args {
+        [Object(o)] if o.is_a::<KotoVec3>() => koto_to_bevy_vec3(&*o.cast::<KotoVec3>()?),
+        [Number(x), Number(y), Number(z)] => Ok(Vec3::new(x.into(), y.into(), z.into())),
+        _ => runtime_error!("{fn_name}: Expected a Vec3, or x, y, and z Numbers"),
+    }
+}
+
+// Restore the cameras' original settings when a script is loaded
+fn on_script_loaded(
+    mut script_loaded_events: EventReader<ScriptLoaded>,
+    mut camera_query: Query<(
+        Entity,
+        &ScriptCamera3d,
+        &mut Transform,
+        Option<&mut Projection>,
+    )>,
+    mut commands: Commands,
+) {
+    for _ in script_loaded_events.read() {
+        for (entity, original, mut transform, projection) in camera_query.iter_mut() {
+            *transform = original.transform;
+            if let (Some(fov), Some(mut projection)) = (original.fov, projection) {
+                if let Projection::Perspective(perspective) = &mut *projection {
+                    perspective.fov = fov;
+                }
+            }
+            commands.entity(entity).remove::<ScriptCamera3d>();
+        }
+    }
+}
+
+type Camera3dComponents = (
+    Entity,
+    &'static KotoCamera,
+    &'static mut Transform,
+    Option<&'static mut Projection>,
+    Has<ScriptCamera3d>,
+);
+
+fn koto_to_bevy_camera_events(
+    channel: Res<KotoReceiver<KotoCameraEvent<UpdateCamera3d>>>,
+    mut camera_query: Query<Camera3dComponents>,
+    mut commands: Commands,
+    mut modified: Local<Vec<Entity>>,
+) {
+    let _span = info_span!("koto_channel", channel = "UpdateCamera3d").entered();
+    modified.clear();
+    while let Some(KotoCameraEvent { camera, event }) = channel.receive() {
+        let Some((entity, _, mut transform, mut projection, has_original)) = camera_query
+            .iter_mut()
+            .find(|(_, koto_camera, ..)| koto_camera.name() == camera.as_str())
+        else {
+            warn!("Unable to find a camera named '{camera}'");
+            continue;
+        };
+
+        // The camera's settings are recorded before they're first modified by the script
+        if !has_original && !modified.contains(&entity) {
+            let fov = match projection.as_deref() {
+                Some(Projection::Perspective(perspective)) => Some(perspective.fov),
+                _ => None,
+            };
+            commands.entity(entity).insert(ScriptCamera3d {
+                transform: *transform,
+                fov,
+            });
+            modified.push(entity);
+        }
+
+        match event {
+            UpdateCamera3d::Fov(fov) => match projection.as_deref_mut() {
+                Some(Projection::Perspective(perspective)) => perspective.fov = fov,
+                _ => warn!("The camera '{camera}' doesn't have a perspective projection"),
+            },
+            UpdateCamera3d::Position(position) => transform.translation = position,
+            UpdateCamera3d::LookAt(target) => transform.look_at(target, Vec3::Y),
+        }
+    }
+}
@@ -0,0 +1,248 @@
+//! Overlap checks between Koto shapes
+
+use crate::{
+    prelude::*,
+    shape::{Shape, ShapeKind},
+};
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+/// Overlap events for the shapes spawned by the [KotoShapePlugin]
+///
+/// A shape's `on_collision` callback is called when it starts to overlap with another shape,
+/// with the shape as `self` and the other shape as the callback's argument, e.g.
+///
+/// ```koto
+/// player.on_collision |other|
+///   other.despawn()
+/// ```
+///
+/// Overlaps are checked during [KotoUpdate::Update] with simple colliders that are based on each
+/// shape's mesh and transform. Circles and polygons use their bounding circle, and squares use
+/// their axis-aligned bounding box. Pairs of shapes are found with a sweep-and-prune broadphase
+/// over the colliders' bounds, so only shapes that are close to each other are tested for
+/// overlaps. The callback is only called when an overlap starts, and is called again if the
+/// shapes separate and then overlap again.
+pub struct KotoCollisionPlugin;
+
+impl Plugin for KotoCollisionPlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+        assert!(app.is_plugin_added::<KotoShapePlugin>());
+
+        app.init_resource::<KotoOverlaps>()
+            .add_systems(KotoSchedule, check_collisions.in_set(KotoUpdate::Update));
+    }
+}
+
+// The pairs of entities that were overlapping when collisions were last checked
+//
+// Pairs are stored with the lower entity first.
+#[derive(Resource, Default)]
+struct KotoOverlaps(HashSet<(Entity, Entity)>);
+
+fn check_collisions(
+    koto: Res<KotoRuntime>,
+    mut overlaps: ResMut<KotoOverlaps>,
+    mut query: Query<(Entity, &mut KotoEntity, &ShapeKind, &GlobalTransform)>,
+    mut script_error: EventWriter<KotoScriptError>,
+) {
+    if !koto.is_ready() {
+        overlaps.0.clear();
+        return;
+    }
+
+    let mut colliders: Vec<_> = query
+        .iter()
+        .filter(|(_, koto_entity, _, _)| koto_entity.is_active)
+        .map(|(entity, koto_entity, shape, transform)| {
+            let has_callback = koto_entity.callbacks.contains_key("on_collision");
+            let collider = Collider::new(&shape.0, transform);
+            let (min, max) = collider.bounds();
+            BroadphaseEntry {
+                entity,
+                collider,
+                min,
+                max,
+                has_callback,
+            }
+        })
+        .collect();
+
+    // Sweep and prune: with the colliders sorted by the left edges of their bounds, each collider
+    // only needs to be checked against the following colliders that start before it ends.
+    colliders.sort_by(|a, b| a.min.x.total_cmp(&b.min.x));
+
+    let mut current = HashSet::new();
+    for (i, a) in colliders.iter().enumerate() {
+        for b in colliders[i + 1..].iter().take_while(|b| b.min.x <= a.max.x) {
+            // Overlaps only need to be checked for pairs where at least one entity has a callback
+            if (a.has_callback || b.has_callback)
+                && a.min.y <= b.max.y
+                && b.min.y <= a.max.y
+                && a.collider.overlaps(&b.collider)
+            {
+                current.insert((a.entity.min(b.entity), a.entity.max(b.entity)));
+            }
+        }
+    }
+
+    let mut started: Vec<_> = current.difference(&overlaps.0).copied().collect();
+    started.sort();
+    overlaps.0 = current;
+
+    for (a, b) in started {
+        let object = |entity| {
+            query
+                .get(entity)
+                .map(|(_, koto_entity, _, _)| koto_entity.object.clone())
+        };
+        let (Ok(object_a), Ok(object_b)) = (object(a), object(b)) else {
+            continue;
+        };
+
+        for (entity, other) in [(a, object_b), (b, object_a)] {
+            let Ok((_, mut koto_entity, _, _)) = query.get_mut(entity) else {
+                continue;
+            };
+            let instance = koto_entity.object.clone();
+            let Some((f, vm)) = koto_entity.callbacks.get_mut("on_collision") else {
+                continue;
+            };
+
+            let _span = info_span!("koto_collision", %entity).entered();
+            if let Err(error) = vm.call_instance_function(instance.into(), f.clone(), other) {
+                let error = KotoScriptError {
+                    phase: ScriptPhase::Collision,
+                    message: error.to_string(),
+                    span: None,
+                    script_path: koto.script_path().map(ToOwned::to_owned),
+                };
+                error!("{error}");
+                script_error.send(error);
+            }
+        }
+    }
+}
+
+// A collider along with its bounds, used for the broadphase
+struct BroadphaseEntry {
+    entity: Entity,
+    collider: Collider,
+    min: Vec2,
+    max: Vec2,
+    has_callback: bool,
+}
+
+enum Collider {
+    Circle { center: Vec2, radius: f32 },
+    Aabb { center: Vec2, half_size: Vec2 },
+}
+
+impl Collider {
+    fn new(shape: &Shape, transform: &GlobalTransform) -> Self {
+        // The 2D axes of the transform are used directly rather than decomposing it,
+        // shapes are often given a Z scale of 0 which would produce NaNs.
+        let affine = transform.affine();
+        let x_axis = affine.matrix3.x_axis.xy();
+        let y_axis = affine.matrix3.y_axis.xy();
+        let center = affine.translation.xy();
+        let max_scale = x_axis.length().max(y_axis.length());
+
+        // The bounding box of a transformed rectangle in the shape's local space
+        let aabb = |local_center: Vec2, size: Vec2| Self::Aabb {
+            center: center + x_axis * local_center.x + y_axis * local_center.y,
+            half_size: (x_axis.abs() * size.x + y_axis.abs() * size.y) / 2.0,
+        };
+
+        match *shape {
+            Shape::Circle => Self::Circle {
+                center,
+                radius: Circle::default().radius * max_scale,
+            },
+            Shape::Polygon(_) | Shape::Star(..) => Self::Circle {
+                center,
+                radius: max_scale,
+            },
+            Shape::Rect(width, height)
+            | Shape::Ellipse(width, height)
+            | Shape::RoundedRect(width, height, _) => aabb(Vec2::ZERO, Vec2::new(width, height)),
+            Shape::Triangle(a, b, c) => {
+                let min = a.min(b).min(c);
+                let max = a.max(b).max(c);
+                aabb((min + max) / 2.0, max - min)
+            }
+            Shape::Capsule(radius, length) => {
+                aabb(Vec2::ZERO, Vec2::new(radius * 2.0, length + radius * 2.0))
+            }
+            Shape::Path(ref path) => {
+                let (min, max) = path.bounds();
+                aabb((min + max) / 2.0, max - min)
+            }
+            Shape::Line(ref points, width) => {
+                let min = points.iter().fold(Vec2::INFINITY, |min, p| min.min(*p));
+                let max = points.iter().fold(Vec2::NEG_INFINITY, |max, p| max.max(*p));
+                aabb((min + max) / 2.0, max - min + width)
+            }
+            Shape::Ring(_, radius) | Shape::Arc(radius, _, _) => Self::Circle {
+                center,
+                radius: radius * max_scale,
+            },
+        }
+    }
+
+    // Returns the min and max corners of the collider's bounding box
+    fn bounds(&self) -> (Vec2, Vec2) {
+        match *self {
+            Self::Circle { center, radius } => (center - radius, center + radius),
+            Self::Aabb { center, half_size } => (center - half_size, center + half_size),
+        }
+    }
+
+    fn overlaps(&self, other: &Self) -> bool {
+        use Collider::*;
+
+        match (self, other) {
+            (
+                Circle {
+                    center: a,
+                    radius: radius_a,
+                },
+                Circle {
+                    center: b,
+                    radius: radius_b,
+                },
+            ) => a.distance_squared(*b) <= (radius_a + radius_b).powi(2),
+            (
+                Aabb {
+                    center: a,
+                    half_size: half_a,
+                },
+                Aabb {
+                    center: b,
+                    half_size: half_b,
+                },
+            ) => {
+                let distance = (*a - *b).abs();
+                distance.x <= half_a.x + half_b.x && distance.y <= half_a.y + half_b.y
+            }
+            (
+                Circle {
+                    center: circle_center,
+                    radius,
+                },
+                Aabb { center, half_size },
+            )
+            | (
+                Aabb { center, half_size },
+                Circle {
+                    center: circle_center,
+                    radius,
+                },
+            ) => {
+                let closest = *center + (*circle_center - *center).clamp(-*half_size, *half_size);
+                closest.distance_squared(*circle_center) <= radius.powi(2)
+            }
+        }
+    }
+}
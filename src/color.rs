@@ -1,15 +1,48 @@
 //! Support for working with Bevy colors in Koto scripts
 
 use crate::prelude::*;
-use bevy::prelude::*;
+use bevy::{
+    asset::embedded_asset,
+    color::palettes::css,
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderRef},
+    sprite::{AlphaMode2d, Material2d, Material2dPlugin},
+    utils::HashMap,
+};
 use cloned::cloned;
-use koto::prelude::*;
+use koto::{derive::*, prelude::*, runtime::Result as KotoResult};
 pub use koto_color::Color as KotoColor;
 
 /// Color support for bevy_koto
 ///
 /// The plugin adds the `color` module from `koto_color` to Koto's prelude,
 /// along with a `set_clear_color` function.
+///
+/// Functions that take colors also accept CSS color names and hex strings, see [parse_color].
+///
+/// A `gradient` function is also added to the prelude, which makes a [ColorGradient] from a list
+/// of colors, or from a list of `(position, color)` stops. Colors are interpolated in the Oklab
+/// color space by default, with `'linear'` or `'srgb'` being accepted as an optional second
+/// argument, e.g.
+///
+/// ```koto
+/// sunset = gradient [color('gold'), color('orangered'), color('purple')]
+/// sunset.sample 0.25
+/// shape.square().set_gradient sunset, 0
+/// ```
+///
+/// Entities with a [ColorMaterial] can also be given a [GradientMaterial] via
+/// [UpdateColorMaterial::Gradient], with the entity's color material being restored when its
+/// color or image is set.
+///
+/// Color materials can be shared between entities until they're modified, see
+/// [SharedColorMaterial].
+///
+/// The colors of entities with color materials are mirrored into their [KotoEntityMapping] during
+/// [KotoUpdate::PreUpdate], allowing scripts to read an entity's current color.
+///
+/// An entity's color or alpha can be animated over time with [UpdateColorMaterial::Tween], which
+/// inserts a [ColorTween] component that's advanced by the plugin using [KotoTime].
 pub struct KotoColorPlugin;
 
 impl Plugin for KotoColorPlugin {
@@ -21,20 +54,30 @@ impl Plugin for KotoColorPlugin {
         let (update_color_sender, update_color_receiver) =
             koto_entity_channel::<UpdateColorMaterial>();
 
-        app.insert_resource(set_clear_color_sender)
+        embedded_asset!(app, "gradient.wgsl");
+
+        app.add_plugins(Material2dPlugin::<GradientMaterial>::default())
+            .insert_resource(set_clear_color_sender)
             .insert_resource(set_clear_color_receiver)
             .insert_resource(update_color_sender)
             .insert_resource(update_color_receiver)
             .add_event::<SetClearColor>()
             .add_systems(Startup, on_startup)
-            .add_systems(KotoSchedule, on_script_loaded.in_set(KotoUpdate::PreUpdate))
+            .add_systems(
+                KotoSchedule,
+                (on_script_loaded, sync_colors).in_set(KotoUpdate::PreUpdate),
+            )
             .add_systems(
                 Update,
-                (set_clear_color, koto_to_bevy_color_material_events),
+                (
+                    set_clear_color,
+                    (koto_to_bevy_color_material_events, update_color_tweens).chain(),
+                ),
             );
     }
 }
 
+#[allow(clippy::result_large_err)]
 fn on_startup(koto: Res<KotoRuntime>, set_clear_color: Res<KotoSender<SetClearColor>>) {
     let prelude = koto.prelude();
 
@@ -55,7 +98,16 @@ fn on_startup(koto: Res<KotoRuntime>, set_clear_color: Res<KotoSender<SetClearCo
                 [Object(o)] if o.is_a::<KotoColor>() => {
                     koto_to_bevy_color(&*o.cast::<KotoColor>()?)
                 }
-                unexpected => return unexpected_args("three or four Numbers", unexpected),
+                [Str(s)] => match parse_color(s) {
+                    Some(color) => color,
+                    None => return runtime_error!("set_clear_color: Unknown color '{s}'"),
+                },
+                unexpected => {
+                    return unexpected_args(
+                        "a Color, a color name or hex String, or three or four Numbers",
+                        unexpected,
+                    )
+                }
             };
 
             set_clear_color.send(SetClearColor(color));
@@ -63,6 +115,80 @@ fn on_startup(koto: Res<KotoRuntime>, set_clear_color: Res<KotoSender<SetClearCo
             Ok(Null)
         }
     });
+
+    prelude.add_fn("gradient", |ctx| {
+        use KValue::*;
+
+        let (stops, space) = match ctx.args() {
+            [List(stops)] => (koto_to_gradient_stops(&stops.data())?, None),
+            [Tuple(stops)] => (koto_to_gradient_stops(stops)?, None),
+            [List(stops), Str(space)] => (koto_to_gradient_stops(&stops.data())?, Some(space)),
+            [Tuple(stops), Str(space)] => (koto_to_gradient_stops(stops)?, Some(space)),
+            unexpected => {
+                return unexpected_args(
+                    "a List of Colors or (position, Color) stops, and an optional color space",
+                    unexpected,
+                )
+            }
+        };
+
+        let space = match space.map(|space| space.as_str()) {
+            None | Some("oklab") => GradientSpace::Oklab,
+            Some("linear") => GradientSpace::LinearRgb,
+            Some("srgb") => GradientSpace::Srgb,
+            Some(unexpected) => {
+                return runtime_error!(
+                    "gradient: Expected 'oklab', 'linear', or 'srgb' as the color space, found \
+                     '{unexpected}'"
+                )
+            }
+        };
+
+        Ok(KotoGradient(ColorGradient::new(stops).with_space(space)).into())
+    });
+}
+
+// Converts a Koto list of colors or (position, color) pairs into gradient stops
+//
+// Stops that are provided as colors without positions are spaced evenly between 0 and 1.
+#[allow(clippy::result_large_err)]
+fn koto_to_gradient_stops(values: &[KValue]) -> KotoResult<Vec<(f32, Color)>> {
+    use KValue::{Number, Object, Tuple};
+
+    if values.is_empty() {
+        return runtime_error!("gradient: Expected at least one color stop");
+    }
+
+    let last_index = (values.len() - 1).max(1) as f32;
+
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| match value {
+            Object(o) if o.is_a::<KotoColor>() => Ok((
+                i as f32 / last_index,
+                koto_to_bevy_color(&*o.cast::<KotoColor>()?),
+            )),
+            KValue::List(l) => match l.data().as_slice() {
+                [Number(position), Object(o)] if o.is_a::<KotoColor>() => Ok((
+                    position.into(),
+                    koto_to_bevy_color(&*o.cast::<KotoColor>()?),
+                )),
+                _ => runtime_error!("gradient: Expected a (position, Color) stop"),
+            },
+            Tuple(t) => match &t[..] {
+                [Number(position), Object(o)] if o.is_a::<KotoColor>() => Ok((
+                    position.into(),
+                    koto_to_bevy_color(&*o.cast::<KotoColor>()?),
+                )),
+                _ => runtime_error!("gradient: Expected a (position, Color) stop"),
+            },
+            unexpected => runtime_error!(
+                "gradient: Expected a Color or a (position, Color) stop, found '{}'",
+                unexpected.type_as_string()
+            ),
+        })
+        .collect()
 }
 
 // Reset the clear color when a script is loaded
@@ -75,7 +201,33 @@ fn on_script_loaded(
     }
 }
 
+// An entity's color material, either active or suspended
+type ActiveOrSuspendedMaterial = AnyOf<(
+    &'static MeshMaterial2d<ColorMaterial>,
+    &'static SuspendedColorMaterial,
+)>;
+
+// Keeps the entities' color snapshots in sync with their color materials
+fn sync_colors(
+    query: Query<(&KotoEntity, ActiveOrSuspendedMaterial)>,
+    materials: Res<Assets<ColorMaterial>>,
+) {
+    for (koto_entity, (material, suspended)) in &query {
+        let Some(material) = material
+            .map(|material| material.id())
+            .or_else(|| suspended.map(|suspended| suspended.0.id()))
+            .and_then(|id| materials.get(id))
+        else {
+            continue;
+        };
+        if koto_entity.entity.color() != Some(material.color) {
+            koto_entity.entity.set_color_snapshot(Some(material.color));
+        }
+    }
+}
+
 fn set_clear_color(channel: Res<KotoReceiver<SetClearColor>>, mut clear_color: ResMut<ClearColor>) {
+    let _span = info_span!("koto_channel", channel = "SetClearColor").entered();
     while let Some(event) = channel.receive() {
         clear_color.0 = event.0;
     }
@@ -102,23 +254,501 @@ pub fn koto_to_bevy_color(koto_color: &KotoColor) -> Color {
     }
 }
 
-fn koto_to_bevy_color_material_events(
+/// Parses a color from a CSS color name or a hex string, e.g. `"rebeccapurple"` or `"#ff8800cc"`
+///
+/// Color names are case-insensitive, and are looked up in Bevy's table of CSS colors. Hex strings
+/// have 3, 4, 6, or 8 digits, with an optional leading `#`.
+pub fn parse_color(s: &str) -> Option<Color> {
+    css_color(&s.to_ascii_lowercase())
+        .or_else(|| Srgba::hex(s).ok())
+        .map(Color::from)
+}
+
+// Looks up a lowercase color name in Bevy's CSS color palette
+fn css_color(name: &str) -> Option<Srgba> {
+    let color = match name {
+        "aliceblue" => css::ALICE_BLUE,
+        "antiquewhite" => css::ANTIQUE_WHITE,
+        "aqua" => css::AQUA,
+        "aquamarine" => css::AQUAMARINE,
+        "azure" => css::AZURE,
+        "beige" => css::BEIGE,
+        "bisque" => css::BISQUE,
+        "black" => css::BLACK,
+        "blanchedalmond" => css::BLANCHED_ALMOND,
+        "blue" => css::BLUE,
+        "blueviolet" => css::BLUE_VIOLET,
+        "brown" => css::BROWN,
+        "burlywood" => css::BURLYWOOD,
+        "cadetblue" => css::CADET_BLUE,
+        "chartreuse" => css::CHARTREUSE,
+        "chocolate" => css::CHOCOLATE,
+        "coral" => css::CORAL,
+        "cornflowerblue" => css::CORNFLOWER_BLUE,
+        "cornsilk" => css::CORNSILK,
+        "crimson" => css::CRIMSON,
+        "darkblue" => css::DARK_BLUE,
+        "darkcyan" => css::DARK_CYAN,
+        "darkgoldenrod" => css::DARK_GOLDENROD,
+        "darkgray" => css::DARK_GRAY,
+        "darkgreen" => css::DARK_GREEN,
+        "darkgrey" => css::DARK_GREY,
+        "darkkhaki" => css::DARK_KHAKI,
+        "darkmagenta" => css::DARK_MAGENTA,
+        "darkolivegreen" => css::DARK_OLIVEGREEN,
+        "darkorange" => css::DARK_ORANGE,
+        "darkorchid" => css::DARK_ORCHID,
+        "darkred" => css::DARK_RED,
+        "darksalmon" => css::DARK_SALMON,
+        "darkseagreen" => css::DARK_SEA_GREEN,
+        "darkslateblue" => css::DARK_SLATE_BLUE,
+        "darkslategray" => css::DARK_SLATE_GRAY,
+        "darkslategrey" => css::DARK_SLATE_GREY,
+        "darkturquoise" => css::DARK_TURQUOISE,
+        "darkviolet" => css::DARK_VIOLET,
+        "deeppink" => css::DEEP_PINK,
+        "deepskyblue" => css::DEEP_SKY_BLUE,
+        "dimgray" => css::DIM_GRAY,
+        "dimgrey" => css::DIM_GREY,
+        "dodgerblue" => css::DODGER_BLUE,
+        "firebrick" => css::FIRE_BRICK,
+        "floralwhite" => css::FLORAL_WHITE,
+        "forestgreen" => css::FOREST_GREEN,
+        "fuchsia" => css::FUCHSIA,
+        "gainsboro" => css::GAINSBORO,
+        "ghostwhite" => css::GHOST_WHITE,
+        "gold" => css::GOLD,
+        "goldenrod" => css::GOLDENROD,
+        "gray" => css::GRAY,
+        "green" => css::GREEN,
+        "greenyellow" => css::GREEN_YELLOW,
+        "grey" => css::GREY,
+        "honeydew" => css::HONEYDEW,
+        "hotpink" => css::HOT_PINK,
+        "indianred" => css::INDIAN_RED,
+        "indigo" => css::INDIGO,
+        "ivory" => css::IVORY,
+        "khaki" => css::KHAKI,
+        "lavender" => css::LAVENDER,
+        "lavenderblush" => css::LAVENDER_BLUSH,
+        "lawngreen" => css::LAWN_GREEN,
+        "lemonchiffon" => css::LEMON_CHIFFON,
+        "lightblue" => css::LIGHT_BLUE,
+        "lightcoral" => css::LIGHT_CORAL,
+        "lightcyan" => css::LIGHT_CYAN,
+        "lightgoldenrodyellow" => css::LIGHT_GOLDENROD_YELLOW,
+        "lightgray" => css::LIGHT_GRAY,
+        "lightgreen" => css::LIGHT_GREEN,
+        "lightgrey" => css::LIGHT_GREY,
+        "lightpink" => css::LIGHT_PINK,
+        "lightsalmon" => css::LIGHT_SALMON,
+        "lightseagreen" => css::LIGHT_SEA_GREEN,
+        "lightskyblue" => css::LIGHT_SKY_BLUE,
+        "lightslategray" => css::LIGHT_SLATE_GRAY,
+        "lightslategrey" => css::LIGHT_SLATE_GREY,
+        "lightsteelblue" => css::LIGHT_STEEL_BLUE,
+        "lightyellow" => css::LIGHT_YELLOW,
+        "lime" => css::LIME,
+        "limegreen" => css::LIMEGREEN,
+        "linen" => css::LINEN,
+        "magenta" => css::MAGENTA,
+        "maroon" => css::MAROON,
+        "mediumaquamarine" => css::MEDIUM_AQUAMARINE,
+        "mediumblue" => css::MEDIUM_BLUE,
+        "mediumorchid" => css::MEDIUM_ORCHID,
+        "mediumpurple" => css::MEDIUM_PURPLE,
+        "mediumseagreen" => css::MEDIUM_SEA_GREEN,
+        "mediumslateblue" => css::MEDIUM_SLATE_BLUE,
+        "mediumspringgreen" => css::MEDIUM_SPRING_GREEN,
+        "mediumturquoise" => css::MEDIUM_TURQUOISE,
+        "mediumvioletred" => css::MEDIUM_VIOLET_RED,
+        "midnightblue" => css::MIDNIGHT_BLUE,
+        "mintcream" => css::MINT_CREAM,
+        "mistyrose" => css::MISTY_ROSE,
+        "moccasin" => css::MOCCASIN,
+        "navajowhite" => css::NAVAJO_WHITE,
+        "navy" => css::NAVY,
+        "oldlace" => css::OLD_LACE,
+        "olive" => css::OLIVE,
+        "olivedrab" => css::OLIVE_DRAB,
+        "orange" => css::ORANGE,
+        "orangered" => css::ORANGE_RED,
+        "orchid" => css::ORCHID,
+        "palegoldenrod" => css::PALE_GOLDENROD,
+        "palegreen" => css::PALE_GREEN,
+        "paleturquoise" => css::PALE_TURQUOISE,
+        "palevioletred" => css::PALE_VIOLETRED,
+        "papayawhip" => css::PAPAYA_WHIP,
+        "peachpuff" => css::PEACHPUFF,
+        "peru" => css::PERU,
+        "pink" => css::PINK,
+        "plum" => css::PLUM,
+        "powderblue" => css::POWDER_BLUE,
+        "purple" => css::PURPLE,
+        "rebeccapurple" => css::REBECCA_PURPLE,
+        "red" => css::RED,
+        "rosybrown" => css::ROSY_BROWN,
+        "royalblue" => css::ROYAL_BLUE,
+        "saddlebrown" => css::SADDLE_BROWN,
+        "salmon" => css::SALMON,
+        "sandybrown" => css::SANDY_BROWN,
+        "seagreen" => css::SEA_GREEN,
+        "seashell" => css::SEASHELL,
+        "sienna" => css::SIENNA,
+        "silver" => css::SILVER,
+        "skyblue" => css::SKY_BLUE,
+        "slateblue" => css::SLATE_BLUE,
+        "slategray" => css::SLATE_GRAY,
+        "slategrey" => css::SLATE_GREY,
+        "snow" => css::SNOW,
+        "springgreen" => css::SPRING_GREEN,
+        "steelblue" => css::STEEL_BLUE,
+        "tan" => css::TAN,
+        "teal" => css::TEAL,
+        "thistle" => css::THISTLE,
+        "tomato" => css::TOMATO,
+        "turquoise" => css::TURQUOISE,
+        "violet" => css::VIOLET,
+        "wheat" => css::WHEAT,
+        "white" => css::WHITE,
+        "whitesmoke" => css::WHITE_SMOKE,
+        "yellow" => css::YELLOW,
+        "yellowgreen" => css::YELLOW_GREEN,
+        _ => return None,
+    };
+    Some(color)
+}
+
+/// A function that converts a Bevy color into a Koto color
+///
+/// Colors in the HSL, HSV, Oklab, and Oklch color spaces keep their color space, with other colors
+/// being converted to sRGB.
+pub fn bevy_to_koto_color(color: Color) -> KotoColor {
+    match color {
+        Color::Hsla(c) => palette::Hsla::new(c.hue, c.saturation, c.lightness, c.alpha).into(),
+        Color::Hsva(c) => palette::Hsva::new(c.hue, c.saturation, c.value, c.alpha).into(),
+        Color::Oklaba(c) => palette::Oklaba::new(c.lightness, c.a, c.b, c.alpha).into(),
+        Color::Oklcha(c) => palette::Oklcha::new(c.lightness, c.chroma, c.hue, c.alpha).into(),
+        _ => {
+            let c = color.to_srgba();
+            palette::Srgba::new(c.red, c.green, c.blue, c.alpha).into()
+        }
+    }
+}
+
+/// A color ramp made from a series of color stops
+///
+/// Colors are interpolated between the stops in the gradient's [GradientSpace], with positions
+/// before the first stop or after the last stop taking the color of the nearest stop.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorGradient {
+    // The gradient's stops, sorted by position
+    stops: Vec<(f32, Color)>,
+    space: GradientSpace,
+}
+
+impl ColorGradient {
+    /// Makes a gradient from a series of `(position, color)` stops
+    ///
+    /// The stops don't need to be in order, and positions are typically in the range `0..=1`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no stops are provided.
+    pub fn new(stops: impl IntoIterator<Item = (f32, Color)>) -> Self {
+        let mut stops: Vec<_> = stops.into_iter().collect();
+        assert!(
+            !stops.is_empty(),
+            "A gradient needs at least one color stop"
+        );
+        stops.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        Self {
+            stops,
+            space: GradientSpace::default(),
+        }
+    }
+
+    /// Makes a gradient with the colors spaced evenly between 0 and 1
+    pub fn evenly_spaced(colors: impl IntoIterator<Item = Color>) -> Self {
+        let colors: Vec<_> = colors.into_iter().collect();
+        let last_index = (colors.len().saturating_sub(1)).max(1) as f32;
+        Self::new(
+            colors
+                .into_iter()
+                .enumerate()
+                .map(|(i, color)| (i as f32 / last_index, color)),
+        )
+    }
+
+    /// Sets the color space that the gradient's colors are interpolated in
+    #[must_use]
+    pub fn with_space(mut self, space: GradientSpace) -> Self {
+        self.space = space;
+        self
+    }
+
+    /// The gradient's stops, sorted by position
+    pub fn stops(&self) -> &[(f32, Color)] {
+        &self.stops
+    }
+
+    /// Returns the gradient's color at the given position
+    pub fn sample(&self, t: f32) -> Color {
+        let next = self.stops.partition_point(|(position, _)| *position <= t);
+        match (next.checked_sub(1), self.stops.get(next)) {
+            (Some(previous), Some((end, end_color))) => {
+                let (start, start_color) = self.stops[previous];
+                let amount = (t - start) / (end - start);
+                self.space.mix(start_color, *end_color, amount)
+            }
+            (Some(previous), None) => self.stops[previous].1,
+            (None, _) => self.stops[0].1,
+        }
+    }
+
+    /// Returns `count` colors sampled at evenly spaced positions between 0 and 1
+    pub fn sample_many(&self, count: usize) -> impl Iterator<Item = Color> + '_ {
+        let last_index = (count.saturating_sub(1)).max(1) as f32;
+        (0..count).map(move |i| self.sample(i as f32 / last_index))
+    }
+}
+
+/// The color space that a [ColorGradient]'s colors are interpolated in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GradientSpace {
+    /// The perceptually uniform Oklab color space, avoiding the muddy midpoints of RGB gradients
+    #[default]
+    Oklab,
+    /// Linear RGB
+    LinearRgb,
+    /// Gamma-encoded sRGB
+    Srgb,
+}
+
+impl GradientSpace {
+    fn mix(self, a: Color, b: Color, amount: f32) -> Color {
+        match self {
+            Self::Oklab => Oklaba::from(a).mix(&Oklaba::from(b), amount).into(),
+            Self::LinearRgb => LinearRgba::from(a).mix(&LinearRgba::from(b), amount).into(),
+            Self::Srgb => Srgba::from(a).mix(&Srgba::from(b), amount).into(),
+        }
+    }
+}
+
+/// A [ColorGradient] that's been made in a Koto script with the `gradient` function
+#[derive(Clone, KotoType, KotoCopy)]
+#[koto(type_name = "Gradient")]
+pub struct KotoGradient(pub ColorGradient);
+
+impl KotoObject for KotoGradient {}
+
+#[allow(clippy::result_large_err)]
+#[koto_impl]
+impl KotoGradient {
+    #[koto_method]
+    fn sample(&self, args: &[KValue]) -> KotoResult<KValue> {
+        match args {
+            [KValue::Number(t)] => Ok(bevy_to_koto_color(self.0.sample(t.into())).into()),
+            _ => runtime_error!("Gradient.sample: Expected a Number"),
+        }
+    }
+
+    #[koto_method]
+    fn sample_many(&self, args: &[KValue]) -> KotoResult<KValue> {
+        match args {
+            [KValue::Number(n)] if *n >= 0 => Ok(KList::with_data(
+                self.0
+                    .sample_many(n.into())
+                    .map(|color| bevy_to_koto_color(color).into())
+                    .collect(),
+            )
+            .into()),
+            _ => runtime_error!("Gradient.sample_many: Expected a non-negative Number"),
+        }
+    }
+}
+
+impl From<KotoGradient> for KValue {
+    fn from(gradient: KotoGradient) -> Self {
+        KObject::from(gradient).into()
+    }
+}
+
+type EntityMaterials = (
+    Option<&'static MeshMaterial2d<ColorMaterial>>,
+    Option<&'static MeshMaterial2d<GradientMaterial>>,
+    Option<&'static SuspendedColorMaterial>,
+    Has<SharedColorMaterial>,
+);
+
+pub(crate) fn koto_to_bevy_color_material_events(
     channel: Res<KotoEntityReceiver<UpdateColorMaterial>>,
-    query: Query<&MeshMaterial2d<ColorMaterial>>,
+    query: Query<EntityMaterials>,
     asset_server: Res<AssetServer>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    mut gradients: ResMut<Assets<GradientMaterial>>,
+    mut commands: Commands,
 ) {
+    let _span = info_span!("koto_channel", channel = "UpdateColorMaterial").entered();
+
+    // Gradients are switched on or off after all events have been received,
+    // with `None` restoring the entity's color material.
+    let mut pending_gradients: HashMap<Entity, Option<GradientMaterial>> = HashMap::default();
+
+    // Shared materials are copied before being modified,
+    // with the entities being given their copies after all events have been received.
+    let mut forked: HashMap<Entity, Handle<ColorMaterial>> = HashMap::default();
+
+    // Returns the entity's color material handle, and its gradient if it has one
+    let get_materials = |entity,
+                         pending: &HashMap<Entity, Option<GradientMaterial>>,
+                         forked: &HashMap<Entity, Handle<ColorMaterial>>,
+                         gradients: &Assets<GradientMaterial>| {
+        let (color, gradient, suspended, _) = query.get(entity).ok()?;
+        let color = forked
+            .get(&entity)
+            .cloned()
+            .or_else(|| color.map(|color| color.0.clone()))
+            .or_else(|| suspended.map(|s| s.0.clone()))?;
+        let gradient = match pending.get(&entity) {
+            Some(pending) => pending.clone(),
+            None => gradient.and_then(|gradient| gradients.get(gradient.id()).cloned()),
+        };
+        Some((color, gradient))
+    };
+
     while let Some(event) = channel.receive() {
-        let handle = query.get(event.entity.get()).unwrap();
-        let material = materials.get_mut(handle.id()).unwrap();
+        let entity = event.entity.get();
+
+        // Materials are copied before the entity's own material is borrowed
+        let source_materials = match &event.event {
+            UpdateColorMaterial::CopyFrom(source) => {
+                get_materials(source.get(), &pending_gradients, &forked, &gradients).and_then(
+                    |(color, gradient)| Some((materials.get(color.id())?.clone(), gradient)),
+                )
+            }
+            _ => None,
+        };
+
+        // The entity may have been despawned or returned to the entity pool
+        let Some((mut color_handle, gradient)) =
+            get_materials(entity, &pending_gradients, &forked, &gradients)
+        else {
+            continue;
+        };
+
+        // Tweens modify the material while they're running,
+        // so shared materials are copied when the tween starts.
+        let modifies_material = !matches!(
+            event.event,
+            UpdateColorMaterial::Gradient(_) | UpdateColorMaterial::UvTransform(_)
+        );
+        let is_shared = query.get(entity).is_ok_and(|(.., is_shared)| is_shared);
+        if modifies_material && is_shared && !forked.contains_key(&entity) {
+            let Some(shared) = materials.get(color_handle.id()).cloned() else {
+                continue;
+            };
+            color_handle = materials.add(shared);
+            forked.insert(entity, color_handle.clone());
+        }
+
+        let Some(material) = materials.get_mut(color_handle.id()) else {
+            continue;
+        };
+
         match event.event {
-            UpdateColorMaterial::Color(color) => material.color = color,
+            UpdateColorMaterial::Color(color) => {
+                material.color = color;
+                pending_gradients.insert(entity, None);
+                commands.entity(entity).remove::<ColorTween>();
+            }
             UpdateColorMaterial::Alpha(alpha) => {
                 material.color.set_alpha(alpha);
+                commands.entity(entity).remove::<ColorTween>();
+                if let Some(mut gradient) = gradient {
+                    gradient.set_alpha(alpha);
+                    pending_gradients.insert(entity, Some(gradient));
+                }
             }
             UpdateColorMaterial::SetImagePath(image_path) => {
                 material.texture = image_path.map(|path| asset_server.load(path));
+                pending_gradients.insert(entity, None);
+            }
+            UpdateColorMaterial::CopyFrom(_) => {
+                if let Some((source_material, source_gradient)) = source_materials {
+                    *material = source_material;
+                    pending_gradients.insert(entity, source_gradient);
+                }
+            }
+            UpdateColorMaterial::Gradient(gradient) => {
+                pending_gradients.insert(entity, Some(*gradient));
+            }
+            UpdateColorMaterial::UvTransform(uv_transform) => {
+                commands.entity(entity).insert(uv_transform);
+            }
+            UpdateColorMaterial::Tween(tween) => {
+                commands.entity(entity).insert(tween);
             }
+            UpdateColorMaterial::AlphaMode(alpha_mode) => {
+                material.alpha_mode = alpha_mode;
+            }
+        }
+    }
+
+    for (entity, handle) in &forked {
+        let Ok((color, _, suspended, _)) = query.get(*entity) else {
+            continue;
+        };
+        let Some(mut entity_commands) = commands.get_entity(*entity) else {
+            continue;
+        };
+
+        entity_commands.remove::<SharedColorMaterial>();
+        if color.is_some() {
+            entity_commands.insert(MeshMaterial2d(handle.clone()));
+        }
+        if suspended.is_some() {
+            entity_commands.insert(SuspendedColorMaterial(handle.clone()));
+        }
+    }
+
+    for (entity, pending) in pending_gradients {
+        let Ok((color, gradient, suspended, _)) = query.get(entity) else {
+            continue;
+        };
+        // The entity's color material, either active or suspended
+        let color = forked
+            .get(&entity)
+            .cloned()
+            .or_else(|| color.map(|color| color.0.clone()))
+            .or_else(|| suspended.map(|suspended| suspended.0.clone()));
+        let Some(mut entity_commands) = commands.get_entity(entity) else {
+            continue;
+        };
+
+        match (pending, gradient) {
+            (Some(pending), Some(gradient)) => {
+                if let Some(gradient) = gradients.get_mut(gradient.id()) {
+                    *gradient = pending;
+                }
+            }
+            (Some(pending), None) => {
+                entity_commands.insert(MeshMaterial2d(gradients.add(pending)));
+                if let Some(color) = color {
+                    entity_commands
+                        .remove::<MeshMaterial2d<ColorMaterial>>()
+                        .insert(SuspendedColorMaterial(color));
+                }
+            }
+            (None, Some(_)) => {
+                entity_commands.remove::<MeshMaterial2d<GradientMaterial>>();
+                if let Some(color) = color {
+                    entity_commands
+                        .remove::<SuspendedColorMaterial>()
+                        .insert(MeshMaterial2d(color));
+                }
+            }
+            (None, None) => {}
         }
     }
 }
@@ -132,4 +762,288 @@ pub enum UpdateColorMaterial {
     Alpha(f32),
     /// Sets the material's image path
     SetImagePath(Option<String>),
+    /// Copies the properties of another entity's material
+    CopyFrom(KotoEntityMapping),
+    /// Replaces the entity's color material with a gradient
+    Gradient(Box<GradientMaterial>),
+    /// Sets the transform that's applied to the UV coordinates of the entity's mesh
+    UvTransform(UvTransform),
+    /// Animates the material's color or alpha, replacing any tween that's already running
+    ///
+    /// Setting the material's color or alpha cancels the tween.
+    Tween(ColorTween),
+    /// Sets the material's alpha mode
+    ///
+    /// Masked materials are rendered as opaque cutouts, which avoids the sorting problems of
+    /// overlapping blended entities. Gradients are always blended.
+    AlphaMode(AlphaMode2d),
+}
+
+/// An offset and scale that are applied to the UV coordinates of an entity's mesh
+///
+/// The transform is applied to the meshes of shapes by the [KotoShapePlugin], with the mesh's
+/// original UV coordinates being kept in a separate vertex attribute. Images are sampled with
+/// Bevy's default sampler, so tiling an image with a UV scale larger than 1 requires a repeating
+/// address mode to be set in [ImagePlugin::default_sampler].
+#[derive(Clone, Copy, Component, Debug, PartialEq)]
+pub struct UvTransform {
+    /// The offset that's added to the scaled UV coordinates
+    pub offset: Vec2,
+    /// The scale that's applied to the UV coordinates
+    pub scale: Vec2,
+}
+
+impl Default for UvTransform {
+    fn default() -> Self {
+        Self {
+            offset: Vec2::ZERO,
+            scale: Vec2::ONE,
+        }
+    }
+}
+
+/// The number of colors that a [GradientMaterial] samples from its [ColorGradient]
+///
+/// The material's shader interpolates linearly between the samples.
+pub const GRADIENT_MATERIAL_SAMPLES: usize = 16;
+
+/// A 2D material that fills a mesh with a linear or radial gradient
+///
+/// The gradient is based on the mesh's UV coordinates, so it covers the whole of the mesh.
+#[derive(Asset, AsBindGroup, Clone, Debug, TypePath)]
+pub struct GradientMaterial {
+    #[uniform(0)]
+    colors: [LinearRgba; GRADIENT_MATERIAL_SAMPLES],
+    #[uniform(0)]
+    direction: Vec2,
+    #[uniform(0)]
+    is_radial: u32,
+}
+
+impl GradientMaterial {
+    /// A gradient that goes from `color_a` to `color_b` in the direction of the given angle
+    ///
+    /// The angle is in radians, with 0 producing a gradient from left to right.
+    pub fn linear(color_a: Color, color_b: Color, angle: f32) -> Self {
+        Self::linear_from(&two_color_gradient(color_a, color_b), angle)
+    }
+
+    /// A gradient that goes from `color_a` at the center of the mesh to `color_b` at its edges
+    pub fn radial(color_a: Color, color_b: Color) -> Self {
+        Self::radial_from(&two_color_gradient(color_a, color_b))
+    }
+
+    /// A linear gradient that follows the [ColorGradient] in the direction of the given angle
+    ///
+    /// The gradient's range from 0 to 1 covers the mesh.
+    pub fn linear_from(gradient: &ColorGradient, angle: f32) -> Self {
+        // UV coordinates increase downwards, so the direction's Y component is inverted.
+        // The direction is then scaled so that the gradient reaches the corners of the UV square.
+        let direction = Vec2::new(angle.cos(), -angle.sin());
+        Self {
+            colors: gradient_samples(gradient),
+            direction: direction / direction.abs().element_sum(),
+            is_radial: 0,
+        }
+    }
+
+    /// A radial gradient that follows the [ColorGradient] from the center of the mesh to its edges
+    pub fn radial_from(gradient: &ColorGradient) -> Self {
+        Self {
+            colors: gradient_samples(gradient),
+            direction: Vec2::ZERO,
+            is_radial: 1,
+        }
+    }
+
+    fn set_alpha(&mut self, alpha: f32) {
+        for color in self.colors.iter_mut() {
+            color.set_alpha(alpha);
+        }
+    }
 }
+
+// Two-color gradients are interpolated in linear RGB, matching the blending of the GPU
+fn two_color_gradient(color_a: Color, color_b: Color) -> ColorGradient {
+    ColorGradient::new([(0.0, color_a), (1.0, color_b)]).with_space(GradientSpace::LinearRgb)
+}
+
+fn gradient_samples(gradient: &ColorGradient) -> [LinearRgba; GRADIENT_MATERIAL_SAMPLES] {
+    let mut result = [LinearRgba::NONE; GRADIENT_MATERIAL_SAMPLES];
+    for (sample, color) in result
+        .iter_mut()
+        .zip(gradient.sample_many(GRADIENT_MATERIAL_SAMPLES))
+    {
+        *sample = color.into();
+    }
+    result
+}
+
+impl Material2d for GradientMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "embedded://bevy_koto/gradient.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Blend
+    }
+}
+
+// Advances the entities' color tweens, removing them once they've finished
+fn update_color_tweens(
+    mut query: Query<(
+        Entity,
+        &mut ColorTween,
+        ActiveOrSuspendedMaterial,
+        Option<&MeshMaterial2d<GradientMaterial>>,
+    )>,
+    koto_time: Res<KotoTime>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut gradients: ResMut<Assets<GradientMaterial>>,
+    mut commands: Commands,
+) {
+    for (entity, mut tween, (material, suspended), gradient) in query.iter_mut() {
+        let Some(material) = material
+            .map(|material| material.id())
+            .or_else(|| suspended.map(|suspended| suspended.0.id()))
+            .and_then(|id| materials.get_mut(id))
+        else {
+            continue;
+        };
+
+        let start = *tween.start.get_or_insert(material.color);
+        tween.elapsed += koto_time.delta() as f32;
+        let amount = if tween.duration > 0.0 {
+            tween
+                .easing
+                .apply((tween.elapsed / tween.duration).min(1.0))
+        } else {
+            1.0
+        };
+
+        match tween.target {
+            TweenTarget::Color(target) => {
+                material.color = GradientSpace::Oklab.mix(start, target, amount);
+            }
+            TweenTarget::Alpha(target) => {
+                let alpha = start.alpha() + (target - start.alpha()) * amount;
+                material.color.set_alpha(alpha);
+                if let Some(gradient) = gradient.and_then(|g| gradients.get_mut(g.id())) {
+                    gradient.set_alpha(alpha);
+                }
+            }
+        }
+
+        if tween.elapsed >= tween.duration {
+            commands.entity(entity).remove::<ColorTween>();
+        }
+    }
+}
+
+/// An animation of an entity's [ColorMaterial], see [UpdateColorMaterial::Tween]
+///
+/// Colors are interpolated in the Oklab color space.
+#[derive(Clone, Component, Debug)]
+pub struct ColorTween {
+    target: TweenTarget,
+    duration: f32,
+    easing: Easing,
+    elapsed: f32,
+    // The material's color when the tween started, captured on the tween's first update
+    start: Option<Color>,
+}
+
+impl ColorTween {
+    /// Makes a tween that animates the material's color towards `target`
+    pub fn color(target: Color, duration: f32, easing: Easing) -> Self {
+        Self::new(TweenTarget::Color(target), duration, easing)
+    }
+
+    /// Makes a tween that animates the material's alpha value towards `target`
+    pub fn alpha(target: f32, duration: f32, easing: Easing) -> Self {
+        Self::new(TweenTarget::Alpha(target), duration, easing)
+    }
+
+    fn new(target: TweenTarget, duration: f32, easing: Easing) -> Self {
+        Self {
+            target,
+            duration: duration.max(0.0),
+            easing,
+            elapsed: 0.0,
+            start: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum TweenTarget {
+    Color(Color),
+    Alpha(f32),
+}
+
+/// An easing curve that's applied to the progress of a [ColorTween]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Easing {
+    /// Constant speed
+    #[default]
+    Linear,
+    /// Starts slowly and speeds up
+    EaseIn,
+    /// Starts quickly and slows down
+    EaseOut,
+    /// Starts and ends slowly
+    EaseInOut,
+}
+
+impl Easing {
+    /// Returns the easing with the given name
+    ///
+    /// The available names are `linear`, `ease_in`, `ease_out`, and `ease_in_out`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let result = match name {
+            "linear" => Self::Linear,
+            "ease_in" => Self::EaseIn,
+            "ease_out" => Self::EaseOut,
+            "ease_in_out" => Self::EaseInOut,
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    /// Maps a linear progress value in the range `0..=1` onto the easing curve
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t * t,
+            Self::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+// Gets an easing from an optional name that was passed to a Koto function
+#[cfg(feature = "shape")]
+#[allow(clippy::result_large_err)]
+pub(crate) fn koto_to_easing(fn_name: &str, name: Option<&KString>) -> KotoResult<Easing> {
+    match name {
+        Some(name) => match Easing::from_name(name) {
+            Some(easing) => Ok(easing),
+            None => runtime_error!(
+                "{fn_name}: Unknown easing '{name}', \
+                 expected 'linear', 'ease_in', 'ease_out', or 'ease_in_out'"
+            ),
+        },
+        None => Ok(Easing::default()),
+    }
+}
+
+/// A marker for entities whose [ColorMaterial] is shared with other entities
+///
+/// The material is copied when it's first modified by an [UpdateColorMaterial] event, with the
+/// entity then being given its own material.
+#[derive(Component)]
+pub struct SharedColorMaterial;
+
+/// The color material of an entity that's currently using a [GradientMaterial]
+#[derive(Component)]
+pub(crate) struct SuspendedColorMaterial(pub(crate) Handle<ColorMaterial>);
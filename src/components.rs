@@ -0,0 +1,248 @@
+//! Reflection-based access to the components of Koto entities
+
+use crate::{
+    prelude::*,
+    reflect::{apply_koto_to_reflect, reflect_to_koto},
+    runtime::ScriptHook,
+};
+use bevy::{
+    ecs::world::{EntityRef, EntityWorldMut},
+    prelude::*,
+    reflect::TypePath,
+};
+use koto::prelude::*;
+
+/// Gives scripts access to an allowlist of components on Koto entities
+///
+/// Koto entities (e.g. shapes and text) provide the following methods:
+/// - `get_component(name)`: Returns the component's value, or `null` if the entity doesn't have
+///   the component.
+/// - `set_component(name, value)`: Sets the component's value. If the value is a map then only the
+///   fields that are present in the map are modified.
+///
+/// Components are referred to by their short type name, and need to be added to the allowlist with
+/// [KotoComponentsPlugin::allow]. Values are converted using [reflect_to_koto] and
+/// [apply_koto_to_reflect].
+///
+/// The values returned by `get_component` are snapshots that are taken at the start of the
+/// [KotoSchedule], so components won't be available until the frame after the entity has been
+/// spawned. Changes made with `set_component` are applied in the [KotoUpdate::PostUpdate] system
+/// set.
+///
+/// When an allowed component is added to or removed from a Koto entity (e.g. by a gameplay system
+/// in the app), the script's exported `on_component_added` or `on_component_removed` function is
+/// called with the entity and the component's name, e.g.
+///
+/// ```koto
+/// export on_component_added = |entity, name|
+///   if name == 'Damaged'
+///     entity.set_color 1, 0, 0
+/// ```
+///
+/// Changes are detected with observers, and are passed to the script during the
+/// [KotoUpdate::PreUpdate] system set.
+#[derive(Default)]
+pub struct KotoComponentsPlugin {
+    allowed: Vec<AllowedComponent>,
+}
+
+impl KotoComponentsPlugin {
+    /// Allows scripts to access components of type `C`
+    #[must_use]
+    pub fn allow<C>(mut self) -> Self
+    where
+        C: Component + Reflect + TypePath,
+    {
+        self.allowed.push(AllowedComponent {
+            name: C::short_type_path(),
+            snapshot: snapshot_component::<C>,
+            update: update_component::<C>,
+            add_observers: add_component_observers::<C>,
+        });
+        self
+    }
+}
+
+impl Plugin for KotoComponentsPlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoEntityPlugin>());
+
+        for component in &self.allowed {
+            (component.add_observers)(app);
+        }
+
+        app.insert_resource(AllowedComponents(self.allowed.clone()))
+            .init_resource::<PendingComponentChanges>()
+            .add_systems(
+                KotoSchedule,
+                (
+                    snapshot_components.in_set(KotoUpdate::Register),
+                    run_component_hooks.in_set(KotoUpdate::PreUpdate),
+                    update_components.in_set(KotoUpdate::PostUpdate),
+                ),
+            );
+    }
+}
+
+#[derive(Clone)]
+struct AllowedComponent {
+    name: &'static str,
+    snapshot: fn(EntityRef) -> Option<KValue>,
+    update: fn(EntityWorldMut, &KValue) -> Result<(), String>,
+    add_observers: fn(&mut App),
+}
+
+// Component changes that are waiting to be passed to the script
+#[derive(Resource, Default)]
+struct PendingComponentChanges(Vec<ComponentChange>);
+
+struct ComponentChange {
+    hook: ScriptHook,
+    object: KObject,
+    name: &'static str,
+}
+
+#[derive(Resource)]
+struct AllowedComponents(Vec<AllowedComponent>);
+
+fn snapshot_component<C: Component + Reflect>(entity: EntityRef) -> Option<KValue> {
+    entity
+        .get::<C>()
+        .map(|component| reflect_to_koto(component.as_partial_reflect()))
+}
+
+fn update_component<C: Component + Reflect>(
+    mut entity: EntityWorldMut,
+    value: &KValue,
+) -> Result<(), String> {
+    match entity.get_mut::<C>() {
+        Some(mut component) => apply_koto_to_reflect(value, component.as_partial_reflect_mut()),
+        None => Err("the entity doesn't have the component".into()),
+    }
+}
+
+fn add_component_observers<C: Component + TypePath>(app: &mut App) {
+    app.add_observer(
+        |trigger: Trigger<OnAdd, C>,
+         query: Query<(&KotoEntity, Option<&Name>)>,
+         registry: Res<KotoEntityRegistry>,
+         mut pending: ResMut<PendingComponentChanges>| {
+            queue_component_change(
+                ScriptHook::OnComponentAdded,
+                C::short_type_path(),
+                trigger.entity(),
+                &query,
+                &registry,
+                &mut pending,
+            );
+        },
+    )
+    .add_observer(
+        |trigger: Trigger<OnRemove, C>,
+         query: Query<(&KotoEntity, Option<&Name>)>,
+         registry: Res<KotoEntityRegistry>,
+         mut pending: ResMut<PendingComponentChanges>| {
+            queue_component_change(
+                ScriptHook::OnComponentRemoved,
+                C::short_type_path(),
+                trigger.entity(),
+                &query,
+                &registry,
+                &mut pending,
+            );
+        },
+    );
+}
+
+fn queue_component_change(
+    hook: ScriptHook,
+    name: &'static str,
+    entity: Entity,
+    query: &Query<(&KotoEntity, Option<&Name>)>,
+    registry: &KotoEntityRegistry,
+    pending: &mut PendingComponentChanges,
+) {
+    // Entities that the script no longer refers to (e.g. entities that are being despawned)
+    // aren't passed to the script.
+    if let Ok((koto_entity, name_component)) = query.get(entity) {
+        if koto_entity.is_active && registry.is_referenced_by_script(koto_entity, name_component) {
+            pending.0.push(ComponentChange {
+                hook,
+                object: koto_entity.object.clone(),
+                name,
+            });
+        }
+    }
+}
+
+fn run_component_hooks(
+    mut koto: ResMut<KotoRuntime>,
+    mut pending: ResMut<PendingComponentChanges>,
+    mut script_error: EventWriter<KotoScriptError>,
+) {
+    for ComponentChange { hook, object, name } in pending.0.drain(..) {
+        if !koto.is_ready() {
+            continue;
+        }
+
+        if let Err(error) = koto.run_hook(hook, &[object.into(), name.into()]) {
+            let phase = match hook {
+                ScriptHook::OnComponentRemoved => ScriptPhase::OnComponentRemoved,
+                _ => ScriptPhase::OnComponentAdded,
+            };
+            let error = koto.make_error(phase, error);
+            error!("{error}");
+            script_error.send(error);
+        }
+    }
+}
+
+fn koto_entities(world: &mut World) -> Vec<(Entity, KotoEntityMapping)> {
+    world
+        .query::<(Entity, &KotoEntity)>()
+        .iter(world)
+        .map(|(entity, koto_entity)| (entity, koto_entity.entity.clone()))
+        .collect()
+}
+
+fn snapshot_components(world: &mut World) {
+    let entities = koto_entities(world);
+    let allowed = world.resource::<AllowedComponents>();
+
+    for (entity, mapping) in entities {
+        let Ok(entity) = world.get_entity(entity) else {
+            continue;
+        };
+
+        for component in allowed.0.iter() {
+            mapping.set_component_snapshot(component.name, (component.snapshot)(entity));
+        }
+    }
+}
+
+fn update_components(world: &mut World) {
+    let entities = koto_entities(world);
+
+    for (entity, mapping) in entities {
+        for (name, value) in mapping.take_pending_components() {
+            let Some(component) = world
+                .resource::<AllowedComponents>()
+                .0
+                .iter()
+                .find(|component| component.name == name)
+                .cloned()
+            else {
+                error!("Unable to set '{name}', it isn't an allowed component");
+                continue;
+            };
+
+            let Ok(entity) = world.get_entity_mut(entity) else {
+                continue;
+            };
+
+            if let Err(error) = (component.update)(entity, &value) {
+                error!("Failed to set component '{name}': {error}");
+            }
+        }
+    }
+}
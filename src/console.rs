@@ -0,0 +1,68 @@
+//! A console for evaluating Koto code in the running script
+
+use crate::prelude::*;
+use bevy::prelude::*;
+
+/// Evaluates lines of Koto code in the context of the running script
+///
+/// Code is sent to the console with [KotoConsoleInput] events, and is evaluated with
+/// [KotoRuntime::evaluate], giving it access to the script's exports, and to the script's user
+/// data as `state`. E.g. `state.speed = 2` would modify the script's state without needing to
+/// reload the script.
+///
+/// The result of each evaluation is sent as a [KotoConsoleOutput] event, and is also logged.
+/// Output from `print` is sent as [KotoScriptOutput] events as usual.
+///
+/// Input is evaluated in the [KotoUpdate::PreUpdate] system set, so that changes are visible to
+/// the script's next update.
+pub struct KotoConsolePlugin;
+
+impl Plugin for KotoConsolePlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+
+        app.add_event::<KotoConsoleInput>()
+            .add_event::<KotoConsoleOutput>()
+            .add_systems(
+                KotoSchedule,
+                evaluate_console_input.in_set(KotoUpdate::PreUpdate),
+            );
+    }
+}
+
+/// Event containing Koto code that should be evaluated by the [KotoConsolePlugin]
+#[derive(Event, Clone, Debug)]
+pub struct KotoConsoleInput(pub String);
+
+/// Event containing the result of evaluating a [KotoConsoleInput]
+#[derive(Event, Clone, Debug)]
+pub struct KotoConsoleOutput {
+    /// The code that was evaluated
+    pub input: String,
+    /// The evaluated value rendered as a string, or an error message
+    pub result: Result<String, String>,
+}
+
+fn evaluate_console_input(
+    mut koto: ResMut<KotoRuntime>,
+    mut console_input: EventReader<KotoConsoleInput>,
+    mut console_output: EventWriter<KotoConsoleOutput>,
+) {
+    for KotoConsoleInput(input) in console_input.read() {
+        let _span = info_span!("koto_console").entered();
+
+        let result = koto
+            .evaluate(input)
+            .map(|value| koto.value_to_string(&value));
+
+        match &result {
+            Ok(value) => info!("> {input}\n{value}"),
+            Err(error) => warn!("> {input}\n{error}"),
+        }
+
+        console_output.send(KotoConsoleOutput {
+            input: input.clone(),
+            result,
+        });
+    }
+}
@@ -0,0 +1,196 @@
+//! Typed conversions for calling script functions from Rust
+//!
+//! See [KotoRuntime::call_exported](crate::runtime::KotoRuntime::call_exported).
+
+use koto::prelude::*;
+
+/// Converts a Rust value into the arguments for a call to a Koto function
+///
+/// Implemented for `()` and for tuples of up to 8 values that can be converted into [KValue]s,
+/// e.g. `(1.0, "hello")`. Single arguments need to be wrapped in a tuple, e.g. `(42,)`.
+pub trait IntoKotoArgs {
+    /// Converts the value into a list of arguments
+    fn into_koto_args(self) -> Vec<KValue>;
+}
+
+impl IntoKotoArgs for () {
+    fn into_koto_args(self) -> Vec<KValue> {
+        Vec::new()
+    }
+}
+
+impl IntoKotoArgs for Vec<KValue> {
+    fn into_koto_args(self) -> Vec<KValue> {
+        self
+    }
+}
+
+macro_rules! impl_into_koto_args {
+    ($($name:ident),+) => {
+        impl<$($name),+> IntoKotoArgs for ($($name,)+)
+        where
+            $($name: Into<KValue>),+
+        {
+            #[allow(non_snake_case)]
+            fn into_koto_args(self) -> Vec<KValue> {
+                let ($($name,)+) = self;
+                vec![$($name.into()),+]
+            }
+        }
+    };
+}
+
+impl_into_koto_args!(A);
+impl_into_koto_args!(A, B);
+impl_into_koto_args!(A, B, C);
+impl_into_koto_args!(A, B, C, D);
+impl_into_koto_args!(A, B, C, D, E);
+impl_into_koto_args!(A, B, C, D, E, F);
+impl_into_koto_args!(A, B, C, D, E, F, G);
+impl_into_koto_args!(A, B, C, D, E, F, G, H);
+
+/// Converts the value returned from a Koto function into a Rust value
+///
+/// `None` is returned when the value doesn't have the expected type.
+pub trait FromKotoValue: Sized {
+    /// A description of the expected Koto type, used in error messages
+    const EXPECTED: &'static str;
+
+    /// Attempts to convert the Koto value into the implementing type
+    fn from_koto_value(value: KValue) -> Option<Self>;
+}
+
+impl FromKotoValue for KValue {
+    const EXPECTED: &'static str = "any value";
+
+    fn from_koto_value(value: KValue) -> Option<Self> {
+        Some(value)
+    }
+}
+
+impl FromKotoValue for () {
+    const EXPECTED: &'static str = "null";
+
+    fn from_koto_value(value: KValue) -> Option<Self> {
+        matches!(value, KValue::Null).then_some(())
+    }
+}
+
+impl FromKotoValue for bool {
+    const EXPECTED: &'static str = "a Bool";
+
+    fn from_koto_value(value: KValue) -> Option<Self> {
+        match value {
+            KValue::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_from_koto_number {
+    ($($t:ty),+) => {
+        $(
+            impl FromKotoValue for $t {
+                const EXPECTED: &'static str = "a Number";
+
+                fn from_koto_value(value: KValue) -> Option<Self> {
+                    match value {
+                        KValue::Number(n) => Some(n.into()),
+                        _ => None,
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_from_koto_number!(f32, f64, i32, i64, u32, u64, usize);
+
+impl FromKotoValue for String {
+    const EXPECTED: &'static str = "a String";
+
+    fn from_koto_value(value: KValue) -> Option<Self> {
+        match value {
+            KValue::Str(s) => Some(s.to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl FromKotoValue for KString {
+    const EXPECTED: &'static str = "a String";
+
+    fn from_koto_value(value: KValue) -> Option<Self> {
+        match value {
+            KValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl FromKotoValue for KList {
+    const EXPECTED: &'static str = "a List";
+
+    fn from_koto_value(value: KValue) -> Option<Self> {
+        match value {
+            KValue::List(l) => Some(l),
+            _ => None,
+        }
+    }
+}
+
+impl FromKotoValue for KMap {
+    const EXPECTED: &'static str = "a Map";
+
+    fn from_koto_value(value: KValue) -> Option<Self> {
+        match value {
+            KValue::Map(m) => Some(m),
+            _ => None,
+        }
+    }
+}
+
+impl<T: FromKotoValue> FromKotoValue for Option<T> {
+    const EXPECTED: &'static str = T::EXPECTED;
+
+    fn from_koto_value(value: KValue) -> Option<Self> {
+        match value {
+            KValue::Null => Some(None),
+            value => T::from_koto_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: FromKotoValue> FromKotoValue for Vec<T> {
+    const EXPECTED: &'static str = "a List or Tuple";
+
+    fn from_koto_value(value: KValue) -> Option<Self> {
+        match value {
+            KValue::List(l) => l.data().iter().cloned().map(T::from_koto_value).collect(),
+            KValue::Tuple(t) => t.iter().cloned().map(T::from_koto_value).collect(),
+            _ => None,
+        }
+    }
+}
+
+/// An error that can occur when calling a script function with
+/// [KotoRuntime::call_exported](crate::runtime::KotoRuntime::call_exported)
+#[derive(Debug, thiserror::Error)]
+pub enum KotoCallError {
+    /// The script doesn't export a function with the given name
+    #[error("'{0}' isn't exported from the script")]
+    MissingFunction(String),
+    /// An error occurred while running the function
+    #[error(transparent)]
+    Runtime(#[from] koto::Error),
+    /// The function returned a value with an unexpected type
+    #[error("expected {expected} as the result of '{function}', found {found}")]
+    UnexpectedResult {
+        /// The name of the function
+        function: String,
+        /// A description of the expected type
+        expected: &'static str,
+        /// The type of the returned value
+        found: String,
+    },
+}
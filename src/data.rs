@@ -0,0 +1,264 @@
+//! Support for working with structured data formats in Koto scripts
+
+use crate::prelude::*;
+use bevy::prelude::*;
+use koto::{prelude::*, runtime::Result as KotoResult};
+use serde::{
+    de::{Deserializer, MapAccess, SeqAccess, Visitor},
+    ser::{self, SerializeMap, SerializeSeq, Serializer},
+    Deserialize, Serialize,
+};
+use std::fmt;
+
+/// Structured data support for bevy_koto
+///
+/// The plugin adds `json`, `toml`, and `ron` modules to Koto's prelude, each with the following
+/// functions:
+/// - `from_string(string)`: Parses the string and returns the resulting value.
+/// - `to_string(value)`: Serializes the value into a string.
+///
+/// Null, bools, numbers, strings, lists, tuples, and maps with string keys can be serialized.
+///
+/// The contents of config files from the assets folder can be loaded with `io.read_to_string`,
+/// e.g. `json.from_string io.read_to_string 'assets/config.json'`.
+pub struct KotoDataPlugin;
+
+impl Plugin for KotoDataPlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+
+        app.register_koto_module("json", make_json_module)
+            .register_koto_module("toml", make_toml_module)
+            .register_koto_module("ron", make_ron_module);
+    }
+}
+
+/// Converts a Koto value into a JSON value
+pub fn kvalue_to_json(value: &KValue) -> Result<serde_json::Value, serde_json::Error> {
+    serde_json::to_value(SerializableKValue(value))
+}
+
+/// Converts a JSON value into a Koto value
+pub fn json_to_kvalue(value: &serde_json::Value) -> KValue {
+    use serde_json::Value;
+
+    match value {
+        Value::Null => KValue::Null,
+        Value::Bool(b) => (*b).into(),
+        Value::Number(n) => match n.as_i64() {
+            Some(n) => n.into(),
+            None => n.as_f64().unwrap_or(f64::NAN).into(),
+        },
+        Value::String(s) => s.as_str().into(),
+        Value::Array(values) => {
+            KList::with_data(values.iter().map(json_to_kvalue).collect()).into()
+        }
+        Value::Object(entries) => {
+            let map = KMap::with_capacity(entries.len());
+            for (key, value) in entries {
+                map.insert(key.as_str(), json_to_kvalue(value));
+            }
+            map.into()
+        }
+    }
+}
+
+/// A wrapper that allows a [KValue] to be serialized with serde
+pub struct SerializableKValue<'a>(pub &'a KValue);
+
+impl Serialize for SerializableKValue<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            KValue::Null => serializer.serialize_unit(),
+            KValue::Bool(b) => serializer.serialize_bool(*b),
+            KValue::Number(KNumber::I64(n)) => serializer.serialize_i64(*n),
+            KValue::Number(KNumber::F64(n)) => serializer.serialize_f64(*n),
+            KValue::Str(s) => serializer.serialize_str(s),
+            KValue::List(list) => serialize_seq(list.data().iter(), serializer),
+            KValue::Tuple(tuple) => serialize_seq(tuple.iter(), serializer),
+            KValue::Map(map) => {
+                let data = map.data();
+                let mut result = serializer.serialize_map(Some(data.len()))?;
+                for (key, value) in data.iter() {
+                    let KValue::Str(key) = key.value() else {
+                        return Err(ser::Error::custom(format!(
+                            "unable to serialize a map key of type {}",
+                            key.value().type_as_string()
+                        )));
+                    };
+                    result.serialize_entry(key.as_str(), &SerializableKValue(value))?;
+                }
+                result.end()
+            }
+            unexpected => Err(ser::Error::custom(format!(
+                "unable to serialize a value of type {}",
+                unexpected.type_as_string()
+            ))),
+        }
+    }
+}
+
+fn serialize_seq<'a, S: Serializer>(
+    values: impl ExactSizeIterator<Item = &'a KValue>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut result = serializer.serialize_seq(Some(values.len()))?;
+    for value in values {
+        result.serialize_element(&SerializableKValue(value))?;
+    }
+    result.end()
+}
+
+/// A wrapper that allows a [KValue] to be deserialized with serde
+pub struct DeserializedKValue(pub KValue);
+
+impl<'de> Deserialize<'de> for DeserializedKValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(KValueVisitor).map(Self)
+    }
+}
+
+struct KValueVisitor;
+
+impl<'de> Visitor<'de> for KValueVisitor {
+    type Value = KValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a value that can be represented in Koto")
+    }
+
+    fn visit_unit<E>(self) -> Result<KValue, E> {
+        Ok(KValue::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<KValue, E> {
+        Ok(KValue::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<KValue, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<KValue, E> {
+        Ok(b.into())
+    }
+
+    fn visit_i64<E>(self, n: i64) -> Result<KValue, E> {
+        Ok(n.into())
+    }
+
+    fn visit_u64<E>(self, n: u64) -> Result<KValue, E> {
+        Ok(match i64::try_from(n) {
+            Ok(n) => n.into(),
+            Err(_) => (n as f64).into(),
+        })
+    }
+
+    fn visit_f64<E>(self, n: f64) -> Result<KValue, E> {
+        Ok(n.into())
+    }
+
+    fn visit_char<E>(self, c: char) -> Result<KValue, E> {
+        Ok(c.to_string().into())
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<KValue, E> {
+        Ok(s.into())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<KValue, A::Error> {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(DeserializedKValue(value)) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(KList::with_data(values.into()).into())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut entries: A) -> Result<KValue, A::Error> {
+        let map = KMap::with_capacity(entries.size_hint().unwrap_or_default());
+        while let Some((key, DeserializedKValue(value))) = entries.next_entry::<String, _>()? {
+            map.insert(key.as_str(), value);
+        }
+        Ok(map.into())
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn make_json_module() -> KMap {
+    let module = KMap::with_type("json");
+
+    module.add_fn("from_string", |ctx| match ctx.args() {
+        [KValue::Str(s)] => match serde_json::from_str::<DeserializedKValue>(s) {
+            Ok(value) => Ok(value.0),
+            Err(error) => runtime_error!("json.from_string: {error}"),
+        },
+        unexpected => unexpected_args("a String", unexpected),
+    });
+
+    module.add_fn("to_string", |ctx| match ctx.args() {
+        [value] => to_string_result(
+            "json.to_string",
+            serde_json::to_string_pretty(&SerializableKValue(value)),
+        ),
+        unexpected => unexpected_args("a value", unexpected),
+    });
+
+    module
+}
+
+#[allow(clippy::result_large_err)]
+fn make_toml_module() -> KMap {
+    let module = KMap::with_type("toml");
+
+    module.add_fn("from_string", |ctx| match ctx.args() {
+        [KValue::Str(s)] => match toml::from_str::<DeserializedKValue>(s) {
+            Ok(value) => Ok(value.0),
+            Err(error) => runtime_error!("toml.from_string: {error}"),
+        },
+        unexpected => unexpected_args("a String", unexpected),
+    });
+
+    module.add_fn("to_string", |ctx| match ctx.args() {
+        [value] => to_string_result(
+            "toml.to_string",
+            toml::to_string(&SerializableKValue(value)),
+        ),
+        unexpected => unexpected_args("a value", unexpected),
+    });
+
+    module
+}
+
+#[allow(clippy::result_large_err)]
+fn make_ron_module() -> KMap {
+    let module = KMap::with_type("ron");
+
+    module.add_fn("from_string", |ctx| match ctx.args() {
+        [KValue::Str(s)] => match ron::from_str::<DeserializedKValue>(s) {
+            Ok(value) => Ok(value.0),
+            Err(error) => runtime_error!("ron.from_string: {error}"),
+        },
+        unexpected => unexpected_args("a String", unexpected),
+    });
+
+    module.add_fn("to_string", |ctx| match ctx.args() {
+        [value] => to_string_result(
+            "ron.to_string",
+            ron::ser::to_string_pretty(&SerializableKValue(value), default()),
+        ),
+        unexpected => unexpected_args("a value", unexpected),
+    });
+
+    module
+}
+
+#[allow(clippy::result_large_err)]
+fn to_string_result<E: fmt::Display>(
+    function_name: &str,
+    result: Result<String, E>,
+) -> KotoResult<KValue> {
+    match result {
+        Ok(s) => Ok(s.into()),
+        Err(error) => runtime_error!("{function_name}: {error}"),
+    }
+}
@@ -0,0 +1,61 @@
+//! Script timing diagnostics for bevy_koto
+
+use crate::{prelude::*, runtime::KotoScriptTimings};
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic},
+    prelude::*,
+};
+
+/// Records the time taken by script calls in Bevy's `DiagnosticsStore`
+///
+/// The following diagnostics are recorded, in milliseconds:
+/// - [KotoDiagnosticsPlugin::COMPILE]: The time taken to compile the most recent script.
+/// - [KotoDiagnosticsPlugin::INITIALIZE]: The time taken to run the script's top-level code,
+///   along with its `setup` and `on_load` functions.
+/// - [KotoDiagnosticsPlugin::UPDATE]: The time taken by the script's `update` function.
+/// - [KotoDiagnosticsPlugin::ENTITY_UPDATE]: The total time taken by the `on_update` functions
+///   of the script's entities.
+///
+/// Measurements are only added when the corresponding calls have been made, so compilation and
+/// initialization measurements are only recorded when a script is loaded.
+///
+/// The diagnostics can be displayed with Bevy's `LogDiagnosticsPlugin`, or in an overlay.
+pub struct KotoDiagnosticsPlugin;
+
+impl KotoDiagnosticsPlugin {
+    /// The time taken to compile a script
+    pub const COMPILE: DiagnosticPath = DiagnosticPath::const_new("koto/compile");
+    /// The time taken to initialize a script
+    pub const INITIALIZE: DiagnosticPath = DiagnosticPath::const_new("koto/initialize");
+    /// The time taken by the script's `update` function
+    pub const UPDATE: DiagnosticPath = DiagnosticPath::const_new("koto/update");
+    /// The time taken by the `on_update` functions of the script's entities
+    pub const ENTITY_UPDATE: DiagnosticPath = DiagnosticPath::const_new("koto/entity_update");
+}
+
+impl Plugin for KotoDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+
+        app.register_diagnostic(Diagnostic::new(Self::COMPILE).with_suffix("ms"))
+            .register_diagnostic(Diagnostic::new(Self::INITIALIZE).with_suffix("ms"))
+            .register_diagnostic(Diagnostic::new(Self::UPDATE).with_suffix("ms"))
+            .register_diagnostic(Diagnostic::new(Self::ENTITY_UPDATE).with_suffix("ms"))
+            .add_systems(Last, record_script_timings);
+    }
+}
+
+fn record_script_timings(mut diagnostics: Diagnostics, mut timings: ResMut<KotoScriptTimings>) {
+    let timings = std::mem::take(&mut *timings);
+
+    for (path, timing) in [
+        (KotoDiagnosticsPlugin::COMPILE, timings.compile),
+        (KotoDiagnosticsPlugin::INITIALIZE, timings.initialize),
+        (KotoDiagnosticsPlugin::UPDATE, timings.update),
+        (KotoDiagnosticsPlugin::ENTITY_UPDATE, timings.entity_update),
+    ] {
+        if let Some(timing) = timing {
+            diagnostics.add_measurement(&path, || timing.as_secs_f64() * 1000.0);
+        }
+    }
+}
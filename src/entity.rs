@@ -1,16 +1,84 @@
 //! Support for mapping Koto objects to Bevy entities
 
-use crate::prelude::*;
-use bevy::prelude::*;
-use koto::prelude::*;
-use parking_lot::RwLock;
-use std::sync::Arc;
+use crate::{prelude::*, runtime::KotoScriptTimings};
+use bevy::{
+    ecs::{component::ComponentId, system::SystemParam, world::DeferredWorld},
+    prelude::*,
+    render::view::RenderLayers,
+    utils::Instant,
+};
+use koto::{prelude::*, ErrorKind};
+use parking_lot::{Mutex, RwLock};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 /// Support for mapping Koto objects to Bevy entities
 ///
 /// Entities with the [KotoEntity] component will be automatically despawned when the script no
 /// longer refers to them.
-pub struct KotoEntityPlugin;
+///
+/// Koto entities can be arranged in a hierarchy with [UpdateKotoEntity::SetParent] and
+/// [UpdateKotoEntity::AddChild], using Bevy's `Parent` and `Children` components so that a child's
+/// transform is relative to its parent's. When an entity is despawned, its Koto children are
+/// detached, and are then despawned separately once the script no longer refers to them. Children
+/// that aren't Koto entities (e.g. entities attached by the app) are despawned recursively along
+/// with their parent.
+///
+/// Entities can be given names with [UpdateKotoEntity::SetName], which inserts Bevy's [Name]
+/// component and records the entity in the [KotoEntityRegistry] resource. The plugin adds an
+/// `entities` module to the Koto prelude, with `entities.find(name)` returning the named entity,
+/// or `null` if no entity has been given the name. Names that are given to Koto entities by the
+/// app are also registered, and entities' names are made available to scripts via
+/// [KotoEntityMapping::name].
+///
+/// Tags can be added to entities with [UpdateKotoEntity::AddTag], which are stored in the entity's
+/// [KotoTag] component. Systems can then find the entities with a given tag via [KotoTagQuery].
+///
+/// Entities can be despawned automatically after a given amount of [KotoTime] has passed with
+/// [UpdateKotoEntity::DespawnAfter], which inserts a [KotoLifetime] component.
+///
+/// Entities with the [KotoPooled] component are returned to the [KotoEntityPool] rather than being
+/// despawned, allowing them to be reused by the plugin that spawned them.
+///
+/// Entity `on_update` functions are passed the [KotoTime] delta, so they follow the script's time
+/// speed, and they aren't called while the script's time is paused, or while stepping with
+/// [KotoFrameControl] and no step is pending.
+///
+/// Entity `on_update` functions are called in parallel by default, so the order in which they're
+/// called isn't deterministic. Scripts that depend on the update order can be supported with
+/// [KotoEntityPlugin::with_ordered_updates].
+#[derive(Default)]
+pub struct KotoEntityPlugin {
+    /// If true, then entities are updated sequentially in order of their
+    /// [update priority](KotoEntity::update_priority)
+    ///
+    /// Entities with higher priorities are updated first, with entities that share a priority
+    /// being updated in the order of their Bevy [Entity] ids.
+    pub ordered_updates: bool,
+}
+
+impl KotoEntityPlugin {
+    /// Updates entities sequentially in order of their update priority
+    ///
+    /// See [KotoEntityPlugin::ordered_updates].
+    #[must_use]
+    pub fn with_ordered_updates(mut self) -> Self {
+        self.ordered_updates = true;
+        self
+    }
+}
+
+// The plugin's settings, made available to the plugin's systems
+#[derive(Resource)]
+pub(crate) struct KotoEntitySettings {
+    ordered_updates: bool,
+}
 
 impl Plugin for KotoEntityPlugin {
     fn build(&self, app: &mut App) {
@@ -19,13 +87,26 @@ impl Plugin for KotoEntityPlugin {
         let (update_entity_sender, update_entity_receiver) =
             koto_entity_channel::<UpdateKotoEntity>();
 
+        let registry = KotoEntityRegistry::default();
+        app.world()
+            .resource::<KotoRuntime>()
+            .prelude()
+            .insert("entities", make_entities_module(registry.clone()));
+
         app.insert_resource(update_entity_sender)
+            .insert_resource(registry)
+            .insert_resource(KotoEntitySettings {
+                ordered_updates: self.ordered_updates,
+            })
+            .init_resource::<KotoEntityPool>()
             .insert_resource(update_entity_receiver)
             .add_systems(
                 KotoSchedule,
                 (
-                    on_script_loaded.in_set(KotoUpdate::PreUpdate),
-                    update_koto_entities.in_set(KotoUpdate::PostUpdate),
+                    (on_script_loaded, update_lifetimes, sync_names).in_set(KotoUpdate::PreUpdate),
+                    (despawn_unused_koto_entities, update_koto_entities)
+                        .chain()
+                        .in_set(KotoUpdate::PostUpdate),
                 ),
             )
             .add_systems(Update, koto_to_bevy_entity_events);
@@ -48,53 +129,435 @@ fn on_script_loaded(
     }
 }
 
-fn update_koto_entities(
-    time: Res<Time>,
-    mut query: Query<&mut KotoEntity>,
+// Keeps the entities' name snapshots and the registry in sync with their Name components,
+// including names that have been given to the entities by the app
+fn sync_names(
+    named: Query<(Entity, &KotoEntity, &Name), Changed<Name>>,
+    koto_entities: Query<&KotoEntity>,
+    mut removed_names: RemovedComponents<Name>,
+    registry: Res<KotoEntityRegistry>,
+) {
+    for entity in removed_names.read() {
+        if let Ok(koto_entity) = koto_entities.get(entity) {
+            if let Some(previous_name) = koto_entity.entity.name() {
+                registry.remove(&previous_name, entity);
+            }
+            koto_entity.entity.set_name_snapshot(None);
+        }
+    }
+
+    for (entity, koto_entity, name) in &named {
+        if let Some(previous_name) = koto_entity.entity.name() {
+            if previous_name != name.as_str() {
+                registry.remove(&previous_name, entity);
+            }
+        }
+        if registry.get(name.as_str()) != Some(entity) {
+            registry.insert(name.to_string(), entity, koto_entity.object.clone());
+        }
+        koto_entity
+            .entity
+            .set_name_snapshot(Some(name.as_str().to_string()));
+    }
+}
+
+fn update_lifetimes(
+    mut query: Query<(Entity, &mut KotoLifetime)>,
+    koto_time: Res<KotoTime>,
     mut commands: Commands,
 ) {
-    let time_delta = time.delta_secs_f64();
+    for (entity, mut lifetime) in query.iter_mut() {
+        lifetime.0 -= koto_time.delta();
+        if lifetime.0 <= 0.0 {
+            debug!("Despawning {entity} at the end of its lifetime");
+            despawn_koto_entity(&mut commands, entity, false);
+        }
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn make_entities_module(registry: KotoEntityRegistry) -> KMap {
+    let module = KMap::with_type("entities");
 
-    for koto_entity in &query {
-        // If ref_count is 1 then the Koto script is no longer referencing the entity,
-        // so it can be despawned.
-        if koto_entity.object.ref_count() == 1 || !koto_entity.is_active {
+    module.add_fn("find", move |ctx| match ctx.args() {
+        [KValue::Str(name)] => Ok(registry
+            .get_object(name)
+            .map_or(KValue::Null, KValue::Object)),
+        unexpected => unexpected_args("an entity name", unexpected),
+    });
+
+    module
+}
+
+pub(crate) fn despawn_unused_koto_entities(
+    query: Query<(&KotoEntity, Option<&Name>)>,
+    registry: Res<KotoEntityRegistry>,
+    mut commands: Commands,
+) {
+    for (koto_entity, name) in &query {
+        // If the script is no longer referencing the entity, then it can be despawned.
+        let is_referenced = registry.is_referenced_by_script(koto_entity, name);
+        if !is_referenced || !koto_entity.is_active {
             debug!("Despawning {}", koto_entity.entity.get());
-            commands.entity(koto_entity.entity.get()).despawn();
+            // Entities that are still referenced by the script can't be reused
+            despawn_koto_entity(&mut commands, koto_entity.entity.get(), !is_referenced);
         }
     }
+}
 
-    query.par_iter_mut().for_each(|mut koto_entity| {
-        if koto_entity.is_active && koto_entity.object.ref_count() > 1 {
-            let instance = koto_entity.object.clone();
-            if let Some((on_update, vm)) = koto_entity.on_update.as_mut() {
-                if let Err(error) =
-                    vm.call_instance_function(instance.into(), on_update.clone(), time_delta)
-                {
-                    error!("Error while calling Entity::on_update():\n{error}");
-                }
+pub(crate) fn update_koto_entities(
+    koto: Res<KotoRuntime>,
+    koto_time: Res<KotoTime>,
+    mut query: Query<(&mut KotoEntity, Option<&Name>)>,
+    registry: Res<KotoEntityRegistry>,
+    settings: Res<KotoEntitySettings>,
+    mut script_error: EventWriter<KotoScriptError>,
+    mut timings: ResMut<KotoScriptTimings>,
+) {
+    // Entities are frozen along with the script's time, and while waiting for a step
+    if koto_time.is_paused() || !koto_time.is_advanced() {
+        return;
+    }
+
+    let time_delta = koto_time.delta();
+
+    let errors = Mutex::new(Vec::new());
+    let start = Instant::now();
+
+    let update_entity = |koto_entity: &mut KotoEntity, name: Option<&Name>| {
+        if koto_entity.is_active && registry.is_referenced_by_script(koto_entity, name) {
+            if let Err(error) = run_on_update(koto_entity, time_delta) {
+                errors.lock().push(error.to_string());
             }
         }
-    });
+    };
+
+    if settings.ordered_updates {
+        let mut entities: Vec<_> = query.iter_mut().collect();
+        entities.sort_by_key(|(koto_entity, _)| {
+            (
+                Reverse(koto_entity.update_priority),
+                koto_entity.entity.get(),
+            )
+        });
+        for (mut koto_entity, name) in entities {
+            update_entity(&mut koto_entity, name);
+        }
+    } else {
+        query
+            .par_iter_mut()
+            .for_each(|(mut koto_entity, name)| update_entity(&mut koto_entity, name));
+    }
+
+    timings.entity_update = Some(start.elapsed());
+
+    for message in errors.into_inner() {
+        let error = KotoScriptError {
+            phase: ScriptPhase::EntityUpdate,
+            message,
+            span: None,
+            script_path: koto.script_path().map(ToOwned::to_owned),
+        };
+        error!("{error}");
+        script_error.send(error);
+    }
+}
+
+// Calls the entity's on_update function if an update is due
+#[allow(clippy::result_large_err)]
+fn run_on_update(koto_entity: &mut KotoEntity, time_delta: f64) -> koto::runtime::Result<()> {
+    let instance = koto_entity.object.clone();
+    let entity = koto_entity.entity.get();
+
+    // Entities with an update rate accumulate time until their next update is due
+    let mut delta = time_delta;
+    if let Some(rate) = koto_entity.update_rate {
+        if koto_entity.on_update.is_none() {
+            return Ok(());
+        }
+        koto_entity.time_since_update += time_delta;
+        if koto_entity.time_since_update < rate.recip() {
+            return Ok(());
+        }
+        delta = std::mem::take(&mut koto_entity.time_since_update);
+    }
+
+    if let Some((on_update, vm)) = koto_entity.on_update.as_mut() {
+        let _span = info_span!("koto_entity_update", %entity).entered();
+        if let Err(error) = vm.call_instance_function(instance.into(), on_update.clone(), delta) {
+            // Remove functions that exceed the execution limit so that they don't stall
+            // every subsequent frame
+            if matches!(error.error, ErrorKind::Timeout(_)) {
+                koto_entity.on_update = None;
+            }
+            return Err(error);
+        }
+    }
+
+    Ok(())
 }
 
 fn koto_to_bevy_entity_events(
     channel: Res<KotoEntityReceiver<UpdateKotoEntity>>,
     mut query: Query<&mut KotoEntity>,
+    parents: Query<&Parent>,
+    names: Query<&Name>,
+    registry: Res<KotoEntityRegistry>,
     mut commands: Commands,
 ) {
+    let _span = info_span!("koto_channel", channel = "UpdateKotoEntity").entered();
     while let Some(event) = channel.receive() {
         let bevy_entity = event.entity.get();
-        let mut koto_entity = query.get_mut(bevy_entity).unwrap();
         match event.event {
-            UpdateKotoEntity::SetOnUpdate(on_update) => koto_entity.on_update = on_update,
-            UpdateKotoEntity::Despawn => commands.entity(bevy_entity).despawn(),
+            UpdateKotoEntity::SetOnUpdate(on_update) => {
+                if let Ok(mut koto_entity) = query.get_mut(bevy_entity) {
+                    koto_entity.on_update = on_update;
+                }
+            }
+            UpdateKotoEntity::SetUpdateRate(rate) => {
+                if let Ok(mut koto_entity) = query.get_mut(bevy_entity) {
+                    koto_entity.update_rate = rate;
+                    koto_entity.time_since_update = 0.0;
+                }
+            }
+            UpdateKotoEntity::SetUpdatePriority(priority) => {
+                if let Ok(mut koto_entity) = query.get_mut(bevy_entity) {
+                    koto_entity.update_priority = priority;
+                }
+            }
+            UpdateKotoEntity::SetCallback(name, callback) => {
+                if let Ok(mut koto_entity) = query.get_mut(bevy_entity) {
+                    match callback {
+                        Some(callback) => koto_entity.callbacks.insert(name, callback),
+                        None => koto_entity.callbacks.remove(name),
+                    };
+                }
+            }
+            UpdateKotoEntity::SetParent(Some(parent)) => {
+                if let Some(parent) = find_koto_entity(&query, &parent) {
+                    set_parent(&mut commands, &parents, bevy_entity, parent);
+                }
+            }
+            UpdateKotoEntity::SetParent(None) => {
+                if let Some(mut entity) = commands.get_entity(bevy_entity) {
+                    entity.remove_parent();
+                }
+            }
+            UpdateKotoEntity::AddChild(child) => {
+                if let Some(child) = find_koto_entity(&query, &child) {
+                    set_parent(&mut commands, &parents, child, bevy_entity);
+                }
+            }
+            UpdateKotoEntity::SetName(name) => {
+                let Ok(koto_entity) = query.get(bevy_entity) else {
+                    continue;
+                };
+                if let Ok(previous_name) = names.get(bevy_entity) {
+                    registry.remove(previous_name.as_str(), bevy_entity);
+                }
+                registry.insert(name.clone(), bevy_entity, koto_entity.object.clone());
+                commands.entity(bevy_entity).insert(Name::new(name));
+            }
+            UpdateKotoEntity::AddTag(tag) => {
+                commands.queue(move |world: &mut World| {
+                    let Ok(mut entity) = world.get_entity_mut(bevy_entity) else {
+                        return;
+                    };
+                    match entity.get_mut::<KotoTag>() {
+                        Some(mut tags) => {
+                            tags.0.insert(tag);
+                        }
+                        None => {
+                            entity.insert(KotoTag(HashSet::from([tag])));
+                        }
+                    }
+                });
+            }
+            UpdateKotoEntity::RemoveTag(tag) => {
+                commands.queue(move |world: &mut World| {
+                    if let Some(mut tags) = world
+                        .get_entity_mut(bevy_entity)
+                        .ok()
+                        .and_then(|entity| entity.into_mut::<KotoTag>())
+                    {
+                        tags.0.remove(&tag);
+                    }
+                });
+            }
+            UpdateKotoEntity::SetVisibility(visible) => {
+                if let Some(mut entity) = commands.get_entity(bevy_entity) {
+                    entity.try_insert(if visible {
+                        Visibility::Inherited
+                    } else {
+                        Visibility::Hidden
+                    });
+                }
+            }
+            UpdateKotoEntity::SetRenderLayer(layer) => {
+                if let Some(mut entity) = commands.get_entity(bevy_entity) {
+                    entity.try_insert(RenderLayers::layer(layer));
+                }
+            }
+            UpdateKotoEntity::DespawnAfter(seconds) => {
+                if let Some(mut entity) = commands.get_entity(bevy_entity) {
+                    entity.try_insert(KotoLifetime(seconds));
+                }
+            }
+            UpdateKotoEntity::Despawn => despawn_koto_entity(&mut commands, bevy_entity, false),
+        }
+    }
+}
+
+// Finds the Bevy entity that corresponds to the given Koto object
+fn find_koto_entity(query: &Query<&mut KotoEntity>, object: &KObject) -> Option<Entity> {
+    let result = query
+        .iter()
+        .find(|koto_entity| koto_entity.object.is_same_instance(object))
+        .map(|koto_entity| koto_entity.entity.get());
+
+    if result.is_none() {
+        warn!("Unable to find the Bevy entity for a Koto object");
+    }
+
+    result
+}
+
+fn set_parent(commands: &mut Commands, parents: &Query<&Parent>, child: Entity, parent: Entity) {
+    // Check that the child isn't one of the parent's ancestors
+    let mut ancestor = Some(parent);
+    while let Some(entity) = ancestor {
+        if entity == child {
+            warn!("Unable to make {child} a child of {parent}, the hierarchy would be cyclic");
+            return;
+        }
+        ancestor = parents.get(entity).ok().map(Parent::get);
+    }
+
+    if let Some(mut child) = commands.get_entity(child) {
+        child.set_parent(parent);
+    }
+}
+
+// Despawns the entity, or returns it to the entity pool if it's pooled and reuse is allowed
+//
+// Children that are Koto entities are detached rather than despawned, leaving them to be cleaned
+// up along with the other Koto entities once the script no longer refers to them. Any other
+// children (e.g. entities that were attached by the host app) are despawned recursively.
+fn despawn_koto_entity(commands: &mut Commands, entity: Entity, allow_reuse: bool) {
+    commands.queue(move |world: &mut World| {
+        let Ok(mut entity_mut) = world.get_entity_mut(entity) else {
+            return;
+        };
+        let children = entity_mut.take::<Children>();
+        let name = entity_mut.take::<Name>();
+        let pooled = entity_mut
+            .get::<KotoPooled>()
+            .copied()
+            .filter(|_| allow_reuse);
+        entity_mut.remove_parent();
+
+        if let (Some(name), Some(registry)) = (name, world.get_resource::<KotoEntityRegistry>()) {
+            registry.remove(name.as_str(), entity);
+        }
+
+        let is_released = pooled.is_some_and(|KotoPooled(pool)| {
+            world
+                .get_resource_mut::<KotoEntityPool>()
+                .is_some_and(|mut entity_pool| entity_pool.release(pool, entity))
+        });
+
+        let mut entity_mut = world.entity_mut(entity);
+        if is_released {
+            if let Some(koto_entity) = entity_mut.take::<KotoEntity>() {
+                // Any pending events for the released entity will be ignored
+                koto_entity.entity.clear_bevy_entity();
+            }
+            entity_mut
+                .remove::<(KotoTag, KotoLifetime)>()
+                .insert((Transform::default(), Visibility::Hidden));
+        } else {
+            entity_mut.despawn();
+        }
+
+        for child in children.iter().flatten() {
+            let Ok(mut child) = world.get_entity_mut(*child) else {
+                continue;
+            };
+            if child.contains::<KotoEntity>() {
+                child.remove::<Parent>();
+            } else {
+                child.despawn_recursive();
+            }
+        }
+    });
+}
+
+/// Marks a Koto entity that should be returned to the [KotoEntityPool] rather than being despawned
+///
+/// The component contains the name of the pool that the entity belongs to.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KotoPooled(pub &'static str);
+
+/// Pools of entities that are no longer used by the script, ready to be reused
+///
+/// When a [KotoPooled] entity is no longer referred to by the script, then rather than being
+/// despawned it's hidden and added to its pool, as long as the pool has capacity. Before being
+/// added to the pool, the entity's [KotoEntity], [Name], and [KotoTag] components are removed, it's
+/// detached from the hierarchy, and its transform is reset. Resetting any other components is up
+/// to the plugin that reuses the entity.
+///
+/// Pools are disabled by default, with entities being despawned until a capacity is set for their
+/// pool with [KotoEntityPool::set_capacity].
+#[derive(Resource, Debug, Default)]
+pub struct KotoEntityPool {
+    pools: HashMap<&'static str, Pool>,
+}
+
+#[derive(Debug, Default)]
+struct Pool {
+    capacity: usize,
+    entities: Vec<Entity>,
+}
+
+impl KotoEntityPool {
+    /// Sets the maximum number of entities that will be kept in the given pool
+    pub fn set_capacity(&mut self, pool: &'static str, capacity: usize) {
+        self.pools.entry(pool).or_default().capacity = capacity;
+    }
+
+    /// Returns the capacity of the given pool
+    pub fn capacity(&self, pool: &str) -> usize {
+        self.pools.get(pool).map_or(0, |pool| pool.capacity)
+    }
+
+    /// Returns the number of entities that are available for reuse in the given pool
+    pub fn len(&self, pool: &str) -> usize {
+        self.pools.get(pool).map_or(0, |pool| pool.entities.len())
+    }
+
+    /// Takes an entity from the given pool, if one is available
+    pub fn take(&mut self, pool: &str) -> Option<Entity> {
+        self.pools.get_mut(pool)?.entities.pop()
+    }
+
+    // Adds the entity to the pool, returning false if the pool is full
+    fn release(&mut self, pool: &'static str, entity: Entity) -> bool {
+        let pool = self.pools.entry(pool).or_default();
+        if pool.entities.len() < pool.capacity {
+            pool.entities.push(entity);
+            true
+        } else {
+            false
         }
     }
 }
 
 /// A Koto-scriptable Bevy entity
+///
+/// When the component is removed, e.g. when the entity is despawned, the entity's mapping is
+/// marked as released so that any pending events for the entity are dropped.
 #[derive(Debug, Clone, Component)]
+#[component(on_remove = release_koto_entity)]
 pub struct KotoEntity {
     /// The Koto object that corresponds to the Bevy entity
     pub object: KObject,
@@ -102,6 +565,17 @@ pub struct KotoEntity {
     pub entity: KotoEntityMapping,
     /// The Koto value that should be called on each update
     pub on_update: Option<(KValue, KotoVm)>,
+    /// The rate in Hz at which `on_update` should be called, or `None` to call it on every update
+    pub update_rate: Option<f64>,
+    /// The [KotoTime] in seconds that has passed since `on_update` was last called
+    ///
+    /// Only used when an `update_rate` has been set, with the accumulated time being passed to
+    /// `on_update` as the time delta.
+    pub time_since_update: f64,
+    /// The entity's update priority, see [KotoEntityPlugin::ordered_updates]
+    pub update_priority: i64,
+    /// Functions that are called in response to events, e.g. `on_click`
+    pub callbacks: HashMap<&'static str, (KValue, KotoVm)>,
     /// True if the entity should be displayed, false when transitioning away from a script
     pub is_active: bool,
 }
@@ -113,20 +587,192 @@ impl KotoEntity {
             object,
             entity,
             on_update: None,
+            update_rate: None,
+            time_since_update: 0.0,
+            update_priority: 0,
+            callbacks: HashMap::new(),
             is_active: true,
         }
     }
 }
 
+fn release_koto_entity(world: DeferredWorld, entity: Entity, _: ComponentId) {
+    if let Some(koto_entity) = world.get::<KotoEntity>(entity) {
+        koto_entity.entity.release();
+    }
+}
+
 /// Event for updating properties of the Koto entity
 #[derive(Clone, Event)]
 pub enum UpdateKotoEntity {
     /// Sets the `on_update` function that should be called when updating the entity
     SetOnUpdate(Option<(KValue, KotoVm)>),
+    /// Sets the rate in Hz at which `on_update` is called, see [KotoEntity::update_rate]
+    SetUpdateRate(Option<f64>),
+    /// Sets the entity's update priority, see [KotoEntity::update_priority]
+    SetUpdatePriority(i64),
+    /// Sets or removes the callback with the given name, see [KotoEntity::callbacks]
+    SetCallback(&'static str, Option<(KValue, KotoVm)>),
+    /// Sets the entity's parent to the given Koto entity, or detaches it from its parent if `None`
+    SetParent(Option<KObject>),
+    /// Adds the given Koto entity as a child of the entity
+    AddChild(KObject),
+    /// Sets the entity's name, see [KotoEntityRegistry]
+    SetName(String),
+    /// Adds a tag to the entity's [KotoTag] component
+    AddTag(String),
+    /// Removes a tag from the entity's [KotoTag] component
+    RemoveTag(String),
+    /// Shows or hides the entity by setting its [Visibility]
+    ///
+    /// Visible entities are set to [Visibility::Inherited], so they remain hidden if a parent is hidden.
+    SetVisibility(bool),
+    /// Sets the entity's [RenderLayers], controlling which cameras will render the entity
+    SetRenderLayer(usize),
+    /// The entity should be despawned after the given number of seconds, see [KotoLifetime]
+    DespawnAfter(f64),
     /// The entity has been manually despawned from Koto, and should be despawned in Bevy
     Despawn,
 }
 
+/// A registry of the Koto entities that have been given names
+///
+/// Entities are added to the registry with [UpdateKotoEntity::SetName], e.g. from the `set_name`
+/// method on shapes, allowing systems to find entities that have been spawned by the script.
+/// Names are registered when the entity's events are processed in Bevy's [Update] schedule, and
+/// are removed when the entity is despawned.
+///
+/// If more than one entity is given the same name, then the most recently named entity is used.
+#[derive(Resource, Clone, Default)]
+pub struct KotoEntityRegistry {
+    entities: Arc<RwLock<HashMap<String, NamedEntity>>>,
+}
+
+#[derive(Clone)]
+struct NamedEntity {
+    entity: Entity,
+    object: KObject,
+}
+
+impl KotoEntityRegistry {
+    /// Returns the entity that has been given the name, if it exists
+    pub fn get(&self, name: &str) -> Option<Entity> {
+        self.entities.read().get(name).map(|named| named.entity)
+    }
+
+    /// Returns the names that are currently registered
+    pub fn names(&self) -> Vec<String> {
+        self.entities.read().keys().cloned().collect()
+    }
+
+    /// Returns the number of named entities
+    pub fn len(&self) -> usize {
+        self.entities.read().len()
+    }
+
+    /// Returns true if no entities have been named
+    pub fn is_empty(&self) -> bool {
+        self.entities.read().is_empty()
+    }
+
+    fn get_object(&self, name: &str) -> Option<KObject> {
+        self.entities
+            .read()
+            .get(name)
+            .map(|named| named.object.clone())
+    }
+
+    fn insert(&self, name: String, entity: Entity, object: KObject) {
+        self.entities
+            .write()
+            .insert(name, NamedEntity { entity, object });
+    }
+
+    // Removes the name if it's registered to the given entity
+    fn remove(&self, name: &str, entity: Entity) {
+        let mut entities = self.entities.write();
+        if entities
+            .get(name)
+            .is_some_and(|named| named.entity == entity)
+        {
+            entities.remove(name);
+        }
+    }
+
+    // Checks if the script holds references to the entity's object,
+    // ignoring the references held by the entity itself and by the registry
+    pub(crate) fn is_referenced_by_script(
+        &self,
+        koto_entity: &KotoEntity,
+        name: Option<&Name>,
+    ) -> bool {
+        let is_registered = name.is_some_and(|name| {
+            self.entities
+                .read()
+                .get(name.as_str())
+                .is_some_and(|named| named.object.is_same_instance(&koto_entity.object))
+        });
+
+        koto_entity.object.ref_count() > 1 + usize::from(is_registered)
+    }
+}
+
+/// The tags that have been added to a Koto entity
+///
+/// Tags are added by scripts, e.g. with `shape.add_tag 'enemy'`, see [KotoTagQuery] for finding
+/// the entities with a given tag.
+#[derive(Component, Clone, Debug, Default)]
+pub struct KotoTag(HashSet<String>);
+
+impl KotoTag {
+    /// Returns true if the entity has the given tag
+    pub fn has(&self, tag: &str) -> bool {
+        self.0.contains(tag)
+    }
+
+    /// Returns an iterator over the entity's tags
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+}
+
+/// The remaining time in seconds before a Koto entity is despawned
+///
+/// The lifetime counts down with [KotoTime] during [KotoUpdate::PreUpdate], so it's affected by the
+/// script's time speed and is paused along with the script's time.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct KotoLifetime(pub f64);
+
+/// A system parameter for finding the Koto entities that have a given tag
+///
+/// e.g.
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_koto::prelude::*;
+/// fn count_enemies(tagged: KotoTagQuery) {
+///     info!("There are {} enemies", tagged.iter("enemy").count());
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct KotoTagQuery<'w, 's> {
+    query: Query<'w, 's, (Entity, &'static KotoTag)>,
+}
+
+impl KotoTagQuery<'_, '_> {
+    /// Returns an iterator over the entities that have the given tag
+    pub fn iter<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = Entity> + 'a {
+        self.query
+            .iter()
+            .filter(move |(_, tags)| tags.has(tag))
+            .map(|(entity, _)| entity)
+    }
+
+    /// Returns true if the entity has the given tag
+    pub fn has_tag(&self, entity: Entity, tag: &str) -> bool {
+        self.query.get(entity).is_ok_and(|(_, tags)| tags.has(tag))
+    }
+}
+
 /// A Bevy entity that can be referred to from Koto scripts
 ///
 /// When an entity is first created in a Koto script, it needs to be referred to immediately during
@@ -137,6 +783,29 @@ pub enum UpdateKotoEntity {
 #[derive(Clone, Debug)]
 pub struct KotoEntityMapping {
     bevy_entity: Arc<RwLock<Entity>>,
+    is_released: Arc<AtomicBool>,
+    name: Arc<RwLock<Option<String>>>,
+    components: Arc<RwLock<ComponentCache>>,
+    transform: Arc<RwLock<Transform>>,
+    color: Arc<RwLock<Option<Color>>>,
+}
+
+// The state of an entity mapping, used to decide what to do with the entity's events
+enum MappingState {
+    // The Bevy entity hasn't been assigned yet
+    Unassigned,
+    Assigned,
+    // The entity has been despawned or returned to the entity pool
+    Released,
+}
+
+// Snapshots of the entity's components, along with pending component updates from the script
+//
+// See `KotoComponentsPlugin`.
+#[derive(Debug, Default)]
+struct ComponentCache {
+    snapshots: HashMap<String, KValue>,
+    pending: Vec<(String, KValue)>,
 }
 
 impl KotoEntityMapping {
@@ -147,16 +816,127 @@ impl KotoEntityMapping {
         *inner = entity;
     }
 
+    // Clears the Bevy entity when the entity is returned to the entity pool
+    fn clear_bevy_entity(&self) {
+        *self.bevy_entity.write() = Entity::PLACEHOLDER;
+        self.release();
+    }
+
+    // Marks the mapping as released when its entity is despawned or returned to the entity pool
+    fn release(&self) {
+        self.is_released.store(true, Ordering::Relaxed);
+    }
+
+    fn state(&self) -> MappingState {
+        if self.is_released.load(Ordering::Relaxed) {
+            MappingState::Released
+        } else if self.get() == Entity::PLACEHOLDER {
+            MappingState::Unassigned
+        } else {
+            MappingState::Assigned
+        }
+    }
+
     /// Gets the Bevy entity associated with the Koto entity
     pub fn get(&self) -> Entity {
         *self.bevy_entity.read()
     }
+
+    /// Gets the entity's name, if it has been given one
+    ///
+    /// The name reflects the entity's [Name] component, which is synced during
+    /// [KotoUpdate::PreUpdate], along with any name that has been set by the script.
+    pub fn name(&self) -> Option<String> {
+        self.name.read().clone()
+    }
+
+    /// Sets the snapshot of the entity's name
+    pub fn set_name_snapshot(&self, name: Option<String>) {
+        *self.name.write() = name;
+    }
+
+    /// Gets the most recent snapshot of the entity's transform
+    ///
+    /// Transform snapshots are provided by the `KotoGeometryPlugin`.
+    pub fn transform(&self) -> Transform {
+        *self.transform.read()
+    }
+
+    /// Sets the snapshot of the entity's transform
+    pub fn set_transform_snapshot(&self, transform: Transform) {
+        *self.transform.write() = transform;
+    }
+
+    /// Gets the most recent snapshot of the entity's color, if it has one
+    ///
+    /// Color snapshots are provided by the plugins that render entities with colors, e.g. the
+    /// `KotoColorPlugin` for shapes and the `KotoTextPlugin` for text.
+    pub fn color(&self) -> Option<Color> {
+        *self.color.read()
+    }
+
+    /// Sets the snapshot of the entity's color
+    pub fn set_color_snapshot(&self, color: Option<Color>) {
+        *self.color.write() = color;
+    }
+
+    /// Sets the alpha value of the entity's color snapshot, if it has one
+    pub fn set_alpha_snapshot(&self, alpha: f32) {
+        if let Some(color) = self.color.write().as_mut() {
+            color.set_alpha(alpha);
+        }
+    }
+
+    /// Gets the most recent snapshot of the entity's component with the given name
+    ///
+    /// Component snapshots are provided by the `KotoComponentsPlugin`.
+    pub fn get_component(&self, name: &str) -> Option<KValue> {
+        self.components.read().snapshots.get(name).cloned()
+    }
+
+    /// Queues an update for the entity's component with the given name
+    ///
+    /// The value is also applied to the component's snapshot so that the change is visible
+    /// immediately. Updates are applied to the component by the `KotoComponentsPlugin`.
+    pub fn set_component(&self, name: &str, value: KValue) {
+        let mut components = self.components.write();
+        match (components.snapshots.get(name), &value) {
+            (Some(KValue::Map(snapshot)), KValue::Map(fields)) => {
+                for (key, value) in fields.data().iter() {
+                    snapshot.insert(key.clone(), value.clone());
+                }
+            }
+            _ => {
+                components.snapshots.insert(name.to_string(), value.clone());
+            }
+        }
+        components.pending.push((name.to_string(), value));
+    }
+
+    /// Sets the snapshot of the entity's component with the given name
+    pub fn set_component_snapshot(&self, name: &str, snapshot: Option<KValue>) {
+        let mut components = self.components.write();
+        match snapshot {
+            Some(snapshot) => components.snapshots.insert(name.to_string(), snapshot),
+            None => components.snapshots.remove(name),
+        };
+    }
+
+    /// Takes the component updates that have been queued with [Self::set_component]
+    pub fn take_pending_components(&self) -> Vec<(String, KValue)> {
+        std::mem::take(&mut self.components.write().pending)
+    }
 }
 
 impl Default for KotoEntityMapping {
     fn default() -> Self {
         Self {
             bevy_entity: Arc::new(RwLock::new(Entity::PLACEHOLDER)),
+            is_released: Default::default(),
+            name: Default::default(),
+            components: Default::default(),
+            transform: Default::default(),
+            color: Default::default(),
         }
     }
 }
@@ -179,11 +959,729 @@ impl<T> KotoEntityEvent<T> {
 
 /// A type alias for events being sent from Koto that are associated with a specific entity
 pub type KotoEntitySender<T> = KotoSender<KotoEntityEvent<T>>;
-/// A type alias for events being received from Koto that are associated with a specific entity
-pub type KotoEntityReceiver<T> = KotoReceiver<KotoEntityEvent<T>>;
+
+/// A receiver for events from Koto that are associated with a specific entity
+///
+/// Events can be sent for an entity before its Bevy entity has been assigned, e.g. when an entity
+/// is spawned and modified by a script in a stage that runs after the entity's spawning system.
+/// Events for entities that haven't been assigned yet are buffered, and are returned from
+/// [KotoEntityReceiver::receive] once the Bevy entity is available. Events for entities that have
+/// been despawned or returned to the [KotoEntityPool] are dropped.
+///
+/// Buffered events are checked once each time the receiver is drained, i.e. on the first call to
+/// `receive` after it last returned `None`. Events whose entities still haven't been assigned
+/// after 16 checks are dropped.
+#[derive(Resource)]
+pub struct KotoEntityReceiver<T> {
+    receiver: KotoReceiver<KotoEntityEvent<T>>,
+    pending: Arc<Mutex<PendingEvents<T>>>,
+}
+
+// The number of times the receiver can be drained before unassigned events are dropped
+const MAX_PENDING_DRAINS: u32 = 16;
+
+// The events that are waiting for their entities to be assigned
+struct PendingEvents<T> {
+    // Buffered events whose entities have since been assigned, ready to be received
+    ready: VecDeque<KotoEntityEvent<T>>,
+    // Events for unassigned entities, along with the number of times they've been checked
+    unassigned: Vec<(KotoEntityEvent<T>, u32)>,
+    // True while the receiver is being drained, cleared when `receive` returns `None`
+    is_draining: bool,
+}
+
+impl<T> Default for PendingEvents<T> {
+    fn default() -> Self {
+        Self {
+            ready: VecDeque::new(),
+            unassigned: Vec::new(),
+            is_draining: false,
+        }
+    }
+}
+
+impl<T> PendingEvents<T> {
+    // Moves the events whose entities are now available to the ready queue,
+    // dropping events for released entities, and events that have been waiting for too long
+    fn partition(&mut self) {
+        for (event, checks) in std::mem::take(&mut self.unassigned) {
+            match event.entity.state() {
+                MappingState::Unassigned if checks < MAX_PENDING_DRAINS => {
+                    self.unassigned.push((event, checks + 1));
+                }
+                MappingState::Unassigned => {
+                    warn!("Dropping an event for an entity that hasn't been spawned");
+                }
+                MappingState::Assigned => self.ready.push_back(event),
+                MappingState::Released => {}
+            }
+        }
+    }
+}
+
+impl<T> Clone for KotoEntityReceiver<T> {
+    fn clone(&self) -> Self {
+        Self {
+            receiver: self.receiver.clone(),
+            pending: self.pending.clone(),
+        }
+    }
+}
+
+impl<T> KotoEntityReceiver<T> {
+    fn new(receiver: KotoReceiver<KotoEntityEvent<T>>) -> Self {
+        Self {
+            receiver,
+            pending: Default::default(),
+        }
+    }
+
+    /// Receives an event for an entity that has been assigned a Bevy entity
+    ///
+    /// This is non-blocking, if no event is available then `None` is returned.
+    pub fn receive(&self) -> Option<KotoEntityEvent<T>> {
+        let mut pending = self.pending.lock();
+
+        // Check for buffered events whose entities are now available at the start of each drain
+        if !pending.is_draining {
+            pending.is_draining = true;
+            pending.partition();
+        }
+
+        if let Some(event) = pending.ready.pop_front() {
+            return Some(event);
+        }
+
+        while let Some(event) = self.receiver.receive() {
+            match event.entity.state() {
+                MappingState::Unassigned => pending.unassigned.push((event, 0)),
+                MappingState::Assigned => return Some(event),
+                MappingState::Released => {}
+            }
+        }
+
+        pending.is_draining = false;
+        None
+    }
+
+    /// Returns the number of events that are waiting for their entities to be assigned
+    pub fn pending_count(&self) -> usize {
+        let pending = self.pending.lock();
+        pending.ready.len() + pending.unassigned.len()
+    }
+}
 
 /// A helper for building a channel for entity events from Koto to Bevy
 pub fn koto_entity_channel<T>() -> (KotoEntitySender<T>, KotoEntityReceiver<T>) {
-    let (sender, receiver) = crossbeam_channel::unbounded();
-    (KotoSender(sender), KotoReceiver(receiver))
+    let (sender, receiver) = koto_channel();
+    (sender, KotoEntityReceiver::new(receiver))
 }
+
+/// A helper for building a bounded channel for entity events from Koto to Bevy
+///
+/// See [koto_channel_bounded]
+pub fn koto_entity_channel_bounded<T>(
+    capacity: usize,
+    overflow_policy: OverflowPolicy,
+) -> (KotoEntitySender<T>, KotoEntityReceiver<T>) {
+    let (sender, receiver) = koto_channel_bounded(capacity, overflow_policy);
+    (sender, KotoEntityReceiver::new(receiver))
+}
+
+/// Implements Koto methods for an entity object, along with the methods that entities share
+///
+/// The shared methods are added in sets, with each set expecting the object to have some fields:
+/// - `entity`: `on_update`, `set_update_priority`, `set_parent`, `name`, `set_name`, `add_tag`,
+///   `remove_tag`, `get_component`, and `set_component`, along with the methods from `lifetime`.
+///   Expects `entity` and `update_entity` fields.
+/// - `lifetime`: `set_visible`, `despawn_after`, and `despawn`. Expects `entity` and
+///   `update_entity` fields.
+/// - `layer`: `set_layer`. Expects `entity` and `update_entity` fields.
+/// - `children`: `add_child`. Expects `entity` and `update_entity` fields.
+/// - `state`: `state` and `set_state`. Expects a `state` field.
+/// - `transform_2d`: `get_position`, `get_rotation`, `get_scale`, `set_position`, and
+///   `set_rotation`. Expects `entity` and `update_transform` fields.
+/// - `z_index`: `set_z_index`. Expects `entity` and `update_transform` fields.
+/// - `size_2d`: `set_size`, with the entity's Z scale set to zero. Expects `entity` and
+///   `update_transform` fields.
+/// - `transform_3d`: The 3D versions of the methods from `transform_2d` and `size_2d`, along with
+///   `look_at`. Expects `entity` and `update_transform` fields.
+///
+/// The object's type name is used as the prefix for error messages, e.g.
+///
+/// ```ignore
+/// koto_entity_impl! {
+///     impl KotoSprite as "Sprite" with [entity, children, state, transform_2d] {
+///         #[koto_method]
+///         fn play(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+///             ...
+///         }
+///     }
+/// }
+/// ```
+#[cfg(any(
+    feature = "group",
+    feature = "shape",
+    feature = "shape3d",
+    feature = "sprite",
+    feature = "text"
+))]
+macro_rules! koto_entity_impl {
+    (impl $ty:ident as $name:literal with [$($set:ident),* $(,)?] { $($methods:tt)* }) => {
+        $crate::entity::koto_entity_impl!(@munch $ty $name [$($methods)*] [$($set)*]);
+    };
+
+    (@munch $ty:ident $name:literal [$($acc:tt)*] []) => {
+        // Koto's error type is large, and it's returned by every method exposed to scripts
+        #[allow(clippy::result_large_err)]
+        #[koto_impl]
+        impl $ty {
+            $($acc)*
+        }
+    };
+
+    (@munch $ty:ident $name:literal [$($acc:tt)*] [entity $($rest:ident)*]) => {
+        $crate::entity::koto_entity_impl!(@munch $ty $name [$($acc)*
+            #[koto_method]
+            fn on_update(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                let type_name = $name;
+                let (f, rate) = match ctx.args {
+                    [f] if f.is_callable() => (f.clone(), None),
+                    [f, KValue::Number(rate)] if f.is_callable() && f64::from(rate) > 0.0 => {
+                        (f.clone(), Some(rate.into()))
+                    }
+                    _ => {
+                        return runtime_error!(
+                            "{type_name}.on_update: Expected a callable value, \
+                             and an optional rate in Hz"
+                        )
+                    }
+                };
+
+                let this = ctx.instance()?;
+                this.update_entity.send(KotoEntityEvent::new(
+                    this.entity.clone(),
+                    UpdateKotoEntity::SetOnUpdate(Some((f, ctx.vm.spawn_shared_vm()))),
+                ));
+                this.update_entity.send(KotoEntityEvent::new(
+                    this.entity.clone(),
+                    UpdateKotoEntity::SetUpdateRate(rate),
+                ));
+
+                ctx.instance_result()
+            }
+
+            #[koto_method]
+            fn set_update_priority(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                let type_name = $name;
+                let priority = match ctx.args {
+                    [KValue::Number(n)] => n.into(),
+                    _ => {
+                        return runtime_error!("{type_name}.set_update_priority: Expected a Number")
+                    }
+                };
+
+                let this = ctx.instance()?;
+                this.update_entity.send(KotoEntityEvent::new(
+                    this.entity.clone(),
+                    UpdateKotoEntity::SetUpdatePriority(priority),
+                ));
+
+                ctx.instance_result()
+            }
+
+            #[koto_method]
+            fn set_parent(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                let type_name = $name;
+                let parent = match ctx.args {
+                    [KValue::Object(parent)] => Some(parent.clone()),
+                    [KValue::Null] => None,
+                    _ => {
+                        return runtime_error!(
+                            "{type_name}.set_parent: Expected another entity, or null"
+                        )
+                    }
+                };
+
+                let this = ctx.instance()?;
+                this.update_entity.send(KotoEntityEvent::new(
+                    this.entity.clone(),
+                    UpdateKotoEntity::SetParent(parent),
+                ));
+
+                ctx.instance_result()
+            }
+
+            #[koto_method]
+            fn name(&self) -> KValue {
+                self.entity.name().map_or(KValue::Null, KValue::from)
+            }
+
+            #[koto_method]
+            fn set_name(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                let type_name = $name;
+                let name = match ctx.args {
+                    [KValue::Str(name)] => name.to_string(),
+                    _ => return runtime_error!("{type_name}.set_name: Expected a name as a string"),
+                };
+
+                let this = ctx.instance()?;
+                this.entity.set_name_snapshot(Some(name.clone()));
+                this.update_entity.send(KotoEntityEvent::new(
+                    this.entity.clone(),
+                    UpdateKotoEntity::SetName(name),
+                ));
+
+                ctx.instance_result()
+            }
+
+            #[koto_method]
+            fn add_tag(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                let type_name = $name;
+                let tag = match ctx.args {
+                    [KValue::Str(tag)] => tag.to_string(),
+                    _ => return runtime_error!("{type_name}.add_tag: Expected a tag as a string"),
+                };
+
+                let this = ctx.instance()?;
+                this.update_entity.send(KotoEntityEvent::new(
+                    this.entity.clone(),
+                    UpdateKotoEntity::AddTag(tag),
+                ));
+
+                ctx.instance_result()
+            }
+
+            #[koto_method]
+            fn remove_tag(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                let type_name = $name;
+                let tag = match ctx.args {
+                    [KValue::Str(tag)] => tag.to_string(),
+                    _ => {
+                        return runtime_error!("{type_name}.remove_tag: Expected a tag as a string")
+                    }
+                };
+
+                let this = ctx.instance()?;
+                this.update_entity.send(KotoEntityEvent::new(
+                    this.entity.clone(),
+                    UpdateKotoEntity::RemoveTag(tag),
+                ));
+
+                ctx.instance_result()
+            }
+
+            #[koto_method]
+            fn get_component(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                let type_name = $name;
+                let name = match ctx.args {
+                    [KValue::Str(name)] => name,
+                    _ => {
+                        return runtime_error!(
+                            "{type_name}.get_component: Expected a component name"
+                        )
+                    }
+                };
+
+                Ok(ctx
+                    .instance()?
+                    .entity
+                    .get_component(name)
+                    .unwrap_or_default())
+            }
+
+            #[koto_method]
+            fn set_component(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                let type_name = $name;
+                let (name, value) = match ctx.args {
+                    [KValue::Str(name), value] => (name, value.clone()),
+                    _ => {
+                        return runtime_error!(
+                            "{type_name}.set_component: Expected a component name and a value"
+                        )
+                    }
+                };
+
+                ctx.instance()?.entity.set_component(name, value);
+
+                ctx.instance_result()
+            }
+        ] [lifetime $($rest)*]);
+    };
+
+    (@munch $ty:ident $name:literal [$($acc:tt)*] [lifetime $($rest:ident)*]) => {
+        $crate::entity::koto_entity_impl!(@munch $ty $name [$($acc)*
+            #[koto_method]
+            fn set_visible(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                let type_name = $name;
+                let visible = match ctx.args {
+                    [KValue::Bool(visible)] => *visible,
+                    _ => return runtime_error!("{type_name}.set_visible: Expected a Bool"),
+                };
+
+                let this = ctx.instance()?;
+                this.update_entity.send(KotoEntityEvent::new(
+                    this.entity.clone(),
+                    UpdateKotoEntity::SetVisibility(visible),
+                ));
+
+                ctx.instance_result()
+            }
+
+            #[koto_method]
+            fn despawn_after(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                let type_name = $name;
+                let seconds = match ctx.args {
+                    [KValue::Number(n)] => n.into(),
+                    _ => {
+                        return runtime_error!(
+                            "{type_name}.despawn_after: Expected a duration in seconds"
+                        )
+                    }
+                };
+
+                let this = ctx.instance()?;
+                this.update_entity.send(KotoEntityEvent::new(
+                    this.entity.clone(),
+                    UpdateKotoEntity::DespawnAfter(seconds),
+                ));
+
+                ctx.instance_result()
+            }
+
+            #[koto_method]
+            fn despawn(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                let this = ctx.instance()?;
+                this.update_entity.send(KotoEntityEvent::new(
+                    this.entity.clone(),
+                    UpdateKotoEntity::Despawn,
+                ));
+
+                Ok(KValue::Null)
+            }
+        ] [$($rest)*]);
+    };
+
+    (@munch $ty:ident $name:literal [$($acc:tt)*] [layer $($rest:ident)*]) => {
+        $crate::entity::koto_entity_impl!(@munch $ty $name [$($acc)*
+            #[koto_method]
+            fn set_layer(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                let type_name = $name;
+                let layer = match ctx.args {
+                    [KValue::Number(n)] => match usize::try_from(i64::from(n)) {
+                        Ok(layer) => layer,
+                        Err(_) => {
+                            return runtime_error!("{type_name}.set_layer: Invalid layer '{n}'")
+                        }
+                    },
+                    _ => return runtime_error!("{type_name}.set_layer: Expected a Number"),
+                };
+
+                let this = ctx.instance()?;
+                this.update_entity.send(KotoEntityEvent::new(
+                    this.entity.clone(),
+                    UpdateKotoEntity::SetRenderLayer(layer),
+                ));
+
+                ctx.instance_result()
+            }
+        ] [$($rest)*]);
+    };
+
+    (@munch $ty:ident $name:literal [$($acc:tt)*] [children $($rest:ident)*]) => {
+        $crate::entity::koto_entity_impl!(@munch $ty $name [$($acc)*
+            #[koto_method]
+            fn add_child(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                let type_name = $name;
+                let child = match ctx.args {
+                    [KValue::Object(child)] => child.clone(),
+                    _ => return runtime_error!("{type_name}.add_child: Expected another entity"),
+                };
+
+                let this = ctx.instance()?;
+                this.update_entity.send(KotoEntityEvent::new(
+                    this.entity.clone(),
+                    UpdateKotoEntity::AddChild(child),
+                ));
+
+                ctx.instance_result()
+            }
+        ] [$($rest)*]);
+    };
+
+    (@munch $ty:ident $name:literal [$($acc:tt)*] [state $($rest:ident)*]) => {
+        $crate::entity::koto_entity_impl!(@munch $ty $name [$($acc)*
+            #[koto_method]
+            fn state(&self) -> KValue {
+                self.state.clone()
+            }
+
+            #[koto_method]
+            fn set_state(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                let type_name = $name;
+                match ctx.args {
+                    [state] => ctx.instance_mut()?.state = state.clone(),
+                    _ => return runtime_error!("{type_name}.set_state: Expected a single value"),
+                };
+
+                ctx.instance_result()
+            }
+        ] [$($rest)*]);
+    };
+
+    (@munch $ty:ident $name:literal [$($acc:tt)*] [transform_2d $($rest:ident)*]) => {
+        $crate::entity::koto_entity_impl!(@munch $ty $name [$($acc)*
+            #[koto_method]
+            fn get_position(&self) -> KValue {
+                let position = self.entity.transform().translation;
+                KotoVec2::new(position.x.into(), position.y.into()).into()
+            }
+
+            #[koto_method]
+            fn get_rotation(&self) -> KValue {
+                let (rotation, _, _) = self.entity.transform().rotation.to_euler(EulerRot::ZYX);
+                rotation.into()
+            }
+
+            #[koto_method]
+            fn get_scale(&self) -> KValue {
+                let scale = self.entity.transform().scale;
+                KotoVec2::new(scale.x.into(), scale.y.into()).into()
+            }
+
+            #[koto_method]
+            fn set_position(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                use KValue::{Number, Object};
+
+                let type_name = $name;
+                let update = match ctx.args {
+                    [Number(x), Number(y)] => {
+                        UpdateTransform::Position2d(Vec2::new(x.into(), y.into()))
+                    }
+                    [Number(x), Number(y), Number(z)] => {
+                        UpdateTransform::Position(Vec3::new(x.into(), y.into(), z.into()))
+                    }
+                    [Object(v)] if v.is_a::<KotoVec2>() => {
+                        let v = v.cast::<KotoVec2>()?.inner();
+                        UpdateTransform::Position2d(Vec2::new(v.x as f32, v.y as f32))
+                    }
+                    [Object(v), Number(z)] if v.is_a::<KotoVec2>() => {
+                        let v = v.cast::<KotoVec2>()?.inner();
+                        UpdateTransform::Position(Vec3::new(v.x as f32, v.y as f32, z.into()))
+                    }
+                    _ => {
+                        return runtime_error!(
+                            "{type_name}.set_position: Expected x, y, (and optionally z) positions"
+                        )
+                    }
+                };
+
+                let this = ctx.instance()?;
+                send_transform_update(&this.update_transform, &this.entity, update);
+
+                ctx.instance_result()
+            }
+
+            #[koto_method]
+            fn set_rotation(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                let type_name = $name;
+                let rotation = match ctx.args {
+                    [KValue::Number(x)] => x.into(),
+                    _ => {
+                        return runtime_error!(
+                            "{type_name}.set_rotation: Expected a Number in radians"
+                        )
+                    }
+                };
+
+                let this = ctx.instance()?;
+                send_transform_update(
+                    &this.update_transform,
+                    &this.entity,
+                    UpdateTransform::Rotation(rotation),
+                );
+
+                ctx.instance_result()
+            }
+        ] [$($rest)*]);
+    };
+
+    (@munch $ty:ident $name:literal [$($acc:tt)*] [z_index $($rest:ident)*]) => {
+        $crate::entity::koto_entity_impl!(@munch $ty $name [$($acc)*
+            #[koto_method(alias = "set_z")]
+            fn set_z_index(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                let type_name = $name;
+                let z = match ctx.args {
+                    [KValue::Number(z)] => z.into(),
+                    _ => return runtime_error!("{type_name}.set_z_index: Expected a Number"),
+                };
+
+                let this = ctx.instance()?;
+                send_transform_update(
+                    &this.update_transform,
+                    &this.entity,
+                    UpdateTransform::ZIndex(z),
+                );
+
+                ctx.instance_result()
+            }
+        ] [$($rest)*]);
+    };
+
+    (@munch $ty:ident $name:literal [$($acc:tt)*] [size_2d $($rest:ident)*]) => {
+        $crate::entity::koto_entity_impl!(@munch $ty $name [$($acc)*
+            #[koto_method]
+            fn set_size(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                use KValue::Number;
+
+                let type_name = $name;
+                let size = match ctx.args {
+                    [Number(size)] => {
+                        let size = f32::from(size);
+                        Vec3::new(size, size, 0.0)
+                    }
+                    [Number(x), Number(y)] => Vec3::new(f32::from(x), f32::from(y), 0.0),
+                    _ => return runtime_error!("{type_name}.set_size: Expected Numbers"),
+                };
+
+                let this = ctx.instance()?;
+                send_transform_update(
+                    &this.update_transform,
+                    &this.entity,
+                    UpdateTransform::Scale(size),
+                );
+
+                ctx.instance_result()
+            }
+        ] [$($rest)*]);
+    };
+
+    (@munch $ty:ident $name:literal [$($acc:tt)*] [transform_3d $($rest:ident)*]) => {
+        $crate::entity::koto_entity_impl!(@munch $ty $name [$($acc)*
+            #[koto_method]
+            fn get_position(&self) -> KValue {
+                let position = self.entity.transform().translation.as_dvec3();
+                KotoVec3::from(position).into()
+            }
+
+            #[koto_method]
+            fn get_rotation(&self) -> KValue {
+                let (x, y, z) = self.entity.transform().rotation.to_euler(EulerRot::XYZ);
+                KotoVec3::new(x.into(), y.into(), z.into()).into()
+            }
+
+            #[koto_method]
+            fn get_scale(&self) -> KValue {
+                let scale = self.entity.transform().scale.as_dvec3();
+                KotoVec3::from(scale).into()
+            }
+
+            #[koto_method]
+            fn set_position(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                use KValue::Number;
+
+                let type_name = $name;
+                let position = match ctx.args {
+                    [Number(x), Number(y), Number(z)] => Vec3::new(x.into(), y.into(), z.into()),
+                    _ => {
+                        return runtime_error!(
+                            "{type_name}.set_position: Expected x, y, and z positions"
+                        )
+                    }
+                };
+
+                let this = ctx.instance()?;
+                send_transform_update(
+                    &this.update_transform,
+                    &this.entity,
+                    UpdateTransform::Position(position),
+                );
+
+                ctx.instance_result()
+            }
+
+            #[koto_method]
+            fn set_rotation(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                use KValue::Number;
+
+                let type_name = $name;
+                let rotation = match ctx.args {
+                    [Number(x), Number(y), Number(z)] => {
+                        Quat::from_euler(EulerRot::XYZ, x.into(), y.into(), z.into())
+                    }
+                    _ => {
+                        return runtime_error!(
+                            "{type_name}.set_rotation: Expected x, y, and z rotations in radians"
+                        )
+                    }
+                };
+
+                let this = ctx.instance()?;
+                send_transform_update(
+                    &this.update_transform,
+                    &this.entity,
+                    UpdateTransform::Rotation3d(rotation),
+                );
+
+                ctx.instance_result()
+            }
+
+            #[koto_method]
+            fn set_size(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                use KValue::Number;
+
+                let type_name = $name;
+                let size = match ctx.args {
+                    [Number(size)] => Vec3::splat(size.into()),
+                    [Number(x), Number(y), Number(z)] => Vec3::new(x.into(), y.into(), z.into()),
+                    _ => {
+                        return runtime_error!(
+                            "{type_name}.set_size: Expected a Number, or x, y, and z sizes"
+                        )
+                    }
+                };
+
+                let this = ctx.instance()?;
+                send_transform_update(
+                    &this.update_transform,
+                    &this.entity,
+                    UpdateTransform::Scale(size),
+                );
+
+                ctx.instance_result()
+            }
+
+            #[koto_method]
+            fn look_at(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+                use KValue::Number;
+
+                let type_name = $name;
+                let target = match ctx.args {
+                    [Number(x), Number(y), Number(z)] => Vec3::new(x.into(), y.into(), z.into()),
+                    _ => {
+                        return runtime_error!("{type_name}.look_at: Expected x, y, and z positions")
+                    }
+                };
+
+                let this = ctx.instance()?;
+                let rotation = this.entity.transform().looking_at(target, Vec3::Y).rotation;
+                send_transform_update(
+                    &this.update_transform,
+                    &this.entity,
+                    UpdateTransform::Rotation3d(rotation),
+                );
+
+                ctx.instance_result()
+            }
+        ] [$($rest)*]);
+    };
+}
+
+#[cfg(any(
+    feature = "group",
+    feature = "shape",
+    feature = "shape3d",
+    feature = "sprite",
+    feature = "text"
+))]
+pub(crate) use koto_entity_impl;
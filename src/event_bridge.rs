@@ -0,0 +1,73 @@
+//! Forwarding of Bevy events to Koto scripts
+
+use crate::{prelude::*, reflect::reflect_to_koto, runtime::ScriptHook};
+use bevy::{prelude::*, reflect::TypePath};
+
+/// Forwards Bevy events to the running script
+///
+/// Each forwarded event results in a call to the script's exported `on_event` function (if it
+/// exists), with the event's short type name and its payload as arguments. The payload is
+/// converted from the event's reflected value, see [reflect_to_koto].
+///
+/// Events are forwarded in the [KotoUpdate::PreUpdate] system set.
+///
+/// For example, after adding the plugin with
+/// `KotoEventBridgePlugin::default().forward::<LevelComplete>()`, a script could respond to
+/// the event with:
+///
+/// ```koto
+/// export on_event = |kind, payload|
+///   match kind
+///     'LevelComplete' then print 'Score: {payload.score}'
+/// ```
+#[derive(Default)]
+pub struct KotoEventBridgePlugin {
+    forwarders: Vec<fn(&mut App)>,
+}
+
+impl KotoEventBridgePlugin {
+    /// Forwards events of type `E` to the script
+    ///
+    /// The event is added to the app if it hasn't been added already.
+    #[must_use]
+    pub fn forward<E>(mut self) -> Self
+    where
+        E: Event + Reflect + TypePath,
+    {
+        self.forwarders.push(|app| {
+            app.add_event::<E>().add_systems(
+                KotoSchedule,
+                forward_events::<E>.in_set(KotoUpdate::PreUpdate),
+            );
+        });
+        self
+    }
+}
+
+impl Plugin for KotoEventBridgePlugin {
+    fn build(&self, app: &mut App) {
+        debug_assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+
+        for add_forwarder in &self.forwarders {
+            add_forwarder(app);
+        }
+    }
+}
+
+fn forward_events<E>(mut koto: ResMut<KotoRuntime>, mut events: EventReader<E>)
+where
+    E: Event + Reflect + TypePath,
+{
+    for event in events.read() {
+        if !koto.is_ready() {
+            continue;
+        }
+
+        let kind = E::short_type_path();
+        let payload = reflect_to_koto(event.as_partial_reflect());
+
+        if let Err(error) = koto.run_hook(ScriptHook::OnEvent, &[kind.into(), payload]) {
+            error!("Error in 'on_event':\n{error}");
+        }
+    }
+}
@@ -1,12 +1,16 @@
 //! 2D geometry utilities for Koto
 
 use crate::prelude::*;
-use bevy::prelude::*;
-pub use koto_geometry::Vec2 as KotoVec2;
+use bevy::{ecs::world::EntityWorldMut, prelude::*};
+pub use koto_geometry::{Rect as KotoRect, Vec2 as KotoVec2, Vec3 as KotoVec3};
 
 /// 2D geometry utilities for Koto
 ///
 /// The plugin adds the `geometry` module from `koto_geometry` to Koto's prelude.
+///
+/// The transforms of Koto entities are mirrored into their [KotoEntityMapping] during
+/// [KotoUpdate::PreUpdate], allowing scripts to read the current transform of an entity, e.g. after
+/// it has been moved by a physics system.
 pub struct KotoGeometryPlugin;
 
 impl Plugin for KotoGeometryPlugin {
@@ -14,35 +18,76 @@ impl Plugin for KotoGeometryPlugin {
         debug_assert!(app.is_plugin_added::<KotoRuntimePlugin>());
         debug_assert!(app.is_plugin_added::<KotoEntityPlugin>());
 
-        let (update_transform_sender, update_transform_receiver) =
-            koto_entity_channel::<UpdateTransform>();
+        app.register_koto_module("geometry", koto_geometry::make_module)
+            .add_koto_entity_event(update_transform)
+            .add_systems(KotoSchedule, sync_transforms.in_set(KotoUpdate::PreUpdate));
+    }
+}
 
-        app.insert_resource(update_transform_sender)
-            .insert_resource(update_transform_receiver)
-            .add_systems(Startup, on_startup)
-            .add_systems(Update, update_transform);
+fn update_transform(mut entity: EntityWorldMut, event: UpdateTransform) {
+    if let Some(mut transform) = entity.get_mut::<Transform>() {
+        event.apply(&mut transform);
     }
 }
 
-fn on_startup(koto: Res<KotoRuntime>) {
-    koto.prelude()
-        .insert("geometry", koto_geometry::make_module());
+fn sync_transforms(query: Query<(&KotoEntity, &Transform), Changed<Transform>>) {
+    for (koto_entity, transform) in &query {
+        koto_entity.entity.set_transform_snapshot(*transform);
+    }
 }
 
-fn update_transform(
-    channel: Res<KotoEntityReceiver<UpdateTransform>>,
-    mut q: Query<&mut Transform>,
+/// Sends a transform update for the given entity
+///
+/// The update is also applied to the entity's transform snapshot so that the change is visible to
+/// the script immediately.
+pub fn send_transform_update(
+    sender: &KotoEntitySender<UpdateTransform>,
+    entity: &KotoEntityMapping,
+    update: UpdateTransform,
 ) {
-    while let Some(event) = channel.receive() {
-        let mut transform = q.get_mut(event.entity.get()).unwrap();
-        match event.event {
-            UpdateTransform::Position(position) => transform.translation = position,
-            UpdateTransform::Rotation(rotation) => {
-                transform.rotation = Quat::from_rotation_z(rotation)
-            }
-            UpdateTransform::Scale(scale) => transform.scale = scale,
+    let mut transform = entity.transform();
+    update.apply(&mut transform);
+    entity.set_transform_snapshot(transform);
+
+    sender.send(KotoEntityEvent::new(entity.clone(), update));
+}
+
+/// Sends transform updates that copy the transform snapshot of one entity to another
+///
+/// This is useful when cloning entities, with the copy being applied in order with any other
+/// pending transform updates.
+pub fn send_transform_copy(
+    sender: &KotoEntitySender<UpdateTransform>,
+    source: &KotoEntityMapping,
+    target: &KotoEntityMapping,
+) {
+    let transform = source.transform();
+    for update in [
+        UpdateTransform::Position(transform.translation),
+        UpdateTransform::Rotation3d(transform.rotation),
+        UpdateTransform::Scale(transform.scale),
+    ] {
+        send_transform_update(sender, target, update);
+    }
+}
+
+// Converts a geometry.vec3 into a Bevy Vec3
+//
+// koto_geometry's Vec3 doesn't provide access to its inner value, so its components are read via
+// indexing.
+#[cfg(feature = "camera")]
+#[allow(clippy::result_large_err)]
+pub(crate) fn koto_to_bevy_vec3(v: &KotoVec3) -> koto::runtime::Result<Vec3> {
+    use koto::prelude::*;
+
+    let mut result = Vec3::ZERO;
+    for (i, component) in result.as_mut().iter_mut().enumerate() {
+        match v.index(&KValue::Number(i.into()))? {
+            KValue::Number(n) => *component = n.into(),
+            unexpected => return unexpected_type("Number", &unexpected),
         }
     }
+    Ok(result)
 }
 
 /// Event for updating the properties of an entity's transform
@@ -50,8 +95,30 @@ fn update_transform(
 pub enum UpdateTransform {
     /// Sets the transform's position
     Position(Vec3),
-    /// Sets the transform's rotation
+    /// Sets the transform's X and Y position, leaving its Z position unchanged
+    Position2d(Vec2),
+    /// Sets the transform's rotation around the Z axis
     Rotation(f32),
+    /// Sets the transform's rotation in 3D
+    Rotation3d(Quat),
     /// Sets the transform's scale
     Scale(Vec3),
+    /// Sets the transform's Z position, which determines the draw order of 2D entities
+    ZIndex(f32),
+}
+
+impl UpdateTransform {
+    /// Applies the update to the given transform
+    pub fn apply(&self, transform: &mut Transform) {
+        match *self {
+            Self::Position(position) => transform.translation = position,
+            Self::Position2d(position) => {
+                transform.translation = position.extend(transform.translation.z);
+            }
+            Self::Rotation(rotation) => transform.rotation = Quat::from_rotation_z(rotation),
+            Self::Rotation3d(rotation) => transform.rotation = rotation,
+            Self::Scale(scale) => transform.scale = scale,
+            Self::ZIndex(z) => transform.translation.z = z,
+        }
+    }
 }
@@ -0,0 +1,33 @@
+// A linear or radial gradient, used by bevy_koto's GradientMaterial
+
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+// Matches GRADIENT_MATERIAL_SAMPLES
+const SAMPLE_COUNT: u32 = 16u;
+
+struct GradientMaterial {
+    // Colors sampled at evenly spaced positions along the gradient
+    colors: array<vec4<f32>, SAMPLE_COUNT>,
+    // The direction of a linear gradient in UV space,
+    // scaled so that the gradient covers the mesh's UV range
+    direction: vec2<f32>,
+    is_radial: u32,
+}
+
+@group(2) @binding(0) var<uniform> material: GradientMaterial;
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    let offset = mesh.uv - vec2(0.5);
+
+    var t: f32;
+    if material.is_radial != 0u {
+        t = length(offset) * 2.0;
+    } else {
+        t = dot(offset, material.direction) + 0.5;
+    }
+
+    let x = clamp(t, 0.0, 1.0) * f32(SAMPLE_COUNT - 1u);
+    let i = min(u32(x), SAMPLE_COUNT - 2u);
+    return mix(material.colors[i], material.colors[i + 1u], x - f32(i));
+}
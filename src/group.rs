@@ -0,0 +1,173 @@
+//! Entity groups for bevy_koto
+
+use crate::{entity::koto_entity_impl, prelude::*};
+use bevy::prelude::*;
+use cloned::cloned;
+use koto::{derive::*, prelude::*, runtime::Result as KotoResult};
+
+/// Entity groups for bevy_koto
+///
+/// The plugin adds a `group` function to Koto's prelude, which spawns an empty parent entity and
+/// returns a `Group` object. Shapes and text can be added to the group with `group.add`, with the
+/// group's position, rotation, and scale then being applied to all of its children, e.g.
+///
+/// ```koto
+/// clock = group()
+/// clock.add shape.circle(), shape.square().set_size(0.1, 0.8)
+/// clock.set_position 2, 0
+/// ```
+///
+/// The group keeps its children alive while the group is referred to by the script. The children
+/// are arranged using [UpdateKotoEntity::AddChild], so when the group is despawned, any children
+/// that are still referred to by the script are detached rather than despawned.
+pub struct KotoGroupPlugin;
+
+impl Plugin for KotoGroupPlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+        assert!(app.is_plugin_added::<KotoEntityPlugin>());
+        assert!(app.is_plugin_added::<KotoGeometryPlugin>());
+
+        let (spawn_group_sender, spawn_group_receiver) = koto_channel::<SpawnGroup>();
+
+        app.insert_resource(spawn_group_sender)
+            .insert_resource(spawn_group_receiver)
+            .add_systems(Startup, on_startup)
+            .add_systems(KotoSchedule, spawn_groups.in_set(KotoUpdate::PostUpdate));
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn on_startup(
+    koto: ResMut<KotoRuntime>,
+    spawn_group: Res<KotoSender<SpawnGroup>>,
+    update_entity: Res<KotoEntitySender<UpdateKotoEntity>>,
+    update_transform: Res<KotoEntitySender<UpdateTransform>>,
+) {
+    koto.prelude().add_fn("group", {
+        cloned!(spawn_group, update_entity, update_transform);
+
+        move |ctx| {
+            if !ctx.args().is_empty() {
+                return unexpected_args("no arguments", ctx.args());
+            }
+
+            let entity = KotoEntityMapping::default();
+
+            let result: KObject = KotoGroup {
+                entity: entity.clone(),
+                children: Vec::new(),
+                update_entity: update_entity.clone(),
+                update_transform: update_transform.clone(),
+            }
+            .into();
+
+            spawn_group.send(SpawnGroup {
+                koto_entity: KotoEntity::new(result.clone(), entity),
+            });
+
+            Ok(result.into())
+        }
+    });
+}
+
+fn spawn_groups(channel: Res<KotoReceiver<SpawnGroup>>, mut commands: Commands) {
+    let _span = info_span!("koto_channel", channel = "SpawnGroup").entered();
+    while let Some(SpawnGroup { mut koto_entity }) = channel.receive() {
+        let bevy_entity = commands
+            .spawn((
+                Transform::default(),
+                Visibility::default(),
+                koto_entity.clone(),
+            ))
+            .id();
+        koto_entity.entity.assign_bevy_entity(bevy_entity);
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SpawnGroup {
+    koto_entity: KotoEntity,
+}
+
+#[derive(Clone, KotoType, KotoCopy)]
+#[koto(type_name = "Group")]
+struct KotoGroup {
+    entity: KotoEntityMapping,
+    // The group's children, kept here so that they aren't despawned while the group is in use
+    children: Vec<KObject>,
+    update_entity: KotoEntitySender<UpdateKotoEntity>,
+    update_transform: KotoEntitySender<UpdateTransform>,
+}
+
+impl KotoObject for KotoGroup {}
+
+koto_entity_impl! {
+    impl KotoGroup as "Group" with [entity, transform_2d] {
+        #[koto_method]
+        fn add(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            if ctx.args.is_empty() {
+                return runtime_error!("Group.add: Expected one or more entities");
+            }
+
+            let mut this = ctx.instance_mut()?;
+            for child in ctx.args {
+                let KValue::Object(child) = child else {
+                    return runtime_error!("Group.add: Expected one or more entities");
+                };
+                if !this.children.iter().any(|c| c.is_same_instance(child)) {
+                    this.children.push(child.clone());
+                }
+                this.update_entity.send(KotoEntityEvent::new(
+                    this.entity.clone(),
+                    UpdateKotoEntity::AddChild(child.clone()),
+                ));
+            }
+            drop(this);
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn children(&self) -> KValue {
+            KTuple::from(
+                self.children
+                    .iter()
+                    .cloned()
+                    .map(KValue::from)
+                    .collect::<Vec<_>>(),
+            )
+            .into()
+        }
+
+        #[koto_method]
+        fn set_scale(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            use KValue::Number;
+
+            // The Z scale is left at 1 so that the children's Z positions are preserved
+            let scale = match ctx.args {
+                [Number(scale)] => {
+                    let scale = f32::from(scale);
+                    Vec3::new(scale, scale, 1.0)
+                }
+                [Number(x), Number(y)] => Vec3::new(f32::from(x), f32::from(y), 1.0),
+                _ => return runtime_error!("Group.set_scale: Expected Numbers"),
+            };
+
+            let this = ctx.instance()?;
+            send_transform_update(
+                &this.update_transform,
+                &this.entity,
+                UpdateTransform::Scale(scale),
+            );
+
+            ctx.instance_result()
+        }
+    }
+}
+
+impl From<KotoGroup> for KValue {
+    fn from(group: KotoGroup) -> Self {
+        KObject::from(group).into()
+    }
+}
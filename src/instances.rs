@@ -0,0 +1,866 @@
+//! Batches of instanced shapes for Koto scripts
+
+use crate::{
+    entity::koto_entity_impl,
+    prelude::*,
+    shape::{koto_to_points, make_mesh, with_packed_values, Shape},
+};
+use bevy::{
+    asset::embedded_asset,
+    core_pipeline::{
+        core_2d::Transparent2d,
+        tonemapping::{DebandDither, Tonemapping},
+    },
+    ecs::system::{lifetimeless::SRes, SystemParamItem},
+    math::FloatOrd,
+    prelude::*,
+    render::{
+        batching::NoAutomaticBatching,
+        mesh::{
+            allocator::MeshAllocator, MeshVertexBufferLayoutRef, RenderMesh, RenderMeshBufferInfo,
+        },
+        render_asset::RenderAssets,
+        render_phase::{
+            AddRenderCommand, DrawFunctions, PhaseItem, PhaseItemExtraIndex, RenderCommand,
+            RenderCommandResult, SetItemPipeline, TrackedRenderPass, ViewSortedRenderPhases,
+        },
+        render_resource::{
+            binding_types::uniform_buffer, BindGroup, BindGroupEntries, BindGroupLayout,
+            BindGroupLayoutEntries, BufferUsages, PipelineCache, RawBufferVec,
+            RenderPipelineDescriptor, ShaderStages, SpecializedMeshPipeline,
+            SpecializedMeshPipelineError, SpecializedMeshPipelines, UniformBuffer, VertexAttribute,
+            VertexBufferLayout, VertexFormat, VertexStepMode,
+        },
+        renderer::{RenderDevice, RenderQueue},
+        sync_world::{MainEntity, MainEntityHashMap},
+        view::{ExtractedView, NoFrustumCulling, RenderLayers, RenderVisibleEntities},
+        Extract, Render, RenderApp, RenderSet,
+    },
+    sprite::{tonemapping_pipeline_key, Mesh2dPipeline, Mesh2dPipelineKey, SetMesh2dViewBindGroup},
+};
+use cloned::cloned;
+use koto::{derive::*, prelude::*, runtime::Result as KotoResult};
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+/// Batches of instanced shapes for bevy_koto
+///
+/// The plugin adds an `instanced` function to the `shape` module, which spawns a batch of
+/// identical shapes that are drawn with a single instanced draw call. Spawning thousands of
+/// individual shapes is expensive, so batches should be used when a script needs large numbers of
+/// simple shapes, e.g. for particles.
+///
+/// `shape.instanced(base, count)` takes the name of the base shape (`'circle'`, `'square'`, or
+/// `'triangle'`) or a number of sides for a regular polygon, and returns an `Instances` object.
+///
+/// Each instance has a position, scale, rotation, and color, which can be set individually with
+/// `set_position(i, x, y)`, `set_scale(i, scale)`, `set_rotation(i, radians)`, and
+/// `set_color(i, color)`. The properties of many instances can be updated at once by passing a
+/// packed list of values to `set_positions`, `set_scales`, `set_rotations`, and `set_colors`,
+/// e.g.
+///
+/// ```koto
+/// dots = shape.instanced 'circle', 1000
+/// dots.set_scales (0..1000).each(|_| 0.05).to_tuple()
+///
+/// export update = ||
+///   dots.set_positions positions # x, y, x, y, ...
+/// ```
+///
+/// Positions can be provided as a flat list of numbers or as `geometry.vec2` values, and colors
+/// can be provided as `Color` values or as packed `r, g, b, a` numbers. Lists that are shorter
+/// than the batch only update the first instances.
+///
+/// The instance data is shared directly with the render world rather than being sent as events.
+/// The base shape's mesh is uploaded once, and the instance data is copied into a per-instance
+/// vertex buffer in frames where it has changed, with the instances being transformed in the
+/// vertex shader. Batches are alpha blended, aren't frustum culled, and don't support picking or
+/// collision.
+pub struct KotoInstancesPlugin;
+
+impl Plugin for KotoInstancesPlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+        assert!(app.is_plugin_added::<KotoEntityPlugin>());
+        assert!(app.is_plugin_added::<KotoShapePlugin>());
+
+        let (spawn_instances_sender, spawn_instances_receiver) = koto_channel::<SpawnInstances>();
+
+        embedded_asset!(app, "instances.wgsl");
+
+        app.insert_resource(spawn_instances_sender)
+            .insert_resource(spawn_instances_receiver)
+            .add_systems(Startup, on_startup.after(crate::shape::on_startup))
+            .add_systems(KotoSchedule, spawn_instances.in_set(KotoUpdate::PostUpdate));
+
+        // Instances are only drawn when rendering is enabled
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .add_render_command::<Transparent2d, DrawInstances>()
+            .init_resource::<SpecializedMeshPipelines<InstancePipeline>>()
+            .init_resource::<ExtractedInstanceBatches>()
+            .init_resource::<GpuInstanceBatches>()
+            .add_systems(ExtractSchedule, extract_instance_batches)
+            .add_systems(
+                Render,
+                (
+                    queue_instance_batches.in_set(RenderSet::QueueMeshes),
+                    prepare_instance_batches.in_set(RenderSet::PrepareResources),
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.init_resource::<InstancePipeline>();
+        }
+    }
+}
+
+// The maximum number of instances in a batch
+const MAX_INSTANCES: usize = 1 << 20;
+
+#[allow(clippy::result_large_err)]
+fn on_startup(
+    koto: Res<KotoRuntime>,
+    spawn_instances: Res<KotoSender<SpawnInstances>>,
+    update_entity: Res<KotoEntitySender<UpdateKotoEntity>>,
+    update_transform: Res<KotoEntitySender<UpdateTransform>>,
+) {
+    let Some(KValue::Map(shape_module)) = koto.prelude().get("shape") else {
+        panic!("Missing shape module in the Koto prelude");
+    };
+
+    shape_module.add_fn("instanced", {
+        cloned!(spawn_instances, update_entity, update_transform);
+
+        move |ctx| {
+            let (base, count) = match ctx.args() {
+                [KValue::Str(name), KValue::Number(count)] => {
+                    let base = match name.as_str() {
+                        "circle" => Shape::Circle,
+                        "square" => Shape::Rect(1.0, 1.0),
+                        "triangle" => Shape::Polygon(3),
+                        _ => return runtime_error!("shape.instanced: Unknown shape '{name}'"),
+                    };
+                    (base, count)
+                }
+                [KValue::Number(sides), KValue::Number(count)] if *sides > 1 => {
+                    (Shape::Polygon(sides.into()), count)
+                }
+                unexpected => {
+                    return unexpected_args("a base shape and a number of instances", unexpected)
+                }
+            };
+
+            let count = match usize::try_from(i64::from(count)) {
+                Ok(count) if count <= MAX_INSTANCES => count,
+                _ => {
+                    return runtime_error!(
+                        "shape.instanced: Expected a number of instances up to {MAX_INSTANCES}"
+                    )
+                }
+            };
+
+            let entity = KotoEntityMapping::default();
+            let buffer = InstanceBuffer::new(count);
+
+            let result: KObject = KotoInstances {
+                entity: entity.clone(),
+                buffer: buffer.clone(),
+                update_entity: update_entity.clone(),
+                update_transform: update_transform.clone(),
+            }
+            .into();
+
+            spawn_instances.send(SpawnInstances {
+                koto_entity: KotoEntity::new(result.clone(), entity),
+                base,
+                buffer,
+            });
+
+            Ok(result.into())
+        }
+    });
+}
+
+fn spawn_instances(
+    channel: Res<KotoReceiver<SpawnInstances>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut commands: Commands,
+) {
+    let _span = info_span!("koto_channel", channel = "SpawnInstances").entered();
+    while let Some(SpawnInstances {
+        mut koto_entity,
+        base,
+        buffer,
+    }) = channel.receive()
+    {
+        let bevy_entity = commands
+            .spawn((
+                // The base shape's mesh, which is drawn once per instance
+                Mesh2d(meshes.add(make_mesh(&base))),
+                Transform::default(),
+                Visibility::default(),
+                RenderLayers::layer(0),
+                // The batch's bounds change as the instances move
+                NoFrustumCulling,
+                // Each batch is drawn with its own instance buffer
+                NoAutomaticBatching,
+                InstanceBatch { buffer },
+                koto_entity.clone(),
+            ))
+            .id();
+        koto_entity.entity.assign_bevy_entity(bevy_entity);
+    }
+}
+
+#[derive(Clone)]
+struct SpawnInstances {
+    koto_entity: KotoEntity,
+    base: Shape,
+    buffer: InstanceBuffer,
+}
+
+#[derive(Component)]
+struct InstanceBatch {
+    buffer: InstanceBuffer,
+}
+
+// A visible batch, extracted into the render world
+struct ExtractedInstanceBatch {
+    mesh_asset_id: AssetId<Mesh>,
+    world_from_local: Mat4,
+    buffer: InstanceBuffer,
+}
+
+#[derive(Resource, Default, Deref, DerefMut)]
+struct ExtractedInstanceBatches(MainEntityHashMap<ExtractedInstanceBatch>);
+
+// The GPU buffers for a batch, kept between frames so that unchanged instances aren't re-uploaded
+struct GpuInstanceBatch {
+    instances: RawBufferVec<InstanceVertex>,
+    world_from_local: UniformBuffer<Mat4>,
+    bind_group: Option<BindGroup>,
+}
+
+#[derive(Resource, Default, Deref, DerefMut)]
+struct GpuInstanceBatches(MainEntityHashMap<GpuInstanceBatch>);
+
+// The per-instance vertex data, matching the instance buffer layout in InstancePipeline:
+// `[x, y, scale, rotation, r, g, b, a]`
+type InstanceVertex = [f32; 8];
+
+// The components of a batch that are extracted into the render world
+type BatchComponents = (
+    Entity,
+    &'static ViewVisibility,
+    &'static GlobalTransform,
+    &'static Mesh2d,
+    &'static InstanceBatch,
+);
+
+fn extract_instance_batches(
+    mut batches: ResMut<ExtractedInstanceBatches>,
+    query: Extract<Query<BatchComponents>>,
+) {
+    batches.clear();
+
+    for (entity, visibility, transform, mesh, batch) in &query {
+        if !visibility.get() {
+            continue;
+        }
+
+        batches.insert(
+            entity.into(),
+            ExtractedInstanceBatch {
+                mesh_asset_id: mesh.id(),
+                world_from_local: transform.compute_matrix(),
+                buffer: batch.buffer.clone(),
+            },
+        );
+    }
+}
+
+fn prepare_instance_batches(
+    batches: Res<ExtractedInstanceBatches>,
+    mut gpu_batches: ResMut<GpuInstanceBatches>,
+    pipeline: Res<InstancePipeline>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    gpu_batches.retain(|entity, _| batches.contains_key(entity));
+
+    for (entity, batch) in batches.iter() {
+        let is_new = !gpu_batches.contains_key(entity);
+        let gpu_batch = gpu_batches
+            .entry(*entity)
+            .or_insert_with(|| GpuInstanceBatch {
+                instances: RawBufferVec::new(BufferUsages::VERTEX),
+                world_from_local: UniformBuffer::default(),
+                bind_group: None,
+            });
+
+        // The instance buffer is only updated when the script has changed the instance data
+        let mut data = batch.buffer.0.lock();
+        if data.is_dirty || is_new {
+            data.is_dirty = false;
+            gpu_batch.instances.clear();
+            for i in 0..data.count() {
+                let Vec2 { x, y } = data.positions[i];
+                let [r, g, b, a] = data.colors[i];
+                gpu_batch
+                    .instances
+                    .push([x, y, data.scales[i], data.rotations[i], r, g, b, a]);
+            }
+            drop(data);
+            gpu_batch
+                .instances
+                .write_buffer(&render_device, &render_queue);
+        } else {
+            drop(data);
+        }
+
+        gpu_batch.world_from_local.set(batch.world_from_local);
+        gpu_batch
+            .world_from_local
+            .write_buffer(&render_device, &render_queue);
+
+        if gpu_batch.bind_group.is_none() {
+            if let Some(binding) = gpu_batch.world_from_local.binding() {
+                gpu_batch.bind_group = Some(render_device.create_bind_group(
+                    "koto_instances_bind_group",
+                    &pipeline.batch_layout,
+                    &BindGroupEntries::single(binding),
+                ));
+            }
+        }
+    }
+}
+
+// The components of the views that batches are queued for
+type InstanceView = (
+    Entity,
+    &'static RenderVisibleEntities,
+    &'static ExtractedView,
+    &'static Msaa,
+    Option<&'static Tonemapping>,
+    Option<&'static DebandDither>,
+);
+
+#[allow(clippy::too_many_arguments)]
+fn queue_instance_batches(
+    draw_functions: Res<DrawFunctions<Transparent2d>>,
+    pipeline: Res<InstancePipeline>,
+    mut pipelines: ResMut<SpecializedMeshPipelines<InstancePipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    render_meshes: Res<RenderAssets<RenderMesh>>,
+    batches: Res<ExtractedInstanceBatches>,
+    mut phases: ResMut<ViewSortedRenderPhases<Transparent2d>>,
+    views: Query<InstanceView>,
+) {
+    if batches.is_empty() {
+        return;
+    }
+
+    let draw_function = draw_functions.read().id::<DrawInstances>();
+
+    for (view_entity, visible_entities, view, msaa, tonemapping, dither) in &views {
+        let Some(phase) = phases.get_mut(&view_entity) else {
+            continue;
+        };
+
+        let mut view_key = Mesh2dPipelineKey::from_msaa_samples(msaa.samples())
+            | Mesh2dPipelineKey::from_hdr(view.hdr)
+            | Mesh2dPipelineKey::BLEND_ALPHA;
+        if !view.hdr {
+            if let Some(tonemapping) = tonemapping {
+                view_key |= Mesh2dPipelineKey::TONEMAP_IN_SHADER;
+                view_key |= tonemapping_pipeline_key(*tonemapping);
+            }
+            if let Some(DebandDither::Enabled) = dither {
+                view_key |= Mesh2dPipelineKey::DEBAND_DITHER;
+            }
+        }
+
+        for (render_entity, visible_entity) in visible_entities.iter::<With<Mesh2d>>() {
+            let Some(batch) = batches.get(visible_entity) else {
+                continue;
+            };
+            let Some(mesh) = render_meshes.get(batch.mesh_asset_id) else {
+                continue;
+            };
+
+            let key =
+                view_key | Mesh2dPipelineKey::from_primitive_topology(mesh.primitive_topology());
+            let pipeline_id =
+                match pipelines.specialize(&pipeline_cache, &pipeline, key, &mesh.layout) {
+                    Ok(id) => id,
+                    Err(error) => {
+                        error!("Failed to specialize the instance pipeline: {error}");
+                        continue;
+                    }
+                };
+
+            phase.add(Transparent2d {
+                sort_key: FloatOrd(batch.world_from_local.w_axis.z),
+                entity: (*render_entity, *visible_entity),
+                pipeline: pipeline_id,
+                draw_function,
+                batch_range: 0..1,
+                extra_index: PhaseItemExtraIndex::NONE,
+            });
+        }
+    }
+}
+
+// The render pipeline for batches, which extends Bevy's 2d mesh pipeline with an instance buffer
+#[derive(Resource)]
+struct InstancePipeline {
+    mesh2d_pipeline: Mesh2dPipeline,
+    // The layout of the bind group containing the batch's transform
+    batch_layout: BindGroupLayout,
+    shader: Handle<Shader>,
+}
+
+impl FromWorld for InstancePipeline {
+    fn from_world(world: &mut World) -> Self {
+        let batch_layout = world.resource::<RenderDevice>().create_bind_group_layout(
+            "koto_instances_layout",
+            &BindGroupLayoutEntries::single(ShaderStages::VERTEX, uniform_buffer::<Mat4>(false)),
+        );
+
+        Self {
+            mesh2d_pipeline: Mesh2dPipeline::from_world(world),
+            batch_layout,
+            shader: world.load_asset("embedded://bevy_koto/instances.wgsl"),
+        }
+    }
+}
+
+impl SpecializedMeshPipeline for InstancePipeline {
+    type Key = Mesh2dPipelineKey;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayoutRef,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        let mut descriptor = self.mesh2d_pipeline.specialize(key, layout)?;
+
+        descriptor.label = Some("koto_instances_pipeline".into());
+        descriptor.layout = vec![
+            self.mesh2d_pipeline.view_layout.clone(),
+            self.batch_layout.clone(),
+        ];
+        descriptor.vertex.shader = self.shader.clone();
+        // The instance attributes follow the mesh attributes used by the 2d mesh pipeline
+        descriptor.vertex.buffers.push(VertexBufferLayout {
+            array_stride: size_of::<InstanceVertex>() as u64,
+            step_mode: VertexStepMode::Instance,
+            attributes: vec![
+                VertexAttribute {
+                    format: VertexFormat::Float32x2,
+                    offset: 0,
+                    shader_location: 5,
+                },
+                VertexAttribute {
+                    format: VertexFormat::Float32,
+                    offset: 8,
+                    shader_location: 6,
+                },
+                VertexAttribute {
+                    format: VertexFormat::Float32,
+                    offset: 12,
+                    shader_location: 7,
+                },
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 16,
+                    shader_location: 8,
+                },
+            ],
+        });
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            fragment.shader = self.shader.clone();
+        }
+
+        Ok(descriptor)
+    }
+}
+
+type DrawInstances = (
+    SetItemPipeline,
+    SetMesh2dViewBindGroup<0>,
+    DrawInstanceBatch<1>,
+);
+
+// Sets the batch's bind group and draws the base mesh once per instance
+struct DrawInstanceBatch<const I: usize>;
+
+impl<P: PhaseItem, const I: usize> RenderCommand<P> for DrawInstanceBatch<I> {
+    type Param = (
+        SRes<RenderAssets<RenderMesh>>,
+        SRes<MeshAllocator>,
+        SRes<ExtractedInstanceBatches>,
+        SRes<GpuInstanceBatches>,
+    );
+    type ViewQuery = ();
+    type ItemQuery = ();
+
+    fn render<'w>(
+        item: &P,
+        _view: (),
+        _entity: Option<()>,
+        (meshes, mesh_allocator, batches, gpu_batches): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let main_entity: MainEntity = item.main_entity();
+        let Some(batch) = batches.into_inner().get(&main_entity) else {
+            return RenderCommandResult::Skip;
+        };
+        let Some(gpu_batch) = gpu_batches.into_inner().get(&main_entity) else {
+            return RenderCommandResult::Skip;
+        };
+        let (Some(bind_group), Some(instances)) =
+            (&gpu_batch.bind_group, gpu_batch.instances.buffer())
+        else {
+            return RenderCommandResult::Skip;
+        };
+        let Some(mesh) = meshes.into_inner().get(batch.mesh_asset_id) else {
+            return RenderCommandResult::Skip;
+        };
+        let mesh_allocator = mesh_allocator.into_inner();
+        let Some(vertex_slice) = mesh_allocator.mesh_vertex_slice(&batch.mesh_asset_id) else {
+            return RenderCommandResult::Skip;
+        };
+
+        let instance_range = 0..gpu_batch.instances.len() as u32;
+
+        pass.set_bind_group(I, bind_group, &[]);
+        pass.set_vertex_buffer(0, vertex_slice.buffer.slice(..));
+        pass.set_vertex_buffer(1, instances.slice(..));
+
+        match &mesh.buffer_info {
+            RenderMeshBufferInfo::Indexed {
+                index_format,
+                count,
+            } => {
+                let Some(index_slice) = mesh_allocator.mesh_index_slice(&batch.mesh_asset_id)
+                else {
+                    return RenderCommandResult::Skip;
+                };
+
+                pass.set_index_buffer(index_slice.buffer.slice(..), 0, *index_format);
+                pass.draw_indexed(
+                    index_slice.range.start..(index_slice.range.start + count),
+                    vertex_slice.range.start as i32,
+                    instance_range,
+                );
+            }
+            RenderMeshBufferInfo::NonIndexed => {
+                pass.draw(vertex_slice.range, instance_range);
+            }
+        }
+
+        RenderCommandResult::Success
+    }
+}
+
+// The per-instance data, shared between the Instances object and the batch's entity
+#[derive(Clone)]
+struct InstanceBuffer(Arc<Mutex<InstanceData>>);
+
+impl InstanceBuffer {
+    fn new(count: usize) -> Self {
+        Self(Arc::new(Mutex::new(InstanceData {
+            positions: vec![Vec2::ZERO; count],
+            scales: vec![1.0; count],
+            rotations: vec![0.0; count],
+            colors: vec![[1.0; 4]; count],
+            is_dirty: true,
+        })))
+    }
+}
+
+struct InstanceData {
+    positions: Vec<Vec2>,
+    scales: Vec<f32>,
+    rotations: Vec<f32>,
+    // Colors in linear RGBA
+    colors: Vec<[f32; 4]>,
+    is_dirty: bool,
+}
+
+impl InstanceData {
+    fn count(&self) -> usize {
+        self.positions.len()
+    }
+}
+
+impl std::fmt::Debug for InstanceBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InstanceBuffer")
+            .field("count", &self.0.lock().count())
+            .finish()
+    }
+}
+
+#[derive(Clone, KotoType, KotoCopy)]
+#[koto(type_name = "Instances")]
+struct KotoInstances {
+    entity: KotoEntityMapping,
+    buffer: InstanceBuffer,
+    update_entity: KotoEntitySender<UpdateKotoEntity>,
+    update_transform: KotoEntitySender<UpdateTransform>,
+}
+
+impl KotoObject for KotoInstances {}
+
+koto_entity_impl! {
+    impl KotoInstances as "Instances" with [entity, layer, z_index] {
+        #[koto_method]
+        fn count(&self) -> KValue {
+            self.buffer.0.lock().count().into()
+        }
+
+        #[koto_method]
+        fn set_position(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let (index, position) = match ctx.args {
+                [KValue::Number(index), position @ ..] => match koto_to_points(position).as_deref()
+                {
+                    Some(&[position]) => (index, position),
+                    _ => {
+                        return runtime_error!(
+                            "Instances.set_position: Expected an index and a position"
+                        )
+                    }
+                },
+                _ => {
+                    return runtime_error!(
+                        "Instances.set_position: Expected an index and a position"
+                    )
+                }
+            };
+
+            let this = ctx.instance()?;
+            let mut data = this.buffer.0.lock();
+            let i = instance_index("set_position", index, data.count())?;
+            data.positions[i] = position;
+            data.is_dirty = true;
+            drop(data);
+            drop(this);
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_scale(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let (index, scale) = match ctx.args {
+                [KValue::Number(index), KValue::Number(scale)] => (index, f32::from(scale)),
+                _ => return runtime_error!("Instances.set_scale: Expected an index and a scale"),
+            };
+
+            let this = ctx.instance()?;
+            let mut data = this.buffer.0.lock();
+            let i = instance_index("set_scale", index, data.count())?;
+            data.scales[i] = scale;
+            data.is_dirty = true;
+            drop(data);
+            drop(this);
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_rotation(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let (index, rotation) = match ctx.args {
+                [KValue::Number(index), KValue::Number(rotation)] => (index, f32::from(rotation)),
+                _ => {
+                    return runtime_error!(
+                        "Instances.set_rotation: Expected an index and a rotation in radians"
+                    )
+                }
+            };
+
+            let this = ctx.instance()?;
+            let mut data = this.buffer.0.lock();
+            let i = instance_index("set_rotation", index, data.count())?;
+            data.rotations[i] = rotation;
+            data.is_dirty = true;
+            drop(data);
+            drop(this);
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_color(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let (index, color) = match ctx.args {
+                [KValue::Number(index), color @ ..] => {
+                    match koto_to_linear_colors(color).as_deref() {
+                        Some(&[color]) => (index, color),
+                        _ => {
+                            return runtime_error!(
+                                "Instances.set_color: Expected an index and a Color"
+                            )
+                        }
+                    }
+                }
+                _ => return runtime_error!("Instances.set_color: Expected an index and a Color"),
+            };
+
+            let this = ctx.instance()?;
+            let mut data = this.buffer.0.lock();
+            let i = instance_index("set_color", index, data.count())?;
+            data.colors[i] = color;
+            data.is_dirty = true;
+            drop(data);
+            drop(this);
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_positions(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let Some(positions) = with_packed_values(ctx.args, koto_to_points).flatten() else {
+                return runtime_error!(
+                    "Instances.set_positions: Expected a list of numbers or Vec2s"
+                );
+            };
+
+            let this = ctx.instance()?;
+            let mut data = this.buffer.0.lock();
+            check_packed_count("set_positions", positions.len(), data.count())?;
+            data.positions[..positions.len()].copy_from_slice(&positions);
+            data.is_dirty = true;
+            drop(data);
+            drop(this);
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_scales(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let Some(scales) = with_packed_values(ctx.args, koto_to_numbers).flatten() else {
+                return runtime_error!("Instances.set_scales: Expected a list of numbers");
+            };
+
+            let this = ctx.instance()?;
+            let mut data = this.buffer.0.lock();
+            check_packed_count("set_scales", scales.len(), data.count())?;
+            data.scales[..scales.len()].copy_from_slice(&scales);
+            data.is_dirty = true;
+            drop(data);
+            drop(this);
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_rotations(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let Some(rotations) = with_packed_values(ctx.args, koto_to_numbers).flatten() else {
+                return runtime_error!("Instances.set_rotations: Expected a list of numbers");
+            };
+
+            let this = ctx.instance()?;
+            let mut data = this.buffer.0.lock();
+            check_packed_count("set_rotations", rotations.len(), data.count())?;
+            data.rotations[..rotations.len()].copy_from_slice(&rotations);
+            data.is_dirty = true;
+            drop(data);
+            drop(this);
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_colors(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let Some(colors) = with_packed_values(ctx.args, koto_to_linear_colors).flatten() else {
+                return runtime_error!(
+                    "Instances.set_colors: Expected a list of Colors or numbers"
+                );
+            };
+
+            let this = ctx.instance()?;
+            let mut data = this.buffer.0.lock();
+            check_packed_count("set_colors", colors.len(), data.count())?;
+            data.colors[..colors.len()].copy_from_slice(&colors);
+            data.is_dirty = true;
+            drop(data);
+            drop(this);
+
+            ctx.instance_result()
+        }
+    }
+}
+
+impl From<KotoInstances> for KValue {
+    fn from(instances: KotoInstances) -> Self {
+        KObject::from(instances).into()
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn instance_index(method: &str, index: &KNumber, count: usize) -> KotoResult<usize> {
+    match usize::try_from(i64::from(index)) {
+        Ok(i) if i < count => Ok(i),
+        _ => runtime_error!("Instances.{method}: Index {index} is out of range (count: {count})"),
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn check_packed_count(method: &str, values: usize, count: usize) -> KotoResult<()> {
+    if values > count {
+        runtime_error!("Instances.{method}: Expected up to {count} values, found {values}")
+    } else {
+        Ok(())
+    }
+}
+
+fn koto_to_numbers(values: &[KValue]) -> Option<Vec<f32>> {
+    values
+        .iter()
+        .map(|value| match value {
+            KValue::Number(n) => Some(f32::from(n)),
+            _ => None,
+        })
+        .collect()
+}
+
+// Converts Colors, or packed r, g, b, a numbers in sRGB, into linear RGBA values
+fn koto_to_linear_colors(values: &[KValue]) -> Option<Vec<[f32; 4]>> {
+    if values
+        .iter()
+        .all(|value| matches!(value, KValue::Number(_)))
+    {
+        if !values.len().is_multiple_of(4) {
+            return None;
+        }
+        values
+            .chunks(4)
+            .map(|rgba| {
+                let rgba = koto_to_numbers(rgba)?;
+                Some(
+                    Color::srgba(rgba[0], rgba[1], rgba[2], rgba[3])
+                        .to_linear()
+                        .to_f32_array(),
+                )
+            })
+            .collect()
+    } else {
+        values
+            .iter()
+            .map(|value| match value {
+                KValue::Object(o) => {
+                    let color = o.cast::<KotoColor>().ok()?;
+                    Some(koto_to_bevy_color(&color).to_linear().to_f32_array())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,48 @@
+// Batches of instanced shapes, used by bevy_koto's KotoInstancesPlugin
+
+#import bevy_sprite::mesh2d_view_bindings::view
+
+#ifdef TONEMAP_IN_SHADER
+#import bevy_core_pipeline::tonemapping
+#endif
+
+// The transform of the batch's entity
+@group(1) @binding(0) var<uniform> world_from_local: mat4x4<f32>;
+
+struct Vertex {
+    // The base shape's vertex position
+    @location(0) position: vec3<f32>,
+    // The instance's properties, matching InstanceVertex
+    @location(5) offset: vec2<f32>,
+    @location(6) scale: f32,
+    @location(7) rotation: f32,
+    @location(8) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let scaled = vertex.position.xy * vertex.scale;
+    let c = cos(vertex.rotation);
+    let s = sin(vertex.rotation);
+    let rotated = vec2(scaled.x * c - scaled.y * s, scaled.x * s + scaled.y * c);
+    let local = vec4(rotated + vertex.offset, vertex.position.z, 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = view.clip_from_world * world_from_local * local;
+    out.color = vertex.color;
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    var color = in.color;
+#ifdef TONEMAP_IN_SHADER
+    color = tonemapping::tone_mapping(color, view.color_grading);
+#endif
+    return color;
+}
@@ -4,22 +4,73 @@
 
 #![warn(missing_docs)]
 
+pub mod app;
+pub mod convert;
 pub mod entity;
+pub mod memory;
+pub mod metadata;
+pub mod modules;
 pub mod prelude;
+pub mod reflect;
 pub mod runtime;
+pub mod time;
 
+#[cfg(feature = "browser")]
+pub mod browser;
 #[cfg(feature = "camera")]
 pub mod camera;
+#[cfg(feature = "camera3d")]
+pub mod camera3d;
+#[cfg(feature = "collision")]
+pub mod collision;
 #[cfg(feature = "color")]
 pub mod color;
+#[cfg(feature = "components")]
+pub mod components;
+#[cfg(feature = "console")]
+pub mod console;
+#[cfg(feature = "data")]
+pub mod data;
+#[cfg(feature = "diagnostics")]
+pub mod diagnostics;
+#[cfg(feature = "events")]
+pub mod event_bridge;
 #[cfg(feature = "geometry")]
 pub mod geometry;
+#[cfg(feature = "group")]
+pub mod group;
+#[cfg(feature = "instances")]
+pub mod instances;
+#[cfg(feature = "palette")]
+pub mod palette;
+#[cfg(feature = "picking")]
+pub mod picking;
+#[cfg(feature = "post")]
+pub mod post;
 #[cfg(feature = "random")]
 pub mod random;
+#[cfg(feature = "resources")]
+pub mod resources;
+#[cfg(feature = "scheduler")]
+pub mod scheduler;
+#[cfg(feature = "script_components")]
+pub mod script_component;
+#[cfg(feature = "session")]
+pub mod session;
 #[cfg(feature = "shape")]
 pub mod shape;
+#[cfg(feature = "shape3d")]
+pub mod shape3d;
+#[cfg(feature = "spatial")]
+pub mod spatial;
+#[cfg(feature = "sprite")]
+pub mod sprite;
+#[cfg(feature = "tasks")]
+pub mod tasks;
 #[cfg(feature = "text")]
 pub mod text;
+#[cfg(feature = "text3d")]
+pub mod text3d;
 #[cfg(feature = "window")]
 pub mod window;
 
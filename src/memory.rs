@@ -0,0 +1,119 @@
+//! Memory usage reporting for Koto scripts
+
+use crate::prelude::*;
+use bevy::prelude::*;
+use koto::prelude::*;
+use std::{collections::HashSet, mem::size_of};
+
+/// Estimated memory usage of the running script
+///
+/// The stats are updated each frame after the script's update functions have been called, by
+/// walking the values that are reachable from the script's user data and exports.
+///
+/// Koto doesn't track its allocations, so the byte count is an estimate based on the number of
+/// values and the length of strings. It's intended for detecting growth over time (e.g. a script
+/// that keeps adding values to its user data), rather than for precise measurements.
+///
+/// See [KotoRuntimePlugin::with_memory_limit] for stopping scripts that exceed a memory budget.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct KotoMemoryStats {
+    /// The number of reachable values
+    pub values: usize,
+    /// The number of reachable lists and tuples
+    pub lists: usize,
+    /// The number of reachable maps
+    pub maps: usize,
+    /// The number of reachable objects
+    pub objects: usize,
+    /// The total length in bytes of reachable strings
+    pub string_bytes: usize,
+    /// The estimated number of bytes used by the reachable values
+    pub estimated_bytes: usize,
+}
+
+impl KotoMemoryStats {
+    fn add_values(&mut self, values: &[KValue], visited: &mut HashSet<usize>) {
+        for value in values {
+            self.add_value(value, visited);
+        }
+    }
+
+    fn add_value(&mut self, value: &KValue, visited: &mut HashSet<usize>) {
+        self.values += 1;
+        self.estimated_bytes += size_of::<KValue>();
+
+        match value {
+            KValue::Str(s) => {
+                self.string_bytes += s.len();
+                self.estimated_bytes += s.len();
+            }
+            KValue::List(list) => {
+                let data = list.data();
+                // Containers are shared by reference, so each one is only counted once
+                if visited.insert(data.as_ptr() as usize) {
+                    self.lists += 1;
+                    self.add_values(&data, visited);
+                }
+            }
+            KValue::Tuple(tuple) => {
+                self.lists += 1;
+                self.add_values(tuple, visited);
+            }
+            KValue::Map(map) => {
+                let data = map.data();
+                if visited.insert(&*data as *const _ as usize) {
+                    self.maps += 1;
+                    for (key, value) in data.iter() {
+                        self.add_value(key.value(), visited);
+                        self.add_value(value, visited);
+                    }
+                }
+            }
+            KValue::Function(f) => {
+                if let Some(captures) = &f.captures {
+                    self.add_value(&KValue::List(captures.clone()), visited);
+                }
+            }
+            KValue::Object(_) => self.objects += 1,
+            _ => {}
+        }
+    }
+}
+
+/// The memory limit that was set with [KotoRuntimePlugin::with_memory_limit]
+#[derive(Resource)]
+pub(crate) struct KotoMemoryLimit(pub Option<usize>);
+
+pub(crate) fn update_memory_stats(
+    mut koto: ResMut<KotoRuntime>,
+    mut stats: ResMut<KotoMemoryStats>,
+    limit: Res<KotoMemoryLimit>,
+    mut script_error: EventWriter<KotoScriptError>,
+) {
+    if !koto.is_ready() {
+        return;
+    }
+
+    let mut new_stats = KotoMemoryStats::default();
+    let mut visited = HashSet::new();
+    new_stats.add_value(koto.user_data(), &mut visited);
+    new_stats.add_value(&KValue::Map(koto.exports().clone()), &mut visited);
+    *stats = new_stats;
+
+    if let Some(limit) = limit.0 {
+        if stats.estimated_bytes > limit {
+            let error = KotoScriptError {
+                phase: ScriptPhase::MemoryCheck,
+                message: format!(
+                    "The script's estimated memory usage ({} bytes) exceeds the limit of {limit} bytes",
+                    stats.estimated_bytes
+                ),
+                span: None,
+                script_path: koto.script_path().map(ToOwned::to_owned),
+            };
+            error!("{error}");
+            script_error.send(error);
+            koto.stop();
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Metadata that's parsed from a script's header
+
+use std::collections::HashMap;
+
+/// Metadata that's parsed from the header of a [KotoScript](crate::runtime::KotoScript)
+///
+/// Metadata is provided in the comments at the start of a script, with each line starting with
+/// `#%` followed by a key and a value, e.g.
+///
+/// ```koto
+/// # Lots of scrolling squares
+/// #% title: Scrolling Squares
+/// #% author: irh
+/// #% tags: shapes, animation
+/// ```
+///
+/// Only the comments before the script's first line of code are checked. Keys are
+/// case-insensitive, and `tags` are separated by commas. Keys other than `title`, `author`,
+/// `description`, and `tags` are added to [ScriptMetadata::extra].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScriptMetadata {
+    /// The script's title
+    pub title: Option<String>,
+    /// The script's author
+    pub author: Option<String>,
+    /// A description of the script
+    pub description: Option<String>,
+    /// Tags that can be used to group scripts together
+    pub tags: Vec<String>,
+    /// Any other entries found in the script's header, with lowercase keys
+    pub extra: HashMap<String, String>,
+}
+
+impl ScriptMetadata {
+    /// Parses the metadata from the header of the given script
+    pub fn parse(script: &str) -> Self {
+        let mut result = Self::default();
+
+        for line in script.lines().map(str::trim) {
+            if line.is_empty() {
+                continue;
+            }
+            if !line.starts_with('#') {
+                break;
+            }
+
+            let Some((key, value)) = line
+                .strip_prefix("#%")
+                .and_then(|entry| entry.split_once(':'))
+            else {
+                continue;
+            };
+
+            let key = key.trim().to_lowercase();
+            let value = value.trim().to_string();
+
+            match key.as_str() {
+                "title" => result.title = Some(value),
+                "author" => result.author = Some(value),
+                "description" => result.description = Some(value),
+                "tags" => result.tags.extend(
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|tag| !tag.is_empty())
+                        .map(String::from),
+                ),
+                _ => {
+                    result.extra.insert(key, value);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Returns true if the metadata contains the given tag, ignoring case
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Returns true if no metadata was found
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
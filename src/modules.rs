@@ -0,0 +1,260 @@
+//! Koto modules that are provided from memory
+
+use bevy::{
+    asset::{AssetPath, LoadContext},
+    prelude::*,
+};
+use koto::parser::{AstString, Node, Parser, StringContents};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+/// Koto modules that can be imported by scripts without being read from the filesystem
+///
+/// Koto's module loader reads imported modules from disk, which isn't possible when assets are
+/// fetched over HTTP (e.g. in web builds), or when they're packaged in an asset pack. Modules that
+/// are added to this resource are made available to scripts from memory instead.
+///
+/// When a script is loaded, each module is compiled and run in the order in which it was added,
+/// and its exports are then added to the runtime's prelude, so that `import name` will find the
+/// module before the filesystem is searched. A module can import modules that were added before
+/// it.
+///
+/// Modules with names that are already in the prelude (e.g. modules added by other plugins) are
+/// ignored.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct KotoModules {
+    modules: Vec<KotoModule>,
+}
+
+impl KotoModules {
+    /// Adds a module that can be imported with the given name
+    ///
+    /// The path of the module is relative to the assets folder, and is used in error messages.
+    /// If a module with the same name has already been added, then it will be replaced.
+    pub fn add(
+        &mut self,
+        name: impl Into<String>,
+        path: impl Into<PathBuf>,
+        source: impl Into<String>,
+    ) {
+        let module = KotoModule {
+            name: name.into(),
+            path: path.into(),
+            source: source.into(),
+        };
+
+        match self.modules.iter_mut().find(|m| m.name == module.name) {
+            Some(existing) => *existing = module,
+            None => self.modules.push(module),
+        }
+    }
+
+    /// Removes the module with the given name
+    ///
+    /// Returns false if no matching module was found.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.modules.len();
+        self.modules.retain(|module| module.name != name);
+        self.modules.len() != len
+    }
+
+    /// The modules that have been added, in the order in which they'll be run
+    pub fn iter(&self) -> impl Iterator<Item = &KotoModule> {
+        self.modules.iter()
+    }
+
+    /// Returns true if no modules have been added
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+}
+
+/// A Koto module that's provided from memory, see [KotoModules]
+#[derive(Clone, Debug)]
+pub struct KotoModule {
+    /// The name that's used to import the module
+    pub name: String,
+    /// The module's path, relative to the assets folder
+    pub path: PathBuf,
+    /// The module's source code
+    pub source: String,
+}
+
+// A module that was found while loading a script, along with the paths of the modules it imports
+struct FoundModule {
+    module: KotoModule,
+    imports: Vec<PathBuf>,
+}
+
+// Finds the modules that are imported by a script, reading them via the asset server
+//
+// Imports are resolved in the same way as Koto's module loader, by looking for a neighboring
+// `.koto` file, or for a `main.koto` file in a neighboring folder. Modules are read with
+// `read_asset_bytes`, so that they're registered as dependencies of the script, which then gets
+// reloaded when any of its modules are modified.
+//
+// Imports that can't be found (e.g. modules in the prelude) are skipped, and the modules that are
+// found are returned in the order in which they need to be run.
+pub(crate) async fn load_imported_modules(
+    script: &str,
+    script_path: &Path,
+    load_context: &mut LoadContext<'_>,
+) -> Vec<KotoModule> {
+    let mut found = HashMap::<PathBuf, FoundModule>::new();
+    let mut script_imports = Vec::new();
+    let mut pending = vec![(None, script.to_string(), script_path.to_path_buf())];
+
+    while let Some((module_path, source, path)) = pending.pop() {
+        let folder = path.parent().unwrap_or(Path::new(""));
+        let mut imports = Vec::new();
+
+        for name in imported_module_names(&source) {
+            let Some((import_path, import_source)) = read_module(&name, folder, load_context).await
+            else {
+                continue;
+            };
+
+            if !found.contains_key(&import_path) {
+                found.insert(
+                    import_path.clone(),
+                    FoundModule {
+                        module: KotoModule {
+                            name,
+                            path: import_path.clone(),
+                            source: import_source.clone(),
+                        },
+                        imports: Vec::new(),
+                    },
+                );
+                pending.push((
+                    Some(import_path.clone()),
+                    import_source,
+                    import_path.clone(),
+                ));
+            }
+
+            imports.push(import_path);
+        }
+
+        match module_path.and_then(|module_path| found.get_mut(&module_path)) {
+            Some(module) => module.imports = imports,
+            None => script_imports = imports,
+        }
+    }
+
+    // Sort the modules so that each module is run after the modules that it imports
+    fn visit(
+        path: &Path,
+        found: &HashMap<PathBuf, FoundModule>,
+        visited: &mut HashSet<PathBuf>,
+        result: &mut Vec<KotoModule>,
+    ) {
+        if !visited.insert(path.to_path_buf()) {
+            return;
+        }
+        if let Some(module) = found.get(path) {
+            for import in module.imports.iter() {
+                visit(import, found, visited, result);
+            }
+            result.push(module.module.clone());
+        }
+    }
+
+    let mut visited = HashSet::new();
+    let mut result = Vec::with_capacity(found.len());
+    for import in script_imports.iter() {
+        visit(import, &found, &mut visited, &mut result);
+    }
+    result
+}
+
+// Reads the module with the given name, returning its path and source if it was found
+async fn read_module(
+    name: &str,
+    folder: &Path,
+    load_context: &mut LoadContext<'_>,
+) -> Option<(PathBuf, String)> {
+    for path in module_candidates(name, folder) {
+        let asset_path = AssetPath::from_path(&path)
+            .with_source(load_context.asset_path().source().clone_owned());
+        if let Ok(bytes) = load_context.read_asset_bytes(asset_path).await {
+            match String::from_utf8(bytes) {
+                Ok(source) => return Some((path, source)),
+                Err(error) => {
+                    error!("Failed to read module '{}': {error}", path.display());
+                    return None;
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// Returns the paths that an imported module with the given name can be found at, in the order in
+// which they're searched by Koto's module loader
+fn module_candidates(name: &str, folder: &Path) -> [PathBuf; 2] {
+    [
+        folder.join(name).with_extension("koto"),
+        folder.join(name).join("main.koto"),
+    ]
+}
+
+// Resolves the imports of a script or module, returning each imported name along with the path
+// of the module that it refers to
+//
+// Imports that don't refer to an available module (e.g. modules in the prelude) are skipped.
+pub(crate) fn resolve_imports(
+    source: &str,
+    path: &Path,
+    is_available: impl Fn(&Path) -> bool,
+) -> Vec<(String, PathBuf)> {
+    let folder = path.parent().unwrap_or(Path::new(""));
+    let mut result: Vec<(String, PathBuf)> = Vec::new();
+
+    for name in imported_module_names(source) {
+        if result.iter().any(|(resolved, _)| *resolved == name) {
+            continue;
+        }
+        if let Some(module_path) = module_candidates(&name, folder)
+            .into_iter()
+            .find(|candidate| is_available(candidate))
+        {
+            result.push((name, module_path));
+        }
+    }
+
+    result
+}
+
+// Returns the names of the modules that are imported by a script
+//
+// Scripts that fail to parse return no names, with errors being reported when they're compiled.
+fn imported_module_names(source: &str) -> Vec<String> {
+    let Ok(ast) = Parser::parse(source) else {
+        return Vec::new();
+    };
+
+    let module_name = |index| match &ast.node(index).node {
+        Node::Id(id, ..) => Some(ast.constants().get_str(*id).to_string()),
+        Node::Str(AstString {
+            contents: StringContents::Literal(constant) | StringContents::Raw { constant, .. },
+            ..
+        }) => Some(ast.constants().get_str(*constant).to_string()),
+        _ => None,
+    };
+
+    let mut names = Vec::new();
+    for node in ast.nodes() {
+        if let Node::Import { from, items } = &node.node {
+            match from.first() {
+                // `from foo.bar import baz` imports `foo`
+                Some(root) => names.extend(module_name(*root)),
+                None => names.extend(items.iter().filter_map(|item| module_name(item.item))),
+            }
+        }
+    }
+    names
+}
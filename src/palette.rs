@@ -0,0 +1,310 @@
+//! Support for loading color palettes from the assets folder
+
+use crate::{prelude::*, runtime::AssetsFolderPath};
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+    utils::HashMap,
+};
+use koto::prelude::*;
+use parking_lot::RwLock;
+use std::{fs, str, sync::Arc};
+
+/// Color palettes for bevy_koto
+///
+/// The plugin adds a [KotoPalette] asset type, with palettes being loaded from the following
+/// formats:
+/// - `.hex`: One hex color per line, as used by lospec.com, e.g. `ff8800`.
+/// - `.gpl`: GIMP palettes, with each color's red, green, and blue values given on a line.
+/// - `.palette.json`: A list of hex color strings, or a map with the list in a `colors` entry.
+///
+/// A `palette` module is added to Koto's prelude, with `palette.load(path)` returning a list of
+/// the palette's colors, e.g.
+///
+/// ```koto
+/// colors = palette.load 'palettes/sunset.hex'
+/// shape.circle().set_color colors[0]
+/// ```
+///
+/// Palettes that haven't already been loaded as assets are read from the assets folder when
+/// `palette.load` is called, and are then loaded via the [AssetServer] so that they're available
+/// in `Assets<KotoPalette>`. Where the assets folder isn't available (e.g. in web builds), palettes
+/// should be loaded by the app before the script is run.
+pub struct KotoPalettePlugin;
+
+impl Plugin for KotoPalettePlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+        assert!(app.is_plugin_added::<KotoColorPlugin>());
+
+        let (load_palette_sender, load_palette_receiver) = koto_channel::<LoadPalette>();
+
+        app.init_asset::<KotoPalette>()
+            .register_asset_loader(KotoPaletteAssetLoader)
+            .init_resource::<PaletteCache>()
+            .init_resource::<PaletteHandles>()
+            .insert_resource(load_palette_sender)
+            .insert_resource(load_palette_receiver)
+            .add_systems(Startup, on_startup)
+            .add_systems(Update, (load_palettes, update_palette_cache).chain());
+    }
+}
+
+/// A list of colors loaded from the assets folder
+#[derive(Asset, TypePath, Clone, Debug, Default)]
+pub struct KotoPalette {
+    /// The palette's colors
+    pub colors: Vec<Color>,
+}
+
+impl KotoPalette {
+    /// Parses a palette from the contents of a palette file
+    ///
+    /// The extension determines the file's format, one of `hex`, `gpl`, or `palette.json`.
+    pub fn parse(contents: &str, extension: &str) -> Result<Self, KotoPaletteError> {
+        let colors = match extension {
+            "hex" => parse_hex_palette(contents)?,
+            "gpl" => parse_gpl_palette(contents)?,
+            "palette.json" => parse_json_palette(contents)?,
+            _ => return Err(KotoPaletteError::UnknownFormat(extension.to_string())),
+        };
+        Ok(Self { colors })
+    }
+}
+
+/// Errors that can occur while loading a [KotoPalette]
+#[derive(Debug, thiserror::Error)]
+pub enum KotoPaletteError {
+    /// The palette file couldn't be read
+    #[error("Failed to load palette: {0}")]
+    Io(#[from] std::io::Error),
+    /// The palette file isn't valid UTF-8
+    #[error("Failed to parse palette as UTF-8: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+    /// The palette's extension doesn't match a supported format
+    #[error("Unknown palette format '{0}'")]
+    UnknownFormat(String),
+    /// The palette's contents couldn't be parsed
+    #[error("Failed to parse palette, line {line}: {message}")]
+    Parse {
+        /// The line in the palette file that caused the error
+        line: usize,
+        /// A description of the error
+        message: String,
+    },
+}
+
+fn parse_error(line: usize, message: impl Into<String>) -> KotoPaletteError {
+    KotoPaletteError::Parse {
+        line,
+        message: message.into(),
+    }
+}
+
+// Hex palettes contain a color on each line, with `;` starting a comment
+fn parse_hex_palette(contents: &str) -> Result<Vec<Color>, KotoPaletteError> {
+    let mut result = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+        let color = Srgba::hex(line)
+            .map_err(|_| parse_error(i + 1, format!("Invalid hex color '{line}'")))?;
+        result.push(color.into());
+    }
+    Ok(result)
+}
+
+// GIMP palettes start with a header, followed by lines containing red, green, and blue values
+// with an optional name
+fn parse_gpl_palette(contents: &str) -> Result<Vec<Color>, KotoPaletteError> {
+    let mut lines = contents.lines().enumerate();
+    match lines.next() {
+        Some((_, header)) if header.trim() == "GIMP Palette" => {}
+        _ => return Err(parse_error(1, "Expected 'GIMP Palette' header")),
+    }
+
+    let mut result = Vec::new();
+    for (i, line) in lines {
+        let line = line.trim();
+        if line.is_empty()
+            || line.starts_with('#')
+            || line.starts_with("Name:")
+            || line.starts_with("Columns:")
+        {
+            continue;
+        }
+        let mut components = line.split_whitespace().map(str::parse::<u8>);
+        match (components.next(), components.next(), components.next()) {
+            (Some(Ok(r)), Some(Ok(g)), Some(Ok(b))) => result.push(Color::srgb_u8(r, g, b)),
+            _ => return Err(parse_error(i + 1, "Expected red, green, and blue values")),
+        }
+    }
+    Ok(result)
+}
+
+// JSON palettes are either a list of hex strings, or a map with the list in a `colors` entry
+fn parse_json_palette(contents: &str) -> Result<Vec<Color>, KotoPaletteError> {
+    use serde_json::Value;
+
+    let json: Value = serde_json::from_str(contents)
+        .map_err(|error| parse_error(error.line(), error.to_string()))?;
+    let entries = match &json {
+        Value::Array(entries) => entries,
+        Value::Object(map) => match map.get("colors") {
+            Some(Value::Array(entries)) => entries,
+            _ => return Err(parse_error(1, "Expected a 'colors' list")),
+        },
+        _ => return Err(parse_error(1, "Expected a list of colors")),
+    };
+
+    entries
+        .iter()
+        .map(|entry| match entry {
+            Value::String(s) => Srgba::hex(s)
+                .map(Color::from)
+                .map_err(|_| parse_error(1, format!("Invalid hex color '{s}'"))),
+            _ => Err(parse_error(1, "Expected a hex color string")),
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct KotoPaletteAssetLoader;
+
+impl AssetLoader for KotoPaletteAssetLoader {
+    type Asset = KotoPalette;
+    type Settings = ();
+    type Error = KotoPaletteError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let path = load_context.path().to_string_lossy();
+        KotoPalette::parse(str::from_utf8(&bytes)?, palette_extension(&path))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["hex", "gpl", "palette.json"]
+    }
+}
+
+// Returns the palette format's extension for the given path
+fn palette_extension(path: &str) -> &str {
+    if path.ends_with(".palette.json") {
+        "palette.json"
+    } else {
+        path.rsplit_once('.').map_or("", |(_, extension)| extension)
+    }
+}
+
+// The colors of loaded palettes keyed by their paths in the assets folder,
+// shared between the plugin's systems and the Koto functions
+#[derive(Resource, Clone, Default)]
+struct PaletteCache(Arc<RwLock<HashMap<String, Vec<Color>>>>);
+
+// Handles for the palettes that have been loaded by scripts
+#[derive(Resource, Default)]
+struct PaletteHandles(HashMap<String, Handle<KotoPalette>>);
+
+#[derive(Clone, Debug)]
+struct LoadPalette(String);
+
+#[allow(clippy::result_large_err)]
+fn on_startup(
+    koto: Res<KotoRuntime>,
+    cache: Res<PaletteCache>,
+    assets_folder: Res<AssetsFolderPath>,
+    load_palette: Res<KotoSender<LoadPalette>>,
+) {
+    let module = KMap::with_type("palette");
+
+    module.add_fn("load", {
+        let cache = cache.clone();
+        let assets_folder = assets_folder.0.clone();
+        let load_palette = load_palette.clone();
+
+        move |ctx| {
+            let path = match ctx.args() {
+                [KValue::Str(path)] => path.to_string(),
+                unexpected => return unexpected_args("a path as a String", unexpected),
+            };
+
+            let cached = cache.0.read().get(&path).cloned();
+            let colors = match cached {
+                Some(colors) => colors,
+                None => {
+                    // The palette hasn't been loaded yet, so it's read directly from the assets
+                    // folder, with the asset being loaded for future calls.
+                    let palette = fs::read_to_string(assets_folder.join(&path))
+                        .map_err(KotoPaletteError::from)
+                        .and_then(|contents| {
+                            KotoPalette::parse(&contents, palette_extension(&path))
+                        });
+                    match palette {
+                        Ok(palette) => {
+                            cache.0.write().insert(path.clone(), palette.colors.clone());
+                            load_palette.send(LoadPalette(path));
+                            palette.colors
+                        }
+                        Err(error) => return runtime_error!("palette.load: {error} ('{path}')"),
+                    }
+                }
+            };
+
+            Ok(KList::with_data(
+                colors
+                    .into_iter()
+                    .map(|color| bevy_to_koto_color(color).into())
+                    .collect(),
+            )
+            .into())
+        }
+    });
+
+    koto.prelude().insert("palette", module);
+}
+
+fn load_palettes(
+    channel: Res<KotoReceiver<LoadPalette>>,
+    asset_server: Res<AssetServer>,
+    mut handles: ResMut<PaletteHandles>,
+) {
+    let _span = info_span!("koto_channel", channel = "LoadPalette").entered();
+    while let Some(LoadPalette(path)) = channel.receive() {
+        handles
+            .0
+            .entry(path.clone())
+            .or_insert_with(|| asset_server.load(path));
+    }
+}
+
+// Keeps the cache up to date with the palettes that have been loaded or modified as assets
+fn update_palette_cache(
+    mut asset_events: EventReader<AssetEvent<KotoPalette>>,
+    palettes: Res<Assets<KotoPalette>>,
+    asset_server: Res<AssetServer>,
+    cache: Res<PaletteCache>,
+) {
+    for event in asset_events.read() {
+        match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => {
+                let (Some(palette), Some(path)) = (palettes.get(*id), asset_server.get_path(*id))
+                else {
+                    continue;
+                };
+                cache
+                    .0
+                    .write()
+                    .insert(path.path().to_string_lossy().into(), palette.colors.clone());
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! Pointer callbacks for Koto shapes, using Bevy's picking plugins
+
+use crate::{prelude::*, shape::ShapeKind};
+use bevy::{
+    math::{Affine2, FloatOrd},
+    picking::{
+        backend::prelude::*,
+        events::{Click, Out, Over, Pointer},
+        PickingPlugin,
+    },
+    prelude::*,
+    window::PrimaryWindow,
+};
+use koto::prelude::*;
+use std::{cmp::Reverse, fmt::Debug};
+
+/// Pointer callbacks for the shapes spawned by the [KotoShapePlugin]
+///
+/// The plugin adds a picking backend for Koto shapes, and calls the shape's pointer callbacks in
+/// response to Bevy's picking events:
+/// - `shape.on_click(f)`: `f` is called when the shape is clicked.
+/// - `shape.on_hover_enter(f)`: `f` is called when the pointer moves over the shape.
+/// - `shape.on_hover_exit(f)`: `f` is called when the pointer moves away from the shape.
+///
+/// Callbacks are called during [KotoUpdate::Update] with the shape as `self`, and with the
+/// position where the pointer hit the shape as a `Vec2`. Calling e.g. `shape.on_click null`
+/// removes the callback.
+///
+/// Picking events bubble up the entity hierarchy, so a shape's callbacks are also called for
+/// events on its children.
+///
+/// Bevy's picking plugins need to be added before this plugin, they're included in
+/// `DefaultPlugins` when Bevy's `bevy_picking` feature is enabled.
+pub struct KotoPickingPlugin;
+
+impl Plugin for KotoPickingPlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+        assert!(app.is_plugin_added::<KotoShapePlugin>());
+        assert!(app.is_plugin_added::<PickingPlugin>());
+
+        app.init_resource::<PendingPointerCallbacks>()
+            .add_observer(queue_pointer_callback::<Click>("on_click", |click| {
+                &click.hit
+            }))
+            .add_observer(queue_pointer_callback::<Over>("on_hover_enter", |over| {
+                &over.hit
+            }))
+            .add_observer(queue_pointer_callback::<Out>("on_hover_exit", |out| {
+                &out.hit
+            }))
+            .add_systems(PreUpdate, koto_shape_picking.in_set(PickSet::Backend))
+            .add_systems(
+                KotoSchedule,
+                run_pointer_callbacks.in_set(KotoUpdate::Update),
+            );
+    }
+}
+
+// Pointer callbacks that are waiting to be called during the KotoUpdate::Update set
+#[derive(Resource, Default)]
+struct PendingPointerCallbacks(Vec<PendingPointerCallback>);
+
+struct PendingPointerCallback {
+    entity: Entity,
+    callback: &'static str,
+    position: Option<Vec3>,
+}
+
+// Makes an observer that queues a callback for entities that have a callback with the given name
+fn queue_pointer_callback<E: Debug + Clone + Reflect>(
+    callback: &'static str,
+    hit: fn(&E) -> &HitData,
+) -> impl Fn(Trigger<Pointer<E>>, Query<&KotoEntity>, ResMut<PendingPointerCallbacks>) {
+    move |trigger, query, mut pending| {
+        let entity = trigger.entity();
+        if query
+            .get(entity)
+            .is_ok_and(|koto_entity| koto_entity.callbacks.contains_key(callback))
+        {
+            pending.0.push(PendingPointerCallback {
+                entity,
+                callback,
+                position: hit(&trigger.event().event).position,
+            });
+        }
+    }
+}
+
+fn run_pointer_callbacks(
+    koto: Res<KotoRuntime>,
+    mut pending: ResMut<PendingPointerCallbacks>,
+    mut query: Query<&mut KotoEntity>,
+    mut script_error: EventWriter<KotoScriptError>,
+) {
+    if !koto.is_ready() {
+        pending.0.clear();
+        return;
+    }
+
+    for PendingPointerCallback {
+        entity,
+        callback,
+        position,
+    } in pending.0.drain(..)
+    {
+        let Ok(mut koto_entity) = query.get_mut(entity) else {
+            continue;
+        };
+        if !koto_entity.is_active {
+            continue;
+        }
+
+        let instance = koto_entity.object.clone();
+        let Some((f, vm)) = koto_entity.callbacks.get_mut(callback) else {
+            continue;
+        };
+
+        let _span = info_span!("koto_pointer_callback", %entity, callback).entered();
+        let position = position.map_or(KValue::Null, |position| {
+            KotoVec2::new(position.x.into(), position.y.into()).into()
+        });
+        if let Err(error) = vm.call_instance_function(instance.into(), f.clone(), position) {
+            let error = KotoScriptError {
+                phase: ScriptPhase::PointerCallback,
+                message: error.to_string(),
+                span: None,
+                script_path: koto.script_path().map(ToOwned::to_owned),
+            };
+            error!("{error}");
+            script_error.send(error);
+        }
+    }
+}
+
+type PickableShape = (
+    Entity,
+    &'static ShapeKind,
+    &'static GlobalTransform,
+    Option<&'static PickingBehavior>,
+    &'static ViewVisibility,
+);
+
+// A picking backend for Koto shapes, based on the sprite picking backend from bevy_sprite
+//
+// Hits are checked against the shape's geometry rather than its bounds.
+fn koto_shape_picking(
+    pointers: Query<(&PointerId, &PointerLocation)>,
+    cameras: Query<(Entity, &Camera, &GlobalTransform, &OrthographicProjection)>,
+    primary_window: Query<Entity, With<PrimaryWindow>>,
+    shapes: Query<PickableShape, With<KotoEntity>>,
+    mut output: EventWriter<PointerHits>,
+) {
+    let mut sorted_shapes: Vec<_> = shapes
+        .iter()
+        .filter(|(_, _, transform, _, visibility)| !transform.affine().is_nan() && visibility.get())
+        .collect();
+    sorted_shapes
+        .sort_by_key(|(_, _, transform, _, _)| Reverse(FloatOrd(transform.translation().z)));
+
+    let primary_window = primary_window.get_single().ok();
+
+    for (pointer, location) in pointers
+        .iter()
+        .filter_map(|(pointer, location)| location.location().map(|location| (pointer, location)))
+    {
+        let Some((camera_entity, camera, camera_transform, projection)) = cameras
+            .iter()
+            .filter(|(_, camera, _, _)| camera.is_active)
+            .find(|(_, camera, _, _)| {
+                camera
+                    .target
+                    .normalize(primary_window)
+                    .is_some_and(|target| target == location.target)
+            })
+        else {
+            continue;
+        };
+
+        let viewport_position = camera
+            .logical_viewport_rect()
+            .map(|viewport| viewport.min)
+            .unwrap_or_default();
+        let Ok(ray) =
+            camera.viewport_to_world(camera_transform, location.position - viewport_position)
+        else {
+            continue;
+        };
+        let ray_length = projection.far - projection.near;
+
+        let mut blocked = false;
+        let picks = sorted_shapes
+            .iter()
+            .filter_map(|(entity, shape, transform, picking_behavior, _)| {
+                if blocked {
+                    return None;
+                }
+
+                // Find where the ray intersects the shape's plane
+                let affine = transform.affine();
+                if ray.direction.z == 0.0 {
+                    return None;
+                }
+                let distance = (affine.translation.z - ray.origin.z) / ray.direction.z;
+                if !(0.0..=ray_length).contains(&distance) {
+                    return None;
+                }
+                let hit_position_world = ray.get_point(distance);
+
+                // Only the 2D part of the shape's transform is inverted,
+                // shapes are often given a Z scale of 0 which would produce NaNs.
+                let shape_to_world = Affine2::from_mat2_translation(
+                    Mat2::from_cols(affine.matrix3.x_axis.xy(), affine.matrix3.y_axis.xy()),
+                    affine.translation.xy(),
+                );
+                let hit_position = shape_to_world
+                    .inverse()
+                    .transform_point2(hit_position_world.xy());
+
+                if !shape.0.contains(hit_position) {
+                    return None;
+                }
+
+                blocked = picking_behavior.is_none_or(|behavior| behavior.should_block_lower);
+
+                let hit_position_camera = camera_transform
+                    .affine()
+                    .inverse()
+                    .transform_point3(hit_position_world);
+                let depth = -projection.near - hit_position_camera.z;
+
+                Some((
+                    *entity,
+                    HitData::new(
+                        camera_entity,
+                        depth,
+                        Some(hit_position_world),
+                        Some(*transform.back()),
+                    ),
+                ))
+            })
+            .collect();
+
+        output.send(PointerHits::new(*pointer, picks, camera.order as f32));
+    }
+}
@@ -0,0 +1,324 @@
+//! Support for controlling post-processing effects from Koto scripts
+
+use crate::prelude::*;
+use bevy::{
+    core_pipeline::{bloom::Bloom, tonemapping::Tonemapping},
+    prelude::*,
+    render::view::ColorGrading,
+};
+use cloned::cloned;
+use koto::prelude::*;
+
+/// Post-processing effects for bevy_koto
+///
+/// The plugin adds a `post` module to Koto's prelude, with the following functions:
+/// - `post.set_bloom(intensity)`: Enables bloom on the [KotoCamera] with the given intensity,
+///   switching the camera to HDR rendering. An intensity of `0` or `null` disables bloom.
+/// - `post.set_tonemapping(name)`: Sets the camera's [Tonemapping], with `null` restoring the
+///   camera's previous tonemapping. The available names are `none`, `reinhard`,
+///   `reinhard_luminance`, `aces_fitted`, `agx`, `somewhat_boring`, `tony_mc_mapface`, and
+///   `blender_filmic`. Some of the tonemapping methods require Bevy's `tonemapping_luts` feature.
+/// - `post.set_exposure(ev)`: Sets the exposure of the camera's [ColorGrading] in stops.
+/// - `post.set_saturation(amount)`: Sets the saturation of the camera's color grading, with `1`
+///   leaving colors unchanged and `0` producing grayscale.
+/// - `post.set_contrast(amount)`: Sets the contrast of the camera's color grading, with `1`
+///   leaving colors unchanged.
+///
+/// Colors with components greater than 1 will glow when bloom is enabled, e.g.
+///
+/// ```koto
+/// post.set_bloom 0.3
+/// shape.circle().set_color 4, 2, 1
+/// ```
+///
+/// Effects that have been set by a script are reset when a script is loaded, with the camera's
+/// previous HDR, bloom, tonemapping, and color grading settings being restored.
+pub struct KotoPostPlugin;
+
+impl Plugin for KotoPostPlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+        assert!(app.is_plugin_added::<KotoCameraPlugin>());
+
+        let (update_post_sender, update_post_receiver) = koto_channel::<UpdatePostProcessing>();
+
+        app.insert_resource(update_post_sender)
+            .insert_resource(update_post_receiver)
+            .add_systems(Startup, on_startup)
+            .add_systems(KotoSchedule, on_script_loaded.in_set(KotoUpdate::PreUpdate))
+            .add_systems(Update, koto_to_bevy_post_events);
+    }
+}
+
+/// Event for updating the post-processing effects of the [KotoCamera]
+#[derive(Clone, Debug, Event)]
+pub enum UpdatePostProcessing {
+    /// Sets the intensity of the camera's bloom, with `None` disabling bloom
+    Bloom(Option<f32>),
+    /// Sets the camera's tonemapping, with `None` restoring the camera's previous tonemapping
+    Tonemapping(Option<Tonemapping>),
+    /// Sets the exposure of the camera's color grading in stops
+    Exposure(f32),
+    /// Sets the saturation of each section of the camera's color grading
+    Saturation(f32),
+    /// Sets the contrast of each section of the camera's color grading
+    Contrast(f32),
+}
+
+// The camera settings that were in place before a script enabled bloom
+#[derive(Component)]
+struct ScriptBloom {
+    hdr: bool,
+    bloom: Option<Bloom>,
+}
+
+// The camera's tonemapping before it was changed by a script
+#[derive(Component)]
+struct ScriptTonemapping(Option<Tonemapping>);
+
+// The camera's color grading before it was changed by a script
+#[derive(Component)]
+struct ScriptColorGrading(Option<ColorGrading>);
+
+#[allow(clippy::result_large_err)]
+fn on_startup(koto: Res<KotoRuntime>, update_post: Res<KotoSender<UpdatePostProcessing>>) {
+    let module = KMap::with_type("post");
+
+    module.add_fn("set_bloom", {
+        cloned!(update_post);
+        move |ctx| {
+            let intensity = match ctx.args() {
+                [KValue::Number(n)] if *n > 0.0 => Some(n.into()),
+                [KValue::Number(_)] | [KValue::Null] => None,
+                unexpected => return unexpected_args("a Number, or null", unexpected),
+            };
+            update_post.send(UpdatePostProcessing::Bloom(intensity));
+            Ok(KValue::Null)
+        }
+    });
+
+    module.add_fn("set_tonemapping", {
+        cloned!(update_post);
+        move |ctx| {
+            let tonemapping = match ctx.args() {
+                [KValue::Str(name)] => match tonemapping_from_name(name) {
+                    Some(tonemapping) => Some(tonemapping),
+                    None => {
+                        return runtime_error!("post.set_tonemapping: Unknown tonemapping '{name}'")
+                    }
+                },
+                [KValue::Null] => None,
+                unexpected => return unexpected_args("a tonemapping name, or null", unexpected),
+            };
+            update_post.send(UpdatePostProcessing::Tonemapping(tonemapping));
+            Ok(KValue::Null)
+        }
+    });
+
+    let add_grading_fn = |name: &'static str, make_event: fn(f32) -> UpdatePostProcessing| {
+        module.add_fn(name, {
+            cloned!(update_post);
+            move |ctx| match ctx.args() {
+                [KValue::Number(n)] => {
+                    update_post.send(make_event(n.into()));
+                    Ok(KValue::Null)
+                }
+                unexpected => unexpected_args("a Number", unexpected),
+            }
+        });
+    };
+
+    add_grading_fn("set_exposure", UpdatePostProcessing::Exposure);
+    add_grading_fn("set_saturation", UpdatePostProcessing::Saturation);
+    add_grading_fn("set_contrast", UpdatePostProcessing::Contrast);
+
+    koto.prelude().insert("post", module);
+}
+
+// Reset the camera's effects when a script is loaded
+fn on_script_loaded(
+    mut script_loaded_events: EventReader<ScriptLoaded>,
+    camera_query: Query<Entity, With<KotoCamera>>,
+    mut commands: Commands,
+) {
+    for _ in script_loaded_events.read() {
+        for camera in &camera_query {
+            commands.queue(move |world: &mut World| {
+                set_bloom(world, camera, None);
+                set_tonemapping(world, camera, None);
+                reset_color_grading(world, camera);
+            });
+        }
+    }
+}
+
+fn koto_to_bevy_post_events(
+    channel: Res<KotoReceiver<UpdatePostProcessing>>,
+    camera_query: Query<Entity, With<KotoCamera>>,
+    mut commands: Commands,
+) {
+    let _span = info_span!("koto_channel", channel = "UpdatePostProcessing").entered();
+    while let Some(event) = channel.receive() {
+        for camera in &camera_query {
+            match event {
+                UpdatePostProcessing::Bloom(intensity) => {
+                    commands.queue(move |world: &mut World| set_bloom(world, camera, intensity));
+                }
+                UpdatePostProcessing::Tonemapping(tonemapping) => {
+                    commands.queue(move |world: &mut World| {
+                        set_tonemapping(world, camera, tonemapping)
+                    });
+                }
+                UpdatePostProcessing::Exposure(exposure) => {
+                    commands.queue(move |world: &mut World| {
+                        update_color_grading(world, camera, |grading| {
+                            grading.global.exposure = exposure;
+                        });
+                    });
+                }
+                UpdatePostProcessing::Saturation(saturation) => {
+                    commands.queue(move |world: &mut World| {
+                        update_color_grading(world, camera, |grading| {
+                            for section in grading.all_sections_mut() {
+                                section.saturation = saturation;
+                            }
+                        });
+                    });
+                }
+                UpdatePostProcessing::Contrast(contrast) => {
+                    commands.queue(move |world: &mut World| {
+                        update_color_grading(world, camera, |grading| {
+                            for section in grading.all_sections_mut() {
+                                section.contrast = contrast;
+                            }
+                        });
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn set_bloom(world: &mut World, camera: Entity, intensity: Option<f32>) {
+    let Ok(mut entity) = world.get_entity_mut(camera) else {
+        return;
+    };
+
+    match intensity {
+        Some(intensity) => {
+            if !entity.contains::<ScriptBloom>() {
+                let previous = ScriptBloom {
+                    hdr: entity.get::<Camera>().is_some_and(|camera| camera.hdr),
+                    bloom: entity.get::<Bloom>().cloned(),
+                };
+                entity.insert(previous);
+            }
+            if let Some(mut camera) = entity.get_mut::<Camera>() {
+                camera.hdr = true;
+            }
+            match entity.get_mut::<Bloom>() {
+                Some(mut bloom) => bloom.intensity = intensity,
+                None => {
+                    entity.insert(Bloom {
+                        intensity,
+                        ..Bloom::NATURAL
+                    });
+                }
+            }
+        }
+        None => {
+            // Restore the settings that were in place before the script enabled bloom
+            let Some(previous) = entity.take::<ScriptBloom>() else {
+                return;
+            };
+            if let Some(mut camera) = entity.get_mut::<Camera>() {
+                camera.hdr = previous.hdr;
+            }
+            match previous.bloom {
+                Some(bloom) => {
+                    entity.insert(bloom);
+                }
+                None => {
+                    entity.remove::<Bloom>();
+                }
+            }
+        }
+    }
+}
+
+fn tonemapping_from_name(name: &str) -> Option<Tonemapping> {
+    let result = match name {
+        "none" => Tonemapping::None,
+        "reinhard" => Tonemapping::Reinhard,
+        "reinhard_luminance" => Tonemapping::ReinhardLuminance,
+        "aces_fitted" => Tonemapping::AcesFitted,
+        "agx" => Tonemapping::AgX,
+        "somewhat_boring" => Tonemapping::SomewhatBoringDisplayTransform,
+        "tony_mc_mapface" => Tonemapping::TonyMcMapface,
+        "blender_filmic" => Tonemapping::BlenderFilmic,
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn set_tonemapping(world: &mut World, camera: Entity, tonemapping: Option<Tonemapping>) {
+    let Ok(mut entity) = world.get_entity_mut(camera) else {
+        return;
+    };
+
+    match tonemapping {
+        Some(tonemapping) => {
+            if !entity.contains::<ScriptTonemapping>() {
+                let previous = ScriptTonemapping(entity.get::<Tonemapping>().copied());
+                entity.insert(previous);
+            }
+            entity.insert(tonemapping);
+        }
+        None => {
+            // Restore the tonemapping that was in place before the script changed it
+            let Some(ScriptTonemapping(previous)) = entity.take::<ScriptTonemapping>() else {
+                return;
+            };
+            match previous {
+                Some(tonemapping) => {
+                    entity.insert(tonemapping);
+                }
+                None => {
+                    entity.remove::<Tonemapping>();
+                }
+            }
+        }
+    }
+}
+
+fn update_color_grading(world: &mut World, camera: Entity, f: impl FnOnce(&mut ColorGrading)) {
+    let Ok(mut entity) = world.get_entity_mut(camera) else {
+        return;
+    };
+
+    if !entity.contains::<ScriptColorGrading>() {
+        let previous = ScriptColorGrading(entity.get::<ColorGrading>().cloned());
+        entity.insert(previous);
+    }
+    let mut grading = entity.get::<ColorGrading>().cloned().unwrap_or_default();
+    f(&mut grading);
+    entity.insert(grading);
+}
+
+// Restore the color grading that was in place before the script changed it
+fn reset_color_grading(world: &mut World, camera: Entity) {
+    let Ok(mut entity) = world.get_entity_mut(camera) else {
+        return;
+    };
+    let Some(ScriptColorGrading(previous)) = entity.take::<ScriptColorGrading>() else {
+        return;
+    };
+    match previous {
+        Some(grading) => {
+            entity.insert(grading);
+        }
+        None => {
+            entity.remove::<ColorGrading>();
+        }
+    }
+}
@@ -1,33 +1,119 @@
 //! A collection of useful items to import when using `bevy_koto`
 
+pub use crate::app::KotoAppExt;
+pub use crate::convert::{FromKotoValue, IntoKotoArgs, KotoCallError};
 pub use crate::entity::{
-    koto_entity_channel, KotoEntity, KotoEntityEvent, KotoEntityMapping, KotoEntityPlugin,
-    KotoEntityReceiver, KotoEntitySender, UpdateKotoEntity,
+    koto_entity_channel, koto_entity_channel_bounded, KotoEntity, KotoEntityEvent,
+    KotoEntityMapping, KotoEntityPlugin, KotoEntityPool, KotoEntityReceiver, KotoEntityRegistry,
+    KotoEntitySender, KotoLifetime, KotoPooled, KotoTag, KotoTagQuery, UpdateKotoEntity,
 };
+pub use crate::memory::KotoMemoryStats;
+pub use crate::metadata::ScriptMetadata;
+pub use crate::modules::{KotoModule, KotoModules};
+pub use crate::reflect::{apply_koto_to_reflect, koto_to_reflect, reflect_to_koto};
 pub use crate::runtime::{
-    koto_channel, KotoReceiver, KotoRuntime, KotoRuntimePlugin, KotoSchedule, KotoScript,
-    KotoSender, KotoUpdate, LoadScript, ScriptLoaded,
+    format_compile_error, koto_channel, koto_channel_bounded, ExportedFunction, KotoCustomEvent,
+    KotoReceiver, KotoRuntime, KotoRuntimePlugin, KotoSchedule, KotoSchedulePlacement, KotoScript,
+    KotoScriptError, KotoScriptOutput, KotoSendError, KotoSender, KotoUpdate, LoadScript,
+    LoadScriptSource, OverflowPolicy, ReloadPolicy, ScriptCompileFailed, ScriptCompiling,
+    ScriptLoaded, ScriptOutputStream, ScriptPhase, ScriptReady, ScriptUnloaded,
+};
+pub use crate::time::{KotoFrameControl, KotoTime, UpdateKotoTime};
+
+#[cfg(feature = "browser")]
+pub use crate::browser::{
+    KeyBinding, KotoScriptBrowserPlugin, ScriptBrowserKeys, ScriptPlaylist, ScriptSortOrder,
 };
 
 #[cfg(feature = "camera")]
-pub use crate::camera::{KotoCamera, KotoCameraPlugin, UpdateOrthographicProjection};
+pub use crate::camera::{
+    AddCameraFunctions, CameraShake, KotoCamera, KotoCameraEvent, KotoCameraFunctions,
+    KotoCameraPlugin, KotoCameraScaling, UpdateOrthographicProjection, MAIN_CAMERA,
+};
+
+#[cfg(feature = "camera3d")]
+pub use crate::camera3d::{KotoCamera3dPlugin, UpdateCamera3d};
+
+#[cfg(feature = "collision")]
+pub use crate::collision::KotoCollisionPlugin;
 
 #[cfg(feature = "color")]
 pub use crate::color::{
-    koto_to_bevy_color, KotoColor, KotoColorPlugin, SetClearColor, UpdateColorMaterial,
+    bevy_to_koto_color, koto_to_bevy_color, parse_color, ColorGradient, ColorTween, Easing,
+    GradientMaterial, GradientSpace, KotoColor, KotoColorPlugin, KotoGradient, SetClearColor,
+    SharedColorMaterial, UpdateColorMaterial, UvTransform,
 };
 
+#[cfg(feature = "components")]
+pub use crate::components::KotoComponentsPlugin;
+
+#[cfg(feature = "console")]
+pub use crate::console::{KotoConsoleInput, KotoConsoleOutput, KotoConsolePlugin};
+
+#[cfg(feature = "data")]
+pub use crate::data::{json_to_kvalue, kvalue_to_json, KotoDataPlugin};
+
+#[cfg(feature = "diagnostics")]
+pub use crate::diagnostics::KotoDiagnosticsPlugin;
+
+#[cfg(feature = "events")]
+pub use crate::event_bridge::KotoEventBridgePlugin;
+
 #[cfg(feature = "geometry")]
-pub use crate::geometry::{KotoGeometryPlugin, KotoVec2, UpdateTransform};
+pub use crate::geometry::{
+    send_transform_copy, send_transform_update, KotoGeometryPlugin, KotoRect, KotoVec2, KotoVec3,
+    UpdateTransform,
+};
+
+#[cfg(feature = "group")]
+pub use crate::group::KotoGroupPlugin;
+
+#[cfg(feature = "instances")]
+pub use crate::instances::KotoInstancesPlugin;
+
+#[cfg(feature = "palette")]
+pub use crate::palette::{KotoPalette, KotoPalettePlugin};
+
+#[cfg(feature = "picking")]
+pub use crate::picking::KotoPickingPlugin;
+
+#[cfg(feature = "post")]
+pub use crate::post::{KotoPostPlugin, UpdatePostProcessing};
 
 #[cfg(feature = "random")]
 pub use crate::random::KotoRandomPlugin;
 
+#[cfg(feature = "resources")]
+pub use crate::resources::KotoResourcesPlugin;
+
+#[cfg(feature = "scheduler")]
+pub use crate::scheduler::KotoSchedulerPlugin;
+
+#[cfg(feature = "script_components")]
+pub use crate::script_component::{KotoScriptComponent, KotoScriptComponentPlugin};
+
+#[cfg(feature = "session")]
+pub use crate::session::{KotoSessionPlugin, SaveKotoSession};
+
 #[cfg(feature = "shape")]
-pub use crate::shape::KotoShapePlugin;
+pub use crate::shape::{KotoShapePlugin, UpdateShapeMesh};
+
+#[cfg(feature = "shape3d")]
+pub use crate::shape3d::{KotoShape3dPlugin, UpdateStandardMaterial};
+
+#[cfg(feature = "spatial")]
+pub use crate::spatial::KotoSpatialPlugin;
+
+#[cfg(feature = "sprite")]
+pub use crate::sprite::{KotoSpritePlugin, SpriteAnimation, UpdateSprite};
+
+#[cfg(feature = "tasks")]
+pub use crate::tasks::KotoTasksPlugin;
 
 #[cfg(feature = "text")]
-pub use crate::text::KotoTextPlugin;
+pub use crate::text::{KotoTextPlugin, TextSizing, UpdateText};
+#[cfg(feature = "text3d")]
+pub use crate::text3d::{KotoText3dPlugin, UpdateText3d};
 
 #[cfg(feature = "window")]
 pub use crate::window::KotoWindowPlugin;
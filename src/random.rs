@@ -1,6 +1,6 @@
 //! Random number utilities for Koto scripts
 
-use crate::runtime::{KotoRuntime, KotoRuntimePlugin};
+use crate::{app::KotoAppExt, runtime::KotoRuntimePlugin};
 use bevy::prelude::*;
 
 /// Random number utilities for Koto
@@ -12,10 +12,6 @@ impl Plugin for KotoRandomPlugin {
     fn build(&self, app: &mut App) {
         assert!(app.is_plugin_added::<KotoRuntimePlugin>());
 
-        app.add_systems(Startup, on_startup);
+        app.register_koto_module("random", koto_random::make_module);
     }
 }
-
-fn on_startup(koto: Res<KotoRuntime>) {
-    koto.prelude().insert("random", koto_random::make_module());
-}
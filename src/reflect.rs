@@ -0,0 +1,321 @@
+//! Conversions between Koto values and reflected Bevy values
+
+use bevy::reflect::{
+    std_traits::ReflectDefault, DynamicEnum, DynamicVariant, PartialReflect, ReflectMut,
+    ReflectRef, TypeRegistry, VariantType,
+};
+use koto::prelude::*;
+
+/// Converts a reflected Bevy value into a Koto value
+///
+/// The conversion follows the shape of the reflected value:
+/// - Structs and maps are converted into Koto maps, map entries with non-string keys are skipped.
+/// - Tuples, lists, arrays, and sets are converted into tuples and lists.
+/// - Tuple structs with a single field are converted into the field's value, otherwise they're
+///   converted into tuples.
+/// - Unit enum variants are converted into strings containing the variant's name,
+///   other variants are converted into maps with `variant` and `value` entries.
+/// - Numbers, bools, and strings are converted into their Koto equivalents.
+///
+/// Values that can't be converted are represented as `null`.
+pub fn reflect_to_koto(value: &dyn PartialReflect) -> KValue {
+    match value.reflect_ref() {
+        ReflectRef::Struct(s) => {
+            let map = KMap::with_capacity(s.field_len());
+            for (i, field) in s.iter_fields().enumerate() {
+                if let Some(name) = s.name_at(i) {
+                    map.insert(name, reflect_to_koto(field));
+                }
+            }
+            map.into()
+        }
+        ReflectRef::TupleStruct(s) if s.field_len() == 1 => reflect_to_koto(s.field(0).unwrap()),
+        ReflectRef::TupleStruct(s) => make_tuple(s.iter_fields()),
+        ReflectRef::Tuple(t) => make_tuple(t.iter_fields()),
+        ReflectRef::List(l) => make_list(l.iter()),
+        ReflectRef::Array(a) => make_list(a.iter()),
+        ReflectRef::Set(s) => make_list(s.iter()),
+        ReflectRef::Map(m) => {
+            let map = KMap::with_capacity(m.len());
+            for (key, value) in m.iter() {
+                if let KValue::Str(key) = reflect_to_koto(key) {
+                    map.insert(key, reflect_to_koto(value));
+                }
+            }
+            map.into()
+        }
+        ReflectRef::Enum(e) => {
+            let value = match e.variant_type() {
+                VariantType::Unit => return e.variant_name().into(),
+                VariantType::Tuple if e.field_len() == 1 => reflect_to_koto(e.field_at(0).unwrap()),
+                VariantType::Tuple => make_tuple(e.iter_fields().map(|field| field.value())),
+                VariantType::Struct => {
+                    let map = KMap::with_capacity(e.field_len());
+                    for field in e.iter_fields() {
+                        if let Some(name) = field.name() {
+                            map.insert(name, reflect_to_koto(field.value()));
+                        }
+                    }
+                    map.into()
+                }
+            };
+
+            let map = KMap::with_capacity(2);
+            map.insert("variant", e.variant_name());
+            map.insert("value", value);
+            map.into()
+        }
+        _ => opaque_to_koto(value),
+    }
+}
+
+fn make_tuple<'a>(values: impl Iterator<Item = &'a dyn PartialReflect>) -> KValue {
+    KValue::Tuple(values.map(reflect_to_koto).collect::<Vec<_>>().into())
+}
+
+fn make_list<'a>(values: impl Iterator<Item = &'a dyn PartialReflect>) -> KValue {
+    KList::with_data(values.map(reflect_to_koto).collect()).into()
+}
+
+fn opaque_to_koto(value: &dyn PartialReflect) -> KValue {
+    macro_rules! try_number {
+        ($($t:ty),+) => {
+            $(
+                if let Some(n) = value.try_downcast_ref::<$t>() {
+                    return (*n).into();
+                }
+            )+
+        };
+    }
+
+    try_number!(f32, f64, i8, i16, i32, i64, u8, u16, u32, isize);
+
+    if let Some(n) = value.try_downcast_ref::<u64>() {
+        return (*n as i64).into();
+    }
+    if let Some(n) = value.try_downcast_ref::<usize>() {
+        return (*n as i64).into();
+    }
+    if let Some(b) = value.try_downcast_ref::<bool>() {
+        return (*b).into();
+    }
+    if let Some(s) = value.try_downcast_ref::<String>() {
+        return s.as_str().into();
+    }
+    if let Some(s) = value.try_downcast_ref::<&'static str>() {
+        return (*s).into();
+    }
+    if let Some(c) = value.try_downcast_ref::<char>() {
+        return c.to_string().into();
+    }
+
+    KValue::Null
+}
+
+/// Converts a Koto value into a reflected value of the type with the given type path
+///
+/// The type can be referred to by its full or short type path, and needs to be registered with
+/// `#[reflect(Default)]`. The value is created from the type's default value, with the Koto value
+/// then applied using [apply_koto_to_reflect], so maps only need to contain the fields that
+/// differ from the default.
+///
+/// The returned value can then be applied to components or resources,
+/// e.g. with `ReflectComponent::apply`.
+pub fn koto_to_reflect(
+    value: &KValue,
+    type_path: &str,
+    registry: &TypeRegistry,
+) -> Result<Box<dyn PartialReflect>, String> {
+    let Some(registration) = registry
+        .get_with_type_path(type_path)
+        .or_else(|| registry.get_with_short_type_path(type_path))
+    else {
+        return Err(format!("'{type_path}' is not a registered type"));
+    };
+
+    let Some(reflect_default) = registration.data::<ReflectDefault>() else {
+        return Err(format!("'{type_path}' doesn't reflect Default"));
+    };
+
+    let mut result = reflect_default.default().into_partial_reflect();
+    apply_koto_to_reflect(value, result.as_mut())?;
+    Ok(result)
+}
+
+/// Applies a Koto value to a reflected Bevy value
+///
+/// This is the inverse of [reflect_to_koto], with maps being applied field-by-field so that only
+/// the fields that are present in the map are modified. Lists, arrays, and tuples need to have the
+/// same number of elements as the target value. Enums can be switched to a unit variant by
+/// providing the variant's name as a string.
+///
+/// An error message is returned if the value doesn't match the target's shape.
+pub fn apply_koto_to_reflect(
+    value: &KValue,
+    target: &mut dyn PartialReflect,
+) -> Result<(), String> {
+    match (target.reflect_mut(), value) {
+        (ReflectMut::Struct(s), KValue::Map(map)) => {
+            for (key, value) in map.data().iter() {
+                let KValue::Str(name) = key.value() else {
+                    return Err(format!(
+                        "expected a String as field name, found {}",
+                        key.value().type_as_string()
+                    ));
+                };
+                let Some(field) = s.field_mut(name) else {
+                    return Err(format!(
+                        "'{name}' is not a field of {}",
+                        s.reflect_short_type_path()
+                    ));
+                };
+                apply_koto_to_reflect(value, field).map_err(|error| format!("{name}: {error}"))?;
+            }
+            Ok(())
+        }
+        (ReflectMut::TupleStruct(s), _) if s.field_len() == 1 => {
+            apply_koto_to_reflect(value, s.field_mut(0).unwrap())
+        }
+        (ReflectMut::TupleStruct(s), KValue::Tuple(values)) => {
+            check_len(s.field_len(), values.len())?;
+            for (i, value) in values.iter().enumerate() {
+                apply_koto_to_reflect(value, s.field_mut(i).unwrap())?;
+            }
+            Ok(())
+        }
+        (ReflectMut::Tuple(t), KValue::Tuple(values)) => {
+            check_len(t.field_len(), values.len())?;
+            for (i, value) in values.iter().enumerate() {
+                apply_koto_to_reflect(value, t.field_mut(i).unwrap())?;
+            }
+            Ok(())
+        }
+        (ReflectMut::List(l), KValue::List(values)) => {
+            let values = values.data();
+            check_len(l.len(), values.len())?;
+            for (i, value) in values.iter().enumerate() {
+                apply_koto_to_reflect(value, l.get_mut(i).unwrap())?;
+            }
+            Ok(())
+        }
+        (ReflectMut::Array(a), KValue::List(values)) => {
+            let values = values.data();
+            check_len(a.len(), values.len())?;
+            for (i, value) in values.iter().enumerate() {
+                apply_koto_to_reflect(value, a.get_mut(i).unwrap())?;
+            }
+            Ok(())
+        }
+        (ReflectMut::Enum(e), KValue::Str(variant)) => e
+            .try_apply(&DynamicEnum::new(variant.as_str(), DynamicVariant::Unit))
+            .map_err(|error| error.to_string()),
+        (ReflectMut::Enum(e), KValue::Map(map)) => {
+            match map.get("variant") {
+                Some(KValue::Str(variant)) if variant.as_str() == e.variant_name() => {}
+                Some(KValue::Str(variant)) => {
+                    return Err(format!(
+                        "switching to the non-unit variant '{variant}' isn't supported"
+                    ))
+                }
+                _ => return Err("expected a map with a 'variant' entry".into()),
+            }
+            let Some(value) = map.get("value") else {
+                return Ok(());
+            };
+            match (e.variant_type(), &value) {
+                (VariantType::Tuple, _) if e.field_len() == 1 => {
+                    apply_koto_to_reflect(&value, e.field_at_mut(0).unwrap())
+                }
+                (VariantType::Tuple, KValue::Tuple(values)) => {
+                    check_len(e.field_len(), values.len())?;
+                    for (i, value) in values.iter().enumerate() {
+                        apply_koto_to_reflect(value, e.field_at_mut(i).unwrap())?;
+                    }
+                    Ok(())
+                }
+                (VariantType::Struct, KValue::Map(fields)) => {
+                    for (key, value) in fields.data().iter() {
+                        let field = match key.value() {
+                            KValue::Str(name) => e.field_mut(name),
+                            _ => None,
+                        };
+                        let Some(field) = field else {
+                            return Err(format!(
+                                "unexpected field in variant '{}'",
+                                e.variant_name()
+                            ));
+                        };
+                        apply_koto_to_reflect(value, field)?;
+                    }
+                    Ok(())
+                }
+                _ => Err(format!(
+                    "unexpected {} for the value of variant '{}'",
+                    value.type_as_string(),
+                    e.variant_name()
+                )),
+            }
+        }
+        (ReflectMut::Opaque(target), _) => apply_opaque(value, target),
+        (target, _) => Err(format!(
+            "unable to apply a {} to a {}",
+            value.type_as_string(),
+            target.kind()
+        )),
+    }
+}
+
+fn check_len(expected: usize, found: usize) -> Result<(), String> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(format!("expected {expected} elements, found {found}"))
+    }
+}
+
+fn apply_opaque(value: &KValue, target: &mut dyn PartialReflect) -> Result<(), String> {
+    macro_rules! try_number {
+        ($n:expr, $($t:ty),+) => {
+            $(
+                if let Some(target) = target.try_downcast_mut::<$t>() {
+                    *target = <$t>::try_from(i64::from($n))
+                        .map_err(|_| format!("{} is out of range for {}", $n, stringify!($t)))?;
+                    return Ok(());
+                }
+            )+
+        };
+    }
+
+    match value {
+        KValue::Number(n) => {
+            if let Some(target) = target.try_downcast_mut::<f32>() {
+                *target = f32::from(n);
+                return Ok(());
+            }
+            if let Some(target) = target.try_downcast_mut::<f64>() {
+                *target = f64::from(n);
+                return Ok(());
+            }
+            try_number!(n, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+        }
+        KValue::Bool(b) => {
+            if let Some(target) = target.try_downcast_mut::<bool>() {
+                *target = *b;
+                return Ok(());
+            }
+        }
+        KValue::Str(s) => {
+            if let Some(target) = target.try_downcast_mut::<String>() {
+                *target = s.to_string();
+                return Ok(());
+            }
+        }
+        _ => {}
+    }
+
+    Err(format!(
+        "unable to apply a {} to a value of type {}",
+        value.type_as_string(),
+        target.reflect_short_type_path()
+    ))
+}
@@ -0,0 +1,265 @@
+//! Reflection-based access to Bevy resources from Koto scripts
+
+use crate::{
+    prelude::*,
+    reflect::{apply_koto_to_reflect, reflect_to_koto},
+};
+use bevy::{
+    prelude::*,
+    reflect::{GetPath, TypePath},
+};
+use cloned::cloned;
+use koto::prelude::*;
+use parking_lot::RwLock;
+use std::{collections::HashMap, sync::Arc};
+
+/// Gives scripts access to an allowlist of Bevy resources
+///
+/// The plugin adds a `resources` module to the `bevy` module in Koto's prelude, with the
+/// following functions:
+/// - `bevy.resources.get(name)`: Returns the resource's value.
+/// - `bevy.resources.get(name, path)`: Returns a field from the resource, e.g. `'player.speed'`.
+/// - `bevy.resources.set(name, path, value)`: Sets a field in the resource, or the whole resource
+///   if the path is empty.
+///
+/// Resources are referred to by their short type name, and need to be added to the allowlist
+/// with [KotoResourcesPlugin::allow]. Values are converted using [reflect_to_koto] and
+/// [apply_koto_to_reflect].
+///
+/// The values returned by `get` are snapshots that are taken at the start of the [KotoSchedule],
+/// before scripts are loaded or updated. Changes made with `set` are visible immediately to the
+/// script, and are applied to the resources in the [KotoUpdate::PostUpdate] system set. Setting a
+/// path that doesn't exist in the resource is an error, and if a value can't be applied to the
+/// resource then an error is logged and the snapshot is restored from the resource.
+#[derive(Default)]
+pub struct KotoResourcesPlugin {
+    allowed: Vec<AllowedResource>,
+}
+
+impl KotoResourcesPlugin {
+    /// Allows scripts to access the resource of type `R`
+    #[must_use]
+    pub fn allow<R>(mut self) -> Self
+    where
+        R: Resource + Reflect + TypePath,
+    {
+        self.allowed.push(AllowedResource {
+            name: R::short_type_path(),
+            snapshot: snapshot_resource::<R>,
+            update: update_resource::<R>,
+        });
+        self
+    }
+}
+
+impl Plugin for KotoResourcesPlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+
+        let (update_resource_sender, update_resource_receiver) =
+            koto_channel::<UpdateKotoResource>();
+        let resources = KotoResources {
+            allowed: self.allowed.clone(),
+            snapshots: Default::default(),
+        };
+
+        if let Some(KValue::Map(bevy_module)) =
+            app.world().resource::<KotoRuntime>().prelude().get("bevy")
+        {
+            bevy_module.insert(
+                "resources",
+                make_resources_module(resources.snapshots.clone(), update_resource_sender),
+            );
+        }
+
+        app.insert_resource(resources)
+            .insert_resource(update_resource_receiver)
+            .add_systems(
+                KotoSchedule,
+                (
+                    snapshot_resources.in_set(KotoUpdate::Register),
+                    update_resources.in_set(KotoUpdate::PostUpdate),
+                ),
+            );
+    }
+}
+
+#[derive(Clone)]
+struct AllowedResource {
+    name: &'static str,
+    snapshot: fn(&World) -> Option<KValue>,
+    update: fn(&mut World, &str, &KValue) -> Result<(), String>,
+}
+
+#[derive(Resource)]
+struct KotoResources {
+    allowed: Vec<AllowedResource>,
+    // Snapshots of the allowed resources, shared with the Koto module
+    snapshots: Arc<RwLock<HashMap<String, KValue>>>,
+}
+
+struct UpdateKotoResource {
+    name: String,
+    path: String,
+    value: KValue,
+}
+
+#[allow(clippy::result_large_err)]
+fn make_resources_module(
+    snapshots: Arc<RwLock<HashMap<String, KValue>>>,
+    update_resource: KotoSender<UpdateKotoResource>,
+) -> KMap {
+    let module = KMap::with_type("resources");
+
+    module.add_fn("get", {
+        cloned!(snapshots);
+        move |ctx| {
+            let (name, path) = match ctx.args() {
+                [KValue::Str(name)] => (name, ""),
+                [KValue::Str(name), KValue::Str(path)] => (name, path.as_str()),
+                unexpected => {
+                    return unexpected_args("a String, and an optional String", unexpected)
+                }
+            };
+
+            let snapshots = snapshots.read();
+            let Some(resource) = snapshots.get(name.as_str()) else {
+                return runtime_error!("resources.get: '{name}' is not an allowed resource");
+            };
+
+            match get_path(resource, path) {
+                Some(value) => Ok(value),
+                None => runtime_error!("resources.get: '{path}' not found in '{name}'"),
+            }
+        }
+    });
+
+    module.add_fn("set", move |ctx| {
+        let (name, path, value) = match ctx.args() {
+            [KValue::Str(name), KValue::Str(path), value] => (name, path, value),
+            unexpected => return unexpected_args("a String, a String, and a value", unexpected),
+        };
+
+        let mut snapshots = snapshots.write();
+        let Some(resource) = snapshots.get_mut(name.as_str()) else {
+            return runtime_error!("resources.set: '{name}' is not an allowed resource");
+        };
+
+        if !path.is_empty() && get_path(resource, path).is_none() {
+            return runtime_error!("resources.set: '{path}' not found in '{name}'");
+        }
+
+        // Update the snapshot so that the change is visible to the script immediately,
+        // the snapshot is restored if the change can't be applied to the resource
+        set_path(resource, path, value.clone());
+
+        update_resource.send(UpdateKotoResource {
+            name: name.to_string(),
+            path: path.to_string(),
+            value: value.clone(),
+        });
+
+        Ok(KValue::Null)
+    });
+
+    module
+}
+
+// Finds the value at the given '.'-separated path in a converted resource
+fn get_path(value: &KValue, path: &str) -> Option<KValue> {
+    let mut result = value.clone();
+    for key in path.split('.').filter(|key| !key.is_empty()) {
+        result = match &result {
+            KValue::Map(map) => map.get(key)?,
+            _ => return None,
+        };
+    }
+    Some(result)
+}
+
+fn set_path(value: &mut KValue, path: &str, new_value: KValue) {
+    if path.is_empty() {
+        *value = new_value;
+    } else if let Some((parent, key)) = path.rsplit_once('.') {
+        if let Some(KValue::Map(map)) = get_path(value, parent) {
+            map.insert(key, new_value);
+        }
+    } else if let KValue::Map(map) = value {
+        map.insert(path, new_value);
+    }
+}
+
+fn snapshot_resource<R: Resource + Reflect>(world: &World) -> Option<KValue> {
+    world
+        .get_resource::<R>()
+        .map(|resource| reflect_to_koto(resource.as_partial_reflect()))
+}
+
+fn update_resource<R: Resource + Reflect>(
+    world: &mut World,
+    path: &str,
+    value: &KValue,
+) -> Result<(), String> {
+    let Some(mut resource) = world.get_resource_mut::<R>() else {
+        return Err("the resource doesn't exist".into());
+    };
+
+    if path.is_empty() {
+        apply_koto_to_reflect(value, resource.as_partial_reflect_mut())
+    } else {
+        let field = resource
+            .reflect_path_mut(path)
+            .map_err(|error| error.to_string())?;
+        apply_koto_to_reflect(value, field)
+    }
+}
+
+fn snapshot_resources(world: &World) {
+    let resources = world.resource::<KotoResources>();
+    let mut snapshots = resources.snapshots.write();
+
+    for allowed in resources.allowed.iter() {
+        match (allowed.snapshot)(world) {
+            Some(snapshot) => snapshots.insert(allowed.name.to_string(), snapshot),
+            None => snapshots.remove(allowed.name),
+        };
+    }
+}
+
+fn update_resources(world: &mut World) {
+    let channel = world.resource::<KotoReceiver<UpdateKotoResource>>().clone();
+    let _span = info_span!("koto_channel", channel = "UpdateKotoResource").entered();
+
+    let mut failed = Vec::new();
+
+    while let Some(update) = channel.receive() {
+        let Some(allowed) = world
+            .resource::<KotoResources>()
+            .allowed
+            .iter()
+            .find(|allowed| allowed.name == update.name)
+            .cloned()
+        else {
+            continue;
+        };
+
+        if let Err(error) = (allowed.update)(world, &update.path, &update.value) {
+            error!(
+                "Failed to set '{}' in resource '{}': {error}",
+                update.path, update.name
+            );
+            failed.push(allowed);
+        }
+    }
+
+    // Restore the snapshots of resources with failed updates so that the script doesn't see
+    // values that weren't applied
+    for allowed in failed {
+        let snapshot = (allowed.snapshot)(world);
+        let mut snapshots = world.resource::<KotoResources>().snapshots.write();
+        match snapshot {
+            Some(snapshot) => snapshots.insert(allowed.name.to_string(), snapshot),
+            None => snapshots.remove(allowed.name),
+        };
+    }
+}
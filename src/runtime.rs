@@ -1,18 +1,32 @@
 //! Support for adding a Koto runtime to a Bevy application
 
+use crate::app::{apply_koto_registrations, KotoRegistrations};
+use crate::convert::{FromKotoValue, IntoKotoArgs, KotoCallError};
+use crate::memory::{update_memory_stats, KotoMemoryLimit, KotoMemoryStats};
+use crate::metadata::ScriptMetadata;
+use crate::modules::{load_imported_modules, resolve_imports, KotoModule, KotoModules};
+use crate::time::{update_koto_time, KotoFrameControl, KotoTime, KotoTimeObject, UpdateKotoTime};
 use bevy::{
-    app::MainScheduleOrder,
-    asset::{
-        io::{file::FileAssetReader, Reader},
-        AssetLoader, LoadContext,
-    },
-    ecs::schedule::ScheduleLabel,
+    app::{FixedMainScheduleOrder, MainScheduleOrder},
+    asset::{io::Reader, AssetLoader, LoadContext, LoadState},
+    ecs::schedule::{InternedScheduleLabel, ScheduleLabel},
     prelude::*,
     reflect::TypePath,
+    tasks::{block_on, poll_once, AsyncComputeTaskPool, Task},
+    utils::Instant,
 };
 use cloned::cloned;
-use koto::prelude::*;
+use koto::{
+    bytecode::Compiler,
+    parser::{format_source_excerpt, Span},
+    prelude::*,
+    runtime::Result as KotoResult,
+    Ptr,
+};
+use parking_lot::Mutex;
 use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
     path::{Path, PathBuf},
     str,
     time::Duration,
@@ -22,9 +36,28 @@ use std::{
 #[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct KotoSchedule;
 
+/// Where the [KotoSchedule] is placed in Bevy's schedule order, see [KotoRuntimePlugin]
+#[derive(Clone, Copy, Debug)]
+pub enum KotoSchedulePlacement {
+    /// The schedule runs after the given schedule in Bevy's main schedule order
+    After(InternedScheduleLabel),
+    /// The schedule runs after [FixedUpdate] in Bevy's fixed timestep loop
+    ///
+    /// The script's `update` function will then receive the fixed timestep as its time delta.
+    FixedUpdate,
+}
+
+impl Default for KotoSchedulePlacement {
+    fn default() -> Self {
+        Self::After(PreUpdate.intern())
+    }
+}
+
 /// The system set used for updating the Koto runtime
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
 pub enum KotoUpdate {
+    /// Apply registrations made via [KotoAppExt](crate::app::KotoAppExt)
+    Register,
     /// Detect if the script needs to be reloaded and compiled
     /// - The script's setup function gets called when the script is first loaded.
     /// - The script's on_load function gets called after each compilation.
@@ -50,7 +83,142 @@ pub enum KotoUpdate {
 /// The following events are also added by the plugin:
 /// - [LoadScript]: Sent to load a new script
 /// - [ScriptLoaded]: Sent after a script has been successfully loaded and initialized.
-pub struct KotoRuntimePlugin;
+/// - [ScriptUnloaded]: Sent when the current script is about to be replaced by a new script.
+/// - [ScriptCompiling]: Sent when a script starts being compiled in the background.
+/// - [ScriptReady]: Sent after a script has been compiled and initialized, including hot-reloads.
+/// - [ScriptCompileFailed]: Sent when a script fails to compile, with the error's location.
+/// - [KotoScriptError]: Sent when an error occurs while compiling or running a script.
+/// - [KotoScriptOutput]: Sent when a script writes to stdout or stderr, e.g. with `print`.
+/// - [KotoCustomEvent]: Sent when a script calls `bevy.send_event`.
+///
+/// Script output is also logged via Bevy's logging macros.
+///
+/// The script's `update` function is called each frame with the script's state, the time delta,
+/// and a `Time` object that provides access to the [KotoTime].
+///
+/// If the script exports a `fixed_update` function, then it will be called from Bevy's
+/// [FixedUpdate] schedule, in addition to the per-frame `update` function.
+///
+/// If the script exports an `on_exit` function, then it will be called with the script's state
+/// when an [AppExit] event is sent, e.g. to allow the script to save its state. Scripts can exit
+/// the app with `bevy.quit()`, with an optional exit code.
+///
+/// By default the [KotoSchedule] runs after Bevy's [PreUpdate] schedule, see
+/// [KotoSchedulePlacement] for other options.
+///
+/// Each call into the script is limited by the plugin's execution limit (250ms by default).
+/// Calls that run for longer are interrupted, e.g. when a script gets stuck in an infinite loop,
+/// with the script being marked as errored until it's reloaded, and a [KotoScriptError] being
+/// sent. Entities whose `on_update` function exceeds the limit have the function removed.
+///
+/// The limit applies to individual calls rather than to frames, so an interrupted call still
+/// stalls the app for the duration of the limit, and many calls that each stay under the limit
+/// can together take longer than a frame without being interrupted.
+///
+/// The script's estimated memory usage is reported in the [KotoMemoryStats] resource.
+/// An optional memory limit can be set with [KotoRuntimePlugin::with_memory_limit].
+///
+/// A prelude script can be set with [KotoRuntimePlugin::with_prelude_script], making its exports
+/// available to every script that gets loaded.
+///
+/// Bevy's `AssetPlugin` is optional, but needs to be added before this plugin for script assets to
+/// be available. Without it (e.g. in a headless app using `MinimalPlugins`), scripts need to be
+/// loaded with [LoadScript::inline].
+pub struct KotoRuntimePlugin {
+    /// The timestep used for calls to the script's `fixed_update` function
+    ///
+    /// Note that this sets the timestep of Bevy's `Time<Fixed>` clock, which is shared by all
+    /// systems in the [FixedUpdate] schedule. If `None`, then the clock's timestep is unchanged.
+    pub fixed_timestep: Option<Duration>,
+    /// Where the [KotoSchedule] should be placed in Bevy's schedule order
+    pub schedule_placement: KotoSchedulePlacement,
+    /// The maximum duration of a single call into the script, 250ms by default
+    ///
+    /// If `None`, then calls are allowed to run indefinitely.
+    pub execution_limit: Option<Duration>,
+    /// The maximum estimated memory usage of the script in bytes, see [KotoMemoryStats]
+    ///
+    /// If the limit is exceeded then the script is stopped and a [KotoScriptError] is sent.
+    pub memory_limit: Option<usize>,
+    /// The path of a script whose exports are added to the prelude, relative to the assets folder
+    ///
+    /// See [KotoRuntimePlugin::with_prelude_script].
+    pub prelude_script: Option<PathBuf>,
+}
+
+impl Default for KotoRuntimePlugin {
+    fn default() -> Self {
+        Self {
+            fixed_timestep: None,
+            schedule_placement: default(),
+            execution_limit: Some(Duration::from_millis(250)),
+            memory_limit: None,
+            prelude_script: None,
+        }
+    }
+}
+
+impl KotoRuntimePlugin {
+    /// Places the [KotoSchedule] after the given schedule in Bevy's main schedule order
+    ///
+    /// The schedule needs to already be part of the main schedule order when the plugin is built.
+    #[must_use]
+    pub fn with_schedule_after(mut self, schedule: impl ScheduleLabel) -> Self {
+        self.schedule_placement = KotoSchedulePlacement::After(schedule.intern());
+        self
+    }
+
+    /// Runs the [KotoSchedule] as part of Bevy's fixed timestep loop, after [FixedUpdate]
+    #[must_use]
+    pub fn with_schedule_in_fixed_update(mut self) -> Self {
+        self.schedule_placement = KotoSchedulePlacement::FixedUpdate;
+        self
+    }
+
+    /// Sets the maximum duration of a single call into the script
+    ///
+    /// A runaway call stalls the app for up to the limit before it gets interrupted, so lower
+    /// limits shorten the stall. The limit needs to leave enough time for the script's slowest
+    /// calls though, e.g. a `setup` function that builds a large scene in a debug build.
+    #[must_use]
+    pub fn with_execution_limit(mut self, execution_limit: Duration) -> Self {
+        self.execution_limit = Some(execution_limit);
+        self
+    }
+
+    /// Sets the maximum estimated memory usage of the script in bytes
+    ///
+    /// The script's memory usage is checked after each update, see [KotoMemoryStats].
+    #[must_use]
+    pub fn with_memory_limit(mut self, bytes: usize) -> Self {
+        self.memory_limit = Some(bytes);
+        self
+    }
+
+    /// Sets the path of a prelude script, e.g. `"scripts/_prelude.koto"`
+    ///
+    /// The prelude script is run each time a script is loaded, before the script's top-level code
+    /// and its `setup` function, and its exports are added to the prelude. This allows helper
+    /// functions to be shared between scripts without needing to be imported. Exports with names
+    /// that are already in the prelude are ignored.
+    ///
+    /// Scripts wait for the prelude script to be loaded before being initialized, and the current
+    /// script is reloaded when the prelude script is modified.
+    ///
+    /// The prelude script is loaded as an asset, so Bevy's `AssetPlugin` needs to be available.
+    #[must_use]
+    pub fn with_prelude_script(mut self, path: impl Into<PathBuf>) -> Self {
+        self.prelude_script = Some(path.into());
+        self
+    }
+
+    /// Sets the rate (in Hz) at which the script's `fixed_update` function should be called
+    #[must_use]
+    pub fn with_fixed_update_hz(mut self, hz: f64) -> Self {
+        self.fixed_timestep = Some(Duration::from_secs_f64(1.0 / hz));
+        self
+    }
+}
 
 impl Plugin for KotoRuntimePlugin {
     fn build(&self, app: &mut App) {
@@ -58,6 +226,7 @@ impl Plugin for KotoRuntimePlugin {
             app.init_schedule(KotoSchedule).configure_sets(
                 KotoSchedule,
                 (
+                    KotoUpdate::Register,
                     KotoUpdate::Compile,
                     KotoUpdate::PreUpdate,
                     KotoUpdate::Update,
@@ -66,145 +235,793 @@ impl Plugin for KotoRuntimePlugin {
                     .chain(),
             );
 
-            let mut order = app.world_mut().resource_mut::<MainScheduleOrder>();
-            order.insert_after(PreUpdate, KotoSchedule);
+            match self.schedule_placement {
+                KotoSchedulePlacement::After(schedule) => {
+                    let mut order = app.world_mut().resource_mut::<MainScheduleOrder>();
+                    order.insert_after(schedule, KotoSchedule);
+                }
+                KotoSchedulePlacement::FixedUpdate => {
+                    let mut order = app.world_mut().resource_mut::<FixedMainScheduleOrder>();
+                    order.insert_after(FixedUpdate, KotoSchedule);
+                }
+            }
         }
 
         let (add_dependency_sender, add_dependency_receiver) = koto_channel::<AddDependency>();
-        let koto_runtime = KotoRuntime::new(add_dependency_sender.clone());
+        let (script_output_sender, script_output_receiver) = koto_channel::<ScriptOutputLine>();
+        let (update_time_sender, update_time_receiver) = koto_channel::<UpdateKotoTime>();
+        let (custom_event_sender, custom_event_receiver) = koto_channel::<KotoCustomEvent>();
+        let (quit_sender, quit_receiver) = koto_channel::<QuitRequest>();
+        let koto_runtime = KotoRuntime::new(
+            add_dependency_sender.clone(),
+            script_output_sender,
+            self.execution_limit,
+        );
+        koto_runtime
+            .prelude()
+            .insert("bevy", make_bevy_module(custom_event_sender, quit_sender));
 
         // Hack to get the root path of the assets folder,
         // see https://github.com/bevyengine/bevy/issues/10455
-        let assets_folder_path = FileAssetReader::get_base_path().join("assets");
+        #[cfg(not(target_arch = "wasm32"))]
+        let assets_folder_path =
+            bevy::asset::io::file::FileAssetReader::get_base_path().join("assets");
+        // There's no filesystem available on the web, so script paths are given a virtual root.
+        // Imports can't be resolved from disk, so modules need to be provided via KotoModules.
+        #[cfg(target_arch = "wasm32")]
+        let assets_folder_path = PathBuf::from("assets");
 
         app.insert_resource(koto_runtime)
             .insert_resource(add_dependency_sender)
             .insert_resource(add_dependency_receiver)
+            .insert_resource(script_output_receiver)
+            .insert_resource(update_time_sender)
+            .insert_resource(update_time_receiver)
+            .insert_resource(custom_event_receiver)
+            .insert_resource(quit_receiver)
             .insert_resource(ActiveScript::default())
+            .insert_resource(PendingScripts::default())
+            .insert_resource(CompilingScript::default())
+            .init_resource::<KotoRegistrations>()
+            .insert_resource(KotoTime::default())
+            .insert_resource(KotoFrameControl::default())
+            .insert_resource(KotoMemoryStats::default())
+            .insert_resource(KotoMemoryLimit(self.memory_limit))
+            .init_resource::<KotoScriptTimings>()
             .insert_resource(AssetsFolderPath(assets_folder_path))
+            .init_resource::<KotoModules>()
             .add_event::<LoadScript>()
             .add_event::<ScriptLoaded>()
-            .init_asset::<KotoScript>()
-            .register_asset_loader(KotoScriptAssetLoader)
+            .add_event::<ScriptUnloaded>()
+            .add_event::<ScriptCompiling>()
+            .add_event::<ScriptReady>()
+            .add_event::<ScriptCompileFailed>()
+            .add_event::<KotoScriptError>()
+            .add_event::<KotoScriptOutput>()
+            .add_event::<KotoCustomEvent>()
+            // Added by default in Bevy apps, but not in sub-apps
+            .add_event::<AppExit>()
             .add_systems(
                 KotoSchedule,
                 (
+                    // Apply any pending registrations before compiling
+                    apply_koto_registrations.in_set(KotoUpdate::Register),
                     // Compile the script if necessary
-                    process_load_script_events.in_set(KotoUpdate::Compile),
+                    (
+                        process_load_script_events,
+                        compile_pending_scripts,
+                        initialize_compiled_script,
+                    )
+                        .chain()
+                        .in_set(KotoUpdate::Compile),
                     // Run the script's update function
                     run_script_update.in_set(KotoUpdate::Update),
+                    // Check the script's memory usage once its update functions have been called
+                    update_memory_stats
+                        .after(KotoUpdate::Update)
+                        .before(KotoUpdate::PostUpdate),
                     // Post update tasks
-                    add_script_dependencies.in_set(KotoUpdate::PostUpdate),
+                    (
+                        add_script_dependencies,
+                        process_script_output,
+                        process_custom_events,
+                        process_quit_requests,
+                        update_koto_time,
+                    )
+                        .in_set(KotoUpdate::PostUpdate),
                 ),
             )
+            .add_systems(FixedUpdate, run_script_fixed_update)
             .add_systems(
                 Update,
-                (process_script_asset_events, add_script_dependencies),
+                (
+                    add_script_dependencies,
+                    process_script_output,
+                    process_custom_events,
+                    process_quit_requests,
+                ),
+            )
+            // The app exits once the frame's schedules have run, so on_exit is called in the
+            // frame's last schedule, followed by processing any output from the call.
+            .add_systems(
+                Last,
+                (run_script_on_exit, process_script_output)
+                    .chain()
+                    .run_if(on_event::<AppExit>),
             );
+
+        if app.is_plugin_added::<AssetPlugin>() {
+            app.init_asset::<KotoScript>()
+                .register_asset_loader(KotoScriptAssetLoader)
+                .add_systems(Update, process_script_asset_events);
+
+            if let Some(path) = &self.prelude_script {
+                let handle = app.world().resource::<AssetServer>().load(path.clone());
+                app.insert_resource(KotoPreludeScript(handle));
+            }
+        } else if let Some(path) = &self.prelude_script {
+            error!(
+                "Unable to load the prelude script {} without an AssetServer",
+                path.to_string_lossy()
+            );
+        }
+
+        if let Some(timestep) = self.fixed_timestep {
+            app.insert_resource(Time::<Fixed>::from_duration(timestep));
+        }
     }
 }
 
 fn process_script_asset_events(
     active_script: Res<ActiveScript>,
+    prelude_script: Option<Res<KotoPreludeScript>>,
     mut asset_events: EventReader<AssetEvent<KotoScript>>,
     mut load_script: EventWriter<LoadScript>,
+    mut koto: ResMut<KotoRuntime>,
 ) {
-    if let Some(script) = &active_script.script {
-        for event in asset_events.read() {
-            let id = match event {
-                AssetEvent::Added { id } => *id,
-                AssetEvent::Modified { id } => *id,
-                _ => continue,
-            };
+    for event in asset_events.read() {
+        let id = match event {
+            AssetEvent::Added { id } => *id,
+            AssetEvent::Modified { id } => {
+                // Any script could have been imported as a module, so the module cache needs to
+                // be cleared before the next script is initialized.
+                koto.module_cache_is_stale = true;
+                *id
+            }
+            _ => continue,
+        };
 
+        if let Some(script) = &active_script.script {
             if id == script.id()
+                || prelude_script
+                    .as_ref()
+                    .is_some_and(|prelude| id == prelude.0.id())
                 || active_script
                     .dependencies
                     .iter()
                     .any(|handle| id == handle.id())
             {
-                load_script.send(LoadScript::reload(script.clone()));
+                load_script.send(
+                    LoadScript::reload(script.clone())
+                        .with_args(active_script.args.clone())
+                        .with_reload_policy(active_script.reload_policy),
+                );
             }
         }
     }
 }
 
 fn process_load_script_events(
-    assets_folder: Res<AssetsFolderPath>,
-    assets: Res<Assets<KotoScript>>,
+    asset_server: Option<Res<AssetServer>>,
     mut load_script_events: EventReader<LoadScript>,
+    mut pending_scripts: ResMut<PendingScripts>,
+) {
+    let _span = info_span!("koto_load_script").entered();
+
+    for event in load_script_events.read() {
+        let source = match &event.source {
+            LoadScriptSource::Handle(handle) => PendingSource::Asset(handle.clone()),
+            LoadScriptSource::Path(path) => {
+                let Some(asset_server) = &asset_server else {
+                    error!(
+                        "Unable to load {} without an AssetServer",
+                        path.to_string_lossy()
+                    );
+                    continue;
+                };
+                PendingSource::Asset(asset_server.load(path.clone()))
+            }
+            LoadScriptSource::Inline { name, source } => PendingSource::Inline {
+                name: name.into(),
+                source: source.clone(),
+            },
+        };
+        pending_scripts.0.push(PendingScript {
+            source,
+            call_setup: event.call_setup,
+            args: event.args.clone(),
+            reload_policy: event.reload_policy,
+        });
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn compile_pending_scripts(
+    assets_folder: Res<AssetsFolderPath>,
+    asset_server: Option<Res<AssetServer>>,
+    assets: Option<Res<Assets<KotoScript>>>,
+    mut script_compiling: EventWriter<ScriptCompiling>,
+    mut pending_scripts: ResMut<PendingScripts>,
+    mut compiling_script: ResMut<CompilingScript>,
+    koto: Res<KotoRuntime>,
+    prelude_script: Option<Res<KotoPreludeScript>>,
+) {
+    // Scripts wait for the prelude script to be loaded before they're compiled
+    if let (Some(prelude), Some(asset_server)) = (&prelude_script, &asset_server) {
+        if matches!(
+            asset_server.get_load_state(prelude.0.id()),
+            Some(LoadState::NotLoaded | LoadState::Loading)
+        ) {
+            return;
+        }
+    }
+
+    // Scripts are compiled one at a time in the order that they were requested,
+    // waiting for any that are still being loaded by the asset server.
+    while compiling_script.0.is_none() {
+        let Some(pending) = pending_scripts.0.first() else {
+            break;
+        };
+
+        if let PendingSource::Asset(handle) = &pending.source {
+            let id = handle.id();
+            if !assets.as_ref().is_some_and(|assets| assets.contains(id)) {
+                match asset_server
+                    .as_ref()
+                    .and_then(|server| server.get_load_state(id))
+                {
+                    Some(LoadState::NotLoaded | LoadState::Loading) => break,
+                    Some(LoadState::Failed(error)) => error!("Failed to load script: {error}"),
+                    _ => error!("Unable to load script (id: {id})"),
+                }
+                pending_scripts.0.remove(0);
+                continue;
+            }
+        }
+
+        let pending = pending_scripts.0.remove(0);
+        let (script, path, modules, handle) = match pending.source {
+            PendingSource::Asset(handle) => {
+                // The script's availability was checked above
+                let script = assets
+                    .as_ref()
+                    .and_then(|assets| assets.get(&handle))
+                    .unwrap();
+                (
+                    script.script.clone(),
+                    script.path.clone(),
+                    script.modules.clone(),
+                    Some(handle),
+                )
+            }
+            PendingSource::Inline { name, source } => (source, name, Vec::new(), None),
+        };
+
+        info!("Compiling {}", path.to_string_lossy());
+
+        let imports = resolve_imports(&script, &path, |import_path| {
+            modules.iter().any(|module| module.path == import_path)
+        });
+        let script_path = assets_folder.0.join(path);
+        let cache_key = chunk_cache_key(&script, &script_path);
+        let task_script_path = script_path.clone();
+        let job = match koto.chunk_cache.get(&cache_key) {
+            Some(chunk) => {
+                debug!("Using cached chunk");
+                CompileJob::Cached(chunk.clone())
+            }
+            None => {
+                let koto_script_path = script_path.to_str().map(KString::from);
+                let script_path = script_path.clone();
+                CompileJob::Task(AsyncComputeTaskPool::get().spawn(async move {
+                    let start = Instant::now();
+                    Compiler::compile(&script, koto_script_path.clone(), default())
+                        .map(|chunk| (chunk, start.elapsed()))
+                        .map_err(|error| {
+                            let excerpt = format_source_excerpt(
+                                &script,
+                                &error.span,
+                                koto_script_path.as_deref(),
+                            );
+                            ScriptCompileFailed {
+                                script_path,
+                                message: error.to_string(),
+                                span: error.span,
+                                excerpt,
+                            }
+                        })
+                }))
+            }
+        };
+
+        compiling_script.0 = Some(CompilingScriptTask {
+            job,
+            cache_key,
+            script_path: task_script_path,
+            modules,
+            imports,
+            handle,
+            call_setup: pending.call_setup,
+            args: pending.args,
+            reload_policy: pending.reload_policy,
+        });
+        script_compiling.send_default();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn initialize_compiled_script(
     mut script_loaded: EventWriter<ScriptLoaded>,
+    mut script_unloaded: EventWriter<ScriptUnloaded>,
+    mut script_ready: EventWriter<ScriptReady>,
+    mut compile_failed: EventWriter<ScriptCompileFailed>,
+    mut script_error: EventWriter<KotoScriptError>,
     mut koto: ResMut<KotoRuntime>,
+    mut koto_time: ResMut<KotoTime>,
     mut active_script: ResMut<ActiveScript>,
+    mut compiling_script: ResMut<CompilingScript>,
+    mut timings: ResMut<KotoScriptTimings>,
+    modules: Res<KotoModules>,
+    assets_folder: Res<AssetsFolderPath>,
+    prelude_script: Option<Res<KotoPreludeScript>>,
+    assets: Option<Res<Assets<KotoScript>>>,
 ) {
-    for event in load_script_events.read() {
-        let Some(script) = assets.get(event.script.id()) else {
-            error!("Unable to load script (id: {})", event.script.id());
-            continue;
-        };
+    let Some(compiling) = &mut compiling_script.0 else {
+        return;
+    };
+    let compile_result = match &mut compiling.job {
+        CompileJob::Task(task) => match block_on(poll_once(task)) {
+            Some(Ok((chunk, compile_time))) => {
+                timings.compile = Some(compile_time);
+                Ok(chunk)
+            }
+            Some(Err(error)) => Err(error),
+            None => return,
+        },
+        CompileJob::Cached(chunk) => Ok(chunk.clone()),
+    };
+    let compiled = compiling_script.0.take().unwrap();
+    let _span = info_span!(
+        "koto_initialize_script",
+        script = %compiled.script_path.display()
+    )
+    .entered();
 
-        info!("Loading {}", script.path.to_string_lossy());
+    let chunk = match compile_result {
+        Ok(chunk) => {
+            koto.cache_chunk(compiled.cache_key, chunk.clone());
+            chunk
+        }
+        Err(failed) => {
+            // The current script (if any) continues running when compilation fails
+            let error = KotoScriptError {
+                phase: ScriptPhase::Compile,
+                message: failed.to_string(),
+                span: Some(failed.span),
+                script_path: Some(compiled.script_path),
+            };
+            error!("{error}");
+            script_error.send(error);
+            compile_failed.send(failed);
+            return;
+        }
+    };
 
-        let script_path = assets_folder.0.join(&script.path);
-        if koto
-            .initialize_script(&script.script, Some(&script_path), event.call_setup)
-            .is_ok()
-        {
-            if event.call_setup {
+    // Give the outgoing script a chance to clean up before it gets replaced
+    if compiled.call_setup && std::mem::take(&mut active_script.is_loaded) {
+        active_script.script = None;
+        if let Err(error) = koto.unload_script() {
+            error!("{error}");
+            script_error.send(error);
+        }
+        script_unloaded.send_default();
+    }
+
+    let init = if compiled.call_setup {
+        *koto_time = KotoTime::default();
+        ScriptInit::Load
+    } else {
+        ScriptInit::Reload(compiled.reload_policy)
+    };
+    let prelude_script = prelude_script
+        .as_ref()
+        .zip(assets.as_ref())
+        .and_then(|(prelude, assets)| assets.get(&prelude.0));
+    let prelude_modules = prelude_script
+        .into_iter()
+        .flat_map(|script| script.modules.iter());
+    let start = Instant::now();
+    let result = koto
+        .prepare_prelude(
+            modules.iter(),
+            prelude_modules.chain(compiled.modules.iter()),
+            prelude_script,
+            &assets_folder.0,
+        )
+        .and_then(|_| {
+            koto.initialize_script(
+                chunk,
+                &compiled.script_path,
+                &compiled.imports,
+                init,
+                &compiled.args,
+            )
+        });
+    timings.initialize = Some(start.elapsed());
+    match result {
+        Ok(()) => {
+            if compiled.call_setup {
                 script_loaded.send_default();
             }
+            script_ready.send_default();
 
-            active_script.script = Some(event.script.clone());
+            active_script.script = compiled.handle;
+            active_script.args = compiled.args;
+            active_script.reload_policy = compiled.reload_policy;
+            active_script.is_loaded = true;
             active_script.dependencies.clear();
         }
+        Err(error) => {
+            error!("{error}");
+            script_error.send(error);
+        }
     }
 }
 
 /// Sending this event will load the provided script into the runtime
 #[derive(Event, Default)]
 pub struct LoadScript {
-    script: Handle<KotoScript>,
+    source: LoadScriptSource,
     call_setup: bool, // false for a hot-reload
+    args: KValue,
+    reload_policy: ReloadPolicy,
 }
 
 impl LoadScript {
     /// Creates a LoadScript event for the given script handle
     pub fn load(script: Handle<KotoScript>) -> Self {
-        Self {
-            script,
-            call_setup: true,
-        }
+        Self::new(LoadScriptSource::Handle(script), true)
+    }
+
+    /// Creates a LoadScript event for the script at the given path in the assets folder
+    ///
+    /// The script will be loaded via the `AssetServer`, and then initialized once it's available.
+    pub fn from_path(path: impl Into<PathBuf>) -> Self {
+        Self::new(LoadScriptSource::Path(path.into()), true)
+    }
+
+    /// Creates a LoadScript event for a script that's provided directly as a string
+    ///
+    /// The name is used as the script's path in the assets folder, e.g. for resolving imports.
+    /// Inline scripts (and their imported modules) aren't hot-reloaded.
+    pub fn inline(name: impl Into<String>, source: impl Into<String>) -> Self {
+        let source = LoadScriptSource::Inline {
+            name: name.into(),
+            source: source.into(),
+        };
+        Self::new(source, true)
     }
 
     /// Creates a LoadScript event for the given handle that skips the script's setup function
+    ///
+    /// The script's state is handled according to the event's [ReloadPolicy].
     pub fn reload(script: Handle<KotoScript>) -> Self {
+        Self::new(LoadScriptSource::Handle(script), false)
+    }
+
+    fn new(source: LoadScriptSource, call_setup: bool) -> Self {
         Self {
-            script,
-            call_setup: false,
+            source,
+            call_setup,
+            args: KValue::Null,
+            reload_policy: ReloadPolicy::default(),
         }
     }
+
+    /// Sets the arguments that should be passed to the script's `setup` function
+    ///
+    /// Typically the arguments will be provided as a `KMap`.
+    /// By default the script's `setup` function is called with `null`.
+    #[must_use]
+    pub fn with_args(mut self, args: impl Into<KValue>) -> Self {
+        self.args = args.into();
+        self
+    }
+
+    /// Sets the policy that's used for the script's state when the script is hot-reloaded
+    ///
+    /// The policy is retained for future hot-reloads of the loaded script.
+    #[must_use]
+    pub fn with_reload_policy(mut self, reload_policy: ReloadPolicy) -> Self {
+        self.reload_policy = reload_policy;
+        self
+    }
+}
+
+/// Determines what happens to a script's state (the value returned by `setup`) on hot-reload
+///
+/// If the script exports a `migrate_state` function, then it will be called with the previous
+/// state when the state is preserved or merged, and the returned value will be used in place of
+/// the previous state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ReloadPolicy {
+    /// The script's existing state is kept
+    #[default]
+    PreserveState,
+    /// The script's `setup` function is called again to produce a new state
+    ResetState,
+    /// The script's `setup` function is called again, and then the entries from the previous
+    /// state are added to the new state
+    ///
+    /// This allows new entries to be added to the state while live-coding. If either state isn't a
+    /// map, then the previous state is kept.
+    MergeState,
+}
+
+/// The source of the script to be loaded by a [LoadScript] event
+#[derive(Clone, Debug)]
+pub enum LoadScriptSource {
+    /// A handle to a script asset
+    Handle(Handle<KotoScript>),
+    /// The path of a script in the assets folder
+    Path(PathBuf),
+    /// A script provided as a string, e.g. via `include_str!`
+    Inline {
+        /// The script's name, used as its path in the assets folder
+        name: String,
+        /// The script's contents
+        source: String,
+    },
+}
+
+impl Default for LoadScriptSource {
+    fn default() -> Self {
+        Self::Handle(default())
+    }
 }
 
 /// Sent when a script has been successfully compiled and initialized
 ///
 /// An event isn't sent when a script has been reloaded while running
-/// (i.e. when LoadScript::call_setup is false).
+/// (i.e. when the event was created with [LoadScript::reload]).
 #[derive(Event, Default)]
 pub struct ScriptLoaded;
 
-fn run_script_update(mut koto: ResMut<KotoRuntime>, time: Res<Time>) {
-    if koto.is_ready {
-        koto.run_update(time.delta_secs_f64());
+/// Sent when a script starts being compiled
+///
+/// Scripts are compiled in the background using Bevy's `AsyncComputeTaskPool`, and then
+/// initialized on the main thread once compilation has finished. The current script (if any)
+/// keeps running while the new script is being compiled.
+#[derive(Event, Default)]
+pub struct ScriptCompiling;
+
+/// Sent after a script has been compiled and initialized
+///
+/// Unlike [ScriptLoaded], this event is also sent when a script has been hot-reloaded.
+#[derive(Event, Default)]
+pub struct ScriptReady;
+
+/// Sent when the current script is being replaced by a new script
+///
+/// The outgoing script's `on_unload` function (if it exists) is called before the event is sent.
+/// An event isn't sent when a script is hot-reloaded.
+#[derive(Event, Default)]
+pub struct ScriptUnloaded;
+
+/// Sent when a script fails to compile
+///
+/// A [KotoScriptError] is also sent, this event provides the error's location in a structured
+/// form so that editors and overlays can highlight the offending code.
+#[derive(Event, Clone, Debug, thiserror::Error)]
+#[error("{message}.\n{excerpt}")]
+pub struct ScriptCompileFailed {
+    /// The path of the script that failed to compile
+    pub script_path: PathBuf,
+    /// The error message, without source context
+    pub message: String,
+    /// The location of the error in the script, with zero-based lines and columns
+    pub span: Span,
+    /// An excerpt of the script's source that highlights the error
+    pub excerpt: String,
+}
+
+impl ScriptCompileFailed {
+    /// The line where the error starts, starting from 1
+    pub fn line(&self) -> u32 {
+        self.span.start.line + 1
+    }
+
+    /// The column where the error starts, starting from 1
+    pub fn column(&self) -> u32 {
+        self.span.start.column + 1
+    }
+
+    /// Formats the error with an excerpt taken from the given source
+    ///
+    /// This is useful when the source has been modified since the error was produced,
+    /// e.g. when displaying the error in an editor.
+    pub fn format_with_source(&self, source: &str) -> String {
+        format_compile_error(&self.message, source, &self.span, Some(&self.script_path))
+    }
+}
+
+/// Formats a compilation error with an excerpt from the source that highlights the error's span
+pub fn format_compile_error(
+    message: &str,
+    source: &str,
+    span: &Span,
+    script_path: Option<&Path>,
+) -> String {
+    let excerpt = format_source_excerpt(source, span, script_path.and_then(Path::to_str));
+    format!("{message}.\n{excerpt}")
+}
+
+/// Sent when an error occurs while compiling a script or while calling one of its functions
+#[derive(Event, Clone, Debug, thiserror::Error)]
+#[error("Error in {phase}:\n{message}")]
+pub struct KotoScriptError {
+    /// The stage of the script's lifecycle where the error occurred
+    pub phase: ScriptPhase,
+    /// The error message
+    pub message: String,
+    /// The location in the script where the error occurred, if available
+    ///
+    /// Currently spans are only available for compilation errors.
+    pub span: Option<Span>,
+    /// The path of the script that produced the error, if available
+    pub script_path: Option<PathBuf>,
+}
+
+/// The stages of a script's lifecycle, used to report where a [KotoScriptError] occurred
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ScriptPhase {
+    /// The script is being compiled
+    Compile,
+    /// The script's top-level code is being run
+    Run,
+    /// The script's `setup` function is being called
+    Setup,
+    /// The script's `on_load` function is being called
+    OnLoad,
+    /// The script's `update` function is being called
+    Update,
+    /// The script's `fixed_update` function is being called
+    FixedUpdate,
+    /// The script's `on_unload` function is being called
+    OnUnload,
+    /// The script's `on_exit` function is being called
+    OnExit,
+    /// The script's `migrate_state` function is being called
+    MigrateState,
+    /// An entity's `on_update` function is being called
+    EntityUpdate,
+    /// The script's memory usage is being checked against the memory limit
+    MemoryCheck,
+    /// A task that was spawned with the `tasks` module is being resumed
+    Task,
+    /// A function that was scheduled with the `schedule` module is being called
+    Timer,
+    /// An entity's pointer callback, e.g. `on_click`, is being called
+    PointerCallback,
+    /// An entity's `on_collision` function is being called
+    Collision,
+    /// A text entity's `on_reveal_complete` function is being called
+    TextReveal,
+    /// The script's `on_component_added` function is being called
+    OnComponentAdded,
+    /// The script's `on_component_removed` function is being called
+    OnComponentRemoved,
+}
+
+impl std::fmt::Display for ScriptPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Compile => write!(f, "compilation"),
+            Self::Run => write!(f, "top-level script"),
+            Self::Setup => write!(f, "'setup'"),
+            Self::OnLoad => write!(f, "'on_load'"),
+            Self::Update => write!(f, "'update'"),
+            Self::FixedUpdate => write!(f, "'fixed_update'"),
+            Self::OnUnload => write!(f, "'on_unload'"),
+            Self::OnExit => write!(f, "'on_exit'"),
+            Self::MigrateState => write!(f, "'migrate_state'"),
+            Self::EntityUpdate => write!(f, "entity 'on_update'"),
+            Self::MemoryCheck => write!(f, "memory check"),
+            Self::Task => write!(f, "task"),
+            Self::Timer => write!(f, "scheduled call"),
+            Self::PointerCallback => write!(f, "pointer callback"),
+            Self::Collision => write!(f, "entity 'on_collision'"),
+            Self::TextReveal => write!(f, "text 'on_reveal_complete'"),
+            Self::OnComponentAdded => write!(f, "'on_component_added'"),
+            Self::OnComponentRemoved => write!(f, "'on_component_removed'"),
+        }
+    }
+}
+
+fn run_script_update(
+    mut koto: ResMut<KotoRuntime>,
+    mut koto_time: ResMut<KotoTime>,
+    mut frame_control: ResMut<KotoFrameControl>,
+    time: Res<Time>,
+    update_time: Res<KotoSender<UpdateKotoTime>>,
+    mut script_error: EventWriter<KotoScriptError>,
+    mut timings: ResMut<KotoScriptTimings>,
+) {
+    if !koto.is_ready {
+        return;
+    }
+
+    let _span = info_span!("koto_update", script = ?koto.script_path).entered();
+
+    if let Some(delta) = frame_control.next_delta(time.delta_secs_f64()) {
+        koto_time.advance(delta);
+        let time_object = KotoTimeObject::new(koto_time.clone(), update_time.clone());
+        let start = Instant::now();
+        let result = koto.run_update(koto_time.delta(), KObject::from(time_object).into());
+        timings.update = Some(start.elapsed());
+        if let Err(error) = result {
+            error!("{error}");
+            script_error.send(error);
+        }
+    } else {
+        koto_time.skip();
+    }
+}
+
+fn run_script_on_exit(
+    mut koto: ResMut<KotoRuntime>,
+    mut script_error: EventWriter<KotoScriptError>,
+) {
+    if !koto.is_ready {
+        return;
+    }
+
+    let _span = info_span!("koto_on_exit", script = ?koto.script_path).entered();
+    if let Err(error) = koto.run_on_exit() {
+        error!("{error}");
+        script_error.send(error);
+    }
+}
+
+// Called from the FixedUpdate schedule, where Res<Time> provides the fixed timestep
+fn run_script_fixed_update(
+    mut koto: ResMut<KotoRuntime>,
+    koto_time: Res<KotoTime>,
+    time: Res<Time>,
+    mut script_error: EventWriter<KotoScriptError>,
+) {
+    if koto.is_ready && !koto_time.is_paused() {
+        let _span = info_span!("koto_fixed_update", script = ?koto.script_path).entered();
+        if let Err(error) = koto.run_fixed_update(time.delta_secs_f64()) {
+            error!("{error}");
+            script_error.send(error);
+        }
     }
 }
 
 fn add_script_dependencies(
     assets_folder_path: Res<AssetsFolderPath>,
-    asset_server: Res<AssetServer>,
+    asset_server: Option<Res<AssetServer>>,
     channel: Res<KotoReceiver<AddDependency>>,
     mut active_script: ResMut<ActiveScript>,
 ) {
+    let _span = info_span!("koto_channel", channel = "AddDependency").entered();
     while let Some(dependency) = channel.receive() {
+        // Dependencies are only tracked for hot-reloading when assets are available
+        let Some(asset_server) = &asset_server else {
+            continue;
+        };
+
         if let Ok(path_in_assets) = dependency.0.strip_prefix(&assets_folder_path.0) {
             let handle = asset_server.load(path_in_assets.to_owned());
             active_script.dependencies.push(handle);
@@ -217,6 +1034,121 @@ fn add_script_dependencies(
     }
 }
 
+fn process_script_output(
+    koto: Res<KotoRuntime>,
+    channel: Res<KotoReceiver<ScriptOutputLine>>,
+    mut script_output: EventWriter<KotoScriptOutput>,
+) {
+    let _span = info_span!("koto_channel", channel = "ScriptOutputLine").entered();
+    while let Some(ScriptOutputLine { stream, text }) = channel.receive() {
+        let script_name = koto
+            .script_path
+            .as_deref()
+            .and_then(Path::file_name)
+            .map_or("koto".into(), |name| name.to_string_lossy());
+
+        match stream {
+            ScriptOutputStream::Stdout => info!("[{script_name}] {text}"),
+            ScriptOutputStream::Stderr => warn!("[{script_name}] {text}"),
+        }
+
+        script_output.send(KotoScriptOutput {
+            stream,
+            text,
+            script_path: koto.script_path.clone(),
+        });
+    }
+}
+
+/// Sent when a script writes a line of output to stdout or stderr
+#[derive(Event, Clone, Debug)]
+pub struct KotoScriptOutput {
+    /// The stream that the script wrote to
+    pub stream: ScriptOutputStream,
+    /// The line of text that was written, without a trailing newline
+    pub text: String,
+    /// The path of the script that was running when the output was written
+    pub script_path: Option<PathBuf>,
+}
+
+/// The output streams available to Koto scripts
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ScriptOutputStream {
+    /// The script's stdout, used by `print`
+    Stdout,
+    /// The script's stderr
+    Stderr,
+}
+
+#[derive(Clone, Debug)]
+struct ScriptOutputLine {
+    stream: ScriptOutputStream,
+    text: String,
+}
+
+// A KotoFile that sends each line of output to Bevy
+struct ScriptOutput {
+    stream: ScriptOutputStream,
+    buffer: Mutex<String>,
+    sender: KotoSender<ScriptOutputLine>,
+}
+
+impl ScriptOutput {
+    fn new(stream: ScriptOutputStream, sender: KotoSender<ScriptOutputLine>) -> Self {
+        Self {
+            stream,
+            buffer: Mutex::default(),
+            sender,
+        }
+    }
+
+    fn send_line(&self, text: String) {
+        self.sender.send(ScriptOutputLine {
+            stream: self.stream,
+            text,
+        });
+    }
+}
+
+impl KotoFile for ScriptOutput {
+    fn id(&self) -> KString {
+        match self.stream {
+            ScriptOutputStream::Stdout => "_stdout_".into(),
+            ScriptOutputStream::Stderr => "_stderr_".into(),
+        }
+    }
+}
+
+impl KotoRead for ScriptOutput {}
+
+impl KotoWrite for ScriptOutput {
+    fn write(&self, bytes: &[u8]) -> KotoResult<()> {
+        let mut buffer = self.buffer.lock();
+        buffer.push_str(&String::from_utf8_lossy(bytes));
+        while let Some(newline) = buffer.find('\n') {
+            let line = buffer[..newline].to_string();
+            buffer.drain(..=newline);
+            self.send_line(line);
+        }
+        Ok(())
+    }
+
+    fn write_line(&self, text: &str) -> KotoResult<()> {
+        let mut buffer = self.buffer.lock();
+        buffer.push_str(text);
+        self.send_line(std::mem::take(&mut *buffer));
+        Ok(())
+    }
+
+    fn flush(&self) -> KotoResult<()> {
+        let mut buffer = self.buffer.lock();
+        if !buffer.is_empty() {
+            self.send_line(std::mem::take(&mut *buffer));
+        }
+        Ok(())
+    }
+}
+
 /// A Koto script as read from the assets folder
 #[derive(Asset, TypePath, Debug)]
 pub struct KotoScript {
@@ -227,17 +1159,109 @@ pub struct KotoScript {
     /// Note that Koto currently requires absolute paths for dependency resolution, so this path
     /// needs to be converted to include the asset folder's path before passing it to Koto.
     pub path: PathBuf,
+    /// The modules that are imported by the script, in the order in which they need to be run
+    ///
+    /// Modules are resolved through the asset server when the script is loaded, so that imports
+    /// work with asset packs, embedded assets, and web builds. When the script is initialized,
+    /// each module is run in its own VM, and its exports are provided to the script and modules
+    /// that import it. Modules are matched with their importers by path rather than by name, so
+    /// modules in different folders can share a name.
+    ///
+    /// Imports are resolved from these modules while a script or module's top-level code is
+    /// running, so imports inside functions that are called later are resolved by Koto's module
+    /// loader instead.
+    pub modules: Vec<KotoModule>,
+    /// Metadata that was parsed from the script's header
+    pub metadata: ScriptMetadata,
 }
 
 // The currently loaded script assets
 #[derive(Default, Resource)]
 struct ActiveScript {
+    // None when the script was loaded inline
     script: Option<Handle<KotoScript>>,
     dependencies: Vec<Handle<KotoScript>>,
+    // The arguments and reload policy that the script was loaded with
+    args: KValue,
+    reload_policy: ReloadPolicy,
+    is_loaded: bool,
+}
+
+#[derive(Default, Resource)]
+pub(crate) struct AssetsFolderPath(pub PathBuf);
+
+// The prelude script that was set with KotoRuntimePlugin::with_prelude_script
+#[derive(Resource)]
+pub(crate) struct KotoPreludeScript(pub Handle<KotoScript>);
+
+// Scripts that have been requested via LoadScript, waiting to be initialized
+#[derive(Default, Resource)]
+struct PendingScripts(Vec<PendingScript>);
+
+struct PendingScript {
+    source: PendingSource,
+    call_setup: bool,
+    args: KValue,
+    reload_policy: ReloadPolicy,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ScriptInit {
+    Load,
+    Reload(ReloadPolicy),
+}
+
+enum CompileJob {
+    Task(Task<CompileResult>),
+    Cached(Ptr<Chunk>),
 }
 
+// The compiled chunk along with the time taken to compile it, or the compilation error
+type CompileResult = Result<(Ptr<Chunk>, Duration), ScriptCompileFailed>;
+
+// Removes the imported modules that were added to a script's exports by add_imports,
+// unless the script has replaced them with its own exports
+fn remove_imports(exports: &KMap, added_imports: Vec<(String, KMap)>) {
+    for (name, module) in added_imports {
+        if let Some(KValue::Map(export)) = exports.get(name.as_str()) {
+            if export.is_same_instance(&module) {
+                exports.data_mut().shift_remove(name.as_str());
+            }
+        }
+    }
+}
+
+// Chunks are cached using the script's contents along with its path,
+// which is used when resolving imports.
+fn chunk_cache_key(script: &str, script_path: &Path) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    script.hash(&mut hasher);
+    script_path.hash(&mut hasher);
+    hasher.finish()
+}
+
+enum PendingSource {
+    Asset(Handle<KotoScript>),
+    Inline { name: PathBuf, source: String },
+}
+
+// The script that's currently being compiled in the background
 #[derive(Default, Resource)]
-struct AssetsFolderPath(PathBuf);
+struct CompilingScript(Option<CompilingScriptTask>);
+
+struct CompilingScriptTask {
+    job: CompileJob,
+    cache_key: u64,
+    script_path: PathBuf,
+    // The modules that are imported by the script
+    modules: Vec<KotoModule>,
+    // The names that the script imports, along with the paths of the modules they refer to
+    imports: Vec<(String, PathBuf)>,
+    handle: Option<Handle<KotoScript>>,
+    call_setup: bool,
+    args: KValue,
+    reload_policy: ReloadPolicy,
+}
 
 #[derive(Debug, thiserror::Error)]
 enum KotoScriptAssetLoaderError {
@@ -264,9 +1288,14 @@ impl AssetLoader for KotoScriptAssetLoader {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
         let script = str::from_utf8(&bytes)?.to_string();
+        let path = load_context.path().to_path_buf();
+        let modules = load_imported_modules(&script, &path, load_context).await;
+        let metadata = ScriptMetadata::parse(&script);
         Ok(KotoScript {
             script,
-            path: load_context.path().into(),
+            path,
+            modules,
+            metadata,
         })
     }
 
@@ -278,28 +1307,105 @@ impl AssetLoader for KotoScriptAssetLoader {
 /// The Koto runtime
 #[derive(Default, Resource)]
 pub struct KotoRuntime {
-    runtime: Koto,
+    runtime: KotoVm,
     user_data: KValue,
+    script_path: Option<PathBuf>,
     is_ready: bool,
+    // Compiled scripts and modules, keyed by a hash of their contents and paths
+    chunk_cache: HashMap<u64, Ptr<Chunk>>,
+    // True when a script asset has been modified since the module cache was last cleared
+    module_cache_is_stale: bool,
+    // The prelude entries that were added by KotoModules and by the prelude script
+    prelude_additions: Vec<ValueKey>,
+    // The exports of the modules imported by the script and the prelude script, keyed by the
+    // module's path in the assets folder
+    module_exports: HashMap<PathBuf, KMap>,
+    // The script's hook functions, resolved once the script has been loaded
+    hooks: [Option<KValue>; ScriptHook::COUNT],
 }
 
+// Functions that are exported by the script and called by the runtime each frame, or in response
+// to events
+//
+// Hooks are looked up once the script has been loaded rather than on each call, so changes to the
+// script's exports made after loading aren't picked up until the script is reloaded.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum ScriptHook {
+    Update,
+    FixedUpdate,
+    OnUnload,
+    OnExit,
+    OnWindowSize,
+    OnEvent,
+    OnComponentAdded,
+    OnComponentRemoved,
+}
+
+impl ScriptHook {
+    const ALL: [Self; 8] = [
+        Self::Update,
+        Self::FixedUpdate,
+        Self::OnUnload,
+        Self::OnExit,
+        Self::OnWindowSize,
+        Self::OnEvent,
+        Self::OnComponentAdded,
+        Self::OnComponentRemoved,
+    ];
+    const COUNT: usize = Self::ALL.len();
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Update => "update",
+            Self::FixedUpdate => "fixed_update",
+            Self::OnUnload => "on_unload",
+            Self::OnExit => "on_exit",
+            Self::OnWindowSize => "on_window_size",
+            Self::OnEvent => "on_event",
+            Self::OnComponentAdded => "on_component_added",
+            Self::OnComponentRemoved => "on_component_removed",
+        }
+    }
+}
+
+// The maximum number of compiled scripts and modules that will be cached
+const MAX_CACHED_CHUNKS: usize = 64;
+
 impl KotoRuntime {
-    fn new(add_dependency_sender: KotoSender<AddDependency>) -> Self {
-        let runtime = Koto::with_settings(
-            KotoSettings::default()
-                .with_execution_limit(Duration::from_secs(1))
-                .with_module_imported_callback({
-                    cloned!(add_dependency_sender);
-                    move |path| {
-                        add_dependency_sender.send(AddDependency(path.to_owned()));
-                    }
-                }),
-        );
+    fn new(
+        add_dependency_sender: KotoSender<AddDependency>,
+        script_output_sender: KotoSender<ScriptOutputLine>,
+        execution_limit: Option<Duration>,
+    ) -> Self {
+        let runtime = KotoVm::with_settings(KotoVmSettings {
+            execution_limit,
+            stdout: make_ptr!(ScriptOutput::new(
+                ScriptOutputStream::Stdout,
+                script_output_sender.clone(),
+            )),
+            stderr: make_ptr!(ScriptOutput::new(
+                ScriptOutputStream::Stderr,
+                script_output_sender,
+            )),
+            module_imported_callback: Some(Box::new({
+                cloned!(add_dependency_sender);
+                move |path: &Path| {
+                    add_dependency_sender.send(AddDependency(path.to_owned()));
+                }
+            })),
+            ..default()
+        });
 
         Self {
             runtime,
             user_data: KValue::Null,
+            script_path: None,
             is_ready: false,
+            chunk_cache: HashMap::new(),
+            module_cache_is_stale: false,
+            prelude_additions: Vec::new(),
+            module_exports: HashMap::new(),
+            hooks: default(),
         }
     }
 
@@ -310,54 +1416,62 @@ impl KotoRuntime {
 
     fn initialize_script(
         &mut self,
-        script: &str,
-        script_path: Option<&Path>,
-        call_setup: bool,
-    ) -> Result<(), ()> {
-        let now = std::time::Instant::now();
+        chunk: Ptr<Chunk>,
+        script_path: &Path,
+        imports: &[(String, PathBuf)],
+        init: ScriptInit,
+        args: &KValue,
+    ) -> Result<(), KotoScriptError> {
+        let now = Instant::now();
 
         self.is_ready = false;
+        self.script_path = Some(script_path.to_path_buf());
+        self.hooks = default();
 
-        self.runtime.clear_module_cache();
-        let compile_args = CompileArgs {
-            script,
-            script_path: script_path
-                .and_then(|path| path.to_str())
-                .map(|path| KString::from(path)),
-            compiler_settings: default(),
-        };
-        if let Err(error) = self.runtime.compile(compile_args) {
-            error!("Error while compiling script:\n{error}");
-            return Err(());
+        // Imported modules are cached by the runtime's loader, and only need to be recompiled
+        // when a script has been modified.
+        if std::mem::take(&mut self.module_cache_is_stale) {
+            self.runtime.loader().borrow_mut().clear_cache();
         }
 
-        if call_setup {
+        if init == ScriptInit::Load {
             self.runtime.exports_mut().clear();
         }
 
-        if let Err(e) = self.runtime.run() {
-            error!("Error while running Koto script:\n{e}");
-            return Err(());
+        let exports = self.runtime.exports().clone();
+        let added_imports = self.add_imports(&exports, imports);
+        let result = self.run_chunk(chunk);
+        remove_imports(&exports, added_imports);
+        if let Err(error) = result {
+            return Err(self.make_error(ScriptPhase::Run, error));
         }
 
-        if call_setup {
-            debug!("Calling setup");
-            self.user_data = match self.run_exported_function("setup", &[]) {
-                Ok(Some(data)) => data,
-                Ok(None) => KMap::default().into(),
-                Err(e) => {
-                    error!("Error in 'setup':\n{e}");
-                    return Err(());
+        self.user_data = match init {
+            ScriptInit::Load | ScriptInit::Reload(ReloadPolicy::ResetState) => {
+                self.run_setup(args)?
+            }
+            ScriptInit::Reload(ReloadPolicy::PreserveState) => self.migrate_state()?,
+            ScriptInit::Reload(ReloadPolicy::MergeState) => {
+                let old_state = self.migrate_state()?;
+                match (self.run_setup(args)?, old_state) {
+                    (KValue::Map(new_state), KValue::Map(old_state)) => {
+                        for (key, value) in old_state.data().iter() {
+                            new_state.data_mut().insert(key.clone(), value.clone());
+                        }
+                        new_state.into()
+                    }
+                    (_, old_state) => old_state,
                 }
-            };
-        }
+            }
+        };
 
         debug!("Calling on_load");
-        if let Err(e) = self.run_exported_function("on_load", &[self.user_data.clone()]) {
-            error!("Error in 'on_load':\n{e}");
-            return Err(());
+        let user_data = self.user_data.clone();
+        if let Err(error) = self.run_exported_function("on_load", &[user_data]) {
+            return Err(self.make_error(ScriptPhase::OnLoad, error));
         }
 
+        self.resolve_hooks();
         self.is_ready = true;
 
         info!(
@@ -368,19 +1482,236 @@ impl KotoRuntime {
         Ok(())
     }
 
-    fn run_update(&mut self, time_delta: f64) {
+    // Adds the modules that are provided from memory to the prelude, then runs the modules that
+    // are imported by the script and the prelude script, followed by the prelude script itself,
+    // whose exports are added to the prelude
+    fn prepare_prelude<'a>(
+        &mut self,
+        modules: impl Iterator<Item = &'a KotoModule>,
+        imported_modules: impl Iterator<Item = &'a KotoModule>,
+        prelude_script: Option<&KotoScript>,
+        assets_folder: &Path,
+    ) -> Result<(), KotoScriptError> {
+        // Remove the previous additions, in case any modules or exports have been removed since
+        for key in self.prelude_additions.drain(..) {
+            self.runtime.prelude().data_mut().shift_remove(&key);
+        }
+
+        for module in modules {
+            if self.prelude().get(module.name.as_str()).is_some() {
+                warn!(
+                    "Module '{}' is already in the prelude and won't be imported",
+                    module.name
+                );
+                continue;
+            }
+
+            debug!("Importing module '{}'", module.name);
+            let exports =
+                self.run_module(&module.source, &assets_folder.join(&module.path), &[])?;
+            self.prelude().insert(module.name.as_str(), exports);
+            self.prelude_additions.push(module.name.as_str().into());
+        }
+
+        // Imported modules are kept out of the prelude, with their exports being provided to
+        // their importers by path. The modules are in dependency order, so a module's imports
+        // have been run by the time that it's run.
+        self.module_exports.clear();
+        let imported_modules: Vec<_> = imported_modules.collect();
+        let is_available = |path: &Path| imported_modules.iter().any(|module| module.path == path);
+        for module in imported_modules.iter() {
+            if self.module_exports.contains_key(&module.path) {
+                continue;
+            }
+
+            debug!("Importing module '{}'", module.path.display());
+            let imports = resolve_imports(&module.source, &module.path, is_available);
+            let module_path = assets_folder.join(&module.path);
+            let exports = self.run_module(&module.source, &module_path, &imports)?;
+            self.module_exports.insert(module.path.clone(), exports);
+        }
+
+        if let Some(prelude_script) = prelude_script {
+            debug!("Running the prelude script");
+            let imports =
+                resolve_imports(&prelude_script.script, &prelude_script.path, is_available);
+            let script_path = assets_folder.join(&prelude_script.path);
+            let exports = self.run_module(&prelude_script.script, &script_path, &imports)?;
+            for (key, value) in exports.data().iter() {
+                if self.prelude().get(key).is_some() {
+                    warn!(
+                        "'{key}' is already in the prelude, ignoring the prelude script's export"
+                    );
+                    continue;
+                }
+                self.prelude().insert(key.clone(), value.clone());
+                self.prelude_additions.push(key.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    // Runs a module in its own VM, returning the module's exports
+    //
+    // Compiled modules are cached in the same way as scripts, so that they're only recompiled
+    // when their contents have changed.
+    fn run_module(
+        &mut self,
+        source: &str,
+        module_path: &Path,
+        imports: &[(String, PathBuf)],
+    ) -> Result<KMap, KotoScriptError> {
+        let cache_key = chunk_cache_key(source, module_path);
+        let chunk = match self.chunk_cache.get(&cache_key) {
+            Some(chunk) => chunk.clone(),
+            None => {
+                let koto_module_path = module_path.to_str().map(KString::from);
+                let chunk =
+                    Compiler::compile(source, koto_module_path, default()).map_err(|error| {
+                        KotoScriptError {
+                            phase: ScriptPhase::Compile,
+                            message: format_compile_error(
+                                &error.to_string(),
+                                source,
+                                &error.span,
+                                Some(module_path),
+                            ),
+                            span: Some(error.span),
+                            script_path: Some(module_path.to_path_buf()),
+                        }
+                    })?;
+                self.cache_chunk(cache_key, chunk.clone());
+                chunk
+            }
+        };
+
+        let mut vm = self.spawn_vm();
+        let exports = vm.exports().clone();
+        let added_imports = self.add_imports(&exports, imports);
+        let result = vm.run(chunk);
+        remove_imports(&exports, added_imports);
+        match result {
+            Ok(_) => Ok(exports),
+            Err(error) => Err(KotoScriptError {
+                phase: ScriptPhase::Run,
+                message: error.to_string(),
+                span: None,
+                script_path: Some(module_path.to_path_buf()),
+            }),
+        }
+    }
+
+    // Adds the exports of imported modules to a script's exports map, where they're found by the
+    // script's imports before the prelude is searched
+    //
+    // The added entries are returned so that they can be removed once the script has been run.
+    fn add_imports(&self, exports: &KMap, imports: &[(String, PathBuf)]) -> Vec<(String, KMap)> {
+        imports
+            .iter()
+            .filter_map(|(name, path)| {
+                let module = self.module_exports.get(path)?;
+                exports.insert(name.as_str(), module.clone());
+                Some((name.clone(), module.clone()))
+            })
+            .collect()
+    }
+
+    fn run_setup(&mut self, args: &KValue) -> Result<KValue, KotoScriptError> {
+        debug!("Calling setup");
+        match self.run_exported_function("setup", std::slice::from_ref(args)) {
+            Ok(Some(data)) => Ok(data),
+            Ok(None) => Ok(KMap::default().into()),
+            Err(error) => Err(self.make_error(ScriptPhase::Setup, error)),
+        }
+    }
+
+    fn migrate_state(&mut self) -> Result<KValue, KotoScriptError> {
+        let old_state = self.user_data.clone();
+        match self.run_exported_function("migrate_state", std::slice::from_ref(&old_state)) {
+            Ok(Some(state)) => Ok(state),
+            Ok(None) => Ok(old_state),
+            Err(error) => Err(self.make_error(ScriptPhase::MigrateState, error)),
+        }
+    }
+
+    fn cache_chunk(&mut self, key: u64, chunk: Ptr<Chunk>) {
+        // Keep the cache from growing indefinitely while live-coding
+        if self.chunk_cache.len() >= MAX_CACHED_CHUNKS && !self.chunk_cache.contains_key(&key) {
+            self.chunk_cache.clear();
+        }
+        self.chunk_cache.insert(key, chunk);
+    }
+
+    // Runs the script's top-level code, along with its tests and `@main` function (if defined),
+    // matching the behaviour of `Koto::run`.
+    fn run_chunk(&mut self, chunk: Ptr<Chunk>) -> Result<(), koto::Error> {
+        self.runtime.run(chunk)?;
+        self.runtime.run_tests(self.runtime.exports().clone())?;
+        if let Some(main) = self.runtime.exports().get_meta_value(&MetaKey::Main) {
+            self.runtime.call_function(main, &[])?;
+        }
+        Ok(())
+    }
+
+    fn run_update(&mut self, time_delta: f64, time: KValue) -> Result<(), KotoScriptError> {
         debug_assert!(self.is_ready);
 
-        let now = std::time::Instant::now();
+        let now = Instant::now();
 
-        if let Err(e) =
-            self.run_exported_function("update", &[self.user_data.clone(), time_delta.into()])
-        {
-            error!("Error in 'update':\n{e}");
-            return;
+        let args = [self.user_data.clone(), time_delta.into(), time];
+        if let Err(error) = self.run_hook(ScriptHook::Update, &args) {
+            return Err(self.make_error(ScriptPhase::Update, error));
         }
 
-        trace!("update: {:.3}ms", now.elapsed().as_secs_f64() * 1000.0)
+        trace!("update: {:.3}ms", now.elapsed().as_secs_f64() * 1000.0);
+
+        Ok(())
+    }
+
+    fn unload_script(&mut self) -> Result<(), KotoScriptError> {
+        self.is_ready = false;
+
+        debug!("Calling on_unload");
+        let user_data = self.user_data.clone();
+        if let Err(error) = self.run_hook(ScriptHook::OnUnload, &[user_data]) {
+            return Err(self.make_error(ScriptPhase::OnUnload, error));
+        }
+
+        Ok(())
+    }
+
+    // The script won't be updated again after on_exit has been called
+    fn run_on_exit(&mut self) -> Result<(), KotoScriptError> {
+        self.is_ready = false;
+
+        debug!("Calling on_exit");
+        let user_data = self.user_data.clone();
+        if let Err(error) = self.run_hook(ScriptHook::OnExit, &[user_data]) {
+            return Err(self.make_error(ScriptPhase::OnExit, error));
+        }
+
+        Ok(())
+    }
+
+    fn run_fixed_update(&mut self, time_delta: f64) -> Result<(), KotoScriptError> {
+        debug_assert!(self.is_ready);
+
+        let args = [self.user_data.clone(), time_delta.into()];
+        if let Err(error) = self.run_hook(ScriptHook::FixedUpdate, &args) {
+            return Err(self.make_error(ScriptPhase::FixedUpdate, error));
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn make_error(&self, phase: ScriptPhase, error: koto::Error) -> KotoScriptError {
+        KotoScriptError {
+            phase,
+            message: error.to_string(),
+            span: None,
+            script_path: self.script_path.clone(),
+        }
     }
 
     /// Runs a function that has been exported from the currently running script
@@ -393,15 +1724,195 @@ impl KotoRuntime {
             return Ok(None);
         };
 
+        self.call_script_function(function_name, function, args)
+            .map(Some)
+    }
+
+    // Runs one of the script's hook functions, if the script exports it
+    pub(crate) fn run_hook(
+        &mut self,
+        hook: ScriptHook,
+        args: &[KValue],
+    ) -> Result<Option<KValue>, koto::Error> {
+        let Some(function) = self.hooks[hook as usize].clone() else {
+            return Ok(None);
+        };
+
+        self.call_script_function(hook.name(), function, args)
+            .map(Some)
+    }
+
+    // Looks up the script's hook functions in its exports
+    fn resolve_hooks(&mut self) {
+        let exports = self.runtime.exports().data();
+        for hook in ScriptHook::ALL {
+            self.hooks[hook as usize] = exports.get(hook.name()).cloned();
+        }
+    }
+
+    // Calls a function from the running script, e.g. a callback that was passed to a module
+    //
+    // Unlike the script's exported functions, errors don't stop the script from being updated.
+    #[cfg(feature = "scheduler")]
+    pub(crate) fn call_function(
+        &mut self,
+        function: KValue,
+        args: &[KValue],
+    ) -> Result<KValue, koto::Error> {
+        let _span = info_span!("koto_callback", script = ?self.script_path).entered();
+        self.runtime
+            .call_function(function, args)
+            .map_err(Into::into)
+    }
+
+    fn call_script_function(
+        &mut self,
+        function_name: &str,
+        function: KValue,
+        args: &[KValue],
+    ) -> Result<KValue, koto::Error> {
+        let _span = info_span!(
+            "koto_call",
+            function = function_name,
+            script = ?self.script_path
+        )
+        .entered();
+
         match self.runtime.call_function(function, args) {
-            Ok(result) => Ok(Some(result)),
+            Ok(result) => Ok(result),
             Err(error) => {
                 self.is_ready = false;
-                Err(error)
+                Err(error.into())
             }
         }
     }
 
+    /// Calls a function that has been exported from the currently running script
+    ///
+    /// This is a typed alternative to [KotoRuntime::run_exported_function], with the arguments
+    /// being provided as a Rust tuple, and the result being converted into the requested type,
+    /// e.g. `koto.call_exported::<_, f64>("score", (level, "easy"))`.
+    pub fn call_exported<Args, Ret>(
+        &mut self,
+        function_name: &str,
+        args: Args,
+    ) -> Result<Ret, KotoCallError>
+    where
+        Args: IntoKotoArgs,
+        Ret: FromKotoValue,
+    {
+        let Some(result) = self.run_exported_function(function_name, &args.into_koto_args())?
+        else {
+            return Err(KotoCallError::MissingFunction(function_name.into()));
+        };
+
+        let found = result.type_as_string();
+        Ret::from_koto_value(result).ok_or_else(|| KotoCallError::UnexpectedResult {
+            function: function_name.into(),
+            expected: Ret::EXPECTED,
+            found: found.to_string(),
+        })
+    }
+
+    /// Evaluates a snippet of Koto code in the context of the current script
+    ///
+    /// The script's exports are available to the code, along with its user data as `state`.
+    /// The value of the snippet's last expression is returned, or an error message if the snippet
+    /// failed to compile or run.
+    ///
+    /// Errors don't affect the running script, which continues to be updated as usual.
+    pub fn evaluate(&mut self, source: &str) -> Result<KValue, String> {
+        // The snippet is compiled as the body of a function that takes the user data as its
+        // argument, with the function's result being the value of the last expression.
+        let mut wrapped = String::from("|state|\n");
+        for line in source.lines() {
+            wrapped.push_str("  ");
+            wrapped.push_str(line);
+            wrapped.push('\n');
+        }
+        if source.trim().is_empty() {
+            wrapped.push_str("  null\n");
+        }
+
+        let chunk = Compiler::compile(&wrapped, None, default()).map_err(|error| {
+            format_compile_error(&error.to_string(), &wrapped, &error.span, None)
+        })?;
+        let function = self.runtime.run(chunk).map_err(|error| error.to_string())?;
+        let result = self
+            .runtime
+            .call_function(function, std::slice::from_ref(&self.user_data))
+            .map_err(|error| error.to_string());
+
+        // The snippet might have modified the script's exports
+        if self.is_ready {
+            self.resolve_hooks();
+        }
+
+        result
+    }
+
+    /// Renders a value as a string, as it would be displayed by Koto's `print`
+    pub fn value_to_string(&mut self, value: &KValue) -> String {
+        self.runtime
+            .value_to_string(value)
+            .unwrap_or_else(|error| format!("Error while displaying value: {error}"))
+    }
+
+    /// Returns information about the functions that are exported from the current script
+    ///
+    /// This can be used to detect optional hooks when a script is loaded, rather than looking
+    /// them up on each call.
+    pub fn exported_functions(&self) -> Vec<ExportedFunction> {
+        self.runtime
+            .exports()
+            .data()
+            .iter()
+            .filter_map(|(key, value)| {
+                let KValue::Str(name) = key.value() else {
+                    return None;
+                };
+
+                let (arg_count, variadic) = match value {
+                    KValue::Function(f) => (Some(f.arg_count), f.variadic),
+                    _ if value.is_callable() => (None, false),
+                    _ => return None,
+                };
+
+                Some(ExportedFunction {
+                    name: name.to_string(),
+                    arg_count,
+                    variadic,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns true if the current script exports a function with the given name
+    pub fn has_exported_function(&self, function_name: &str) -> bool {
+        self.runtime
+            .exports()
+            .data()
+            .get(function_name)
+            .is_some_and(KValue::is_callable)
+    }
+
+    // The exports of the currently loaded script
+    pub(crate) fn exports(&self) -> &KMap {
+        self.runtime.exports()
+    }
+
+    // Makes a VM that shares the runtime's prelude and settings, with its own exports
+    pub(crate) fn spawn_vm(&self) -> KotoVm {
+        let mut vm = self.runtime.spawn_shared_vm();
+        *vm.exports_mut() = KMap::new();
+        vm
+    }
+
+    // Stops the current script from being updated until a script is loaded
+    pub(crate) fn stop(&mut self) {
+        self.is_ready = false;
+    }
+
     /// The Koto runtime's prelude
     pub fn prelude(&self) -> &KMap {
         self.runtime.prelude()
@@ -411,41 +1922,252 @@ impl KotoRuntime {
     pub fn user_data(&self) -> &KValue {
         &self.user_data
     }
+
+    /// Replaces the user data that is being held by the current script
+    pub fn set_user_data(&mut self, user_data: KValue) {
+        self.user_data = user_data;
+    }
+
+    /// The path of the currently loaded script
+    pub fn script_path(&self) -> Option<&Path> {
+        self.script_path.as_deref()
+    }
+}
+
+// Timings of the script's most recent calls, see `KotoDiagnosticsPlugin`
+//
+// Timings are taken by the diagnostics plugin each frame.
+#[derive(Resource, Default)]
+pub(crate) struct KotoScriptTimings {
+    pub compile: Option<Duration>,
+    pub initialize: Option<Duration>,
+    pub update: Option<Duration>,
+    pub entity_update: Option<Duration>,
+}
+
+/// A custom event sent from a script with `bevy.send_event`
+///
+/// Scripts send events with a name and an optional payload, e.g.
+/// `bevy.send_event 'level_complete', {score: 42}`, the payload is `null` if not provided.
+#[derive(Event, Clone, Debug)]
+pub struct KotoCustomEvent {
+    /// The event's name
+    pub name: String,
+    /// The value that was sent along with the event
+    pub payload: KValue,
+}
+
+// Sent from `bevy.quit`
+struct QuitRequest(AppExit);
+
+#[allow(clippy::result_large_err)]
+fn make_bevy_module(
+    custom_event: KotoSender<KotoCustomEvent>,
+    quit: KotoSender<QuitRequest>,
+) -> KMap {
+    let module = KMap::with_type("bevy");
+
+    module.add_fn("send_event", move |ctx| {
+        let (name, payload) = match ctx.args() {
+            [KValue::Str(name)] => (name, KValue::Null),
+            [KValue::Str(name), payload] => (name, payload.clone()),
+            unexpected => {
+                return unexpected_args("a String, and an optional payload", unexpected);
+            }
+        };
+
+        custom_event.send(KotoCustomEvent {
+            name: name.to_string(),
+            payload,
+        });
+
+        Ok(KValue::Null)
+    });
+
+    module.add_fn("quit", move |ctx| {
+        let exit = match ctx.args() {
+            [] => AppExit::Success,
+            [KValue::Number(code)] => match u8::try_from(i64::from(code)) {
+                Ok(code) => AppExit::from_code(code),
+                Err(_) => return runtime_error!("Exit codes must be in the range 0..=255"),
+            },
+            unexpected => return unexpected_args("an optional exit code", unexpected),
+        };
+
+        quit.send(QuitRequest(exit));
+
+        Ok(KValue::Null)
+    });
+
+    module
+}
+
+fn process_quit_requests(
+    channel: Res<KotoReceiver<QuitRequest>>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    let _span = info_span!("koto_channel", channel = "QuitRequest").entered();
+    while let Some(QuitRequest(exit)) = channel.receive() {
+        app_exit.send(exit);
+    }
+}
+
+fn process_custom_events(
+    channel: Res<KotoReceiver<KotoCustomEvent>>,
+    mut custom_events: EventWriter<KotoCustomEvent>,
+) {
+    let _span = info_span!("koto_channel", channel = "KotoCustomEvent").entered();
+    while let Some(event) = channel.receive() {
+        custom_events.send(event);
+    }
+}
+
+/// Information about a function that's exported from a script
+///
+/// See [KotoRuntime::exported_functions]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExportedFunction {
+    /// The name of the exported function
+    pub name: String,
+    /// The number of arguments expected by the function
+    ///
+    /// This is `None` when the arity isn't known, e.g. for native functions.
+    pub arg_count: Option<u8>,
+    /// True if the function captures extra arguments in a tuple
+    pub variadic: bool,
 }
 
 /// A helper for making a channel for events from Koto -> Bevy
 pub fn koto_channel<T>() -> (KotoSender<T>, KotoReceiver<T>) {
     let (sender, receiver) = crossbeam_channel::unbounded();
-    (KotoSender(sender), KotoReceiver(receiver))
+    (KotoSender::new(sender), KotoReceiver(receiver))
+}
+
+/// A helper for making a bounded channel for events from Koto -> Bevy
+///
+/// The channel holds up to `capacity` values, when the channel is full then new values are
+/// handled according to the given [OverflowPolicy].
+pub fn koto_channel_bounded<T>(
+    capacity: usize,
+    overflow_policy: OverflowPolicy,
+) -> (KotoSender<T>, KotoReceiver<T>) {
+    let (sender, receiver) = crossbeam_channel::bounded(capacity);
+    (
+        KotoSender(sender, Some((overflow_policy, receiver.clone()))),
+        KotoReceiver(receiver),
+    )
+}
+
+/// Determines what happens when sending a value on a full bounded channel
+///
+/// See [koto_channel_bounded]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// The oldest value in the channel is dropped to make room for the new value
+    DropOldest,
+    /// The new value is dropped
+    #[default]
+    DropNewest,
+    /// An error is returned by [KotoSender::try_send]
+    Error,
+}
+
+/// An error that can occur when sending a value with [KotoSender::try_send]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum KotoSendError {
+    /// The channel is full, and its overflow policy is [OverflowPolicy::Error]
+    #[error("the channel is full")]
+    Full,
+    /// The channel's receiver has been dropped
+    #[error("the channel is disconnected")]
+    Disconnected,
 }
 
 /// A sender for events from Koto -> Bevy
 ///
-/// See [koto_channel]
+/// The underlying channel sender is available via [KotoSender::inner].
+///
+/// See [koto_channel] and [koto_channel_bounded]
 #[derive(Debug, Resource)]
-pub struct KotoSender<T>(pub crossbeam_channel::Sender<T>);
+pub struct KotoSender<T>(
+    pub crossbeam_channel::Sender<T>,
+    // Bounded channels keep a receiver so that the oldest value can be dropped when full
+    Option<(OverflowPolicy, crossbeam_channel::Receiver<T>)>,
+);
 
 impl<T> KotoSender<T> {
+    /// Makes a new sender that wraps the given channel sender
+    ///
+    /// Sending on a full bounded channel drops the new value, see [koto_channel_bounded] for
+    /// channels that use other overflow policies.
+    pub fn new(sender: crossbeam_channel::Sender<T>) -> Self {
+        Self(sender, None)
+    }
+
     /// Sends a value on the channel
     ///
-    /// This is non-blocking, and will panic if sending fails.
+    /// This is non-blocking, if sending fails then the error is logged and the value is dropped.
     pub fn send(&self, value: T) {
-        self.0.try_send(value).expect("Failed to send value")
+        if let Err(error) = self.try_send(value) {
+            error!("Failed to send value: {error}");
+        }
+    }
+
+    /// Sends a value on the channel, returning an error if sending fails
+    ///
+    /// This is non-blocking. If the channel is bounded and full, then the value is handled
+    /// according to the channel's [OverflowPolicy].
+    pub fn try_send(&self, value: T) -> Result<(), KotoSendError> {
+        use crossbeam_channel::TrySendError;
+
+        let mut value = value;
+        loop {
+            match self.0.try_send(value) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Disconnected(_)) => return Err(KotoSendError::Disconnected),
+                Err(TrySendError::Full(unsent)) => match &self.1 {
+                    Some((OverflowPolicy::DropOldest, receiver)) => {
+                        // Make room for the new value and then try again,
+                        // zero-capacity channels have nothing to drop so the value is dropped
+                        if receiver.try_recv().is_err() {
+                            return Ok(());
+                        }
+                        value = unsent;
+                    }
+                    Some((OverflowPolicy::Error, _)) => return Err(KotoSendError::Full),
+                    Some((OverflowPolicy::DropNewest, _)) | None => return Ok(()),
+                },
+            }
+        }
+    }
+
+    /// Returns a reference to the channel's underlying sender
+    ///
+    /// Values that are sent directly with the underlying sender bypass the channel's
+    /// [OverflowPolicy], with sending failing if a bounded channel is full.
+    pub fn inner(&self) -> &crossbeam_channel::Sender<T> {
+        &self.0
     }
 }
 
 impl<T> Clone for KotoSender<T> {
     fn clone(&self) -> Self {
-        Self(self.0.clone())
+        Self(self.0.clone(), self.1.clone())
     }
 }
 
 /// A receiver for events from Koto -> Bevy
 ///
 /// See [koto_channel]
-#[derive(Clone, Debug, Resource)]
+#[derive(Debug, Resource)]
 pub struct KotoReceiver<T>(pub crossbeam_channel::Receiver<T>);
 
+impl<T> Clone for KotoReceiver<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
 impl<T> KotoReceiver<T> {
     /// Receives a value on the channel
     ///
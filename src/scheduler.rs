@@ -0,0 +1,226 @@
+//! Delayed and repeating function calls for Koto scripts
+
+use crate::prelude::*;
+use bevy::prelude::*;
+use cloned::cloned;
+use koto::{derive::*, prelude::*};
+use parking_lot::Mutex;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Adds a `schedule` module to Koto's prelude, for calling functions after a delay
+///
+/// The module contains the following functions:
+/// - `schedule.after(seconds, f)`: Calls `f` once after the given number of seconds.
+/// - `schedule.every(seconds, f)`: Calls `f` repeatedly with the given interval.
+/// - `schedule.debounce(seconds, f)`: Returns a function that calls `f` with its arguments once
+///   the given number of seconds have passed without it being called again.
+///
+/// `after` and `every` return a `Timer` object with `is_active()` and `cancel()` methods.
+///
+/// Time is measured with [KotoTime], so timers are paused and scaled along with the script's
+/// time. Callbacks are called during the [KotoUpdate::Update] system set, and repeating callbacks
+/// are called at most once per frame.
+///
+/// Timers are cancelled when a script is loaded or reloaded. Errors that occur in a callback are
+/// sent as [KotoScriptError] events, and the callback's timer is cancelled.
+pub struct KotoSchedulerPlugin;
+
+impl Plugin for KotoSchedulerPlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+
+        let (add_timer_sender, add_timer_receiver) = koto_channel::<AddTimer>();
+
+        app.world()
+            .resource::<KotoRuntime>()
+            .prelude()
+            .insert("schedule", make_schedule_module(add_timer_sender));
+
+        app.insert_resource(add_timer_receiver)
+            .init_resource::<KotoTimers>()
+            .add_systems(
+                KotoSchedule,
+                (
+                    add_timers.in_set(KotoUpdate::PreUpdate),
+                    run_timers.in_set(KotoUpdate::Update),
+                ),
+            );
+    }
+}
+
+struct AddTimer(ScheduledCall);
+
+// The timers that are currently waiting to call their functions
+#[derive(Resource, Default)]
+struct KotoTimers(Vec<ScheduledCall>);
+
+struct ScheduledCall {
+    function: KValue,
+    args: Vec<KValue>,
+    // The remaining time in seconds until the function should be called
+    remaining: f64,
+    // The interval for repeating calls
+    interval: Option<f64>,
+    is_active: Arc<AtomicBool>,
+}
+
+// The Timer object that's returned from schedule.after and schedule.every
+#[derive(Clone, KotoType, KotoCopy)]
+#[koto(type_name = "Timer")]
+struct Timer {
+    is_active: Arc<AtomicBool>,
+}
+
+impl KotoObject for Timer {}
+
+#[koto_impl]
+impl Timer {
+    #[koto_method]
+    fn is_active(&self) -> KValue {
+        self.is_active.load(Ordering::Relaxed).into()
+    }
+
+    #[koto_method]
+    fn cancel(&self) -> KValue {
+        self.is_active.store(false, Ordering::Relaxed);
+        KValue::Null
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn make_schedule_module(add_timer: KotoSender<AddTimer>) -> KMap {
+    let module = KMap::with_type("schedule");
+
+    let schedule_call = {
+        cloned!(add_timer);
+        move |function: KValue, args: Vec<KValue>, seconds: f64, interval: Option<f64>| {
+            let is_active = Arc::new(AtomicBool::new(true));
+            add_timer.send(AddTimer(ScheduledCall {
+                function,
+                args,
+                remaining: seconds,
+                interval,
+                is_active: is_active.clone(),
+            }));
+            is_active
+        }
+    };
+
+    module.add_fn("after", {
+        cloned!(schedule_call);
+        move |ctx| match ctx.args() {
+            [KValue::Number(seconds), f] if *seconds >= 0.0 && f.is_callable() => {
+                let is_active = schedule_call(f.clone(), Vec::new(), seconds.into(), None);
+                Ok(KObject::from(Timer { is_active }).into())
+            }
+            unexpected => unexpected_args("a non-negative Number, and a function", unexpected),
+        }
+    });
+
+    module.add_fn("every", {
+        cloned!(schedule_call);
+        move |ctx| match ctx.args() {
+            [KValue::Number(seconds), f] if *seconds >= 0.0 && f.is_callable() => {
+                let seconds = f64::from(seconds);
+                let is_active = schedule_call(f.clone(), Vec::new(), seconds, Some(seconds));
+                Ok(KObject::from(Timer { is_active }).into())
+            }
+            unexpected => unexpected_args("a non-negative Number, and a function", unexpected),
+        }
+    });
+
+    module.add_fn("debounce", move |ctx| match ctx.args() {
+        [KValue::Number(seconds), f] if *seconds >= 0.0 && f.is_callable() => {
+            let seconds = f64::from(seconds);
+            let f = f.clone();
+            // The most recent call, which gets cancelled when the function is called again
+            let pending: Mutex<Option<Arc<AtomicBool>>> = Mutex::new(None);
+            cloned!(schedule_call);
+
+            let debounced = KNativeFunction::new(move |ctx: &mut CallContext| {
+                let mut pending = pending.lock();
+                if let Some(is_active) = pending.take() {
+                    is_active.store(false, Ordering::Relaxed);
+                }
+                *pending = Some(schedule_call(f.clone(), ctx.args().to_vec(), seconds, None));
+                Ok(KValue::Null)
+            });
+
+            Ok(KValue::NativeFunction(debounced))
+        }
+        unexpected => unexpected_args("a non-negative Number, and a function", unexpected),
+    });
+
+    module
+}
+
+fn add_timers(
+    channel: Res<KotoReceiver<AddTimer>>,
+    mut timers: ResMut<KotoTimers>,
+    mut script_ready_events: EventReader<ScriptReady>,
+) {
+    // Timers added by the previous script are cancelled when a script is loaded,
+    // with any timers added by the new script then being received from the channel.
+    if script_ready_events.read().count() > 0 {
+        for timer in timers.0.drain(..) {
+            timer.is_active.store(false, Ordering::Relaxed);
+        }
+    }
+
+    let _span = info_span!("koto_channel", channel = "AddTimer").entered();
+    while let Some(AddTimer(timer)) = channel.receive() {
+        timers.0.push(timer);
+    }
+}
+
+fn run_timers(
+    mut koto: ResMut<KotoRuntime>,
+    koto_time: Res<KotoTime>,
+    mut timers: ResMut<KotoTimers>,
+    mut script_error: EventWriter<KotoScriptError>,
+) {
+    if !koto.is_ready() {
+        return;
+    }
+
+    let _span = info_span!("koto_timers", count = timers.0.len()).entered();
+
+    timers.0.retain_mut(|timer| {
+        if !timer.is_active.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        timer.remaining -= koto_time.delta();
+        if timer.remaining > 0.0 {
+            return true;
+        }
+
+        if let Err(error) = koto.call_function(timer.function.clone(), &timer.args) {
+            let error = KotoScriptError {
+                phase: ScriptPhase::Timer,
+                message: error.to_string(),
+                span: None,
+                script_path: koto.script_path().map(ToOwned::to_owned),
+            };
+            error!("{error}");
+            script_error.send(error);
+            timer.is_active.store(false, Ordering::Relaxed);
+            return false;
+        }
+
+        match timer.interval {
+            Some(interval) => {
+                // Repeating calls are made at most once per frame
+                timer.remaining = (timer.remaining + interval).max(0.0);
+                true
+            }
+            None => {
+                timer.is_active.store(false, Ordering::Relaxed);
+                false
+            }
+        }
+    });
+}
@@ -0,0 +1,259 @@
+//! Scripts that are attached to individual Bevy entities
+
+use crate::{prelude::*, runtime::AssetsFolderPath};
+use bevy::prelude::*;
+use koto::{bytecode::Compiler, derive::*, parser::format_source_excerpt, prelude::*, Ptr};
+use std::{collections::HashMap, path::Path};
+
+/// Support for attaching scripts to individual entities
+///
+/// Adding a [KotoScriptComponent] to an entity runs the script in its own VM, separately from the
+/// main script and from other entities. The script's VM shares the main runtime's prelude, so
+/// modules registered by other plugins are available.
+///
+/// The following functions are called if they're exported by the script, with `entity` being a
+/// `ScriptEntity` object that identifies the entity:
+/// - `setup(entity)`: Called when the script is first run, the returned value is used as the
+///   script's state. If `setup` isn't exported, then the state is an empty map.
+/// - `update(state, entity, dt)`: Called each time the [KotoSchedule] runs, with `dt` taken from
+///   [KotoTime].
+///
+/// When the script asset is modified, the script is recompiled and rerun, with the entity's
+/// existing state being preserved. Errors are sent as [KotoScriptError] events, and the entity's
+/// script stops being updated until it's modified.
+///
+/// Bevy's `AssetPlugin` needs to be added before this plugin.
+pub struct KotoScriptComponentPlugin;
+
+impl Plugin for KotoScriptComponentPlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+        assert!(app.is_plugin_added::<AssetPlugin>());
+
+        app.init_resource::<CompiledEntityScripts>().add_systems(
+            KotoSchedule,
+            (
+                (invalidate_modified_scripts, remove_script_instances).in_set(KotoUpdate::Compile),
+                update_script_components.in_set(KotoUpdate::Update),
+            ),
+        );
+    }
+}
+
+/// Attaches a script to an entity, see [KotoScriptComponentPlugin]
+#[derive(Component, Clone, Debug)]
+pub struct KotoScriptComponent(pub Handle<KotoScript>);
+
+// The running instance of an entity's script
+#[derive(Component)]
+struct ScriptInstance {
+    vm: KotoVm,
+    state: KValue,
+    script: AssetId<KotoScript>,
+    generation: u64,
+    is_errored: bool,
+}
+
+// Compiled entity scripts, shared by all entities that use the same script
+#[derive(Resource, Default)]
+struct CompiledEntityScripts {
+    chunks: HashMap<AssetId<KotoScript>, CompiledScript>,
+    next_generation: u64,
+}
+
+struct CompiledScript {
+    // None if compilation failed
+    chunk: Option<Ptr<Chunk>>,
+    generation: u64,
+}
+
+// The entity that's passed to the functions of an entity's script
+#[derive(Clone, KotoType, KotoCopy)]
+#[koto(type_name = "ScriptEntity")]
+struct ScriptEntity {
+    entity: Entity,
+}
+
+impl KotoObject for ScriptEntity {}
+
+#[koto_impl]
+impl ScriptEntity {
+    #[koto_method]
+    fn id(&self) -> KValue {
+        (self.entity.to_bits() as i64).into()
+    }
+
+    #[koto_method]
+    fn index(&self) -> KValue {
+        self.entity.index().into()
+    }
+}
+
+fn invalidate_modified_scripts(
+    mut compiled: ResMut<CompiledEntityScripts>,
+    mut asset_events: EventReader<AssetEvent<KotoScript>>,
+) {
+    for event in asset_events.read() {
+        if let AssetEvent::Modified { id } | AssetEvent::Removed { id } = event {
+            compiled.chunks.remove(id);
+        }
+    }
+}
+
+fn remove_script_instances(
+    mut removed: RemovedComponents<KotoScriptComponent>,
+    mut commands: Commands,
+) {
+    for entity in removed.read() {
+        if let Some(mut entity) = commands.get_entity(entity) {
+            entity.remove::<ScriptInstance>();
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_script_components(
+    koto: Res<KotoRuntime>,
+    koto_time: Res<KotoTime>,
+    assets: Res<Assets<KotoScript>>,
+    assets_folder: Res<AssetsFolderPath>,
+    mut compiled: ResMut<CompiledEntityScripts>,
+    mut query: Query<(Entity, &KotoScriptComponent, Option<&mut ScriptInstance>)>,
+    mut commands: Commands,
+    mut script_error: EventWriter<KotoScriptError>,
+) {
+    for (entity, script_component, instance) in query.iter_mut() {
+        let id = script_component.0.id();
+        let Some(script) = assets.get(id) else {
+            // The script is still being loaded
+            continue;
+        };
+        let script_path = assets_folder.0.join(&script.path);
+        let _span =
+            info_span!("koto_script_component", %entity, script = %script_path.display()).entered();
+
+        let mut send_error = |phase, message: String| {
+            let error = KotoScriptError {
+                phase,
+                message: format!("Entity {entity}: {message}"),
+                span: None,
+                script_path: Some(script_path.clone()),
+            };
+            error!("{error}");
+            script_error.send(error);
+        };
+
+        let compiled_script = compiled.get_or_compile(id, script, &script_path, &mut |message| {
+            send_error(ScriptPhase::Compile, message)
+        });
+
+        let entity_object = KValue::from(KObject::from(ScriptEntity { entity }));
+
+        match instance {
+            Some(mut instance)
+                if instance.script == id && instance.generation == compiled_script.generation =>
+            {
+                if instance.is_errored {
+                    continue;
+                }
+
+                let ScriptInstance { vm, state, .. } = &mut *instance;
+                if let Some(update) = vm.exports().get("update") {
+                    let args = [state.clone(), entity_object, koto_time.delta().into()];
+                    if let Err(error) = vm.call_function(update, &args) {
+                        send_error(ScriptPhase::Update, error.to_string());
+                        instance.is_errored = true;
+                    }
+                }
+            }
+            instance => {
+                // The script is being run for the first time, or it has been modified
+                let previous_state = instance
+                    .filter(|instance| instance.script == id)
+                    .map(|instance| instance.state.clone());
+
+                let mut new_instance = ScriptInstance {
+                    vm: koto.spawn_vm(),
+                    state: KMap::new().into(),
+                    script: id,
+                    generation: compiled_script.generation,
+                    is_errored: true,
+                };
+
+                if let Some(chunk) = compiled_script.chunk.clone() {
+                    match run_entity_script(
+                        &mut new_instance.vm,
+                        chunk,
+                        entity_object,
+                        previous_state,
+                    ) {
+                        Ok(state) => {
+                            new_instance.state = state;
+                            new_instance.is_errored = false;
+                        }
+                        Err((phase, error)) => send_error(phase, error.to_string()),
+                    }
+                }
+
+                commands.entity(entity).insert(new_instance);
+            }
+        }
+    }
+}
+
+// Runs the script's top-level code, and then calls `setup` if no previous state is available
+#[allow(clippy::result_large_err)]
+fn run_entity_script(
+    vm: &mut KotoVm,
+    chunk: Ptr<Chunk>,
+    entity: KValue,
+    previous_state: Option<KValue>,
+) -> Result<KValue, (ScriptPhase, koto::runtime::Error)> {
+    vm.run(chunk).map_err(|error| (ScriptPhase::Run, error))?;
+
+    if let Some(state) = previous_state {
+        return Ok(state);
+    }
+
+    match vm.exports().get("setup") {
+        Some(setup) => vm
+            .call_function(setup, &[entity])
+            .map_err(|error| (ScriptPhase::Setup, error)),
+        None => Ok(KMap::new().into()),
+    }
+}
+
+impl CompiledEntityScripts {
+    fn get_or_compile(
+        &mut self,
+        id: AssetId<KotoScript>,
+        script: &KotoScript,
+        script_path: &Path,
+        on_error: &mut dyn FnMut(String),
+    ) -> &CompiledScript {
+        let next_generation = &mut self.next_generation;
+
+        self.chunks.entry(id).or_insert_with(|| {
+            let koto_script_path = script_path.to_str().map(KString::from);
+            let chunk = match Compiler::compile(&script.script, koto_script_path.clone(), default())
+            {
+                Ok(chunk) => Some(chunk),
+                Err(error) => {
+                    let excerpt = format_source_excerpt(
+                        &script.script,
+                        &error.span,
+                        koto_script_path.as_deref(),
+                    );
+                    on_error(format!("{error}.\n{excerpt}"));
+                    None
+                }
+            };
+
+            *next_generation += 1;
+            CompiledScript {
+                chunk,
+                generation: *next_generation,
+            }
+        })
+    }
+}
@@ -0,0 +1,228 @@
+//! Saving and restoring script sessions for bevy_koto
+
+use crate::prelude::*;
+use bevy::{app::AppExit, prelude::*};
+use koto::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+/// Saves the running script's state to a file, and restores it on the next launch
+///
+/// The script's user data (as returned from its `setup` function) is saved along with the
+/// elapsed [KotoTime]. Only values that can be represented in the session file are saved
+/// (null, bools, numbers, strings, lists, tuples, and maps with string keys), other values are
+/// skipped.
+///
+/// The session is saved when a [SaveKotoSession] event is received, and when the app exits
+/// (unless disabled with [KotoSessionPlugin::with_save_on_exit]).
+///
+/// The saved session is restored when the first script is loaded after launch, as long as the
+/// script's path matches the one that was saved. If the script's user data is a map then the
+/// saved entries are merged into it, otherwise the saved value replaces the user data.
+pub struct KotoSessionPlugin {
+    /// The path of the session file
+    pub path: PathBuf,
+    /// Whether or not the session should be saved when the app exits
+    pub save_on_exit: bool,
+}
+
+impl KotoSessionPlugin {
+    /// Makes a new session plugin that uses the file at the given path
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            save_on_exit: true,
+        }
+    }
+
+    /// Sets whether or not the session should be saved when the app exits
+    #[must_use]
+    pub fn with_save_on_exit(mut self, save_on_exit: bool) -> Self {
+        self.save_on_exit = save_on_exit;
+        self
+    }
+}
+
+impl Plugin for KotoSessionPlugin {
+    fn build(&self, app: &mut App) {
+        debug_assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+
+        app.insert_resource(SessionSettings {
+            path: self.path.clone(),
+            save_on_exit: self.save_on_exit,
+        })
+        .add_event::<SaveKotoSession>()
+        .add_systems(KotoSchedule, restore_session.in_set(KotoUpdate::PreUpdate))
+        .add_systems(Last, save_session);
+    }
+}
+
+/// Event that requests that the current session should be saved
+#[derive(Event, Default)]
+pub struct SaveKotoSession;
+
+#[derive(Resource)]
+struct SessionSettings {
+    path: PathBuf,
+    save_on_exit: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SessionSnapshot {
+    script_path: Option<PathBuf>,
+    elapsed: f64,
+    user_data: SessionValue,
+}
+
+#[derive(Serialize, Deserialize)]
+enum SessionValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    List(Vec<SessionValue>),
+    Tuple(Vec<SessionValue>),
+    Map(Vec<(String, SessionValue)>),
+}
+
+impl SessionValue {
+    fn from_koto(value: &KValue) -> Option<Self> {
+        let result = match value {
+            KValue::Null => Self::Null,
+            KValue::Bool(b) => Self::Bool(*b),
+            KValue::Number(KNumber::I64(n)) => Self::Int(*n),
+            KValue::Number(KNumber::F64(n)) => Self::Float(*n),
+            KValue::Str(s) => Self::Str(s.to_string()),
+            KValue::List(list) => Self::List(Self::from_koto_values(list.data().iter())),
+            KValue::Tuple(tuple) => Self::Tuple(Self::from_koto_values(tuple.iter())),
+            KValue::Map(map) => Self::Map(
+                map.data()
+                    .iter()
+                    .filter_map(|(key, value)| match (key.value(), Self::from_koto(value)) {
+                        (KValue::Str(key), Some(value)) => Some((key.to_string(), value)),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    fn from_koto_values<'a>(values: impl Iterator<Item = &'a KValue>) -> Vec<Self> {
+        values.filter_map(Self::from_koto).collect()
+    }
+
+    fn into_koto(self) -> KValue {
+        match self {
+            Self::Null => KValue::Null,
+            Self::Bool(b) => b.into(),
+            Self::Int(n) => n.into(),
+            Self::Float(n) => n.into(),
+            Self::Str(s) => s.into(),
+            Self::List(values) => {
+                KList::with_data(values.into_iter().map(Self::into_koto).collect()).into()
+            }
+            Self::Tuple(values) => KValue::Tuple(
+                values
+                    .into_iter()
+                    .map(Self::into_koto)
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+            Self::Map(entries) => {
+                let map = KMap::with_capacity(entries.len());
+                for (key, value) in entries {
+                    map.insert(key.as_str(), value.into_koto());
+                }
+                map.into()
+            }
+        }
+    }
+}
+
+fn restore_session(
+    mut koto: ResMut<KotoRuntime>,
+    mut koto_time: ResMut<KotoTime>,
+    settings: Res<SessionSettings>,
+    mut script_loaded_events: EventReader<ScriptLoaded>,
+    mut is_restored: Local<bool>,
+) {
+    if script_loaded_events.read().count() == 0 || std::mem::replace(&mut *is_restored, true) {
+        return;
+    }
+
+    if !settings.path.exists() {
+        return;
+    }
+
+    let snapshot = match fs::read_to_string(&settings.path)
+        .map_err(|error| error.to_string())
+        .and_then(|session| {
+            ron::from_str::<SessionSnapshot>(&session).map_err(|error| error.to_string())
+        }) {
+        Ok(snapshot) => snapshot,
+        Err(error) => {
+            error!(
+                "Failed to read session from '{}': {error}",
+                settings.path.display()
+            );
+            return;
+        }
+    };
+
+    if snapshot.script_path.as_deref() != koto.script_path() {
+        info!("Skipping session restore, the saved session is for a different script");
+        return;
+    }
+
+    match (koto.user_data(), snapshot.user_data.into_koto()) {
+        (KValue::Map(user_data), KValue::Map(saved)) => {
+            let user_data = user_data.clone();
+            for (key, value) in saved.data().iter() {
+                user_data.insert(key.clone(), value.clone());
+            }
+        }
+        (_, saved) => koto.set_user_data(saved),
+    }
+
+    koto_time.set_elapsed(snapshot.elapsed);
+}
+
+fn save_session(
+    koto: Res<KotoRuntime>,
+    koto_time: Res<KotoTime>,
+    settings: Res<SessionSettings>,
+    mut save_events: EventReader<SaveKotoSession>,
+    mut app_exit_events: EventReader<AppExit>,
+) {
+    let save_requested = save_events.read().count() > 0;
+    let is_exiting = app_exit_events.read().count() > 0;
+
+    if !(save_requested || is_exiting && settings.save_on_exit) {
+        return;
+    }
+
+    let Some(user_data) = SessionValue::from_koto(koto.user_data()) else {
+        warn!("Unable to save session, the script's user data can't be serialized");
+        return;
+    };
+
+    let snapshot = SessionSnapshot {
+        script_path: koto.script_path().map(ToOwned::to_owned),
+        elapsed: koto_time.elapsed(),
+        user_data,
+    };
+
+    let result = ron::ser::to_string_pretty(&snapshot, default())
+        .map_err(|error| error.to_string())
+        .and_then(|session| fs::write(&settings.path, session).map_err(|error| error.to_string()));
+
+    if let Err(error) = result {
+        error!(
+            "Failed to save session to '{}': {error}",
+            settings.path.display()
+        );
+    }
+}
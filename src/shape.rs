@@ -1,15 +1,134 @@
 //! Support for adding and updating 2D shapes in Koto scripts
 
-use crate::prelude::*;
-use bevy::{prelude::*, render::view::RenderLayers};
+use crate::{
+    color::{koto_to_bevy_color_material_events, koto_to_easing, SuspendedColorMaterial},
+    entity::koto_entity_impl,
+    prelude::*,
+};
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, MeshAabb, MeshVertexAttribute, PrimitiveTopology, VertexAttributeValues},
+        render_asset::RenderAssetUsages,
+        render_resource::VertexFormat,
+        view::RenderLayers,
+    },
+    sprite::AlphaMode2d,
+    utils::{HashMap, HashSet},
+};
 use cloned::cloned;
 use koto::{derive::*, prelude::*, runtime::Result as KotoResult};
+use std::{
+    f32::consts::{FRAC_PI_2, PI, TAU},
+    sync::Arc,
+};
 
 /// Basic 2d shapes for bevy_koto
 ///
 /// The plugin adds a `shape` module to the Koto prelude.
-/// The currently available shapes are `circle`, `square`, and `polygon`.
-pub struct KotoShapePlugin;
+/// The currently available shapes are `circle`, `square`, `polygon`, `ellipse`, `triangle`,
+/// `capsule`, `ring`, `arc`, `rounded_rect`, `star`, and `line`.
+///
+/// The constructors accept an optional map of initial properties after their other arguments,
+/// which are applied when the shape is spawned, e.g.
+///
+/// ```koto
+/// dot = shape.circle {position: geometry.vec2(1, 0), size: 0.2, color: color 'red', z: 1}
+/// ```
+///
+/// The available properties are `position`, `z`, `rotation`, `size`, `color`, `alpha`, `image`,
+/// `visible`, `name`, and `state`.
+///
+/// Images on shapes can be scrolled and tiled with `set_uv_offset(x, y)` and
+/// `set_uv_scale(x, y)`, see [UvTransform].
+///
+/// `fade_to(alpha, seconds)` and `color_to(color, seconds)` animate a shape's alpha or color over
+/// time, with an optional easing name as a third argument (`'linear'`, `'ease_in'`, `'ease_out'`,
+/// or `'ease_in_out'`), see [ColorTween].
+///
+/// Shapes are blended with the shapes behind them by default. `set_alpha_mode('mask', cutoff)`
+/// renders the shape as an opaque cutout, discarding pixels with an alpha below the cutoff, which
+/// avoids the sorting problems of overlapping blended shapes. `'opaque'` ignores alpha entirely.
+///
+/// Shapes are filled with a flat color by default, `set_gradient(color_a, color_b, angle)` and
+/// `set_radial_gradient(color_a, color_b)` switch the shape to a [GradientMaterial]. A `Gradient`
+/// made with the `gradient` function can be used in place of the two colors.
+///
+/// Shapes with curved outlines can be made with `shape.path()`, which returns a `Path` builder
+/// with `move_to`, `line_to`, `quad_to`, `cubic_to`, and `close` methods. Calling `build` on the
+/// path fills its outlines and returns a new shape, e.g.
+///
+/// ```koto
+/// blob = shape.path()
+///   .move_to -0.5, 0
+///   .quad_to 0, 1, 0.5, 0
+///   .cubic_to 0.5, -0.5, -0.5, -0.5, -0.5, 0
+///   .build()
+/// ```
+///
+/// Each outline in a path is filled separately, so outlines that overlap don't produce holes.
+///
+/// `shape.line(points, width)` makes a strip along a list of points, with mitred joins between
+/// the line's segments. The points can be replaced each frame with `set_points`, e.g. for
+/// drawing trails or plots:
+///
+/// ```koto
+/// wave = shape.line [-1, 0, 1, 0], 0.05
+/// export update = |_, _, time|
+///   wave.set_points (0..100)
+///     .each |i| geometry.vec2 i / 50 - 1, (time.elapsed() + i / 10).sin()
+///     .to_tuple()
+/// ```
+///
+/// The vertices of a shape's mesh can be modified with the `Mesh` object that's returned from
+/// `mesh()`. `set_vertices(list)` replaces the vertex positions, and `displace(f)` calls `f`
+/// with each vertex's original position and index, moving the vertex by the returned `Vec2`,
+/// e.g.
+///
+/// ```koto
+/// blob = shape.circle()
+/// export update = |_, _, time|
+///   blob.mesh().displace |v, i| v * 0.1 * (time.elapsed() * 4 + i).sin()
+/// ```
+///
+/// Modified vertices don't affect picking or collision, which use the shape's original outline.
+///
+/// `set_vertex_colors(list)` gives each of the vertices in a shape's mesh a color, which is
+/// blended smoothly across the shape's triangles, e.g. for cheap gradients or fake lighting.
+/// The vertex colors are multiplied by the shape's color, and are removed by passing `null`.
+/// A line's vertex colors are removed when its points are replaced.
+///
+/// `bounds()` returns a `geometry.rect` containing the shape's mesh after its position, rotation,
+/// and scale have been applied, and `overlaps(other)` checks if the bounds of two shapes overlap.
+/// The bounds are in the space of the shape's parent, so shapes that are compared with each other
+/// should share the same parent.
+///
+/// Shapes with identical meshes share a single [Mesh] asset, and shapes that are spawned without
+/// a color, alpha, or image share a default [ColorMaterial]. Shared assets are copied before
+/// they're modified, see [SharedColorMaterial].
+///
+/// Scripts that spawn and despawn lots of shapes can enable pooling with
+/// [KotoShapePlugin::with_pool_capacity], with shapes that are no longer used by the script being
+/// reused rather than despawned, see [KotoEntityPool].
+#[derive(Default)]
+pub struct KotoShapePlugin {
+    /// The maximum number of unused shapes that are kept for reuse
+    ///
+    /// Pooling is disabled when the capacity is 0, which is the default.
+    pub pool_capacity: usize,
+}
+
+impl KotoShapePlugin {
+    /// Sets the maximum number of unused shapes that are kept for reuse
+    #[must_use]
+    pub fn with_pool_capacity(mut self, capacity: usize) -> Self {
+        self.pool_capacity = capacity;
+        self
+    }
+}
+
+// The name of the shape pool in the KotoEntityPool
+const SHAPE_POOL: &str = "shape";
 
 impl Plugin for KotoShapePlugin {
     fn build(&self, app: &mut App) {
@@ -19,66 +138,207 @@ impl Plugin for KotoShapePlugin {
         assert!(app.is_plugin_added::<KotoGeometryPlugin>());
 
         let (spawn_shape_sender, spawn_shape_receiver) = koto_channel::<SpawnShape>();
+        let (update_mesh_sender, update_mesh_receiver) = koto_entity_channel::<UpdateShapeMesh>();
+
+        app.world_mut()
+            .resource_mut::<KotoEntityPool>()
+            .set_capacity(SHAPE_POOL, self.pool_capacity);
 
         app.insert_resource(spawn_shape_sender)
             .insert_resource(spawn_shape_receiver)
+            .insert_resource(update_mesh_sender)
+            .insert_resource(update_mesh_receiver)
+            .init_resource::<ShapeAssets>()
             .add_systems(Startup, on_startup)
-            .add_systems(KotoSchedule, spawn_shapes.in_set(KotoUpdate::PostUpdate));
+            .add_systems(KotoSchedule, spawn_shapes.in_set(KotoUpdate::PostUpdate))
+            .add_systems(
+                Update,
+                (koto_to_bevy_mesh_events, apply_uv_transforms)
+                    .chain()
+                    .after(koto_to_bevy_color_material_events),
+            );
     }
 }
 
-fn on_startup(
+#[allow(clippy::result_large_err)]
+pub(crate) fn on_startup(
     koto: ResMut<KotoRuntime>,
     spawn_shape: Res<KotoSender<SpawnShape>>,
     update_shape: Res<KotoEntitySender<UpdateColorMaterial>>,
     update_entity: Res<KotoEntitySender<UpdateKotoEntity>>,
+    update_mesh: Res<KotoEntitySender<UpdateShapeMesh>>,
     update_transform: Res<KotoEntitySender<UpdateTransform>>,
 ) {
     let shape_module = KMap::with_type("shape");
 
-    let make_shape = {
-        cloned!(spawn_shape, update_entity, update_shape, update_transform);
+    let make_shape: MakeShape = Arc::new({
+        cloned!(
+            spawn_shape,
+            update_entity,
+            update_mesh,
+            update_shape,
+            update_transform
+        );
 
-        move |shape: Shape| {
+        move |shape: Shape, mut options: ShapeOptions| {
             let entity = KotoEntityMapping::default();
+            entity.set_transform_snapshot(options.transform);
 
             let result: KObject = KotoShape {
                 entity: entity.clone(),
-                state: KValue::Null,
+                shape: shape.clone(),
+                state: std::mem::take(&mut options.state),
+                mesh: None,
+                uv_transform: UvTransform::default(),
+                spawn_shape: spawn_shape.clone(),
                 update_shape: update_shape.clone(),
                 update_entity: update_entity.clone(),
+                update_mesh: update_mesh.clone(),
                 update_transform: update_transform.clone(),
             }
             .into();
 
+            if let Some(name) = options.name.take() {
+                entity.set_name_snapshot(Some(name.clone()));
+                update_entity.send(KotoEntityEvent::new(
+                    entity.clone(),
+                    UpdateKotoEntity::SetName(name),
+                ));
+            }
+
             spawn_shape.send(SpawnShape {
                 koto_entity: KotoEntity::new(result.clone(), entity),
                 shape,
+                options,
             });
             Ok(result.into())
         }
-    };
+    });
 
-    shape_module.add_fn("circle", {
-        cloned!(make_shape);
-        move |ctx| match ctx.args() {
-            &[] => make_shape(Shape::Circle),
-            unexpected => unexpected_args("no arguments", unexpected),
+    // Adds a shape constructor to the module,
+    // with the constructor's arguments optionally being followed by a map of initial properties.
+    let add_shape_fn =
+        |name: &'static str, expected: &'static str, parse_args: fn(&[KValue]) -> Option<Shape>| {
+            shape_module.add_fn(name, {
+                cloned!(make_shape);
+                move |ctx| {
+                    let (args, options) = split_options(ctx.args());
+                    match parse_args(args) {
+                        Some(shape) => make_shape(shape, ShapeOptions::from_koto(name, options)?),
+                        None => unexpected_args(expected, ctx.args()),
+                    }
+                }
+            });
+        };
+
+    add_shape_fn("circle", "no arguments", |args| match args {
+        [] => Some(Shape::Circle),
+        _ => None,
+    });
+
+    add_shape_fn("polygon", "a number of sides", |args| match args {
+        &[KValue::Number(n)] if n > 1 => Some(Shape::Polygon(n.into())),
+        _ => None,
+    });
+
+    add_shape_fn(
+        "rounded_rect",
+        "a width, height, and corner radius",
+        |args| match args {
+            &[KValue::Number(width), KValue::Number(height), KValue::Number(radius)] => Some(
+                Shape::RoundedRect(width.into(), height.into(), radius.into()),
+            ),
+            _ => None,
+        },
+    );
+
+    add_shape_fn(
+        "star",
+        "a number of points and an inner radius",
+        |args| match args {
+            &[KValue::Number(points), KValue::Number(inner_radius)] if points > 1 => {
+                Some(Shape::Star(points.into(), inner_radius.into()))
+            }
+            _ => None,
+        },
+    );
+
+    add_shape_fn(
+        "line",
+        "a list of two or more points, and a width",
+        |args| match args {
+            [points, KValue::Number(width)] => {
+                let points =
+                    with_packed_values(std::slice::from_ref(points), koto_to_points).flatten()?;
+                (points.len() > 1).then(|| Shape::Line(points.into(), width.into()))
+            }
+            _ => None,
+        },
+    );
+
+    add_shape_fn("ellipse", "a width and height", |args| match args {
+        &[KValue::Number(width), KValue::Number(height)] => {
+            Some(Shape::Ellipse(width.into(), height.into()))
         }
+        _ => None,
     });
 
-    shape_module.add_fn("polygon", {
-        cloned!(make_shape);
-        move |ctx| match ctx.args() {
-            &[KValue::Number(n)] if n > 1 => make_shape(Shape::Polygon(n.into())),
-            unexpected => unexpected_args("no arguments", unexpected),
+    add_shape_fn("triangle", "three points", |args| {
+        match koto_to_points(args).as_deref() {
+            Some(&[a, b, c]) => Some(Shape::Triangle(a, b, c)),
+            _ => None,
+        }
+    });
+
+    add_shape_fn("capsule", "a radius and length", |args| match args {
+        &[KValue::Number(radius), KValue::Number(length)] => {
+            Some(Shape::Capsule(radius.into(), length.into()))
         }
+        _ => None,
     });
 
-    shape_module.add_fn("square", {
+    add_shape_fn(
+        "ring",
+        "an inner radius and a larger outer radius",
+        |args| match args {
+            &[KValue::Number(inner), KValue::Number(outer)] if inner >= 0.0 && inner < outer => {
+                Some(Shape::Ring(inner.into(), outer.into()))
+            }
+            _ => None,
+        },
+    );
+
+    add_shape_fn(
+        "arc",
+        "a radius, and start and end angles",
+        |args| match args {
+            &[KValue::Number(radius), KValue::Number(start), KValue::Number(end)]
+                if start <= end =>
+            {
+                let start = f32::from(start);
+                // Arcs are limited to a full circle
+                let end = f32::from(end).min(start + TAU);
+                Some(Shape::Arc(radius.into(), start, end))
+            }
+            _ => None,
+        },
+    );
+
+    add_shape_fn("square", "no arguments", |args| match args {
+        [] => Some(Shape::Rect(1.0, 1.0)),
+        _ => None,
+    });
+
+    shape_module.add_fn("path", {
         cloned!(make_shape);
         move |ctx| match ctx.args() {
-            &[] => make_shape(Shape::Rect(1.0, 1.0)),
+            [] => Ok(KotoPath {
+                outlines: Vec::new(),
+                current: Vec2::ZERO,
+                is_closed: true,
+                make_shape: make_shape.clone(),
+            }
+            .into()),
             unexpected => unexpected_args("no arguments", unexpected),
         }
     });
@@ -86,241 +346,1665 @@ fn on_startup(
     koto.prelude().insert("shape", shape_module);
 }
 
+type PooledShape = (
+    &'static ShapeKind,
+    Option<&'static MeshMaterial2d<ColorMaterial>>,
+    Option<&'static SuspendedColorMaterial>,
+    Has<DeformedMesh>,
+    Has<UvTransform>,
+    Has<SharedColorMaterial>,
+);
+
+#[allow(clippy::too_many_arguments)]
 fn spawn_shapes(
     channel: Res<KotoReceiver<SpawnShape>>,
+    mut pool: ResMut<KotoEntityPool>,
+    pooled_shapes: Query<PooledShape>,
+    mut shape_assets: ResMut<ShapeAssets>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    asset_server: Res<AssetServer>,
     mut commands: Commands,
 ) {
+    let _span = info_span!("koto_channel", channel = "SpawnShape").entered();
+    let is_pooled = pool.capacity(SHAPE_POOL) > 0;
+
     while let Some(SpawnShape {
         mut koto_entity,
         shape,
+        options,
     }) = channel.receive()
     {
-        let mesh: Mesh = match shape {
-            Shape::Rect(width, height) => Rectangle::new(width, height).into(),
-            Shape::Circle => Circle::default().into(),
-            Shape::Polygon(sides) => RegularPolygon::new(1.0, sides).into(),
-        };
+        let visibility = options.visibility();
+
+        // Reuse a pooled shape if one is available
+        let reused = pool.take(SHAPE_POOL).and_then(|entity| {
+            let (
+                shape_kind,
+                pooled_material,
+                suspended,
+                is_deformed,
+                has_uv_transform,
+                is_material_shared,
+            ) = pooled_shapes.get(entity).ok()?;
+            let pooled_material = pooled_material
+                .map(|material| &material.0)
+                .or(suspended.map(|s| &s.0))?;
+
+            let mut entity_commands = commands.entity(entity);
+            // Any tween that was running when the shape was released is cancelled
+            entity_commands.remove::<ColorTween>();
+            // Shapes that were using a gradient are switched back to their color material
+            if suspended.is_some() {
+                entity_commands
+                    .remove::<(MeshMaterial2d<GradientMaterial>, SuspendedColorMaterial)>();
+            }
+
+            // The pooled shape's material is reused if it isn't shared with other shapes
+            let (material, is_shared) = if options.has_material() && !is_material_shared {
+                if let Some(pooled_material) = materials.get_mut(pooled_material.id()) {
+                    *pooled_material = options.material(&asset_server);
+                }
+                (pooled_material.clone(), false)
+            } else {
+                shape_assets.material(&options, &mut materials, &asset_server)
+            };
+            entity_commands.insert(MeshMaterial2d(material));
+            if is_shared {
+                entity_commands.insert(SharedColorMaterial);
+            } else {
+                entity_commands.remove::<SharedColorMaterial>();
+            }
 
-        let bevy_entity = commands
-            .spawn((
-                Mesh2d(meshes.add(mesh)),
-                MeshMaterial2d(materials.add(ColorMaterial {
-                    color: Color::WHITE,
-                    alpha_mode: bevy::sprite::AlphaMode2d::Blend,
-                    texture: None,
-                })),
+            // Meshes that have been modified by the script are replaced
+            if shape_kind.0 != shape || is_deformed || has_uv_transform {
+                let (mesh, is_shared) = shape_assets.mesh(&shape, &mut meshes);
+                entity_commands
+                    .remove::<(DeformedMesh, UvTransform)>()
+                    .insert((Mesh2d(mesh), ShapeKind(shape.clone())));
+                if is_shared {
+                    entity_commands.insert(SharedMesh);
+                } else {
+                    entity_commands.remove::<SharedMesh>();
+                }
+            }
+
+            entity_commands.insert((
+                options.transform,
+                visibility,
                 RenderLayers::layer(0),
                 koto_entity.clone(),
-            ))
-            .id();
+            ));
+            Some(entity)
+        });
+
+        let bevy_entity = reused.unwrap_or_else(|| {
+            let (mesh, is_mesh_shared) = shape_assets.mesh(&shape, &mut meshes);
+            let (material, is_material_shared) =
+                shape_assets.material(&options, &mut materials, &asset_server);
+            let mut entity_commands = commands.spawn((
+                Mesh2d(mesh),
+                MeshMaterial2d(material),
+                options.transform,
+                visibility,
+                RenderLayers::layer(0),
+                ShapeKind(shape),
+                koto_entity.clone(),
+            ));
+            if is_mesh_shared {
+                entity_commands.insert(SharedMesh);
+            }
+            if is_material_shared {
+                entity_commands.insert(SharedColorMaterial);
+            }
+            if is_pooled {
+                entity_commands.insert(KotoPooled(SHAPE_POOL));
+            }
+            entity_commands.id()
+        });
+
         koto_entity.entity.assign_bevy_entity(bevy_entity);
     }
 }
 
+// Assets that are shared between shapes
+#[derive(Resource, Default)]
+struct ShapeAssets {
+    // The meshes of shapes that can be shared, the assets are kept alive by the shapes using them
+    meshes: HashMap<MeshKey, AssetId<Mesh>>,
+    // The cache size at which meshes that are no longer in use are removed from the cache
+    prune_threshold: usize,
+    // The material used by shapes that don't have a color, alpha, or image
+    default_material: Option<Handle<ColorMaterial>>,
+}
+
+impl ShapeAssets {
+    // Returns a mesh for the shape, and true if the mesh is shared with other shapes
+    fn mesh(&mut self, shape: &Shape, meshes: &mut Assets<Mesh>) -> (Handle<Mesh>, bool) {
+        let Some(key) = shape.mesh_key() else {
+            return (meshes.add(make_mesh(shape)), false);
+        };
+
+        if let Some(mesh) = self
+            .meshes
+            .get(&key)
+            .and_then(|id| meshes.get_strong_handle(*id))
+        {
+            return (mesh, true);
+        }
+
+        if self.meshes.len() >= self.prune_threshold {
+            self.meshes.retain(|_, id| meshes.contains(*id));
+            self.prune_threshold = (self.meshes.len() * 2).max(64);
+        }
+
+        let mesh = meshes.add(make_mesh(shape));
+        self.meshes.insert(key, mesh.id());
+        (mesh, true)
+    }
+
+    // Returns a material for the shape, and true if the material is shared with other shapes
+    fn material(
+        &mut self,
+        options: &ShapeOptions,
+        materials: &mut Assets<ColorMaterial>,
+        asset_server: &AssetServer,
+    ) -> (Handle<ColorMaterial>, bool) {
+        if options.has_material() {
+            (materials.add(options.material(asset_server)), false)
+        } else {
+            let material = self
+                .default_material
+                .get_or_insert_with(|| materials.add(default_material()));
+            (material.clone(), true)
+        }
+    }
+}
+
+// Shapes with equal keys have identical meshes
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct MeshKey(u8, [u32; 6]);
+
+// Spawns a shape, returning its Koto object
+type MakeShape = Arc<dyn Fn(Shape, ShapeOptions) -> KotoResult<KValue> + Send + Sync>;
+
+// Splits an optional map of initial properties from the end of a constructor's arguments
+fn split_options(args: &[KValue]) -> (&[KValue], Option<&KMap>) {
+    match args {
+        [args @ .., KValue::Map(options)] => (args, Some(options)),
+        _ => (args, None),
+    }
+}
+
+// Initial properties for a shape, provided to the shape constructors as a map
+#[derive(Clone, Debug, Default)]
+struct ShapeOptions {
+    transform: Transform,
+    color: Option<Color>,
+    alpha: Option<f32>,
+    image: Option<String>,
+    visible: Option<bool>,
+    name: Option<String>,
+    state: KValue,
+}
+
+impl ShapeOptions {
+    const KEYS: &'static [&'static str] = &[
+        "alpha", "color", "image", "name", "position", "rotation", "size", "state", "visible", "z",
+    ];
+
+    #[allow(clippy::result_large_err)]
+    fn from_koto(constructor: &str, options: Option<&KMap>) -> KotoResult<Self> {
+        use KValue::{Bool, Number, Object, Str};
+
+        let mut result = Self::default();
+        let Some(options) = options else {
+            return Ok(result);
+        };
+
+        for (key, value) in options.data().iter() {
+            let Str(key) = key.value() else {
+                return runtime_error!("shape.{constructor}: Expected option names as strings");
+            };
+
+            match (key.as_str(), value) {
+                ("alpha", Number(alpha)) => result.alpha = Some(alpha.into()),
+                ("color", Object(o)) if o.is_a::<KotoColor>() => {
+                    result.color = Some(koto_to_bevy_color(&*o.cast::<KotoColor>()?));
+                }
+                ("color", Str(s)) => match parse_color(s) {
+                    Some(color) => result.color = Some(color),
+                    None => return runtime_error!("shape.{constructor}: Unknown color '{s}'"),
+                },
+                ("image", Str(path)) => result.image = Some(path.to_string()),
+                ("name", Str(name)) => result.name = Some(name.to_string()),
+                ("position", Object(o)) if o.is_a::<KotoVec2>() => {
+                    let v = o.cast::<KotoVec2>()?.inner();
+                    result.transform.translation.x = v.x as f32;
+                    result.transform.translation.y = v.y as f32;
+                }
+                ("rotation", Number(rotation)) => {
+                    result.transform.rotation = Quat::from_rotation_z(rotation.into());
+                }
+                ("size", Number(size)) => {
+                    let size = f32::from(size);
+                    result.transform.scale = Vec3::new(size, size, 0.0);
+                }
+                ("size", Object(o)) if o.is_a::<KotoVec2>() => {
+                    let v = o.cast::<KotoVec2>()?.inner();
+                    result.transform.scale = Vec3::new(v.x as f32, v.y as f32, 0.0);
+                }
+                ("state", state) => result.state = state.clone(),
+                ("visible", Bool(visible)) => result.visible = Some(*visible),
+                ("z", Number(z)) => result.transform.translation.z = z.into(),
+                (key, value) if Self::KEYS.contains(&key) => {
+                    return runtime_error!(
+                        "shape.{constructor}: Unexpected value for '{key}' ({})",
+                        value.type_as_string()
+                    )
+                }
+                (key, _) => return runtime_error!("shape.{constructor}: Unknown option '{key}'"),
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn material(&self, asset_server: &AssetServer) -> ColorMaterial {
+        let mut material = default_material();
+        if let Some(color) = self.color {
+            material.color = color;
+        }
+        if let Some(alpha) = self.alpha {
+            material.color.set_alpha(alpha);
+        }
+        material.texture = self.image.as_ref().map(|path| asset_server.load(path));
+        material
+    }
+
+    // True if the options modify the shape's default material
+    fn has_material(&self) -> bool {
+        self.color.is_some() || self.alpha.is_some() || self.image.is_some()
+    }
+
+    fn visibility(&self) -> Visibility {
+        if self.visible == Some(false) {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        }
+    }
+}
+
+// Gets a list of points from either Vec2s or pairs of x and y Numbers
+pub(crate) fn koto_to_points(args: &[KValue]) -> Option<Vec<Vec2>> {
+    if args.iter().all(|arg| matches!(arg, KValue::Number(_))) {
+        if !args.len().is_multiple_of(2) {
+            return None;
+        }
+        args.chunks(2)
+            .map(|xy| match xy {
+                [KValue::Number(x), KValue::Number(y)] => Some(Vec2::new(x.into(), y.into())),
+                _ => None,
+            })
+            .collect()
+    } else {
+        args.iter()
+            .map(|arg| match arg {
+                KValue::Object(o) => {
+                    let v = o.cast::<KotoVec2>().ok()?.inner();
+                    Some(Vec2::new(v.x as f32, v.y as f32))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn make_mesh(shape: &Shape) -> Mesh {
+    match *shape {
+        Shape::Rect(width, height) => Rectangle::new(width, height).into(),
+        Shape::Circle => Circle::default().into(),
+        Shape::Polygon(sides) => RegularPolygon::new(1.0, sides).into(),
+        Shape::Ellipse(width, height) => Ellipse::new(width / 2.0, height / 2.0).into(),
+        Shape::Triangle(a, b, c) => Triangle2d::new(a, b, c).into(),
+        Shape::Capsule(radius, length) => Capsule2d::new(radius, length).into(),
+        Shape::Ring(inner, outer) => Annulus::new(inner, outer).into(),
+        Shape::RoundedRect(width, height, radius) => {
+            PathMesh::new(&[rounded_rect_outline(width, height, radius)]).to_mesh()
+        }
+        Shape::Star(points, inner_radius) => {
+            PathMesh::new(&[star_outline(points, inner_radius)]).to_mesh()
+        }
+        Shape::Line(ref points, width) => line_mesh(points, width),
+        Shape::Path(ref path) => path.to_mesh(),
+        Shape::Arc(radius, start, end) => {
+            // Bevy's circular sectors are centered on the Y axis,
+            // so the mesh is rotated to place the sector between the start and end angles.
+            let sector = CircularSector::from_radians(radius, end - start);
+            Mesh::from(sector).rotated_by(Quat::from_rotation_z((start + end) / 2.0 - FRAC_PI_2))
+        }
+    }
+}
+
+fn default_material() -> ColorMaterial {
+    ColorMaterial {
+        color: Color::WHITE,
+        alpha_mode: bevy::sprite::AlphaMode2d::Blend,
+        texture: None,
+    }
+}
+
+// The shape that the entity's mesh was made for
+#[derive(Component)]
+pub(crate) struct ShapeKind(pub(crate) Shape);
+
+// Marks shapes whose mesh vertices have been modified by the script,
+// either by moving the vertices or by giving them colors
+#[derive(Component)]
+struct DeformedMesh;
+
+// Marks shapes whose mesh is shared with other shapes
+#[derive(Component)]
+struct SharedMesh;
+
+/// Event for updating the mesh of a shape that was spawned by a Koto script
+#[derive(Clone, Event)]
+pub enum UpdateShapeMesh {
+    /// Replaces the positions of the mesh's vertices
+    ///
+    /// The number of vertices must match the number of vertices in the shape's mesh.
+    SetVertices(Vec<Vec2>),
+    /// Replaces the points of a line, rebuilding the line's mesh
+    ///
+    /// The event is ignored if the shape isn't a line.
+    SetLinePoints(Vec<Vec2>),
+    /// Sets the colors of the mesh's vertices, or removes them if `None`
+    ///
+    /// The number of colors must match the number of vertices in the shape's mesh.
+    /// Vertex colors are multiplied by the color of the shape's material.
+    SetVertexColors(Option<Vec<Color>>),
+}
+
+fn koto_to_bevy_mesh_events(
+    channel: Res<KotoEntityReceiver<UpdateShapeMesh>>,
+    mut query: Query<(&mut Mesh2d, &mut ShapeKind, Has<SharedMesh>)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut commands: Commands,
+    mut forked: Local<HashSet<Entity>>,
+) {
+    let _span = info_span!("koto_channel", channel = "UpdateShapeMesh").entered();
+    forked.clear();
+    while let Some(event) = channel.receive() {
+        let entity = event.entity.get();
+        // The entity may have been despawned
+        let Ok((mut mesh_handle, mut shape_kind, is_shared)) = query.get_mut(entity) else {
+            continue;
+        };
+        // Shared meshes are copied before their vertices are modified
+        let modifies_vertices = !matches!(event.event, UpdateShapeMesh::SetLinePoints(_));
+        if modifies_vertices && is_shared && forked.insert(entity) {
+            let Some(mesh) = meshes.get(mesh_handle.id()).cloned() else {
+                continue;
+            };
+            mesh_handle.0 = meshes.add(mesh);
+            commands.entity(entity).remove::<SharedMesh>();
+        }
+        match event.event {
+            UpdateShapeMesh::SetVertices(vertices) => {
+                let Some(mesh) = meshes.get_mut(mesh_handle.id()) else {
+                    continue;
+                };
+                if vertices.len() != mesh.count_vertices() {
+                    continue;
+                }
+                let positions: Vec<_> = vertices.iter().map(|v| [v.x, v.y, 0.0]).collect();
+                mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+                commands.entity(entity).insert(DeformedMesh);
+            }
+            UpdateShapeMesh::SetVertexColors(colors) => {
+                let Some(mesh) = meshes.get_mut(mesh_handle.id()) else {
+                    continue;
+                };
+                match colors {
+                    Some(colors) if colors.len() == mesh.count_vertices() => {
+                        let colors: Vec<_> = colors
+                            .iter()
+                            .map(|color| color.to_linear().to_f32_array())
+                            .collect();
+                        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+                    }
+                    Some(_) => continue,
+                    None => {
+                        mesh.remove_attribute(Mesh::ATTRIBUTE_COLOR);
+                    }
+                }
+                commands.entity(entity).insert(DeformedMesh);
+            }
+            UpdateShapeMesh::SetLinePoints(points) => {
+                let Shape::Line(_, width) = shape_kind.0 else {
+                    continue;
+                };
+                let Some(mesh) = meshes.get_mut(mesh_handle.id()) else {
+                    continue;
+                };
+                shape_kind.0 = Shape::Line(points.into(), width);
+                *mesh = make_mesh(&shape_kind.0);
+                // Marking the handle as changed causes any UV transform to be reapplied
+                mesh_handle.set_changed();
+                commands.entity(entity).remove::<DeformedMesh>();
+            }
+        }
+    }
+}
+
+// The original UV coordinates of a mesh that has had a UvTransform applied
+const ATTRIBUTE_BASE_UV: MeshVertexAttribute =
+    MeshVertexAttribute::new("Koto_Base_Uv", 0x6b6f_746f, VertexFormat::Float32x2);
+
+type ChangedUvTransform = Or<(Changed<UvTransform>, Changed<Mesh2d>)>;
+
+fn apply_uv_transforms(
+    mut query: Query<(&UvTransform, &mut Mesh2d, Has<SharedMesh>, Entity), ChangedUvTransform>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut commands: Commands,
+) {
+    for (uv_transform, mut mesh_handle, is_shared, entity) in &mut query {
+        // Shared meshes are copied before their UVs are modified
+        if is_shared {
+            let Some(mesh) = meshes.get(mesh_handle.id()).cloned() else {
+                continue;
+            };
+            mesh_handle.bypass_change_detection().0 = meshes.add(mesh);
+            commands.entity(entity).remove::<SharedMesh>();
+        }
+        let Some(mesh) = meshes.get_mut(mesh_handle.id()) else {
+            continue;
+        };
+
+        // The mesh's UVs are copied the first time that they're transformed
+        if !mesh.contains_attribute(ATTRIBUTE_BASE_UV) {
+            let Some(uvs) = mesh.attribute(Mesh::ATTRIBUTE_UV_0).cloned() else {
+                continue;
+            };
+            mesh.insert_attribute(ATTRIBUTE_BASE_UV, uvs);
+        }
+
+        let Some(VertexAttributeValues::Float32x2(base_uvs)) = mesh.attribute(ATTRIBUTE_BASE_UV)
+        else {
+            continue;
+        };
+        let uvs: Vec<_> = base_uvs
+            .iter()
+            .map(|uv| (Vec2::from(*uv) * uv_transform.scale + uv_transform.offset).to_array())
+            .collect();
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    }
+}
+
 #[derive(Clone, Debug)]
-struct SpawnShape {
+pub(crate) struct SpawnShape {
     koto_entity: KotoEntity,
     shape: Shape,
+    options: ShapeOptions,
 }
 
-#[derive(Clone, Debug)]
-enum Shape {
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Shape {
     Rect(f32, f32),
     Circle,
     Polygon(u32),
+    // Width and height
+    Ellipse(f32, f32),
+    Triangle(Vec2, Vec2, Vec2),
+    // Radius and the length of the capsule's straight section
+    Capsule(f32, f32),
+    // Inner and outer radius
+    Ring(f32, f32),
+    // Radius, and start and end angles in radians counter-clockwise from the X axis
+    Arc(f32, f32, f32),
+    // Width, height, and corner radius
+    RoundedRect(f32, f32, f32),
+    // The number of points, and the inner radius
+    Star(u32, f32),
+    // The line's points, and its width
+    Line(Arc<[Vec2]>, f32),
+    Path(Arc<PathMesh>),
+}
+
+impl Shape {
+    // Returns a key for shapes that can share their mesh with other shapes
+    fn mesh_key(&self) -> Option<MeshKey> {
+        let key = |kind, values: &[f32]| {
+            let mut bits = [0; 6];
+            for (bits, value) in bits.iter_mut().zip(values) {
+                *bits = value.to_bits();
+            }
+            Some(MeshKey(kind, bits))
+        };
+
+        match *self {
+            Shape::Rect(width, height) => key(0, &[width, height]),
+            Shape::Circle => key(1, &[]),
+            Shape::Polygon(sides) => key(2, &[sides as f32]),
+            Shape::Ellipse(width, height) => key(3, &[width, height]),
+            Shape::Triangle(a, b, c) => key(4, &[a.x, a.y, b.x, b.y, c.x, c.y]),
+            Shape::Capsule(radius, length) => key(5, &[radius, length]),
+            Shape::Ring(inner, outer) => key(6, &[inner, outer]),
+            Shape::Arc(radius, start, end) => key(7, &[radius, start, end]),
+            Shape::RoundedRect(width, height, radius) => key(8, &[width, height, radius]),
+            Shape::Star(points, inner_radius) => key(9, &[points as f32, inner_radius]),
+            // Lines and paths are usually unique, and lines are modified in place
+            Shape::Line(..) | Shape::Path(_) => None,
+        }
+    }
+
+    // Checks if the point (in the shape's local space) is inside the shape's mesh
+    #[cfg(feature = "picking")]
+    pub(crate) fn contains(&self, point: Vec2) -> bool {
+        match *self {
+            Shape::Rect(width, height) => {
+                point.x.abs() <= width / 2.0 && point.y.abs() <= height / 2.0
+            }
+            Shape::Circle => point.length() <= Circle::default().radius,
+            Shape::Polygon(sides) => {
+                // The polygon is convex with its vertices in counter-clockwise order,
+                // so the point is inside if it's to the left of each edge.
+                let vertices: Vec<Vec2> = RegularPolygon::new(1.0, sides)
+                    .vertices(0.0)
+                    .into_iter()
+                    .collect();
+                vertices
+                    .iter()
+                    .zip(vertices.iter().cycle().skip(1))
+                    .all(|(a, b)| (*b - *a).perp_dot(point - *a) >= 0.0)
+            }
+            Shape::Ellipse(width, height) => {
+                (point / Vec2::new(width, height) * 2.0).length_squared() <= 1.0
+            }
+            Shape::Triangle(a, b, c) => triangle_contains(a, b, c, point),
+            Shape::Capsule(radius, length) => {
+                // The distance from the capsule's vertical center line
+                Vec2::new(point.x, (point.y.abs() - length / 2.0).max(0.0)).length() <= radius
+            }
+            Shape::Ring(inner, outer) => (inner..=outer).contains(&point.length()),
+            Shape::Arc(radius, start, end) => {
+                point.length() <= radius
+                    && (point.to_angle() - start).rem_euclid(TAU) <= end - start
+            }
+            Shape::RoundedRect(width, height, radius) => {
+                // The distance from the rectangle that's inset by the corner radius
+                let radius = radius.min(width.min(height) / 2.0);
+                let inset = Vec2::new(width, height) / 2.0 - radius;
+                (point.abs() - inset).max(Vec2::ZERO).length() <= radius
+            }
+            Shape::Star(points, inner_radius) => {
+                // The number of edges that are crossed by a ray in the +X direction
+                let outline = star_outline(points, inner_radius);
+                let crossings = outline
+                    .iter()
+                    .zip(outline.iter().cycle().skip(1))
+                    .filter(|(a, b)| {
+                        (a.y > point.y) != (b.y > point.y)
+                            && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+                    })
+                    .count();
+                crossings % 2 == 1
+            }
+            Shape::Line(ref points, width) => points.windows(2).any(|segment| {
+                // The distance from the closest point on the segment
+                let (a, b) = (segment[0], segment[1]);
+                let t = ((point - a).dot(b - a) / (b - a).length_squared().max(f32::EPSILON))
+                    .clamp(0.0, 1.0);
+                point.distance(a + (b - a) * t) <= width / 2.0
+            }),
+            Shape::Path(ref path) => path.contains(point),
+        }
+    }
+}
+
+// The number of line segments that are used for each corner of a rounded rectangle
+const CORNER_SEGMENTS: usize = 8;
+
+fn rounded_rect_outline(width: f32, height: f32, radius: f32) -> Vec<Vec2> {
+    let radius = radius.clamp(0.0, width.min(height) / 2.0);
+    let inset = Vec2::new(width, height) / 2.0 - radius;
+
+    // The corners are added counter-clockwise, starting with the top right
+    [
+        (inset, 0.0),
+        (Vec2::new(-inset.x, inset.y), FRAC_PI_2),
+        (-inset, PI),
+        (Vec2::new(inset.x, -inset.y), PI + FRAC_PI_2),
+    ]
+    .into_iter()
+    .flat_map(|(center, start_angle)| {
+        (0..=CORNER_SEGMENTS).map(move |i| {
+            let angle = start_angle + FRAC_PI_2 * i as f32 / CORNER_SEGMENTS as f32;
+            center + Vec2::from_angle(angle) * radius
+        })
+    })
+    .collect()
+}
+
+// A star with an outer radius of 1, with its first point at the top
+fn star_outline(points: u32, inner_radius: f32) -> Vec<Vec2> {
+    let step = PI / points as f32;
+    (0..points * 2)
+        .map(|i| {
+            let radius = if i % 2 == 0 { 1.0 } else { inner_radius };
+            Vec2::from_angle(FRAC_PI_2 + step * i as f32) * radius
+        })
+        .collect()
+}
+
+// The maximum length of a line's mitre joins, relative to the line's width
+const MITRE_LIMIT: f32 = 2.0;
+
+// A strip of triangles along the line's points, with mitred joins between segments
+fn line_mesh(points: &[Vec2], width: f32) -> Mesh {
+    let half_width = width / 2.0;
+    let direction = |a: Vec2, b: Vec2| (b - a).try_normalize().unwrap_or(Vec2::X);
+
+    let mut positions = Vec::with_capacity(points.len() * 2);
+    let mut uvs = Vec::with_capacity(points.len() * 2);
+    let length: f32 = points.windows(2).map(|s| s[0].distance(s[1])).sum();
+    let mut distance = 0.0;
+
+    for (i, point) in points.iter().enumerate() {
+        let previous = (i > 0).then(|| direction(points[i - 1], *point));
+        let next = points.get(i + 1).map(|next| direction(*point, *next));
+        if i > 0 {
+            distance += points[i - 1].distance(*point);
+        }
+
+        let offset = match (previous, next) {
+            (Some(previous), Some(next)) => {
+                // The mitre is perpendicular to the average of the segments' directions,
+                // and is lengthened so that the segments keep their width.
+                let mitre = (previous + next).try_normalize().unwrap_or(previous).perp();
+                let scale = 1.0 / mitre.dot(previous.perp()).max(1.0 / MITRE_LIMIT);
+                mitre * half_width * scale
+            }
+            (Some(direction), None) | (None, Some(direction)) => direction.perp() * half_width,
+            (None, None) => Vec2::ZERO,
+        };
+
+        let u = if length > 0.0 { distance / length } else { 0.0 };
+        for (position, v) in [(*point + offset, 0.0), (*point - offset, 1.0)] {
+            positions.push([position.x, position.y, 0.0]);
+            uvs.push([u, v]);
+        }
+    }
+
+    let indices = (0..points.len().saturating_sub(1) as u32)
+        .flat_map(|i| {
+            let (left, right) = (i * 2, i * 2 + 1);
+            [left, right, left + 2, right, right + 2, left + 2]
+        })
+        .collect();
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_NORMAL,
+        vec![[0.0, 0.0, 1.0]; positions.len()],
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices))
 }
 
+fn triangle_contains(a: Vec2, b: Vec2, c: Vec2, point: Vec2) -> bool {
+    // The vertices can be in either order,
+    // so the point is inside if it's on the same side of each edge.
+    let sides = [(a, b), (b, c), (c, a)].map(|(a, b)| (b - a).perp_dot(point - a));
+    sides.iter().all(|side| *side >= 0.0) || sides.iter().all(|side| *side <= 0.0)
+}
+
+// The number of line segments that are used for each curve in a path
+const CURVE_SEGMENTS: usize = 16;
+
+// The filled outlines of a path, tessellated into triangles
+#[derive(Debug, PartialEq)]
+pub(crate) struct PathMesh {
+    vertices: Vec<Vec2>,
+    indices: Vec<u32>,
+}
+
+impl PathMesh {
+    fn new(outlines: &[Vec<Vec2>]) -> Self {
+        let mut result = Self {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        };
+        for outline in outlines {
+            result.add_outline(outline);
+        }
+        result
+    }
+
+    fn to_mesh(&self) -> Mesh {
+        // UVs are mapped to the path's bounding box, with V increasing downwards
+        let (min, max) = self.bounds();
+        let size = (max - min).max(Vec2::splat(f32::EPSILON));
+        let positions: Vec<_> = self.vertices.iter().map(|v| [v.x, v.y, 0.0]).collect();
+        let uvs: Vec<_> = self
+            .vertices
+            .iter()
+            .map(|v| {
+                let uv = (*v - min) / size;
+                [uv.x, 1.0 - uv.y]
+            })
+            .collect();
+
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(
+            Mesh::ATTRIBUTE_NORMAL,
+            vec![[0.0, 0.0, 1.0]; positions.len()],
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_indices(Indices::U32(self.indices.clone()))
+    }
+
+    #[cfg(feature = "picking")]
+    fn contains(&self, point: Vec2) -> bool {
+        self.indices.chunks_exact(3).any(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|i| self.vertices[triangle[i] as usize]);
+            triangle_contains(a, b, c, point)
+        })
+    }
+
+    // Returns the min and max corners of the path's bounding box
+    pub(crate) fn bounds(&self) -> (Vec2, Vec2) {
+        self.vertices.iter().fold(
+            (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |(min, max), v| (min.min(*v), max.max(*v)),
+        )
+    }
+
+    // Triangulates the outline using ear clipping
+    fn add_outline(&mut self, outline: &[Vec2]) {
+        let mut points = outline.to_vec();
+        points.dedup();
+        if points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+
+        let area: f32 = points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .map(|(a, b)| a.perp_dot(*b))
+            .sum();
+        if points.len() < 3 || area == 0.0 {
+            return;
+        }
+        // Ears are found by checking for convex corners in a counter-clockwise outline
+        if area < 0.0 {
+            points.reverse();
+        }
+
+        let first_index = self.vertices.len() as u32;
+        let mut remaining: Vec<usize> = (0..points.len()).collect();
+        let mut i = 0;
+        // Each pass around the outline should remove at least one corner, if a full pass doesn't
+        // find an ear then the outline is self-intersecting and the remaining area is skipped.
+        let mut attempts = 0;
+
+        while remaining.len() >= 3 && attempts < remaining.len() {
+            let count = remaining.len();
+            let [prev, current, next] = [i + count - 1, i, i + 1].map(|j| remaining[j % count]);
+            let [a, b, c] = [prev, current, next].map(|j| points[j]);
+            let turn = (b - a).perp_dot(c - b);
+
+            if turn == 0.0 {
+                // Collinear corners can be removed without adding a triangle
+                remaining.remove(i);
+                attempts = 0;
+            } else if turn > 0.0
+                && !remaining.iter().any(|&j| {
+                    ![prev, current, next].contains(&j) && triangle_contains(a, b, c, points[j])
+                })
+            {
+                self.indices
+                    .extend([prev, current, next].map(|j| first_index + j as u32));
+                remaining.remove(i);
+                attempts = 0;
+            } else {
+                i += 1;
+                attempts += 1;
+            }
+
+            if !remaining.is_empty() {
+                i %= remaining.len();
+            }
+        }
+
+        self.vertices.extend(points);
+    }
+}
+
+// The Path object that's returned from shape.path
 #[derive(Clone, KotoType, KotoCopy)]
-#[koto(type_name = "Shape")]
-struct KotoShape {
-    entity: KotoEntityMapping,
-    state: KValue,
-    update_shape: KotoEntitySender<UpdateColorMaterial>,
-    update_entity: KotoEntitySender<UpdateKotoEntity>,
-    update_transform: KotoEntitySender<UpdateTransform>,
+#[koto(type_name = "Path")]
+struct KotoPath {
+    outlines: Vec<Vec<Vec2>>,
+    // The current position of the path
+    current: Vec2,
+    // True when the next segment should start a new outline
+    is_closed: bool,
+    make_shape: MakeShape,
 }
 
-impl KotoObject for KotoShape {}
+impl KotoObject for KotoPath {}
 
+impl KotoPath {
+    // Adds points to the current outline, starting a new outline if necessary
+    fn extend(&mut self, points: impl IntoIterator<Item = Vec2>) {
+        if self.is_closed {
+            self.outlines.push(vec![self.current]);
+            self.is_closed = false;
+        }
+        if let Some(outline) = self.outlines.last_mut() {
+            outline.extend(points);
+            self.current = outline.last().copied().unwrap_or(self.current);
+        }
+    }
+}
+
+#[allow(clippy::result_large_err)]
 #[koto_impl]
-impl KotoShape {
+impl KotoPath {
     #[koto_method]
-    fn state(&self) -> KValue {
-        self.state.clone()
+    fn move_to(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+        let Some(&[point]) = koto_to_points(ctx.args).as_deref() else {
+            return runtime_error!("Path.move_to: Expected a point");
+        };
+
+        let mut this = ctx.instance_mut()?;
+        this.current = point;
+        this.is_closed = true;
+        drop(this);
+
+        ctx.instance_result()
     }
 
     #[koto_method]
-    fn set_state(ctx: MethodContext<Self>) -> KotoResult<KValue> {
-        match ctx.args {
-            [state] => ctx.instance_mut()?.state = state.clone(),
-            _ => return runtime_error!("Shape.set_state: Expected a single value"),
+    fn line_to(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+        let Some(&[point]) = koto_to_points(ctx.args).as_deref() else {
+            return runtime_error!("Path.line_to: Expected a point");
         };
 
+        ctx.instance_mut()?.extend([point]);
         ctx.instance_result()
     }
 
     #[koto_method]
-    fn set_alpha(ctx: MethodContext<Self>) -> KotoResult<KValue> {
-        let alpha = match ctx.args {
-            [KValue::Number(n)] => n.into(),
-            _ => return runtime_error!("Shape.set_alpha: Expected a number"),
+    fn quad_to(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+        let Some(&[control, end]) = koto_to_points(ctx.args).as_deref() else {
+            return runtime_error!("Path.quad_to: Expected a control point and an end point");
         };
 
-        let this = ctx.instance()?;
-        this.update_shape.send(KotoEntityEvent::new(
-            this.entity.clone(),
-            UpdateColorMaterial::Alpha(alpha),
-        ));
+        let mut this = ctx.instance_mut()?;
+        let start = this.current;
+        this.extend((1..=CURVE_SEGMENTS).map(|i| {
+            let t = i as f32 / CURVE_SEGMENTS as f32;
+            start.lerp(control, t).lerp(control.lerp(end, t), t)
+        }));
+        drop(this);
 
         ctx.instance_result()
     }
 
-    #[koto_method(alias = "set_colour")]
-    fn set_color(ctx: MethodContext<Self>) -> KotoResult<KValue> {
-        use KValue::{Number, Object};
-
-        let color = match ctx.args {
-            [Number(n1), Number(n2), Number(n3)] => {
-                Color::srgba(f32::from(n1), f32::from(n2), f32::from(n3), 1.0)
-            }
-            [Number(n1), Number(n2), Number(n3), Number(n4)] => {
-                Color::srgba(f32::from(n1), f32::from(n2), f32::from(n3), f32::from(n4))
-            }
-            [Object(o)] if o.is_a::<KotoColor>() => koto_to_bevy_color(&*o.cast::<KotoColor>()?),
-            _ => {
-                return runtime_error!("Shape.set_color: Expected a Color, or 3 or 4 numbers");
-            }
+    #[koto_method]
+    fn cubic_to(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+        let Some(&[control_1, control_2, end]) = koto_to_points(ctx.args).as_deref() else {
+            return runtime_error!("Path.cubic_to: Expected two control points and an end point");
         };
 
-        let this = ctx.instance()?;
-        this.update_shape.send(KotoEntityEvent::new(
-            this.entity.clone(),
-            UpdateColorMaterial::Color(color),
-        ));
+        let mut this = ctx.instance_mut()?;
+        let start = this.current;
+        this.extend((1..=CURVE_SEGMENTS).map(|i| {
+            let t = i as f32 / CURVE_SEGMENTS as f32;
+            let a = start.lerp(control_1, t);
+            let b = control_1.lerp(control_2, t);
+            let c = control_2.lerp(end, t);
+            a.lerp(b, t).lerp(b.lerp(c, t), t)
+        }));
+        drop(this);
 
         ctx.instance_result()
     }
 
     #[koto_method]
-    fn set_image(ctx: MethodContext<Self>) -> KotoResult<KValue> {
-        let path = match ctx.args {
-            [KValue::Str(path)] => path,
-            _ => {
-                return runtime_error!("Shape.set_image: Expected an image path as a string");
+    fn close(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+        if !ctx.args.is_empty() {
+            return runtime_error!("Path.close: Expected no arguments");
+        }
+
+        // The path continues from the start of the closed outline
+        let mut this = ctx.instance_mut()?;
+        if !this.is_closed {
+            if let Some(start) = this.outlines.last().and_then(|outline| outline.first()) {
+                this.current = *start;
             }
+            this.is_closed = true;
+        }
+        drop(this);
+
+        ctx.instance_result()
+    }
+
+    #[koto_method]
+    fn build(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+        let options = match ctx.args {
+            [] => None,
+            [KValue::Map(options)] => Some(options),
+            _ => return runtime_error!("Path.build: Expected an optional map of properties"),
         };
 
         let this = ctx.instance()?;
-        this.update_shape.send(KotoEntityEvent::new(
-            this.entity.clone(),
-            UpdateColorMaterial::SetImagePath(Some(path.to_string())),
-        ));
+        let path = PathMesh::new(&this.outlines);
+        if path.indices.is_empty() {
+            return runtime_error!("Path.build: The path doesn't enclose any area");
+        }
 
-        ctx.instance_result()
+        (this.make_shape)(
+            Shape::Path(Arc::new(path)),
+            ShapeOptions::from_koto("path", options)?,
+        )
     }
+}
 
-    #[koto_method]
-    fn set_position(ctx: MethodContext<Self>) -> KotoResult<KValue> {
-        use KValue::{Number, Object};
+impl From<KotoPath> for KValue {
+    fn from(path: KotoPath) -> Self {
+        KObject::from(path).into()
+    }
+}
+
+#[derive(Clone, KotoType, KotoCopy)]
+#[koto(type_name = "Shape")]
+struct KotoShape {
+    entity: KotoEntityMapping,
+    shape: Shape,
+    state: KValue,
+    // The shape's Mesh object, created when it's first requested by the script
+    mesh: Option<KObject>,
+    uv_transform: UvTransform,
+    spawn_shape: KotoSender<SpawnShape>,
+    update_shape: KotoEntitySender<UpdateColorMaterial>,
+    update_entity: KotoEntitySender<UpdateKotoEntity>,
+    update_mesh: KotoEntitySender<UpdateShapeMesh>,
+    update_transform: KotoEntitySender<UpdateTransform>,
+}
+
+impl KotoObject for KotoShape {}
+
+koto_entity_impl! {
+    impl KotoShape as "Shape" with [
+        entity, layer, children, state, transform_2d, z_index, size_2d,
+    ] {
+        #[koto_method(alias = "get_colour")]
+        fn get_color(&self) -> KValue {
+            self.entity
+                .color()
+                .map_or(KValue::Null, |color| bevy_to_koto_color(color).into())
+        }
+
+        #[koto_method]
+        fn get_alpha(&self) -> KValue {
+            self.entity
+                .color()
+                .map_or(KValue::Null, |color| color.alpha().into())
+        }
+
+        #[koto_method]
+        fn set_alpha(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let alpha = match ctx.args {
+                [KValue::Number(n)] => n.into(),
+                _ => return runtime_error!("Shape.set_alpha: Expected a number"),
+            };
+
+            let this = ctx.instance()?;
+            this.entity.set_alpha_snapshot(alpha);
+            this.update_shape.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateColorMaterial::Alpha(alpha),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method(alias = "set_colour")]
+        fn set_color(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            use KValue::{Number, Object, Str};
+
+            let color = match ctx.args {
+                [Number(n1), Number(n2), Number(n3)] => {
+                    Color::srgba(f32::from(n1), f32::from(n2), f32::from(n3), 1.0)
+                }
+                [Number(n1), Number(n2), Number(n3), Number(n4)] => {
+                    Color::srgba(f32::from(n1), f32::from(n2), f32::from(n3), f32::from(n4))
+                }
+                [Object(o)] if o.is_a::<KotoColor>() => {
+                    koto_to_bevy_color(&*o.cast::<KotoColor>()?)
+                }
+                [Str(s)] => match parse_color(s) {
+                    Some(color) => color,
+                    None => return runtime_error!("Shape.set_color: Unknown color '{s}'"),
+                },
+                _ => {
+                    return runtime_error!(
+                        "Shape.set_color: Expected a Color, a color name or hex String, \
+                         or 3 or 4 numbers"
+                    );
+                }
+            };
+
+            let this = ctx.instance()?;
+            this.entity.set_color_snapshot(Some(color));
+            this.update_shape.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateColorMaterial::Color(color),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_alpha_mode(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            use KValue::{Number, Str};
+
+            let alpha_mode = match ctx.args {
+                [Str(mode)] => koto_to_alpha_mode("Shape.set_alpha_mode", mode, None)?,
+                [Str(mode), Number(cutoff)] => {
+                    koto_to_alpha_mode("Shape.set_alpha_mode", mode, Some(cutoff.into()))?
+                }
+                _ => {
+                    return runtime_error!(
+                        "Shape.set_alpha_mode: Expected 'opaque', 'blend', or 'mask', \
+                         with an optional cutoff for 'mask'"
+                    )
+                }
+            };
+
+            let this = ctx.instance()?;
+            this.update_shape.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateColorMaterial::AlphaMode(alpha_mode),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn fade_to(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            use KValue::{Number, Str};
+
+            let (alpha, seconds, easing) = match ctx.args {
+                [Number(alpha), Number(seconds)] => (alpha, seconds, None),
+                [Number(alpha), Number(seconds), Str(easing)] => (alpha, seconds, Some(easing)),
+                _ => {
+                    return runtime_error!(
+                        "Shape.fade_to: Expected an alpha value and a duration in seconds, \
+                         with an optional easing name"
+                    )
+                }
+            };
+            let easing = koto_to_easing("Shape.fade_to", easing)?;
+
+            let this = ctx.instance()?;
+            this.update_shape.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateColorMaterial::Tween(ColorTween::alpha(alpha.into(), seconds.into(), easing)),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method(alias = "colour_to")]
+        fn color_to(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            use KValue::{Number, Object, Str};
+
+            let (color, seconds, easing) = match ctx.args {
+                [color, Number(seconds)] => (color, seconds, None),
+                [color, Number(seconds), Str(easing)] => (color, seconds, Some(easing)),
+                _ => {
+                    return runtime_error!(
+                        "Shape.color_to: Expected a Color and a duration in seconds, \
+                         with an optional easing name"
+                    )
+                }
+            };
+            let color = match color {
+                Object(o) if o.is_a::<KotoColor>() => koto_to_bevy_color(&*o.cast::<KotoColor>()?),
+                Str(s) => match parse_color(s) {
+                    Some(color) => color,
+                    None => return runtime_error!("Shape.color_to: Unknown color '{s}'"),
+                },
+                unexpected => return runtime_error!(
+                    "Shape.color_to: Expected a Color, or a color name or hex String, found '{}'",
+                    unexpected.type_as_string()
+                ),
+            };
+            let easing = koto_to_easing("Shape.color_to", easing)?;
+
+            let this = ctx.instance()?;
+            this.update_shape.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateColorMaterial::Tween(ColorTween::color(color, seconds.into(), easing)),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_gradient(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            use KValue::{Number, Object};
+
+            let gradient = match ctx.args {
+                [Object(a), Object(b), Number(angle)]
+                    if a.is_a::<KotoColor>() && b.is_a::<KotoColor>() =>
+                {
+                    GradientMaterial::linear(
+                        koto_to_bevy_color(&*a.cast::<KotoColor>()?),
+                        koto_to_bevy_color(&*b.cast::<KotoColor>()?),
+                        angle.into(),
+                    )
+                }
+                [Object(gradient), Number(angle)] if gradient.is_a::<KotoGradient>() => {
+                    GradientMaterial::linear_from(&gradient.cast::<KotoGradient>()?.0, angle.into())
+                }
+                _ => {
+                    return runtime_error!(
+                        "Shape.set_gradient: Expected two Colors or a Gradient, \
+                         and an angle in radians"
+                    )
+                }
+            };
+
+            let this = ctx.instance()?;
+            this.update_shape.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateColorMaterial::Gradient(Box::new(gradient)),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_radial_gradient(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            use KValue::Object;
+
+            let gradient = match ctx.args {
+                [Object(a), Object(b)] if a.is_a::<KotoColor>() && b.is_a::<KotoColor>() => {
+                    GradientMaterial::radial(
+                        koto_to_bevy_color(&*a.cast::<KotoColor>()?),
+                        koto_to_bevy_color(&*b.cast::<KotoColor>()?),
+                    )
+                }
+                [Object(gradient)] if gradient.is_a::<KotoGradient>() => {
+                    GradientMaterial::radial_from(&gradient.cast::<KotoGradient>()?.0)
+                }
+                _ => {
+                    return runtime_error!(
+                        "Shape.set_radial_gradient: Expected two Colors or a Gradient"
+                    )
+                }
+            };
+
+            let this = ctx.instance()?;
+            this.update_shape.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateColorMaterial::Gradient(Box::new(gradient)),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_image(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let path = match ctx.args {
+                [KValue::Str(path)] => path,
+                _ => {
+                    return runtime_error!("Shape.set_image: Expected an image path as a string");
+                }
+            };
+
+            let this = ctx.instance()?;
+            this.update_shape.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateColorMaterial::SetImagePath(Some(path.to_string())),
+            ));
 
-        let position = match ctx.args {
-            [Number(x), Number(y)] => Vec3::new(x.into(), y.into(), 0.0),
-            [Number(x), Number(y), Number(z)] => Vec3::new(x.into(), y.into(), z.into()),
-            [Object(v)] if v.is_a::<KotoVec2>() => {
-                let v = v.cast::<KotoVec2>()?.inner();
-                Vec3::new(v.x as f32, v.y as f32, 0.0)
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn bounds(&self) -> KValue {
+            let bounds = self.transformed_bounds();
+            let (center, size) = (bounds.center(), bounds.size());
+            KotoRect::from_x_y_w_h(
+                center.x.into(),
+                center.y.into(),
+                size.x.into(),
+                size.y.into(),
+            )
+            .into()
+        }
+
+        #[koto_method]
+        fn overlaps(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let other = match ctx.args {
+                [KValue::Object(other)] if other.is_a::<KotoShape>() => {
+                    other.cast::<KotoShape>()?
+                }
+                _ => return runtime_error!("Shape.overlaps: Expected another Shape"),
+            };
+
+            let bounds = ctx.instance()?.transformed_bounds();
+            let other_bounds = other.transformed_bounds();
+            Ok((!bounds.intersect(other_bounds).is_empty()).into())
+        }
+
+        #[koto_method]
+        fn on_click(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            set_callback(ctx, "on_click")
+        }
+
+        #[koto_method]
+        fn on_hover_enter(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            set_callback(ctx, "on_hover_enter")
+        }
+
+        #[koto_method]
+        fn on_hover_exit(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            set_callback(ctx, "on_hover_exit")
+        }
+
+        #[koto_method]
+        fn on_collision(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            set_callback(ctx, "on_collision")
+        }
+
+        #[koto_method]
+        fn set_uv_offset(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let offset = match koto_to_points(ctx.args).as_deref() {
+                Some(&[offset]) => offset,
+                _ => {
+                    return runtime_error!(
+                        "Shape.set_uv_offset: Expected x and y offsets, or a Vec2"
+                    )
+                }
+            };
+
+            let mut this = ctx.instance_mut()?;
+            this.uv_transform.offset = offset;
+            this.update_shape.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateColorMaterial::UvTransform(this.uv_transform),
+            ));
+            drop(this);
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_uv_scale(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let scale = match ctx.args {
+                [KValue::Number(scale)] => Vec2::splat(scale.into()),
+                _ => match koto_to_points(ctx.args).as_deref() {
+                    Some(&[scale]) => scale,
+                    _ => {
+                        return runtime_error!(
+                            "Shape.set_uv_scale: Expected a Number, x and y scales, or a Vec2"
+                        )
+                    }
+                },
+            };
+
+            let mut this = ctx.instance_mut()?;
+            this.uv_transform.scale = scale;
+            this.update_shape.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateColorMaterial::UvTransform(this.uv_transform),
+            ));
+            drop(this);
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_points(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let Some(points) = with_packed_values(ctx.args, koto_to_points)
+                .flatten()
+                .filter(|points| points.len() > 1)
+            else {
+                return runtime_error!("Shape.set_points: Expected a list of two or more points");
+            };
+
+            let mut this = ctx.instance_mut()?;
+            let Shape::Line(_, width) = this.shape else {
+                return runtime_error!("Shape.set_points: Expected the shape to be a line");
+            };
+            this.shape = Shape::Line(points.as_slice().into(), width);
+            // The line's mesh is rebuilt, so any existing Mesh object is no longer valid
+            this.mesh = None;
+            this.update_mesh.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateShapeMesh::SetLinePoints(points),
+            ));
+            drop(this);
+
+            ctx.instance_result()
+        }
+
+        #[koto_method(alias = "set_vertex_colours")]
+        fn set_vertex_colors(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let colors = match ctx.args {
+                [KValue::Null] => None,
+                args => match with_packed_values(args, koto_to_colors) {
+                    Some(colors) => Some(colors?),
+                    None => {
+                        return runtime_error!(
+                            "Shape.set_vertex_colors: Expected a list of colors, or null"
+                        )
+                    }
+                },
+            };
+
+            let this = ctx.instance()?;
+            if let Some(colors) = &colors {
+                let vertex_count = make_mesh(&this.shape).count_vertices();
+                if colors.len() != vertex_count {
+                    return runtime_error!(
+                        "Shape.set_vertex_colors: Expected {vertex_count} colors, found {}",
+                        colors.len()
+                    );
+                }
             }
-            [Object(v), Number(z)] if v.is_a::<KotoVec2>() => {
-                let v = v.cast::<KotoVec2>()?.inner();
-                Vec3::new(v.x as f32, v.y as f32, z.into())
+            this.update_mesh.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateShapeMesh::SetVertexColors(colors),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn mesh(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let mut this = ctx.instance_mut()?;
+            if this.mesh.is_none() {
+                let original: Vec<_> = make_mesh(&this.shape)
+                    .attribute(Mesh::ATTRIBUTE_POSITION)
+                    .and_then(VertexAttributeValues::as_float3)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|[x, y, _]| Vec2::new(*x, *y))
+                    .collect();
+
+                this.mesh = Some(
+                    KotoMesh {
+                        entity: this.entity.clone(),
+                        vertices: original.clone(),
+                        original: original.into(),
+                        update_mesh: this.update_mesh.clone(),
+                    }
+                    .into(),
+                );
             }
-            _ => {
-                return runtime_error!(
-                    "Shape.set_position: Expected x, y, (and optionally z) positions"
-                )
+
+            Ok(this.mesh.clone().map_or(KValue::Null, KValue::from))
+        }
+
+        #[koto_method]
+        fn clone_entity(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let this = ctx.instance()?;
+
+            let entity = KotoEntityMapping::default();
+            let result: KObject = KotoShape {
+                entity: entity.clone(),
+                state: KValue::Null,
+                mesh: None,
+                ..this.clone()
             }
-        };
+            .into();
 
-        let this = ctx.instance()?;
-        this.update_transform
-            .send(KotoEntityEvent::<UpdateTransform>::new(
-                this.entity.clone(),
-                UpdateTransform::Position(position),
+            this.spawn_shape.send(SpawnShape {
+                koto_entity: KotoEntity::new(result.clone(), entity.clone()),
+                shape: this.shape.clone(),
+                options: ShapeOptions::default(),
+            });
+            this.update_shape.send(KotoEntityEvent::new(
+                entity.clone(),
+                UpdateColorMaterial::CopyFrom(this.entity.clone()),
             ));
+            entity.set_color_snapshot(this.entity.color());
+            if this.uv_transform != UvTransform::default() {
+                this.update_shape.send(KotoEntityEvent::new(
+                    entity.clone(),
+                    UpdateColorMaterial::UvTransform(this.uv_transform),
+                ));
+            }
+            send_transform_copy(&this.update_transform, &this.entity, &entity);
 
-        ctx.instance_result()
+            Ok(result.into())
+        }
     }
+}
 
-    #[koto_method]
-    fn set_rotation(ctx: MethodContext<Self>) -> KotoResult<KValue> {
-        let rotation = match ctx.args {
-            [KValue::Number(x)] => x.into(),
-            _ => return runtime_error!("Shape.set_rotation: Expected a Number in radians"),
+// Sets one of the shape's callbacks,
+// e.g. the pointer callbacks that are called by the KotoPickingPlugin.
+#[allow(clippy::result_large_err)]
+fn set_callback(ctx: MethodContext<KotoShape>, name: &'static str) -> KotoResult<KValue> {
+    let callback = match ctx.args {
+        [f] if f.is_callable() => Some((f.clone(), ctx.vm.spawn_shared_vm())),
+        [KValue::Null] => None,
+        _ => return runtime_error!("Shape.{name}: Expected a callable value, or null"),
+    };
+
+    let this = ctx.instance()?;
+    this.update_entity.send(KotoEntityEvent::new(
+        this.entity.clone(),
+        UpdateKotoEntity::SetCallback(name, callback),
+    ));
+
+    ctx.instance_result()
+}
+
+impl KotoShape {
+    // The axis-aligned bounds of the shape's mesh after applying the shape's transform
+    fn transformed_bounds(&self) -> Rect {
+        let local = match self.mesh.as_ref().map(KObject::cast::<KotoMesh>) {
+            // The mesh's vertices may have been modified by the script
+            Some(Ok(mesh)) => bounding_rect(&mesh.vertices),
+            _ => match make_mesh(&self.shape).compute_aabb() {
+                Some(aabb) => Rect::from_center_half_size(aabb.center.xy(), aabb.half_extents.xy()),
+                None => Rect::default(),
+            },
         };
 
-        let this = ctx.instance()?;
-        this.update_transform.send(KotoEntityEvent::new(
-            this.entity.clone(),
-            UpdateTransform::Rotation(rotation),
-        ));
+        let transform = self.entity.transform();
+        let corners = [
+            local.min,
+            Vec2::new(local.max.x, local.min.y),
+            local.max,
+            Vec2::new(local.min.x, local.max.y),
+        ]
+        .map(|corner| transform.transform_point(corner.extend(0.0)).xy());
+        bounding_rect(&corners)
+    }
+}
 
-        ctx.instance_result()
+fn bounding_rect(points: &[Vec2]) -> Rect {
+    let empty = Rect {
+        min: Vec2::INFINITY,
+        max: Vec2::NEG_INFINITY,
+    };
+    points
+        .iter()
+        .fold(empty, |rect, point| rect.union_point(*point))
+}
+
+// Gets an alpha mode from its name and an optional cutoff for masked alpha
+#[allow(clippy::result_large_err)]
+fn koto_to_alpha_mode(fn_name: &str, name: &str, cutoff: Option<f32>) -> KotoResult<AlphaMode2d> {
+    match (name, cutoff) {
+        ("opaque", None) => Ok(AlphaMode2d::Opaque),
+        ("blend", None) => Ok(AlphaMode2d::Blend),
+        ("mask", cutoff) => Ok(AlphaMode2d::Mask(cutoff.unwrap_or(0.5))),
+        ("opaque" | "blend", Some(_)) => {
+            runtime_error!("{fn_name}: A cutoff is only used with the 'mask' alpha mode")
+        }
+        _ => runtime_error!(
+            "{fn_name}: Unknown alpha mode '{name}', expected 'opaque', 'blend', or 'mask'"
+        ),
+    }
+}
+
+impl From<KotoShape> for KValue {
+    fn from(shape: KotoShape) -> Self {
+        KObject::from(shape).into()
     }
+}
+
+// The Mesh object that's returned from Shape.mesh
+#[derive(Clone, KotoType, KotoCopy)]
+#[koto(type_name = "Mesh")]
+struct KotoMesh {
+    entity: KotoEntityMapping,
+    // The current positions of the mesh's vertices
+    vertices: Vec<Vec2>,
+    // The positions of the vertices when the shape was spawned
+    original: Arc<[Vec2]>,
+    update_mesh: KotoEntitySender<UpdateShapeMesh>,
+}
+
+impl KotoObject for KotoMesh {}
 
+#[allow(clippy::result_large_err)]
+#[koto_impl]
+impl KotoMesh {
     #[koto_method]
-    fn set_size(ctx: MethodContext<Self>) -> KotoResult<KValue> {
-        use KValue::Number;
+    fn vertex_count(&self) -> KValue {
+        self.vertices.len().into()
+    }
 
-        let size = match ctx.args {
-            [Number(size)] => {
-                let size = f32::from(size);
-                Vec3::new(size, size, 0.0)
-            }
-            [Number(x), Number(y)] => Vec3::new(f32::from(x), f32::from(y), 0.0),
-            _ => return runtime_error!("Shape.set_size: Expected Numbers"),
+    #[koto_method]
+    fn vertices(&self) -> KValue {
+        KTuple::from(
+            self.vertices
+                .iter()
+                .map(|v| KotoVec2::new(v.x.into(), v.y.into()).into())
+                .collect::<Vec<_>>(),
+        )
+        .into()
+    }
+
+    #[koto_method]
+    fn set_vertices(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+        let Some(vertices) = with_packed_values(ctx.args, koto_to_points).flatten() else {
+            return runtime_error!("Mesh.set_vertices: Expected a list of numbers or Vec2s");
         };
 
-        let this = ctx.instance()?;
-        this.update_transform.send(KotoEntityEvent::new(
-            this.entity.clone(),
-            UpdateTransform::Scale(size),
-        ));
+        let mut this = ctx.instance_mut()?;
+        if vertices.len() != this.original.len() {
+            return runtime_error!(
+                "Mesh.set_vertices: Expected {} vertices, found {}",
+                this.original.len(),
+                vertices.len()
+            );
+        }
+        this.update_vertices(vertices);
+        drop(this);
 
         ctx.instance_result()
     }
 
     #[koto_method]
-    fn on_update(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+    fn displace(ctx: MethodContext<Self>) -> KotoResult<KValue> {
         let f = match ctx.args {
             [f] if f.is_callable() => f.clone(),
-            _ => return runtime_error!("Shape.on_update: Expected a callable value"),
+            _ => return runtime_error!("Mesh.displace: Expected a callable value"),
         };
 
-        let this = ctx.instance()?;
-        this.update_entity.send(KotoEntityEvent::new(
-            this.entity.clone(),
-            UpdateKotoEntity::SetOnUpdate(Some((f, ctx.vm.spawn_shared_vm()))),
-        ));
+        // The instance isn't borrowed while the function is called,
+        // so that the function is free to access the mesh.
+        let original = ctx.instance()?.original.clone();
+        let mut vm = ctx.vm.spawn_shared_vm();
+        let mut vertices = Vec::with_capacity(original.len());
+        for (i, v) in original.iter().enumerate() {
+            let position = KotoVec2::new(v.x.into(), v.y.into());
+            let offset = match vm.call_function(f.clone(), &[position.into(), i.into()])? {
+                KValue::Object(o) if o.is_a::<KotoVec2>() => o.cast::<KotoVec2>()?.inner(),
+                unexpected => {
+                    return unexpected_type("a Vec2 offset from the displace function", &unexpected)
+                }
+            };
+            vertices.push(*v + Vec2::new(offset.x as f32, offset.y as f32));
+        }
+
+        ctx.instance_mut()?.update_vertices(vertices);
 
         ctx.instance_result()
     }
 
     #[koto_method]
-    fn despawn(ctx: MethodContext<Self>) -> KotoResult<KValue> {
-        let this = ctx.instance()?;
-        this.update_entity.send(KotoEntityEvent::new(
-            this.entity.clone(),
-            UpdateKotoEntity::Despawn,
+    fn reset(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+        let mut this = ctx.instance_mut()?;
+        let original = this.original.to_vec();
+        this.update_vertices(original);
+        drop(this);
+
+        ctx.instance_result()
+    }
+}
+
+impl KotoMesh {
+    fn update_vertices(&mut self, vertices: Vec<Vec2>) {
+        self.vertices.clone_from(&vertices);
+        self.update_mesh.send(KotoEntityEvent::new(
+            self.entity.clone(),
+            UpdateShapeMesh::SetVertices(vertices),
         ));
+    }
+}
 
-        Ok(KValue::Null)
+impl From<KotoMesh> for KValue {
+    fn from(mesh: KotoMesh) -> Self {
+        KObject::from(mesh).into()
     }
 }
 
-impl From<KotoShape> for KValue {
-    fn from(shape: KotoShape) -> Self {
-        KObject::from(shape).into()
+// Gets a list of colors from Color objects, or from color names and hex strings
+#[allow(clippy::result_large_err)]
+fn koto_to_colors(values: &[KValue]) -> KotoResult<Vec<Color>> {
+    values
+        .iter()
+        .map(|value| match value {
+            KValue::Object(o) if o.is_a::<KotoColor>() => {
+                Ok(koto_to_bevy_color(&*o.cast::<KotoColor>()?))
+            }
+            KValue::Str(s) => match parse_color(s) {
+                Some(color) => Ok(color),
+                None => runtime_error!("Unknown color '{s}'"),
+            },
+            unexpected => unexpected_type("a Color", unexpected),
+        })
+        .collect()
+}
+
+// Calls the function with the contents of a list or tuple argument
+pub(crate) fn with_packed_values<T>(args: &[KValue], f: impl FnOnce(&[KValue]) -> T) -> Option<T> {
+    match args {
+        [KValue::List(list)] => Some(f(&list.data())),
+        [KValue::Tuple(tuple)] => Some(f(tuple)),
+        _ => None,
     }
 }
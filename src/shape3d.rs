@@ -0,0 +1,318 @@
+//! Support for adding and updating 3D shapes in Koto scripts
+
+use crate::{entity::koto_entity_impl, prelude::*};
+use bevy::prelude::*;
+use cloned::cloned;
+use koto::{derive::*, prelude::*, runtime::Result as KotoResult};
+
+/// Basic 3D shapes for bevy_koto
+///
+/// The plugin adds a `shape3d` module to the Koto prelude, with `cube`, `sphere`, `plane`, and
+/// `cylinder` functions that spawn entities with a [Mesh3d] and a [StandardMaterial], e.g.
+///
+/// ```koto
+/// ball = shape3d.sphere()
+/// ball.set_position 0, 1, 0
+/// ball.set_color 1, 0.5, 0
+/// ```
+///
+/// The shapes are 1 unit in size, with planes facing upwards along the Y axis. Positions,
+/// rotations, and sizes are set with x, y, and z values, and rotations are Euler angles in radians.
+///
+/// The plugin doesn't spawn a camera or any lights, so these need to be added by the application.
+pub struct KotoShape3dPlugin;
+
+impl Plugin for KotoShape3dPlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+        assert!(app.is_plugin_added::<KotoEntityPlugin>());
+        assert!(app.is_plugin_added::<KotoColorPlugin>());
+        assert!(app.is_plugin_added::<KotoGeometryPlugin>());
+
+        let (spawn_shape_sender, spawn_shape_receiver) = koto_channel::<SpawnShape3d>();
+        let (update_material_sender, update_material_receiver) =
+            koto_entity_channel::<UpdateStandardMaterial>();
+
+        app.insert_resource(spawn_shape_sender)
+            .insert_resource(spawn_shape_receiver)
+            .insert_resource(update_material_sender)
+            .insert_resource(update_material_receiver)
+            .add_systems(Startup, on_startup)
+            .add_systems(
+                KotoSchedule,
+                (
+                    sync_colors.in_set(KotoUpdate::PreUpdate),
+                    spawn_shapes.in_set(KotoUpdate::PostUpdate),
+                ),
+            )
+            .add_systems(Update, koto_to_bevy_standard_material_events);
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn on_startup(
+    koto: ResMut<KotoRuntime>,
+    spawn_shape: Res<KotoSender<SpawnShape3d>>,
+    update_material: Res<KotoEntitySender<UpdateStandardMaterial>>,
+    update_entity: Res<KotoEntitySender<UpdateKotoEntity>>,
+    update_transform: Res<KotoEntitySender<UpdateTransform>>,
+) {
+    let shape_module = KMap::with_type("shape3d");
+
+    let make_shape = {
+        cloned!(
+            spawn_shape,
+            update_entity,
+            update_material,
+            update_transform
+        );
+
+        move |shape: Shape3d| {
+            let entity = KotoEntityMapping::default();
+
+            let result: KObject = KotoShape3d {
+                entity: entity.clone(),
+                state: KValue::Null,
+                update_material: update_material.clone(),
+                update_entity: update_entity.clone(),
+                update_transform: update_transform.clone(),
+            }
+            .into();
+
+            spawn_shape.send(SpawnShape3d {
+                koto_entity: KotoEntity::new(result.clone(), entity),
+                shape,
+            });
+            Ok(result.into())
+        }
+    };
+
+    for (name, shape) in [
+        ("cube", Shape3d::Cube),
+        ("cylinder", Shape3d::Cylinder),
+        ("plane", Shape3d::Plane),
+        ("sphere", Shape3d::Sphere),
+    ] {
+        shape_module.add_fn(name, {
+            cloned!(make_shape);
+            move |ctx| match ctx.args() {
+                [] => make_shape(shape),
+                unexpected => unexpected_args("no arguments", unexpected),
+            }
+        });
+    }
+
+    koto.prelude().insert("shape3d", shape_module);
+}
+
+fn spawn_shapes(
+    channel: Res<KotoReceiver<SpawnShape3d>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut commands: Commands,
+) {
+    let _span = info_span!("koto_channel", channel = "SpawnShape3d").entered();
+    while let Some(SpawnShape3d {
+        mut koto_entity,
+        shape,
+    }) = channel.receive()
+    {
+        let mesh: Mesh = match shape {
+            Shape3d::Cube => Cuboid::default().into(),
+            Shape3d::Cylinder => Cylinder::default().into(),
+            Shape3d::Plane => Plane3d::default().into(),
+            Shape3d::Sphere => Sphere::default().into(),
+        };
+
+        let bevy_entity = commands
+            .spawn((
+                Mesh3d(meshes.add(mesh)),
+                MeshMaterial3d(materials.add(StandardMaterial::default())),
+                koto_entity.clone(),
+            ))
+            .id();
+        koto_entity.entity.assign_bevy_entity(bevy_entity);
+    }
+}
+
+// Keeps the entities' color snapshots in sync with the base colors of their materials
+fn sync_colors(
+    query: Query<(&KotoEntity, &MeshMaterial3d<StandardMaterial>)>,
+    materials: Res<Assets<StandardMaterial>>,
+) {
+    for (koto_entity, material) in &query {
+        let Some(material) = materials.get(material.id()) else {
+            continue;
+        };
+        if koto_entity.entity.color() != Some(material.base_color) {
+            koto_entity
+                .entity
+                .set_color_snapshot(Some(material.base_color));
+        }
+    }
+}
+
+fn koto_to_bevy_standard_material_events(
+    channel: Res<KotoEntityReceiver<UpdateStandardMaterial>>,
+    query: Query<&MeshMaterial3d<StandardMaterial>>,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let _span = info_span!("koto_channel", channel = "UpdateStandardMaterial").entered();
+    while let Some(event) = channel.receive() {
+        // The entity may have been despawned
+        let Some(material) = query
+            .get(event.entity.get())
+            .ok()
+            .and_then(|handle| materials.get_mut(handle.id()))
+        else {
+            continue;
+        };
+        match event.event {
+            UpdateStandardMaterial::Color(color) => material.base_color = color,
+            UpdateStandardMaterial::Alpha(alpha) => material.base_color.set_alpha(alpha),
+            UpdateStandardMaterial::SetImagePath(image_path) => {
+                material.base_color_texture = image_path.map(|path| asset_server.load(path));
+            }
+        }
+
+        // Blending is only enabled when needed, opaque materials are cheaper to render
+        material.alpha_mode = if material.base_color.alpha() < 1.0 {
+            AlphaMode::Blend
+        } else {
+            AlphaMode::Opaque
+        };
+    }
+}
+
+/// Event for updating properties of a `StandardMaterial` that was spawned by a Koto script
+#[derive(Clone, Event)]
+pub enum UpdateStandardMaterial {
+    /// Sets the material's base color
+    Color(Color),
+    /// Sets the alpha value of the material's base color
+    Alpha(f32),
+    /// Sets the material's image path
+    SetImagePath(Option<String>),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Shape3d {
+    Cube,
+    Cylinder,
+    Plane,
+    Sphere,
+}
+
+#[derive(Clone, Debug)]
+struct SpawnShape3d {
+    koto_entity: KotoEntity,
+    shape: Shape3d,
+}
+
+#[derive(Clone, KotoType, KotoCopy)]
+#[koto(type_name = "Shape3d")]
+struct KotoShape3d {
+    entity: KotoEntityMapping,
+    state: KValue,
+    update_material: KotoEntitySender<UpdateStandardMaterial>,
+    update_entity: KotoEntitySender<UpdateKotoEntity>,
+    update_transform: KotoEntitySender<UpdateTransform>,
+}
+
+impl KotoObject for KotoShape3d {}
+
+koto_entity_impl! {
+    impl KotoShape3d as "Shape3d" with [entity, layer, children, state, transform_3d] {
+        #[koto_method(alias = "get_colour")]
+        fn get_color(&self) -> KValue {
+            self.entity
+                .color()
+                .map_or(KValue::Null, |color| bevy_to_koto_color(color).into())
+        }
+
+        #[koto_method]
+        fn get_alpha(&self) -> KValue {
+            self.entity
+                .color()
+                .map_or(KValue::Null, |color| color.alpha().into())
+        }
+
+        #[koto_method]
+        fn set_alpha(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let alpha = match ctx.args {
+                [KValue::Number(n)] => n.into(),
+                _ => return runtime_error!("Shape3d.set_alpha: Expected a number"),
+            };
+
+            let this = ctx.instance()?;
+            this.entity.set_alpha_snapshot(alpha);
+            this.update_material.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateStandardMaterial::Alpha(alpha),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method(alias = "set_colour")]
+        fn set_color(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            use KValue::{Number, Object, Str};
+
+            let color = match ctx.args {
+                [Number(n1), Number(n2), Number(n3)] => {
+                    Color::srgba(f32::from(n1), f32::from(n2), f32::from(n3), 1.0)
+                }
+                [Number(n1), Number(n2), Number(n3), Number(n4)] => {
+                    Color::srgba(f32::from(n1), f32::from(n2), f32::from(n3), f32::from(n4))
+                }
+                [Object(o)] if o.is_a::<KotoColor>() => {
+                    koto_to_bevy_color(&*o.cast::<KotoColor>()?)
+                }
+                [Str(s)] => match parse_color(s) {
+                    Some(color) => color,
+                    None => return runtime_error!("Shape3d.set_color: Unknown color '{s}'"),
+                },
+                _ => {
+                    return runtime_error!(
+                        "Shape3d.set_color: Expected a Color, a color name or hex String, \
+                         or 3 or 4 numbers"
+                    );
+                }
+            };
+
+            let this = ctx.instance()?;
+            this.entity.set_color_snapshot(Some(color));
+            this.update_material.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateStandardMaterial::Color(color),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_image(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let path = match ctx.args {
+                [KValue::Str(path)] => path,
+                _ => {
+                    return runtime_error!("Shape3d.set_image: Expected an image path as a string");
+                }
+            };
+
+            let this = ctx.instance()?;
+            this.update_material.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateStandardMaterial::SetImagePath(Some(path.to_string())),
+            ));
+
+            ctx.instance_result()
+        }
+    }
+}
+
+impl From<KotoShape3d> for KValue {
+    fn from(shape: KotoShape3d) -> Self {
+        KObject::from(shape).into()
+    }
+}
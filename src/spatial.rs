@@ -0,0 +1,238 @@
+//! Spatial queries for Koto entities
+
+use crate::{
+    entity::{despawn_unused_koto_entities, update_koto_entities},
+    prelude::*,
+};
+use bevy::{prelude::*, utils::HashMap};
+use koto::{prelude::*, runtime::Result as KotoResult};
+use parking_lot::RwLock;
+use std::sync::Arc;
+
+/// Spatial queries for Koto entities
+///
+/// The plugin maintains a spatial hash of the positions of Koto entities, and adds the following
+/// functions to the `entities` module in Koto's prelude:
+/// - `entities.within_radius(position, radius)`: Returns a tuple containing the entities that are
+///   within the given distance of the position, sorted by distance.
+/// - `entities.in_rect(min, max)`: Returns a tuple containing the entities whose positions are
+///   inside the given rectangle.
+///
+/// Positions can be provided as `geometry.vec2` values or as separate `x` and `y` numbers, e.g.
+///
+/// ```koto
+/// export update = |state|
+///   for boid in state.boids
+///     neighbours = entities.within_radius boid.get_position(), 0.2
+/// ```
+///
+/// The spatial hash is rebuilt during [KotoUpdate::PostUpdate] before the entities' `on_update`
+/// functions are called, using the entities' [GlobalTransform]s from the end of the previous
+/// frame. Entities that have been spawned during the current frame won't be included in the
+/// results until the following frame.
+pub struct KotoSpatialPlugin {
+    /// The size of the cells in the spatial hash
+    ///
+    /// For the best performance the cell size should be similar to the radius of typical queries.
+    pub cell_size: f32,
+}
+
+impl Default for KotoSpatialPlugin {
+    fn default() -> Self {
+        Self { cell_size: 0.25 }
+    }
+}
+
+impl KotoSpatialPlugin {
+    /// Sets the size of the cells in the spatial hash
+    #[must_use]
+    pub fn with_cell_size(mut self, cell_size: f32) -> Self {
+        self.cell_size = cell_size;
+        self
+    }
+}
+
+impl Plugin for KotoSpatialPlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+        assert!(app.is_plugin_added::<KotoEntityPlugin>());
+        assert!(app.is_plugin_added::<KotoGeometryPlugin>());
+        assert!(self.cell_size > 0.0);
+
+        let index = SpatialIndex::new(self.cell_size);
+
+        let koto = app.world().resource::<KotoRuntime>();
+        match koto.prelude().get("entities") {
+            Some(KValue::Map(entities)) => add_spatial_functions(&entities, &index),
+            _ => panic!("Missing entities module in the Koto prelude"),
+        }
+
+        app.insert_resource(index).add_systems(
+            KotoSchedule,
+            (
+                // The index holds references to the entities' Koto objects,
+                // so it needs to be cleared before checking which entities are no longer in use.
+                clear_spatial_index.before(despawn_unused_koto_entities),
+                update_spatial_index
+                    .after(despawn_unused_koto_entities)
+                    .before(update_koto_entities),
+            )
+                .in_set(KotoUpdate::PostUpdate),
+        );
+    }
+}
+
+fn clear_spatial_index(index: Res<SpatialIndex>) {
+    index.0.write().clear();
+}
+
+fn update_spatial_index(index: Res<SpatialIndex>, query: Query<(&KotoEntity, &GlobalTransform)>) {
+    let mut index = index.0.write();
+    for (koto_entity, transform) in &query {
+        if koto_entity.is_active {
+            index.insert(transform.translation().xy(), koto_entity.object.clone());
+        }
+    }
+}
+
+// The spatial hash, shared between the plugin's systems and the Koto functions
+#[derive(Resource, Clone)]
+struct SpatialIndex(Arc<RwLock<SpatialHash>>);
+
+impl SpatialIndex {
+    fn new(cell_size: f32) -> Self {
+        Self(Arc::new(RwLock::new(SpatialHash {
+            cell_size,
+            cells: HashMap::default(),
+        })))
+    }
+}
+
+struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<(Vec2, KObject)>>,
+}
+
+impl SpatialHash {
+    fn cell(&self, position: Vec2) -> IVec2 {
+        (position / self.cell_size).floor().as_ivec2()
+    }
+
+    fn clear(&mut self) {
+        // Cells that were in use are kept to avoid reallocating them when the index is rebuilt
+        self.cells.retain(|_, entries| {
+            let is_in_use = !entries.is_empty();
+            entries.clear();
+            is_in_use
+        });
+    }
+
+    fn insert(&mut self, position: Vec2, object: KObject) {
+        if position.is_finite() {
+            let cell = self.cell(position);
+            self.cells.entry(cell).or_default().push((position, object));
+        }
+    }
+
+    // Calls the visitor with each entry that's inside the rectangle
+    fn visit_rect(&self, min: Vec2, max: Vec2, mut visitor: impl FnMut(Vec2, &KObject)) {
+        let mut visit_entries = |entries: &Vec<(Vec2, KObject)>| {
+            for (position, object) in entries {
+                if position.cmpge(min).all() && position.cmple(max).all() {
+                    visitor(*position, object);
+                }
+            }
+        };
+
+        let min_cell = self.cell(min);
+        let max_cell = self.cell(max);
+        let cell_count = (max_cell.as_dvec2() - min_cell.as_dvec2() + 1.0).element_product();
+
+        // Large rectangles are checked against every entry rather than visiting each cell
+        if cell_count > self.cells.len() as f64 {
+            self.cells.values().for_each(visit_entries);
+        } else {
+            for y in min_cell.y..=max_cell.y {
+                for x in min_cell.x..=max_cell.x {
+                    if let Some(entries) = self.cells.get(&IVec2::new(x, y)) {
+                        visit_entries(entries);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn add_spatial_functions(entities: &KMap, index: &SpatialIndex) {
+    entities.add_fn("within_radius", {
+        let index = index.clone();
+        move |ctx| {
+            let (position, radius) = match ctx.args() {
+                [KValue::Object(position), KValue::Number(radius)] => {
+                    (koto_to_position(position)?, f32::from(radius))
+                }
+                [KValue::Number(x), KValue::Number(y), KValue::Number(radius)] => {
+                    (Vec2::new(x.into(), y.into()), f32::from(radius))
+                }
+                unexpected => return unexpected_args("a position and a radius", unexpected),
+            };
+
+            let mut result = Vec::new();
+            index.0.read().visit_rect(
+                position - radius,
+                position + radius,
+                |entry_position, object| {
+                    let distance = entry_position.distance(position);
+                    if distance <= radius {
+                        result.push((distance, object.clone()));
+                    }
+                },
+            );
+            result.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+            Ok(KTuple::from(
+                result
+                    .into_iter()
+                    .map(|(_, object)| KValue::from(object))
+                    .collect::<Vec<_>>(),
+            )
+            .into())
+        }
+    });
+
+    entities.add_fn("in_rect", {
+        let index = index.clone();
+        move |ctx| {
+            let (a, b) = match ctx.args() {
+                [KValue::Object(a), KValue::Object(b)] => {
+                    (koto_to_position(a)?, koto_to_position(b)?)
+                }
+                [KValue::Number(x1), KValue::Number(y1), KValue::Number(x2), KValue::Number(y2)] => (
+                    Vec2::new(x1.into(), y1.into()),
+                    Vec2::new(x2.into(), y2.into()),
+                ),
+                unexpected => return unexpected_args("min and max positions", unexpected),
+            };
+
+            let mut result = Vec::new();
+            index
+                .0
+                .read()
+                .visit_rect(a.min(b), a.max(b), |_, object| result.push(object.clone().into()));
+
+            Ok(KTuple::from(result).into())
+        }
+    });
+}
+
+#[allow(clippy::result_large_err)]
+fn koto_to_position(object: &KObject) -> KotoResult<Vec2> {
+    match object.cast::<KotoVec2>() {
+        Ok(v) => {
+            let v = v.inner();
+            Ok(Vec2::new(v.x as f32, v.y as f32))
+        }
+        Err(_) => unexpected_type("a Vec2", &object.clone().into()),
+    }
+}
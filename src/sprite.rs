@@ -0,0 +1,507 @@
+//! Support for adding and updating sprites in Koto scripts
+
+use crate::{entity::koto_entity_impl, prelude::*};
+use bevy::prelude::*;
+use cloned::cloned;
+use koto::{derive::*, prelude::*, runtime::Result as KotoResult};
+
+/// Image-backed sprites for bevy_koto
+///
+/// The plugin adds a `sprite` module to Koto's prelude. Calling `sprite` with an asset path
+/// spawns a [Sprite] using the image, e.g.
+///
+/// ```koto
+/// player = sprite 'images/player.png'
+/// player.set_position 0, -1
+/// ```
+///
+/// Sprites are one unit high, with their width matching the aspect ratio of the image once it's
+/// been loaded. The returned `Sprite` object supports the same transform, color, and entity
+/// methods as the objects returned by the `shape` module.
+///
+/// Sprite sheets can be used with `sprite.from_atlas(path, tile_size, columns, rows)`, where the
+/// tile size is either a Number for square tiles or a `geometry.vec2`. The sprite's frames can
+/// then be animated with `play(first, last, fps, looping)`, or shown directly with
+/// `set_frame(index)`, e.g.
+///
+/// ```koto
+/// runner = sprite.from_atlas 'images/runner.png', 64, 8, 2
+/// runner.play 0, 7, 12, true
+/// ```
+///
+/// Animations are advanced using [KotoTime], so they pause along with the script.
+pub struct KotoSpritePlugin;
+
+impl Plugin for KotoSpritePlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+        assert!(app.is_plugin_added::<KotoEntityPlugin>());
+        assert!(app.is_plugin_added::<KotoColorPlugin>());
+        assert!(app.is_plugin_added::<KotoGeometryPlugin>());
+
+        let (spawn_sprite_sender, spawn_sprite_receiver) = koto_channel::<SpawnSprite>();
+        let (update_sprite_sender, update_sprite_receiver) = koto_entity_channel::<UpdateSprite>();
+
+        app.insert_resource(spawn_sprite_sender)
+            .insert_resource(spawn_sprite_receiver)
+            .insert_resource(update_sprite_sender)
+            .insert_resource(update_sprite_receiver)
+            .add_systems(Startup, on_startup)
+            .add_systems(KotoSchedule, spawn_sprites.in_set(KotoUpdate::PostUpdate))
+            .add_systems(
+                Update,
+                (
+                    koto_to_bevy_sprite_events,
+                    animate_sprites,
+                    update_sprite_sizes,
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn on_startup(
+    koto: ResMut<KotoRuntime>,
+    spawn_sprite: Res<KotoSender<SpawnSprite>>,
+    update_sprite: Res<KotoEntitySender<UpdateSprite>>,
+    update_entity: Res<KotoEntitySender<UpdateKotoEntity>>,
+    update_transform: Res<KotoEntitySender<UpdateTransform>>,
+) {
+    let make_sprite = {
+        cloned!(spawn_sprite, update_sprite, update_entity, update_transform);
+
+        move |image_path: String, atlas: Option<SpriteAtlas>| {
+            let entity = KotoEntityMapping::default();
+
+            let result: KObject = KotoSprite {
+                entity: entity.clone(),
+                state: KValue::Null,
+                frame_count: atlas.map(|atlas| atlas.columns * atlas.rows),
+                update_sprite: update_sprite.clone(),
+                update_entity: update_entity.clone(),
+                update_transform: update_transform.clone(),
+            }
+            .into();
+
+            spawn_sprite.send(SpawnSprite {
+                koto_entity: KotoEntity::new(result.clone(), entity),
+                image_path,
+                atlas,
+            });
+
+            Ok(result.into())
+        }
+    };
+
+    let mut sprite_module = KMap::with_type("sprite");
+
+    sprite_module.insert_meta(
+        MetaKey::Call,
+        KValue::NativeFunction(KNativeFunction::new({
+            cloned!(make_sprite);
+            move |ctx| match ctx.args() {
+                [KValue::Str(path)] => make_sprite(path.to_string(), None),
+                unexpected => unexpected_args("an image path as a string", unexpected),
+            }
+        })),
+    );
+
+    sprite_module.add_fn("from_atlas", {
+        cloned!(make_sprite);
+        move |ctx| {
+            use KValue::{Number, Object, Str};
+
+            let (path, tile_size, columns, rows) = match ctx.args() {
+                [Str(path), Number(size), Number(columns), Number(rows)] => {
+                    let size = f32::from(size);
+                    (path, Vec2::splat(size), columns, rows)
+                }
+                [Str(path), Object(size), Number(columns), Number(rows)]
+                    if size.is_a::<KotoVec2>() =>
+                {
+                    let size = size.cast::<KotoVec2>()?.inner();
+                    (path, Vec2::new(size.x as f32, size.y as f32), columns, rows)
+                }
+                unexpected => {
+                    return unexpected_args(
+                        "an image path, a tile size, and the number of columns and rows",
+                        unexpected,
+                    )
+                }
+            };
+
+            let (Ok(columns @ 1..), Ok(rows @ 1..)) = (
+                u32::try_from(i64::from(columns)),
+                u32::try_from(i64::from(rows)),
+            ) else {
+                return runtime_error!("sprite.from_atlas: Invalid atlas size {columns}x{rows}");
+            };
+            if tile_size.cmplt(Vec2::ONE).any() {
+                return runtime_error!("sprite.from_atlas: Invalid tile size {tile_size}");
+            }
+
+            make_sprite(
+                path.to_string(),
+                Some(SpriteAtlas {
+                    tile_size: tile_size.as_uvec2(),
+                    columns,
+                    rows,
+                }),
+            )
+        }
+    });
+
+    koto.prelude().insert("sprite", sprite_module);
+}
+
+fn spawn_sprites(
+    channel: Res<KotoReceiver<SpawnSprite>>,
+    asset_server: Res<AssetServer>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut commands: Commands,
+) {
+    let _span = info_span!("koto_channel", channel = "SpawnSprite").entered();
+    while let Some(SpawnSprite {
+        mut koto_entity,
+        image_path,
+        atlas,
+    }) = channel.receive()
+    {
+        let texture_atlas = atlas.map(|atlas| TextureAtlas {
+            layout: layouts.add(TextureAtlasLayout::from_grid(
+                atlas.tile_size,
+                atlas.columns,
+                atlas.rows,
+                None,
+                None,
+            )),
+            index: 0,
+        });
+
+        let bevy_entity = commands
+            .spawn((
+                Sprite {
+                    image: asset_server.load(image_path),
+                    texture_atlas,
+                    custom_size: Some(Vec2::ONE),
+                    ..default()
+                },
+                SpriteNeedsResize,
+                koto_entity.clone(),
+            ))
+            .id();
+        koto_entity.entity.assign_bevy_entity(bevy_entity);
+    }
+}
+
+fn koto_to_bevy_sprite_events(
+    channel: Res<KotoEntityReceiver<UpdateSprite>>,
+    mut query: Query<&mut Sprite>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let _span = info_span!("koto_channel", channel = "UpdateSprite").entered();
+    while let Some(event) = channel.receive() {
+        let entity = event.entity.get();
+        // The entity may have been despawned
+        let Ok(mut sprite) = query.get_mut(entity) else {
+            continue;
+        };
+        match event.event {
+            UpdateSprite::Color(color) => sprite.color = color,
+            UpdateSprite::Alpha(alpha) => {
+                sprite.color.set_alpha(alpha);
+            }
+            UpdateSprite::SetImagePath(image_path) => {
+                sprite.image = asset_server.load(image_path);
+                commands.entity(entity).insert(SpriteNeedsResize);
+            }
+            UpdateSprite::SetFrame(index) => {
+                if let Some(atlas) = sprite.texture_atlas.as_mut() {
+                    atlas.index = index;
+                }
+                commands.entity(entity).remove::<SpriteAnimation>();
+            }
+            UpdateSprite::Play(animation) => {
+                if let Some(atlas) = sprite.texture_atlas.as_mut() {
+                    atlas.index = animation.first;
+                }
+                commands.entity(entity).insert(animation);
+            }
+        }
+    }
+}
+
+fn animate_sprites(
+    mut query: Query<(Entity, &mut Sprite, &mut SpriteAnimation)>,
+    koto_time: Res<KotoTime>,
+    mut commands: Commands,
+) {
+    for (entity, mut sprite, mut animation) in query.iter_mut() {
+        let Some(atlas) = sprite.texture_atlas.as_mut() else {
+            continue;
+        };
+
+        animation.elapsed += koto_time.delta();
+        while animation.elapsed >= animation.frame_duration {
+            animation.elapsed -= animation.frame_duration;
+
+            if atlas.index < animation.last {
+                atlas.index += 1;
+            } else if animation.looping {
+                atlas.index = animation.first;
+            } else {
+                // The animation has finished, leaving the sprite on its last frame
+                commands.entity(entity).remove::<SpriteAnimation>();
+                break;
+            }
+        }
+    }
+}
+
+// Sprites are given a height of 1, with the width matching the aspect ratio of the image,
+// or of the atlas tiles for sprites that use an atlas.
+fn update_sprite_sizes(
+    mut query: Query<(Entity, &mut Sprite), With<SpriteNeedsResize>>,
+    images: Res<Assets<Image>>,
+    layouts: Res<Assets<TextureAtlasLayout>>,
+    mut commands: Commands,
+) {
+    for (entity, mut sprite) in query.iter_mut() {
+        let size = match &sprite.texture_atlas {
+            Some(atlas) => layouts
+                .get(&atlas.layout)
+                .and_then(|layout| layout.textures.first())
+                .map(|tile| tile.size().as_vec2()),
+            None => images.get(&sprite.image).map(Image::size_f32),
+        };
+        let Some(size) = size else {
+            continue;
+        };
+        if size.y > 0.0 {
+            sprite.custom_size = Some(Vec2::new(size.x / size.y, 1.0));
+        }
+        commands.entity(entity).remove::<SpriteNeedsResize>();
+    }
+}
+
+/// Event for updating properties of a [Sprite] that was spawned by a Koto script
+#[derive(Clone, Event)]
+pub enum UpdateSprite {
+    /// Sets the sprite's color
+    Color(Color),
+    /// Sets the sprite's alpha value
+    Alpha(f32),
+    /// Sets the path of the sprite's image
+    SetImagePath(String),
+    /// Shows a frame from the sprite's texture atlas, stopping any running animation
+    SetFrame(usize),
+    /// Starts animating the sprite's frames
+    Play(SpriteAnimation),
+}
+
+/// An animation that's playing through the frames of a sprite's texture atlas
+#[derive(Clone, Component, Debug)]
+pub struct SpriteAnimation {
+    /// The index of the animation's first frame
+    pub first: usize,
+    /// The index of the animation's last frame
+    pub last: usize,
+    /// The duration of each frame in seconds
+    pub frame_duration: f64,
+    /// True if the animation should restart after the last frame has been shown
+    pub looping: bool,
+    /// The time in seconds since the current frame was shown
+    pub elapsed: f64,
+}
+
+// Added to sprites that need to be resized once their image has been loaded
+#[derive(Component)]
+struct SpriteNeedsResize;
+
+#[derive(Clone, Copy, Debug)]
+struct SpriteAtlas {
+    tile_size: UVec2,
+    columns: u32,
+    rows: u32,
+}
+
+#[derive(Clone, Debug)]
+struct SpawnSprite {
+    koto_entity: KotoEntity,
+    image_path: String,
+    atlas: Option<SpriteAtlas>,
+}
+
+#[derive(Clone, KotoType, KotoCopy)]
+#[koto(type_name = "Sprite")]
+struct KotoSprite {
+    entity: KotoEntityMapping,
+    state: KValue,
+    // The number of frames in the sprite's atlas
+    frame_count: Option<u32>,
+    update_sprite: KotoEntitySender<UpdateSprite>,
+    update_entity: KotoEntitySender<UpdateKotoEntity>,
+    update_transform: KotoEntitySender<UpdateTransform>,
+}
+
+impl KotoObject for KotoSprite {}
+
+koto_entity_impl! {
+    impl KotoSprite as "Sprite" with [
+        entity, layer, children, state, transform_2d, z_index, size_2d,
+    ] {
+        #[koto_method]
+        fn set_alpha(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let alpha = match ctx.args {
+                [KValue::Number(n)] => n.into(),
+                _ => return runtime_error!("Sprite.set_alpha: Expected a number"),
+            };
+
+            let this = ctx.instance()?;
+            this.update_sprite.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateSprite::Alpha(alpha),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method(alias = "set_colour")]
+        fn set_color(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            use KValue::{Number, Object, Str};
+
+            let color = match ctx.args {
+                [Number(n1), Number(n2), Number(n3)] => {
+                    Color::srgba(f32::from(n1), f32::from(n2), f32::from(n3), 1.0)
+                }
+                [Number(n1), Number(n2), Number(n3), Number(n4)] => {
+                    Color::srgba(f32::from(n1), f32::from(n2), f32::from(n3), f32::from(n4))
+                }
+                [Object(o)] if o.is_a::<KotoColor>() => {
+                    koto_to_bevy_color(&*o.cast::<KotoColor>()?)
+                }
+                [Str(s)] => match parse_color(s) {
+                    Some(color) => color,
+                    None => return runtime_error!("Sprite.set_color: Unknown color '{s}'"),
+                },
+                _ => {
+                    return runtime_error!(
+                        "Sprite.set_color: Expected a Color, a color name or hex String, \
+                         or 3 or 4 numbers"
+                    );
+                }
+            };
+
+            let this = ctx.instance()?;
+            this.update_sprite.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateSprite::Color(color),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_image(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let path = match ctx.args {
+                [KValue::Str(path)] => path,
+                _ => {
+                    return runtime_error!("Sprite.set_image: Expected an image path as a string");
+                }
+            };
+
+            let this = ctx.instance()?;
+            this.update_sprite.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateSprite::SetImagePath(path.to_string()),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_frame(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let index = match ctx.args {
+                [KValue::Number(n)] => i64::from(n),
+                _ => return runtime_error!("Sprite.set_frame: Expected a frame index"),
+            };
+
+            let this = ctx.instance()?;
+            let index = this.frame_index(index, "set_frame")?;
+            this.update_sprite.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateSprite::SetFrame(index),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn play(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            use KValue::{Bool, Number};
+
+            let (first, last, fps, looping) = match ctx.args {
+                [Number(first), Number(last), Number(fps)] => (first, last, fps, true),
+                [Number(first), Number(last), Number(fps), Bool(looping)] => {
+                    (first, last, fps, *looping)
+                }
+                _ => {
+                    return runtime_error!(
+                        "Sprite.play: Expected first and last frames, a frame rate, \
+                         and optionally a looping flag"
+                    )
+                }
+            };
+
+            let this = ctx.instance()?;
+            let first = this.frame_index(first.into(), "play")?;
+            let last = this.frame_index(last.into(), "play")?;
+            let fps = f64::from(fps);
+            if first > last {
+                return runtime_error!(
+                    "Sprite.play: The first frame must come before the last frame"
+                );
+            }
+            if fps <= 0.0 {
+                return runtime_error!("Sprite.play: Invalid frame rate '{fps}'");
+            }
+
+            this.update_sprite.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateSprite::Play(SpriteAnimation {
+                    first,
+                    last,
+                    frame_duration: 1.0 / fps,
+                    looping,
+                    elapsed: 0.0,
+                }),
+            ));
+
+            ctx.instance_result()
+        }
+    }
+}
+
+impl KotoSprite {
+    // Checks that the index refers to a frame in the sprite's atlas
+    #[allow(clippy::result_large_err)]
+    fn frame_index(&self, index: i64, method: &str) -> KotoResult<usize> {
+        let Some(frame_count) = self.frame_count else {
+            return runtime_error!("Sprite.{method}: The sprite doesn't have a texture atlas");
+        };
+        match usize::try_from(index) {
+            Ok(index) if index < frame_count as usize => Ok(index),
+            _ => runtime_error!(
+                "Sprite.{method}: Frame {index} is out of range, the atlas has {frame_count} frames"
+            ),
+        }
+    }
+}
+
+impl From<KotoSprite> for KValue {
+    fn from(sprite: KotoSprite) -> Self {
+        KObject::from(sprite).into()
+    }
+}
@@ -0,0 +1,240 @@
+//! Coroutine tasks that are resumed by the runtime each frame
+
+use crate::prelude::*;
+use bevy::prelude::*;
+use koto::{bytecode::Compiler, derive::*, prelude::*};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Adds a `tasks` module to Koto's prelude, for running sequences of actions over multiple frames
+///
+/// The module contains the following functions:
+/// - `tasks.spawn(f)`: Starts a new task, where `f` is a generator function (i.e. a function that
+///   contains `yield`), or an iterator. Returns a `Task` object with `is_done()` and `cancel()`
+///   methods.
+/// - `tasks.wait(seconds)`: Returns a value that pauses the task for the given duration when it
+///   gets yielded.
+///
+/// Each task is resumed once per frame after the script's `update` function has been called,
+/// running until its next `yield`. Yielding the result of `tasks.wait` pauses the task until the
+/// given amount of [KotoTime] has passed, with any other yielded value resuming the task on the
+/// next frame, e.g.
+///
+/// ```koto
+/// tasks.spawn ||
+///   state.color = 'red'
+///   yield tasks.wait 0.5
+///   state.color = 'blue'
+/// ```
+///
+/// Running tasks are cancelled when a script is loaded or reloaded. Errors that occur while
+/// running a task are sent as [KotoScriptError] events, and the task is stopped.
+pub struct KotoTasksPlugin;
+
+impl Plugin for KotoTasksPlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+
+        let (spawn_task_sender, spawn_task_receiver) = koto_channel::<SpawnTask>();
+
+        let koto = app.world().resource::<KotoRuntime>();
+        let call_function = make_call_function(koto);
+        koto.prelude()
+            .insert("tasks", make_tasks_module(spawn_task_sender, call_function));
+
+        app.insert_resource(spawn_task_receiver)
+            .init_resource::<KotoTasks>()
+            .add_systems(
+                KotoSchedule,
+                (
+                    add_spawned_tasks.in_set(KotoUpdate::PreUpdate),
+                    resume_tasks
+                        .after(KotoUpdate::Update)
+                        .before(KotoUpdate::PostUpdate),
+                ),
+            );
+    }
+}
+
+struct SpawnTask {
+    iterator: KIterator,
+    status: Arc<TaskStatus>,
+}
+
+// The tasks that are currently running
+#[derive(Resource, Default)]
+struct KotoTasks(Vec<RunningTask>);
+
+struct RunningTask {
+    iterator: KIterator,
+    status: Arc<TaskStatus>,
+    // The remaining time in seconds before the task should be resumed
+    wait: f64,
+}
+
+// Shared between a running task and its Task objects
+#[derive(Default)]
+struct TaskStatus {
+    is_done: AtomicBool,
+    is_cancelled: AtomicBool,
+}
+
+// The Task object that's returned from tasks.spawn
+#[derive(Clone, KotoType, KotoCopy)]
+#[koto(type_name = "Task")]
+struct Task {
+    status: Arc<TaskStatus>,
+}
+
+impl KotoObject for Task {}
+
+#[koto_impl]
+impl Task {
+    #[koto_method]
+    fn is_done(&self) -> KValue {
+        self.status.is_done.load(Ordering::Relaxed).into()
+    }
+
+    #[koto_method]
+    fn cancel(&self) -> KValue {
+        self.status.is_cancelled.store(true, Ordering::Relaxed);
+        KValue::Null
+    }
+}
+
+// The value that's returned from tasks.wait
+#[derive(Clone, KotoType, KotoCopy)]
+#[koto(type_name = "Wait")]
+struct Wait {
+    seconds: f64,
+}
+
+impl KotoObject for Wait {}
+
+#[koto_impl]
+impl Wait {
+    #[koto_method]
+    fn seconds(&self) -> KValue {
+        self.seconds.into()
+    }
+}
+
+// Generator functions can't be called directly from Rust,
+// so they're called via a Koto function that calls its argument.
+fn make_call_function(koto: &KotoRuntime) -> KValue {
+    let chunk = Compiler::compile("|f| f()", None, default()).expect("Failed to compile");
+    koto.spawn_vm().run(chunk).expect("Failed to run")
+}
+
+#[allow(clippy::result_large_err)]
+fn make_tasks_module(spawn_task: KotoSender<SpawnTask>, call_function: KValue) -> KMap {
+    let module = KMap::with_type("tasks");
+
+    module.add_fn("spawn", move |ctx| {
+        let task = match ctx.args() {
+            [task @ KValue::Function(_)] => {
+                let task = task.clone();
+                ctx.vm.call_function(call_function.clone(), &[task])?
+            }
+            [task @ KValue::Iterator(_)] => task.clone(),
+            unexpected => return unexpected_args("a generator function or iterator", unexpected),
+        };
+
+        let KValue::Iterator(iterator) = task else {
+            return unexpected_type("the result of a generator function", &task);
+        };
+
+        let status = Arc::new(TaskStatus::default());
+        spawn_task.send(SpawnTask {
+            iterator,
+            status: status.clone(),
+        });
+
+        Ok(KObject::from(Task { status }).into())
+    });
+
+    module.add_fn("wait", |ctx| match ctx.args() {
+        [KValue::Number(seconds)] => Ok(KObject::from(Wait {
+            seconds: seconds.into(),
+        })
+        .into()),
+        unexpected => unexpected_args("a Number", unexpected),
+    });
+
+    module
+}
+
+fn add_spawned_tasks(
+    channel: Res<KotoReceiver<SpawnTask>>,
+    mut tasks: ResMut<KotoTasks>,
+    mut script_ready_events: EventReader<ScriptReady>,
+) {
+    // Tasks spawned by the previous script are cancelled when a script is loaded,
+    // with any tasks spawned by the new script then being received from the channel.
+    if script_ready_events.read().count() > 0 {
+        for task in tasks.0.drain(..) {
+            task.status.is_cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    let _span = info_span!("koto_channel", channel = "SpawnTask").entered();
+    while let Some(SpawnTask { iterator, status }) = channel.receive() {
+        tasks.0.push(RunningTask {
+            iterator,
+            status,
+            wait: 0.0,
+        });
+    }
+}
+
+fn resume_tasks(
+    koto: Res<KotoRuntime>,
+    koto_time: Res<KotoTime>,
+    mut tasks: ResMut<KotoTasks>,
+    mut script_error: EventWriter<KotoScriptError>,
+) {
+    if !koto.is_ready() {
+        return;
+    }
+
+    let _span = info_span!("koto_tasks", count = tasks.0.len()).entered();
+
+    tasks.0.retain_mut(|task| {
+        if task.status.is_cancelled.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        if task.wait > 0.0 {
+            task.wait -= koto_time.delta();
+            if task.wait > 0.0 {
+                return true;
+            }
+        }
+
+        match task.iterator.next() {
+            Some(KIteratorOutput::Value(KValue::Object(o))) if o.is_a::<Wait>() => {
+                task.wait = o.cast::<Wait>().map_or(0.0, |wait| wait.seconds);
+                true
+            }
+            Some(KIteratorOutput::Error(error)) => {
+                let error = KotoScriptError {
+                    phase: ScriptPhase::Task,
+                    message: error.to_string(),
+                    span: None,
+                    script_path: koto.script_path().map(ToOwned::to_owned),
+                };
+                error!("{error}");
+                script_error.send(error);
+                task.status.is_done.store(true, Ordering::Relaxed);
+                false
+            }
+            Some(_) => true,
+            None => {
+                task.status.is_done.store(true, Ordering::Relaxed);
+                false
+            }
+        }
+    });
+}
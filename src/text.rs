@@ -1,15 +1,70 @@
 //! Text support for bevy_koto
 
-use crate::prelude::*;
-use bevy::prelude::*;
+use crate::{entity::koto_entity_impl, prelude::*};
+use bevy::{
+    prelude::*,
+    text::{TextBounds, Update2dText},
+    transform::TransformSystem,
+};
 use cloned::cloned;
 use koto::{derive::*, prelude::*, runtime::Result as KotoResult};
+use std::f32::consts::FRAC_PI_4;
 
 /// Text support for bevy_koto
 ///
-/// This plugin is currently underbaked (appropriate font sizing needs to be figured out),
-/// but it's a start.
-pub struct KotoTextPlugin;
+/// `make_text(text, font)` spawns text in world space, and `ui_text(text, anchor)` spawns UI text
+/// that's anchored to a corner or edge of the screen, e.g. `ui_text 'Score: 0', 'top_left'`.
+/// The available anchors are `top_left`, `top`, `top_right`, `left`, `center`, `right`,
+/// `bottom_left`, `bottom`, and `bottom_right`.
+///
+/// Text can be revealed a character at a time with `text.reveal(chars_per_second)`, with the
+/// text's `on_reveal_complete` callback being called once all of the text is visible.
+/// The reveal is driven by [KotoTime], so it pauses along with the script's time.
+///
+/// The colors of text entities are mirrored into their [KotoEntityMapping] during
+/// [KotoUpdate::PreUpdate], and can be read with `get_color()` and `get_alpha()`.
+///
+/// By default world-space text is sized in pixels, with a default font size of 100. Apps with an
+/// orthographic camera that shows a fixed area of the world (like the camera used with
+/// `KotoCameraPlugin`) can switch to [TextSizing::World] with [KotoTextPlugin::with_sizing],
+/// so that text sizes match the sizes of other entities. UI text is always sized in pixels.
+#[derive(Default)]
+pub struct KotoTextPlugin {
+    /// How the sizes of text entities are interpreted
+    pub sizing: TextSizing,
+}
+
+impl KotoTextPlugin {
+    /// Sets how the sizes of text entities are interpreted
+    #[must_use]
+    pub fn with_sizing(mut self, sizing: TextSizing) -> Self {
+        self.sizing = sizing;
+        self
+    }
+}
+
+/// How the sizes of text entities are interpreted, see [KotoTextPlugin::with_sizing]
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextSizing {
+    /// Font sizes and wrap widths are in pixels, with a default font size of 100
+    #[default]
+    Pixels,
+    /// Font sizes and wrap widths are in world units, with a default font size of 1
+    ///
+    /// A text entity with a size of 0.1 will have lines that are 0.1 units high,
+    /// matching a 0.1 sized shape. Text is rendered at a resolution that matches the
+    /// orthographic projection of the active camera, so it stays sharp when the camera zooms.
+    World,
+}
+
+impl TextSizing {
+    fn default_font_size(self) -> f32 {
+        match self {
+            TextSizing::Pixels => 100.0,
+            TextSizing::World => 1.0,
+        }
+    }
+}
 
 impl Plugin for KotoTextPlugin {
     fn build(&self, app: &mut App) {
@@ -19,36 +74,80 @@ impl Plugin for KotoTextPlugin {
         assert!(app.is_plugin_added::<KotoGeometryPlugin>());
 
         let (spawn_text_sender, spawn_text_receiver) = koto_channel::<SpawnText>();
+        let (update_text_sender, update_text_receiver) = koto_entity_channel::<UpdateText>();
 
         app.insert_resource(spawn_text_sender)
             .insert_resource(spawn_text_receiver)
+            .insert_resource(update_text_sender)
+            .insert_resource(update_text_receiver)
+            .insert_resource(self.sizing)
             .add_systems(Startup, on_startup)
-            .add_systems(KotoSchedule, spawn_text.in_set(KotoUpdate::PostUpdate));
+            .add_systems(
+                KotoSchedule,
+                (
+                    sync_text_colors.in_set(KotoUpdate::PreUpdate),
+                    update_text_reveals.in_set(KotoUpdate::Update),
+                    spawn_text.in_set(KotoUpdate::PostUpdate),
+                ),
+            )
+            .add_systems(Update, koto_to_bevy_text_events)
+            .add_systems(
+                PostUpdate,
+                (update_world_space_text, update_text_effects)
+                    .chain()
+                    .after(TransformSystem::TransformPropagate)
+                    .before(Update2dText),
+            );
     }
 }
 
+#[allow(clippy::result_large_err)]
 fn on_startup(
     koto: ResMut<KotoRuntime>,
+    sizing: Res<TextSizing>,
     spawn_text: Res<KotoSender<SpawnText>>,
+    update_text: Res<KotoEntitySender<UpdateText>>,
     update_material: Res<KotoEntitySender<UpdateColorMaterial>>,
     update_entity: Res<KotoEntitySender<UpdateKotoEntity>>,
     update_transform: Res<KotoEntitySender<UpdateTransform>>,
 ) {
     let prelude = koto.prelude();
+    let font_size = sizing.default_font_size();
     prelude.add_fn("make_text", {
-        cloned!(spawn_text, update_entity, update_material, update_transform);
+        cloned!(
+            spawn_text,
+            update_text,
+            update_entity,
+            update_material,
+            update_transform
+        );
 
         move |ctx| {
             let entity = KotoEntityMapping::default();
 
-            let text = match ctx.args() {
-                [KValue::Str(s)] => s.to_string(),
-                [] => String::new(),
-                unexpected => return unexpected_args("an optional string", unexpected),
+            let (text, font) = match ctx.args() {
+                [KValue::Str(s), KValue::Str(font)] => (s.to_string(), Some(font.to_string())),
+                [KValue::Str(s)] => (s.to_string(), None),
+                [] => (String::new(), None),
+                unexpected => {
+                    return unexpected_args(
+                        "an optional string, and an optional font path",
+                        unexpected,
+                    )
+                }
+            };
+            let style = TextStyle {
+                font,
+                font_size,
+                ..default()
             };
 
             let result: KObject = KotoText {
                 entity: entity.clone(),
+                text: text.clone(),
+                style: style.clone(),
+                spawn_text: spawn_text.clone(),
+                update_text: update_text.clone(),
                 update_material: update_material.clone(),
                 update_entity: update_entity.clone(),
                 update_transform: update_transform.clone(),
@@ -58,6 +157,47 @@ fn on_startup(
             spawn_text.send(SpawnText {
                 koto_entity: KotoEntity::new(result.clone(), entity),
                 text,
+                style,
+                ui_anchor: None,
+            });
+
+            Ok(result.into())
+        }
+    });
+
+    prelude.add_fn("ui_text", {
+        cloned!(spawn_text, update_text, update_entity);
+
+        move |ctx| {
+            let entity = KotoEntityMapping::default();
+
+            let (text, anchor) = match ctx.args() {
+                [KValue::Str(s), KValue::Str(anchor)] => match UiTextAnchor::from_str(anchor) {
+                    Some(anchor) => (s.to_string(), anchor),
+                    None => return runtime_error!("ui_text: Unknown anchor '{anchor}'"),
+                },
+                [KValue::Str(s)] => (s.to_string(), UiTextAnchor::TopLeft),
+                unexpected => {
+                    return unexpected_args("a string, and an optional anchor", unexpected)
+                }
+            };
+
+            let result: KObject = KotoUiText {
+                entity: entity.clone(),
+                update_text: update_text.clone(),
+                update_entity: update_entity.clone(),
+            }
+            .into();
+
+            spawn_text.send(SpawnText {
+                koto_entity: KotoEntity::new(result.clone(), entity),
+                text,
+                style: TextStyle {
+                    font_size: UI_TEXT_FONT_SIZE,
+                    justify: anchor.justify(),
+                    ..default()
+                },
+                ui_anchor: Some(anchor),
             });
 
             Ok(result.into())
@@ -65,35 +205,648 @@ fn on_startup(
     });
 }
 
-fn spawn_text(channel: Res<KotoReceiver<SpawnText>>, mut commands: Commands) {
+fn spawn_text(
+    channel: Res<KotoReceiver<SpawnText>>,
+    sizing: Res<TextSizing>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let _span = info_span!("koto_channel", channel = "SpawnText").entered();
     while let Some(SpawnText {
         mut koto_entity,
         text,
+        style,
+        ui_anchor,
     }) = channel.receive()
     {
         debug!("Spawning text '{text}'");
-        let bevy_entity = commands
-            .spawn((
-                Text2d::new(text),
-                TextFont::from_font_size(100.0),
-                TextLayout::new_with_justify(JustifyText::Center),
-                koto_entity.clone(),
-            ))
-            .id();
+        let mut text_font = TextFont::from_font_size(style.font_size);
+        if let Some(font) = style.font {
+            text_font.font = asset_server.load(font);
+        }
+
+        if let Some(anchor) = ui_anchor {
+            // UI text is placed in a container that covers the screen,
+            // with the container's alignment anchoring the text.
+            let text_entity = commands
+                .spawn((
+                    Text::new(text),
+                    text_font,
+                    TextLayout::new_with_justify(style.justify),
+                ))
+                .id();
+            let (justify_content, align_items) = anchor.alignment();
+            let bevy_entity = commands
+                .spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        padding: UiRect::all(Val::Px(UI_TEXT_MARGIN)),
+                        justify_content,
+                        align_items,
+                        ..default()
+                    },
+                    UiTextContainer(text_entity),
+                    koto_entity.clone(),
+                ))
+                .add_child(text_entity)
+                .id();
+            koto_entity.entity.assign_bevy_entity(bevy_entity);
+            continue;
+        }
+
+        let mut entity_commands = commands.spawn((
+            Text2d::new(text),
+            text_font,
+            TextLayout::new_with_justify(style.justify),
+            koto_entity.clone(),
+        ));
+        match *sizing {
+            TextSizing::Pixels => {
+                entity_commands.insert(TextBounds {
+                    width: style.wrap_width,
+                    height: None,
+                });
+            }
+            TextSizing::World => {
+                entity_commands.insert(WorldSpaceText {
+                    font_size: style.font_size,
+                    wrap_width: style.wrap_width,
+                });
+            }
+        }
+        let bevy_entity = entity_commands.id();
+
+        let effects = TextEffects {
+            outline: style
+                .outline
+                .map(|(color, width)| spawn_outline(&mut commands, bevy_entity, color, width)),
+            shadow: style
+                .shadow
+                .map(|(color, offset)| spawn_shadow(&mut commands, bevy_entity, color, offset)),
+        };
+        commands.entity(bevy_entity).insert(effects);
+
         koto_entity.entity.assign_bevy_entity(bevy_entity);
     }
 }
 
+fn koto_to_bevy_text_events(
+    channel: Res<KotoEntityReceiver<UpdateText>>,
+    mut query: Query<TextComponents>,
+    ui_text_containers: Query<&UiTextContainer>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let _span = info_span!("koto_channel", channel = "UpdateText").entered();
+    while let Some(event) = channel.receive() {
+        let mut entity = event.entity.get();
+        // Events for UI text are sent to its container
+        if let Ok(container) = ui_text_containers.get(entity) {
+            entity = container.0;
+        }
+        // The entity may have been despawned
+        let Ok((
+            (text_2d, ui_text),
+            mut text_font,
+            mut text_color,
+            mut layout,
+            bounds,
+            world_space,
+            effects,
+            reveal,
+        )) = query.get_mut(entity)
+        else {
+            continue;
+        };
+        match (event.event, world_space) {
+            (UpdateText::Content(content), _) => {
+                if let Some(mut text) = text_2d {
+                    match reveal {
+                        // Text that's being revealed continues to show the same number of characters
+                        Some(mut reveal) => {
+                            let count = reveal.shown.chars().count();
+                            reveal.shown = content.chars().take(count).collect();
+                            reveal.text = content;
+                            text.0.clone_from(&reveal.shown);
+                        }
+                        None => text.0 = content,
+                    }
+                } else if let Some(mut text) = ui_text {
+                    text.0 = content;
+                }
+            }
+            (UpdateText::SetFontPath(font_path), _) => {
+                text_font.font = asset_server.load(font_path)
+            }
+            (UpdateText::FontSize(size), Some(mut world_space)) => world_space.font_size = size,
+            (UpdateText::FontSize(size), None) => text_font.font_size = size,
+            (UpdateText::Justify(justify), _) => layout.justify = justify,
+            (UpdateText::WrapWidth(width), Some(mut world_space)) => world_space.wrap_width = width,
+            (UpdateText::WrapWidth(width), None) => {
+                if let Some(mut bounds) = bounds {
+                    bounds.width = width;
+                }
+            }
+            (UpdateText::Color(color), _) => text_color.0 = color,
+            (UpdateText::Alpha(alpha), _) => text_color.0.set_alpha(alpha),
+            (UpdateText::Reveal(rate), _) => match reveal {
+                // The full text is kept when restarting a reveal that's in progress
+                Some(mut reveal) => {
+                    reveal.rate = rate;
+                    reveal.elapsed = 0.0;
+                }
+                None => {
+                    let Some(mut text) = text_2d else {
+                        continue;
+                    };
+                    commands.entity(entity).insert(TextReveal {
+                        rate,
+                        elapsed: 0.0,
+                        text: std::mem::take(&mut text.0),
+                        shown: String::new(),
+                    });
+                }
+            },
+            (UpdateText::Outline(outline), _) => {
+                let Some(mut effects) = effects else {
+                    continue;
+                };
+                match (outline, &mut effects.outline) {
+                    (Some((color, width)), Some(existing)) => {
+                        existing.color = color;
+                        existing.offsets = outline_offsets(width);
+                    }
+                    (Some((color, width)), None) => {
+                        effects.outline = Some(spawn_outline(&mut commands, entity, color, width));
+                    }
+                    (None, _) => {
+                        if let Some(existing) = effects.outline.take() {
+                            existing.despawn(&mut commands);
+                        }
+                    }
+                }
+            }
+            (UpdateText::Shadow(shadow), _) => {
+                let Some(mut effects) = effects else {
+                    continue;
+                };
+                match (shadow, &mut effects.shadow) {
+                    (Some((color, offset)), Some(existing)) => {
+                        existing.color = color;
+                        existing.offsets = vec![offset];
+                    }
+                    (Some((color, offset)), None) => {
+                        effects.shadow = Some(spawn_shadow(&mut commands, entity, color, offset));
+                    }
+                    (None, _) => {
+                        if let Some(existing) = effects.shadow.take() {
+                            existing.despawn(&mut commands);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+type TextComponents = (
+    AnyOf<(&'static mut Text2d, &'static mut Text)>,
+    &'static mut TextFont,
+    &'static mut TextColor,
+    &'static mut TextLayout,
+    Option<&'static mut TextBounds>,
+    Option<&'static mut WorldSpaceText>,
+    Option<&'static mut TextEffects>,
+    Option<&'static mut TextReveal>,
+);
+
+// Reveals a text entity's characters over time
+#[derive(Component)]
+struct TextReveal {
+    // The number of characters to reveal per second
+    rate: f64,
+    // The time in seconds since the reveal started
+    elapsed: f64,
+    // The full text that's being revealed
+    text: String,
+    // The part of the text that's currently shown
+    //
+    // If the entity's text doesn't match then it has been changed elsewhere,
+    // and the new text becomes the full text that's being revealed.
+    shown: String,
+}
+
+fn update_text_reveals(
+    koto: Res<KotoRuntime>,
+    koto_time: Res<KotoTime>,
+    mut query: Query<(Entity, &mut TextReveal, &mut Text2d, &mut KotoEntity)>,
+    mut script_error: EventWriter<KotoScriptError>,
+    mut commands: Commands,
+) {
+    if !koto.is_ready() {
+        return;
+    }
+
+    for (entity, mut reveal, mut text, mut koto_entity) in &mut query {
+        if text.0 != reveal.shown {
+            reveal.text = text.0.clone();
+        }
+        reveal.elapsed += koto_time.delta();
+
+        let full_text = &reveal.text;
+        let count = (reveal.elapsed * reveal.rate) as usize;
+        let shown = match full_text.char_indices().nth(count) {
+            Some((end, _)) => &full_text[..end],
+            None => full_text,
+        };
+        let is_complete = shown.len() == full_text.len();
+
+        if text.0 != shown {
+            text.0 = shown.to_string();
+        }
+        reveal.shown = text.0.clone();
+
+        if !is_complete {
+            continue;
+        }
+        commands.entity(entity).remove::<TextReveal>();
+
+        let instance = koto_entity.object.clone();
+        let Some((f, vm)) = koto_entity.callbacks.get_mut("on_reveal_complete") else {
+            continue;
+        };
+
+        let _span = info_span!("koto_text_reveal", %entity).entered();
+        if let Err(error) = vm.call_instance_function(instance.into(), f.clone(), &[]) {
+            let error = KotoScriptError {
+                phase: ScriptPhase::TextReveal,
+                message: error.to_string(),
+                span: None,
+                script_path: koto.script_path().map(ToOwned::to_owned),
+            };
+            error!("{error}");
+            script_error.send(error);
+        }
+    }
+}
+
+// The outline and shadow of a world-space text entity
+#[derive(Component, Default)]
+struct TextEffects {
+    outline: Option<TextEffect>,
+    shadow: Option<TextEffect>,
+}
+
+// Copies of a text entity that are drawn behind it, offset in font units
+struct TextEffect {
+    color: Color,
+    offsets: Vec<Vec2>,
+    entities: Vec<Entity>,
+}
+
+impl TextEffect {
+    fn spawn(commands: &mut Commands, parent: Entity, color: Color, offsets: Vec<Vec2>) -> Self {
+        let entities = offsets
+            .iter()
+            .map(|_| {
+                commands
+                    .spawn((Text2d::default(), TextColor(color)))
+                    .set_parent(parent)
+                    .id()
+            })
+            .collect();
+        Self {
+            color,
+            offsets,
+            entities,
+        }
+    }
+
+    fn despawn(self, commands: &mut Commands) {
+        for entity in self.entities {
+            if let Some(entity_commands) = commands.get_entity(entity) {
+                entity_commands.despawn_recursive();
+            }
+        }
+    }
+}
+
+fn spawn_outline(commands: &mut Commands, parent: Entity, color: Color, width: f32) -> TextEffect {
+    TextEffect::spawn(commands, parent, color, outline_offsets(width))
+}
+
+fn spawn_shadow(commands: &mut Commands, parent: Entity, color: Color, offset: Vec2) -> TextEffect {
+    TextEffect::spawn(commands, parent, color, vec![offset])
+}
+
+// Outlines are made from copies of the text that surround it in 8 directions
+fn outline_offsets(width: f32) -> Vec<Vec2> {
+    (0..8)
+        .map(|i| Vec2::from_angle(i as f32 * FRAC_PI_4) * width)
+        .collect()
+}
+
+// The distance in z between effect layers, with outlines drawn in front of shadows
+const TEXT_EFFECT_Z_STEP: f32 = 1.0e-4;
+
+// Keeps the copies used by outlines and shadows in sync with their text
+//
+// The copies are positioned using the text's global transform after any world-space scaling has
+// been applied, with effect offsets converted from font units to the text's layout units.
+fn update_text_effects(
+    texts: Query<TextEffectSource>,
+    mut copies: Query<TextEffectCopy, Without<TextEffects>>,
+) {
+    for (effects, text, font, layout, bounds, global, world_space) in &texts {
+        let layout_scale =
+            world_space.map_or(1.0, |world_space| font.font_size / world_space.font_size);
+
+        let layers = [(&effects.outline, 1.0), (&effects.shadow, 2.0)];
+        for (effect, layer) in layers {
+            let Some(effect) = effect else {
+                continue;
+            };
+            for (copy, offset) in effect.entities.iter().zip(&effect.offsets) {
+                let Ok((
+                    mut copy_text,
+                    mut copy_font,
+                    mut copy_layout,
+                    mut copy_bounds,
+                    mut color,
+                    mut copy_global,
+                )) = copies.get_mut(*copy)
+                else {
+                    continue;
+                };
+
+                if copy_text.0 != text.0 {
+                    copy_text.0.clone_from(&text.0);
+                }
+                if copy_font.font != font.font || copy_font.font_size != font.font_size {
+                    *copy_font = font.clone();
+                }
+                if copy_layout.justify != layout.justify {
+                    copy_layout.justify = layout.justify;
+                }
+                if copy_bounds.width != bounds.width || copy_bounds.height != bounds.height {
+                    *copy_bounds = *bounds;
+                }
+                if color.0 != effect.color {
+                    color.0 = effect.color;
+                }
+
+                let mut affine = global.affine();
+                let position = global.transform_point((*offset * layout_scale).extend(0.0));
+                affine.translation = (position - Vec3::Z * TEXT_EFFECT_Z_STEP * layer).into();
+                *copy_global = affine.into();
+            }
+        }
+    }
+}
+
+type TextEffectSource = (
+    &'static TextEffects,
+    &'static Text2d,
+    &'static TextFont,
+    &'static TextLayout,
+    &'static TextBounds,
+    &'static GlobalTransform,
+    Option<&'static WorldSpaceText>,
+);
+
+type TextEffectCopy = (
+    &'static mut Text2d,
+    &'static mut TextFont,
+    &'static mut TextLayout,
+    &'static mut TextBounds,
+    &'static mut TextColor,
+    &'static mut GlobalTransform,
+);
+
+// The default font size of UI text, in pixels
+const UI_TEXT_FONT_SIZE: f32 = 32.0;
+// The distance in pixels between UI text and the edges of the screen
+const UI_TEXT_MARGIN: f32 = 16.0;
+
+// Keeps the color snapshots of text entities in sync with their text colors
+fn sync_text_colors(
+    texts: Query<(&KotoEntity, &TextColor)>,
+    containers: Query<(&KotoEntity, &UiTextContainer)>,
+    text_colors: Query<&TextColor>,
+) {
+    let ui_texts = containers.iter().filter_map(|(koto_entity, container)| {
+        Some((koto_entity, text_colors.get(container.0).ok()?))
+    });
+    for (koto_entity, color) in texts.iter().chain(ui_texts) {
+        if koto_entity.entity.color() != Some(color.0) {
+            koto_entity.entity.set_color_snapshot(Some(color.0));
+        }
+    }
+}
+
+// The screen-covering node that anchors UI text, referring to the text's entity
+#[derive(Component)]
+struct UiTextContainer(Entity);
+
+// The position on the screen that UI text is anchored to
+#[derive(Clone, Copy, Debug)]
+enum UiTextAnchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl UiTextAnchor {
+    fn from_str(s: &str) -> Option<Self> {
+        use UiTextAnchor::*;
+
+        let result = match s {
+            "top_left" => TopLeft,
+            "top" => Top,
+            "top_right" => TopRight,
+            "left" => Left,
+            "center" | "centre" => Center,
+            "right" => Right,
+            "bottom_left" => BottomLeft,
+            "bottom" => Bottom,
+            "bottom_right" => BottomRight,
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    // The horizontal and vertical alignment of the text in its container
+    fn alignment(self) -> (JustifyContent, AlignItems) {
+        use UiTextAnchor::*;
+
+        let horizontal = match self {
+            TopLeft | Left | BottomLeft => JustifyContent::FlexStart,
+            Top | Center | Bottom => JustifyContent::Center,
+            TopRight | Right | BottomRight => JustifyContent::FlexEnd,
+        };
+        let vertical = match self {
+            TopLeft | Top | TopRight => AlignItems::FlexStart,
+            Left | Center | Right => AlignItems::Center,
+            BottomLeft | Bottom | BottomRight => AlignItems::FlexEnd,
+        };
+        (horizontal, vertical)
+    }
+
+    // The justification of multi-line text, matching the anchor's horizontal alignment
+    fn justify(self) -> JustifyText {
+        match self.alignment().0 {
+            JustifyContent::FlexStart => JustifyText::Left,
+            JustifyContent::FlexEnd => JustifyText::Right,
+            _ => JustifyText::Center,
+        }
+    }
+}
+
+// The size of a text entity that's sized in world units
+#[derive(Component)]
+struct WorldSpaceText {
+    font_size: f32,
+    wrap_width: Option<f32>,
+}
+
+type WorldSpaceTextComponents = (
+    &'static WorldSpaceText,
+    &'static Transform,
+    Option<&'static Parent>,
+    &'static mut GlobalTransform,
+    &'static mut TextFont,
+    &'static mut TextBounds,
+);
+
+// The range of font sizes that world-space text is rendered at
+const MIN_PIXEL_SIZE: f32 = 1.0;
+const MAX_PIXEL_SIZE: f32 = 1024.0;
+
+// Lays out world-space text at a font size that matches its size on screen,
+// and then scales the text's global transform so that the font size is in world units.
+//
+// The global transform is rebuilt from the local transform each frame so that the scaling isn't
+// compounded, and the text's children aren't affected given that their transforms have already
+// been propagated.
+fn update_world_space_text(
+    mut query: Query<WorldSpaceTextComponents>,
+    parents: Query<&GlobalTransform, Without<WorldSpaceText>>,
+    cameras: Query<(&Camera, &OrthographicProjection)>,
+) {
+    // The number of pixels per world unit
+    let pixels_per_unit = cameras
+        .iter()
+        .filter(|(camera, _)| camera.is_active)
+        .find_map(|(camera, projection)| {
+            let viewport = camera.logical_viewport_size()?;
+            Some(viewport.y / projection.area.height())
+        })
+        .unwrap_or(1.0);
+
+    for (world_space, transform, parent, mut global, mut text_font, mut bounds) in &mut query {
+        let unscaled = parent
+            .and_then(|parent| parents.get(parent.get()).ok())
+            .copied()
+            .unwrap_or_default()
+            .mul_transform(*transform);
+
+        let pixel_size = (world_space.font_size * unscaled.scale().y.abs() * pixels_per_unit)
+            .clamp(MIN_PIXEL_SIZE, MAX_PIXEL_SIZE);
+        let scale = world_space.font_size / pixel_size;
+
+        if text_font.font_size != pixel_size {
+            text_font.font_size = pixel_size;
+        }
+        let width = world_space.wrap_width.map(|width| width / scale);
+        if bounds.width != width {
+            bounds.width = width;
+        }
+        *global = unscaled.mul_transform(Transform::from_scale(Vec3::new(scale, scale, 1.0)));
+    }
+}
+
+/// Event for updating properties of a [Text2d] or UI [Text] that was spawned by a Koto script
+#[derive(Clone, Event)]
+pub enum UpdateText {
+    /// Replaces the text's contents
+    Content(String),
+    /// Sets the path of the font used to render the text
+    SetFontPath(String),
+    /// Sets the text's font size
+    FontSize(f32),
+    /// Sets the justification of the text's lines
+    Justify(JustifyText),
+    /// Sets the width at which the text wraps onto new lines, or disables wrapping with `None`
+    ///
+    /// The event is ignored by UI text.
+    WrapWidth(Option<f32>),
+    /// Sets the text's color
+    Color(Color),
+    /// Sets the text's alpha value
+    Alpha(f32),
+    /// Reveals the text's characters over time, at the given number of characters per second
+    ///
+    /// The event is ignored by UI text.
+    Reveal(f64),
+    /// Sets the color and width of the text's outline, or removes the outline with `None`
+    ///
+    /// The width is in the same units as the text's font size. The event is ignored by UI text.
+    Outline(Option<(Color, f32)>),
+    /// Sets the color and offset of the text's shadow, or removes the shadow with `None`
+    ///
+    /// The offset is in the same units as the text's font size. The event is ignored by UI text.
+    Shadow(Option<(Color, Vec2)>),
+}
+
 #[derive(Clone, Debug)]
 struct SpawnText {
     koto_entity: KotoEntity,
     text: String,
+    style: TextStyle,
+    // Set for UI text, which is anchored to the screen rather than placed in the world
+    ui_anchor: Option<UiTextAnchor>,
+}
+
+// The style that a text entity is spawned with, updated by the script so that clones match
+#[derive(Clone, Debug)]
+struct TextStyle {
+    font: Option<String>,
+    font_size: f32,
+    justify: JustifyText,
+    wrap_width: Option<f32>,
+    outline: Option<(Color, f32)>,
+    shadow: Option<(Color, Vec2)>,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            font: None,
+            font_size: 100.0,
+            justify: JustifyText::Center,
+            wrap_width: None,
+            outline: None,
+            shadow: None,
+        }
+    }
 }
 
 #[derive(Clone, KotoType, KotoCopy)]
 #[koto(type_name = "Text")]
 struct KotoText {
     entity: KotoEntityMapping,
+    text: String,
+    style: TextStyle,
+    spawn_text: KotoSender<SpawnText>,
+    update_text: KotoEntitySender<UpdateText>,
     update_material: KotoEntitySender<UpdateColorMaterial>,
     update_entity: KotoEntitySender<UpdateKotoEntity>,
     update_transform: KotoEntitySender<UpdateTransform>,
@@ -101,179 +854,333 @@ struct KotoText {
 
 impl KotoObject for KotoText {}
 
-#[koto_impl]
-impl KotoText {
-    #[koto_method]
-    fn set_alpha(ctx: MethodContext<Self>) -> KotoResult<KValue> {
-        let alpha = match ctx.args {
-            [KValue::Number(n)] => n.into(),
-            _ => return runtime_error!("Shape.set_alpha: Expected a number"),
-        };
+koto_entity_impl! {
+    impl KotoText as "Text" with [entity, layer, children, transform_2d, z_index, size_2d] {
+        #[koto_method(alias = "get_colour")]
+        fn get_color(&self) -> KValue {
+            self.entity
+                .color()
+                .map_or(KValue::Null, |color| bevy_to_koto_color(color).into())
+        }
 
-        let this = ctx.instance()?;
-        this.update_material.send(KotoEntityEvent::new(
-            this.entity.clone(),
-            UpdateColorMaterial::Alpha(alpha),
-        ));
+        #[koto_method]
+        fn get_alpha(&self) -> KValue {
+            self.entity
+                .color()
+                .map_or(KValue::Null, |color| color.alpha().into())
+        }
 
-        ctx.instance_result()
-    }
+        #[koto_method]
+        fn set_alpha(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let alpha = match ctx.args {
+                [KValue::Number(n)] => n.into(),
+                _ => return runtime_error!("Text.set_alpha: Expected a number"),
+            };
 
-    #[koto_method(alias = "set_colour")]
-    fn set_color(ctx: MethodContext<Self>) -> KotoResult<KValue> {
-        use KValue::{Number, Object};
+            let this = ctx.instance()?;
+            this.entity.set_alpha_snapshot(alpha);
+            this.update_text.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateText::Alpha(alpha),
+            ));
 
-        let color = match ctx.args {
-            [Number(n1), Number(n2), Number(n3)] => {
-                Color::srgba(f32::from(n1), f32::from(n2), f32::from(n3), 1.0)
-            }
-            [Number(n1), Number(n2), Number(n3), Number(n4)] => {
-                Color::srgba(f32::from(n1), f32::from(n2), f32::from(n3), f32::from(n4))
-            }
-            [Object(o)] if o.is_a::<KotoColor>() => koto_to_bevy_color(&*o.cast::<KotoColor>()?),
-            _ => {
-                return runtime_error!("Shape.set_color: Expected a Color, or 3 or 4 numbers");
-            }
-        };
+            ctx.instance_result()
+        }
 
-        let this = ctx.instance()?;
-        this.update_material.send(KotoEntityEvent::new(
-            this.entity.clone(),
-            UpdateColorMaterial::Color(color),
-        ));
+        #[koto_method(alias = "set_colour")]
+        fn set_color(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            use KValue::{Number, Object, Str};
 
-        ctx.instance_result()
-    }
+            let color = match ctx.args {
+                [Number(n1), Number(n2), Number(n3)] => {
+                    Color::srgba(f32::from(n1), f32::from(n2), f32::from(n3), 1.0)
+                }
+                [Number(n1), Number(n2), Number(n3), Number(n4)] => {
+                    Color::srgba(f32::from(n1), f32::from(n2), f32::from(n3), f32::from(n4))
+                }
+                [Object(o)] if o.is_a::<KotoColor>() => {
+                    koto_to_bevy_color(&*o.cast::<KotoColor>()?)
+                }
+                [Str(s)] => match parse_color(s) {
+                    Some(color) => color,
+                    None => return runtime_error!("Text.set_color: Unknown color '{s}'"),
+                },
+                _ => {
+                    return runtime_error!(
+                        "Text.set_color: Expected a Color, a color name or hex String, \
+                         or 3 or 4 numbers"
+                    );
+                }
+            };
 
-    #[koto_method]
-    fn set_image(ctx: MethodContext<Self>) -> KotoResult<KValue> {
-        let path = match ctx.args {
-            [KValue::Str(path)] => path,
-            _ => {
-                return runtime_error!("Shape.set_image: Expected an image path as a string");
-            }
-        };
+            let this = ctx.instance()?;
+            this.entity.set_color_snapshot(Some(color));
+            this.update_text.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateText::Color(color),
+            ));
 
-        let this = ctx.instance()?;
-        this.update_material.send(KotoEntityEvent::new(
-            this.entity.clone(),
-            UpdateColorMaterial::SetImagePath(Some(path.to_string())),
-        ));
+            ctx.instance_result()
+        }
 
-        ctx.instance_result()
-    }
+        #[koto_method]
+        fn set_image(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let path = match ctx.args {
+                [KValue::Str(path)] => path,
+                _ => {
+                    return runtime_error!("Text.set_image: Expected an image path as a string");
+                }
+            };
 
-    #[koto_method]
-    fn set_position(ctx: MethodContext<Self>) -> KotoResult<KValue> {
-        use KValue::{Number, Object};
+            let this = ctx.instance()?;
+            this.update_material.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateColorMaterial::SetImagePath(Some(path.to_string())),
+            ));
 
-        let position = match ctx.args {
-            [Number(x), Number(y)] => Vec3::new(x.into(), y.into(), 0.0),
-            [Number(x), Number(y), Number(z)] => Vec3::new(x.into(), y.into(), z.into()),
-            [Object(v)] if v.is_a::<KotoVec2>() => {
-                let v = v.cast::<KotoVec2>()?.inner();
-                Vec3::new(v.x as f32, v.y as f32, 0.0)
-            }
-            [Object(v), Number(z)] if v.is_a::<KotoVec2>() => {
-                let v = v.cast::<KotoVec2>()?.inner();
-                Vec3::new(v.x as f32, v.y as f32, z.into())
-            }
-            _ => {
-                return runtime_error!(
-                    "Shape.set_position: Expected x, y, (and optionally z) positions"
-                )
-            }
-        };
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_text(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let text = match ctx.args {
+                [KValue::Str(text)] => text.to_string(),
+                _ => return runtime_error!("Text.set_text: Expected a string"),
+            };
 
-        let this = ctx.instance()?;
-        this.update_transform
-            .send(KotoEntityEvent::<UpdateTransform>::new(
+            let mut this = ctx.instance_mut()?;
+            this.text = text.clone();
+            this.update_text.send(KotoEntityEvent::new(
                 this.entity.clone(),
-                UpdateTransform::Position(position),
+                UpdateText::Content(text),
             ));
+            drop(this);
 
-        ctx.instance_result()
-    }
+            ctx.instance_result()
+        }
 
-    #[koto_method]
-    fn set_rotation(ctx: MethodContext<Self>) -> KotoResult<KValue> {
-        let rotation = match ctx.args {
-            [KValue::Number(x)] => x.into(),
-            _ => return runtime_error!("Shape.set_rotation: Expected a Number in radians"),
-        };
+        #[koto_method]
+        fn set_font(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let font = match ctx.args {
+                [KValue::Str(font)] => font.to_string(),
+                _ => return runtime_error!("Text.set_font: Expected a font path as a string"),
+            };
 
-        let this = ctx.instance()?;
-        this.update_transform.send(KotoEntityEvent::new(
-            this.entity.clone(),
-            UpdateTransform::Rotation(rotation),
-        ));
+            let mut this = ctx.instance_mut()?;
+            this.style.font = Some(font.clone());
+            this.update_text.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateText::SetFontPath(font),
+            ));
+            drop(this);
 
-        ctx.instance_result()
-    }
+            ctx.instance_result()
+        }
 
-    #[koto_method]
-    fn set_size(ctx: MethodContext<Self>) -> KotoResult<KValue> {
-        use KValue::Number;
+        #[koto_method]
+        fn set_font_size(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let size = match ctx.args {
+                [KValue::Number(n)] if f32::from(n) > 0.0 => n.into(),
+                _ => return runtime_error!("Text.set_font_size: Expected a positive Number"),
+            };
 
-        let size = match ctx.args {
-            [Number(size)] => {
-                let size = f32::from(size);
-                Vec3::new(size, size, 0.0)
-            }
-            [Number(x), Number(y)] => Vec3::new(f32::from(x), f32::from(y), 0.0),
-            _ => return runtime_error!("Shape.set_size: Expected Numbers"),
-        };
+            let mut this = ctx.instance_mut()?;
+            this.style.font_size = size;
+            this.update_text.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateText::FontSize(size),
+            ));
+            drop(this);
 
-        let this = ctx.instance()?;
-        this.update_transform.send(KotoEntityEvent::new(
-            this.entity.clone(),
-            UpdateTransform::Scale(size),
-        ));
+            ctx.instance_result()
+        }
 
-        ctx.instance_result()
-    }
+        #[koto_method]
+        fn set_justify(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let justify = match ctx.args {
+                [KValue::Str(justify)] => match justify.as_str() {
+                    "left" => JustifyText::Left,
+                    "center" | "centre" => JustifyText::Center,
+                    "right" => JustifyText::Right,
+                    other => {
+                        return runtime_error!("Text.set_justify: Unknown justification '{other}'")
+                    }
+                },
+                _ => {
+                    return runtime_error!(
+                        "Text.set_justify: Expected 'left', 'center', or 'right' as a string"
+                    )
+                }
+            };
 
-    // #[koto_method]
-    // fn set_text(ctx: MethodContext<Self>) -> KotoResult<KValue> {
-    //     let text = match ctx.args {
-    //         [KValue::Str(text)] => Vec3::new(size, size, 0.0),
-    //         _ => return runtime_error!("Text.set_text: Expected a string"),
-    //     };
-    //
-    //     let this = ctx.instance()?;
-    //     this.update_transform.send(KotoEntityEvent::new(
-    //         this.entity.clone(),
-    //         UpdateTransform::Scale(size),
-    //     ));
-    //
-    //     ctx.instance_result()
-    // }
-
-    #[koto_method]
-    fn on_update(ctx: MethodContext<Self>) -> KotoResult<KValue> {
-        let f = match ctx.args {
-            [f] if f.is_callable() => f.clone(),
-            _ => return runtime_error!("Shape.on_update: Expected a callable value"),
-        };
+            let mut this = ctx.instance_mut()?;
+            this.style.justify = justify;
+            this.update_text.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateText::Justify(justify),
+            ));
+            drop(this);
 
-        let this = ctx.instance()?;
-        this.update_entity.send(KotoEntityEvent::new(
-            this.entity.clone(),
-            UpdateKotoEntity::SetOnUpdate(Some((f, ctx.vm.spawn_shared_vm()))),
-        ));
+            ctx.instance_result()
+        }
 
-        ctx.instance_result()
-    }
+        #[koto_method]
+        fn set_wrap_width(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let width = match ctx.args {
+                [KValue::Number(n)] if f32::from(n) > 0.0 => Some(n.into()),
+                [KValue::Null] => None,
+                _ => {
+                    return runtime_error!(
+                        "Text.set_wrap_width: Expected a positive Number, or null"
+                    )
+                }
+            };
 
-    #[koto_method]
-    fn despawn(ctx: MethodContext<Self>) -> KotoResult<KValue> {
-        let this = ctx.instance()?;
-        this.update_entity.send(KotoEntityEvent::new(
-            this.entity.clone(),
-            UpdateKotoEntity::Despawn,
-        ));
+            let mut this = ctx.instance_mut()?;
+            this.style.wrap_width = width;
+            this.update_text.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateText::WrapWidth(width),
+            ));
+            drop(this);
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn reveal(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let rate = match ctx.args {
+                [KValue::Number(n)] if f64::from(n) > 0.0 => n.into(),
+                _ => {
+                    return runtime_error!(
+                        "Text.reveal: Expected a positive Number of characters per second"
+                    )
+                }
+            };
+
+            let this = ctx.instance()?;
+            this.update_text.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateText::Reveal(rate),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn on_reveal_complete(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let callback = match ctx.args {
+                [f] if f.is_callable() => Some((f.clone(), ctx.vm.spawn_shared_vm())),
+                [KValue::Null] => None,
+                _ => {
+                    return runtime_error!(
+                        "Text.on_reveal_complete: Expected a callable value, or null"
+                    )
+                }
+            };
+
+            let this = ctx.instance()?;
+            this.update_entity.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateKotoEntity::SetCallback("on_reveal_complete", callback),
+            ));
+
+            ctx.instance_result()
+        }
 
-        Ok(KValue::Null)
+        #[koto_method]
+        fn set_outline(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            use KValue::{Null, Number, Object};
+
+            let outline = match ctx.args {
+                [Object(color), Number(width)] if color.is_a::<KotoColor>() => Some((
+                    koto_to_bevy_color(&*color.cast::<KotoColor>()?),
+                    f32::from(width),
+                )),
+                [Null] => None,
+                _ => {
+                    return runtime_error!(
+                        "Text.set_outline: Expected a Color and a width as a Number, or null"
+                    )
+                }
+            };
+
+            let mut this = ctx.instance_mut()?;
+            this.style.outline = outline;
+            this.update_text.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateText::Outline(outline),
+            ));
+            drop(this);
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_shadow(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            use KValue::{Null, Number, Object};
+
+            let shadow = match ctx.args {
+                [Object(color), Object(offset)]
+                    if color.is_a::<KotoColor>() && offset.is_a::<KotoVec2>() =>
+                {
+                    let offset = offset.cast::<KotoVec2>()?.inner();
+                    Some((
+                        koto_to_bevy_color(&*color.cast::<KotoColor>()?),
+                        Vec2::new(offset.x as f32, offset.y as f32),
+                    ))
+                }
+                [Object(color), Number(x), Number(y)] if color.is_a::<KotoColor>() => Some((
+                    koto_to_bevy_color(&*color.cast::<KotoColor>()?),
+                    Vec2::new(x.into(), y.into()),
+                )),
+                [Null] => None,
+                _ => {
+                    return runtime_error!(
+                        "Text.set_shadow: Expected a Color and an offset as a Vec2 or x and y Numbers, or null"
+                    )
+                }
+            };
+
+            let mut this = ctx.instance_mut()?;
+            this.style.shadow = shadow;
+            this.update_text.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateText::Shadow(shadow),
+            ));
+            drop(this);
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn clone_entity(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let this = ctx.instance()?;
+
+            let entity = KotoEntityMapping::default();
+            let result: KObject = KotoText {
+                entity: entity.clone(),
+                ..this.clone()
+            }
+            .into();
+
+            this.spawn_text.send(SpawnText {
+                koto_entity: KotoEntity::new(result.clone(), entity.clone()),
+                text: this.text.clone(),
+                style: this.style.clone(),
+                ui_anchor: None,
+            });
+            if let Some(color) = this.entity.color() {
+                entity.set_color_snapshot(Some(color));
+                this.update_text.send(KotoEntityEvent::new(
+                    entity.clone(),
+                    UpdateText::Color(color),
+                ));
+            }
+            send_transform_copy(&this.update_transform, &this.entity, &entity);
+
+            Ok(result.into())
+        }
     }
 }
 
@@ -282,3 +1189,138 @@ impl From<KotoText> for KValue {
         KObject::from(shape).into()
     }
 }
+
+#[derive(Clone, KotoType, KotoCopy)]
+#[koto(type_name = "UiText")]
+struct KotoUiText {
+    entity: KotoEntityMapping,
+    update_text: KotoEntitySender<UpdateText>,
+    update_entity: KotoEntitySender<UpdateKotoEntity>,
+}
+
+impl KotoObject for KotoUiText {}
+
+koto_entity_impl! {
+    impl KotoUiText as "UiText" with [lifetime] {
+        #[koto_method]
+        fn set_text(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let text = match ctx.args {
+                [KValue::Str(text)] => text.to_string(),
+                _ => return runtime_error!("UiText.set_text: Expected a string"),
+            };
+
+            let this = ctx.instance()?;
+            this.update_text.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateText::Content(text),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method(alias = "set_colour")]
+        fn set_color(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            use KValue::{Number, Object, Str};
+
+            let color = match ctx.args {
+                [Number(n1), Number(n2), Number(n3)] => {
+                    Color::srgba(f32::from(n1), f32::from(n2), f32::from(n3), 1.0)
+                }
+                [Number(n1), Number(n2), Number(n3), Number(n4)] => {
+                    Color::srgba(f32::from(n1), f32::from(n2), f32::from(n3), f32::from(n4))
+                }
+                [Object(o)] if o.is_a::<KotoColor>() => {
+                    koto_to_bevy_color(&*o.cast::<KotoColor>()?)
+                }
+                [Str(s)] => match parse_color(s) {
+                    Some(color) => color,
+                    None => return runtime_error!("UiText.set_color: Unknown color '{s}'"),
+                },
+                _ => {
+                    return runtime_error!(
+                        "UiText.set_color: Expected a Color, a color name or hex String, \
+                         or 3 or 4 numbers"
+                    );
+                }
+            };
+
+            let this = ctx.instance()?;
+            this.entity.set_color_snapshot(Some(color));
+            this.update_text.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateText::Color(color),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method(alias = "get_colour")]
+        fn get_color(&self) -> KValue {
+            self.entity
+                .color()
+                .map_or(KValue::Null, |color| bevy_to_koto_color(color).into())
+        }
+
+        #[koto_method]
+        fn get_alpha(&self) -> KValue {
+            self.entity
+                .color()
+                .map_or(KValue::Null, |color| color.alpha().into())
+        }
+
+        #[koto_method]
+        fn set_alpha(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let alpha = match ctx.args {
+                [KValue::Number(n)] => n.into(),
+                _ => return runtime_error!("UiText.set_alpha: Expected a number"),
+            };
+
+            let this = ctx.instance()?;
+            this.entity.set_alpha_snapshot(alpha);
+            this.update_text.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateText::Alpha(alpha),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_font(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let font = match ctx.args {
+                [KValue::Str(font)] => font.to_string(),
+                _ => return runtime_error!("UiText.set_font: Expected a font path as a string"),
+            };
+
+            let this = ctx.instance()?;
+            this.update_text.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateText::SetFontPath(font),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_font_size(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let size = match ctx.args {
+                [KValue::Number(n)] if f32::from(n) > 0.0 => n.into(),
+                _ => return runtime_error!("UiText.set_font_size: Expected a positive Number"),
+            };
+
+            let this = ctx.instance()?;
+            this.update_text.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateText::FontSize(size),
+            ));
+
+            ctx.instance_result()
+        }
+    }
+}
+
+impl From<KotoUiText> for KValue {
+    fn from(text: KotoUiText) -> Self {
+        KObject::from(text).into()
+    }
+}
@@ -0,0 +1,535 @@
+//! 3D text support for bevy_koto
+
+use crate::{entity::koto_entity_impl, prelude::*};
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+        view::RenderLayers,
+    },
+    text::{TextLayoutInfo, Update2dText},
+    transform::TransformSystem,
+    window::PrimaryWindow,
+};
+use cloned::cloned;
+use koto::{derive::*, prelude::*, runtime::Result as KotoResult};
+
+/// 3D text for bevy_koto
+///
+/// The plugin adds a `text3d(text, font)` function to the Koto prelude that spawns text in 3D
+/// space, with the optional font being given as a path, e.g.
+///
+/// ```koto
+/// label = text3d 'Hello'
+/// label.set_position 0, 2, 0
+/// label.set_color 1, 0.5, 0
+/// ```
+///
+/// The text is 1 unit high, and is centered on its position. By default the text is a flat mesh
+/// facing along the Z axis, `set_billboard(true)` makes the text face the camera.
+///
+/// The text is laid out by Bevy's text pipeline using a hidden [Text2d], so the [UpdateText]
+/// events for content, fonts, and colors apply to 3D text as well. The glyphs are rendered with
+/// unlit [StandardMaterial]s, one for each font atlas that the text uses.
+pub struct KotoText3dPlugin;
+
+impl Plugin for KotoText3dPlugin {
+    fn build(&self, app: &mut App) {
+        assert!(app.is_plugin_added::<KotoRuntimePlugin>());
+        assert!(app.is_plugin_added::<KotoEntityPlugin>());
+        assert!(app.is_plugin_added::<KotoColorPlugin>());
+        assert!(app.is_plugin_added::<KotoGeometryPlugin>());
+        assert!(app.is_plugin_added::<KotoTextPlugin>());
+        assert!(app.is_plugin_added::<KotoShape3dPlugin>());
+
+        let (spawn_text_sender, spawn_text_receiver) = koto_channel::<SpawnText3d>();
+        let (update_text3d_sender, update_text3d_receiver) = koto_entity_channel::<UpdateText3d>();
+
+        app.insert_resource(spawn_text_sender)
+            .insert_resource(spawn_text_receiver)
+            .insert_resource(update_text3d_sender)
+            .insert_resource(update_text3d_receiver)
+            .add_systems(Startup, on_startup)
+            .add_systems(KotoSchedule, spawn_text3d.in_set(KotoUpdate::PostUpdate))
+            .add_systems(Update, koto_to_bevy_text3d_events)
+            .add_systems(
+                PostUpdate,
+                (
+                    update_text3d_meshes.after(Update2dText),
+                    billboard_text3d.after(TransformSystem::TransformPropagate),
+                ),
+            );
+    }
+}
+
+// The font size that 3D text is laid out with, determining the resolution of the glyphs
+const TEXT3D_FONT_SIZE: f32 = 64.0;
+
+#[allow(clippy::result_large_err)]
+fn on_startup(
+    koto: ResMut<KotoRuntime>,
+    spawn_text: Res<KotoSender<SpawnText3d>>,
+    update_text: Res<KotoEntitySender<UpdateText>>,
+    update_text3d: Res<KotoEntitySender<UpdateText3d>>,
+    update_entity: Res<KotoEntitySender<UpdateKotoEntity>>,
+    update_transform: Res<KotoEntitySender<UpdateTransform>>,
+) {
+    koto.prelude().add_fn("text3d", {
+        cloned!(
+            spawn_text,
+            update_text,
+            update_text3d,
+            update_entity,
+            update_transform
+        );
+
+        move |ctx| {
+            let entity = KotoEntityMapping::default();
+
+            let (text, font) = match ctx.args() {
+                [KValue::Str(s), KValue::Str(font)] => (s.to_string(), Some(font.to_string())),
+                [KValue::Str(s)] => (s.to_string(), None),
+                unexpected => {
+                    return unexpected_args("a string, and an optional font path", unexpected)
+                }
+            };
+
+            let result: KObject = KotoText3d {
+                entity: entity.clone(),
+                update_text: update_text.clone(),
+                update_text3d: update_text3d.clone(),
+                update_entity: update_entity.clone(),
+                update_transform: update_transform.clone(),
+            }
+            .into();
+
+            spawn_text.send(SpawnText3d {
+                koto_entity: KotoEntity::new(result.clone(), entity),
+                text,
+                font,
+            });
+
+            Ok(result.into())
+        }
+    });
+}
+
+fn spawn_text3d(
+    channel: Res<KotoReceiver<SpawnText3d>>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let _span = info_span!("koto_channel", channel = "SpawnText3d").entered();
+    while let Some(SpawnText3d {
+        mut koto_entity,
+        text,
+        font,
+    }) = channel.receive()
+    {
+        let mut text_font = TextFont::from_font_size(TEXT3D_FONT_SIZE);
+        if let Some(font) = font {
+            text_font.font = asset_server.load(font);
+        }
+        let bevy_entity = commands
+            .spawn((
+                Text2d::new(text),
+                text_font,
+                TextLayout::new_with_justify(JustifyText::Center),
+                // The Text2d isn't rendered, its layout is used to build the 3D text's meshes
+                RenderLayers::none(),
+                Text3d::default(),
+                koto_entity.clone(),
+            ))
+            .id();
+        koto_entity.entity.assign_bevy_entity(bevy_entity);
+    }
+}
+
+/// Events that update 3D text, sent from Koto
+#[derive(Clone, Debug)]
+pub enum UpdateText3d {
+    /// Enables or disables billboarding, with billboarded text facing the camera
+    Billboard(bool),
+}
+
+fn koto_to_bevy_text3d_events(
+    channel: Res<KotoEntityReceiver<UpdateText3d>>,
+    mut query: Query<&mut Text3d>,
+    mut parts: Query<&mut Transform, Without<Text3d>>,
+) {
+    let _span = info_span!("koto_channel", channel = "UpdateText3d").entered();
+    while let Some(event) = channel.receive() {
+        // The entity may have been despawned
+        let Ok(mut text3d) = query.get_mut(event.entity.get()) else {
+            continue;
+        };
+        match event.event {
+            UpdateText3d::Billboard(billboard) => {
+                text3d.billboard = billboard;
+                if !billboard {
+                    // Mark the parts as changed so that they follow the text's transform again
+                    for part in &text3d.parts {
+                        if let Ok(mut transform) = parts.get_mut(part.entity) {
+                            transform.set_changed();
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Attached to the hidden Text2d entity of a 3D text
+#[derive(Component, Default)]
+struct Text3d {
+    // True if the text should face the camera
+    billboard: bool,
+    // The child entities that render the text, one for each font atlas used by the glyphs
+    parts: Vec<Text3dPart>,
+}
+
+struct Text3dPart {
+    entity: Entity,
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+// Rebuilds the meshes of 3D text after its layout has changed, and updates the text's color
+fn update_text3d_meshes(
+    mut query: Query<(Entity, &mut Text3d, Ref<TextLayoutInfo>, Ref<TextColor>)>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    atlas_layouts: Res<Assets<TextureAtlasLayout>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut commands: Commands,
+) {
+    // Glyphs are laid out in physical pixels, matching the scale factor used by Bevy's text layout
+    let scale_factor = windows
+        .get_single()
+        .map(|window| window.resolution.scale_factor())
+        .unwrap_or(1.0);
+
+    for (entity, mut text3d, layout, color) in &mut query {
+        if layout.is_changed() {
+            let groups = glyph_meshes(&layout, &atlas_layouts, scale_factor);
+            let used = groups.len();
+
+            for (i, (texture, mesh)) in groups.into_iter().enumerate() {
+                match text3d.parts.get(i) {
+                    Some(part) => {
+                        meshes.insert(part.mesh.id(), mesh);
+                        if let Some(material) = materials.get_mut(part.material.id()) {
+                            material.base_color_texture = Some(texture);
+                        }
+                    }
+                    None => {
+                        let mesh = meshes.add(mesh);
+                        let material = materials.add(text3d_material(color.0, texture));
+                        let part_entity = commands
+                            .spawn((
+                                Mesh3d(mesh.clone()),
+                                MeshMaterial3d(material.clone()),
+                                Transform::default(),
+                            ))
+                            .set_parent(entity)
+                            .id();
+                        text3d.parts.push(Text3dPart {
+                            entity: part_entity,
+                            mesh,
+                            material,
+                        });
+                    }
+                }
+            }
+
+            // Remove the parts for atlases that are no longer used
+            let part_count = text3d.parts.len();
+            for part in text3d.parts.drain(used.min(part_count)..) {
+                commands.entity(part.entity).despawn_recursive();
+            }
+        }
+
+        if color.is_changed() {
+            for part in &text3d.parts {
+                if let Some(material) = materials.get_mut(part.material.id()) {
+                    material.base_color = color.0;
+                }
+            }
+        }
+    }
+}
+
+fn text3d_material(color: Color, texture: Handle<Image>) -> StandardMaterial {
+    StandardMaterial {
+        base_color: color,
+        base_color_texture: Some(texture),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        cull_mode: None,
+        double_sided: true,
+        ..default()
+    }
+}
+
+// Makes a mesh of glyph quads for each font atlas texture used by the layout
+//
+// The quads are scaled so that the font size is 1 unit, and are centered on the origin.
+fn glyph_meshes(
+    layout: &TextLayoutInfo,
+    atlas_layouts: &Assets<TextureAtlasLayout>,
+    scale_factor: f32,
+) -> Vec<(Handle<Image>, Mesh)> {
+    struct Quads {
+        positions: Vec<[f32; 3]>,
+        uvs: Vec<[f32; 2]>,
+        indices: Vec<u32>,
+    }
+
+    let mut groups: Vec<(Handle<Image>, Quads)> = Vec::new();
+    let offset = -layout.size / 2.0;
+    let scale = 1.0 / TEXT3D_FONT_SIZE;
+
+    for glyph in &layout.glyphs {
+        let Some(atlas) = atlas_layouts.get(&glyph.atlas_info.texture_atlas) else {
+            continue;
+        };
+        let Some(rect) = atlas.textures.get(glyph.atlas_info.location.glyph_index) else {
+            continue;
+        };
+
+        let texture = &glyph.atlas_info.texture;
+        let quads = match groups.iter().position(|(t, _)| t == texture) {
+            Some(i) => &mut groups[i].1,
+            None => {
+                groups.push((
+                    texture.clone(),
+                    Quads {
+                        positions: Vec::new(),
+                        uvs: Vec::new(),
+                        indices: Vec::new(),
+                    },
+                ));
+                &mut groups.last_mut().unwrap().1
+            }
+        };
+
+        let center = (offset + glyph.position / scale_factor) * scale;
+        let half_size = rect.size().as_vec2() / scale_factor * scale / 2.0;
+        let min = center - half_size;
+        let max = center + half_size;
+        let atlas_size = atlas.size.as_vec2();
+        let uv_min = rect.min.as_vec2() / atlas_size;
+        let uv_max = rect.max.as_vec2() / atlas_size;
+
+        let first = quads.positions.len() as u32;
+        quads.positions.extend([
+            [min.x, min.y, 0.0],
+            [max.x, min.y, 0.0],
+            [max.x, max.y, 0.0],
+            [min.x, max.y, 0.0],
+        ]);
+        // Atlas textures have their origin in the top left corner
+        quads.uvs.extend([
+            [uv_min.x, uv_max.y],
+            [uv_max.x, uv_max.y],
+            [uv_max.x, uv_min.y],
+            [uv_min.x, uv_min.y],
+        ]);
+        quads
+            .indices
+            .extend([0, 1, 2, 0, 2, 3].map(|index| first + index));
+    }
+
+    groups
+        .into_iter()
+        .map(|(texture, quads)| {
+            let normals = vec![[0.0, 0.0, 1.0]; quads.positions.len()];
+            let mesh = Mesh::new(
+                PrimitiveTopology::TriangleList,
+                RenderAssetUsages::default(),
+            )
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, quads.positions)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, quads.uvs)
+            .with_inserted_indices(Indices::U32(quads.indices));
+            (texture, mesh)
+        })
+        .collect()
+}
+
+// Rotates the meshes of billboarded text to face the active 3D camera
+fn billboard_text3d(
+    texts: Query<(&Text3d, &GlobalTransform)>,
+    mut parts: Query<&mut GlobalTransform, (Without<Text3d>, Without<Camera3d>)>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+) {
+    let Some(camera_rotation) = cameras
+        .iter()
+        .find(|(camera, _)| camera.is_active)
+        .map(|(_, transform)| transform.compute_transform().rotation)
+    else {
+        return;
+    };
+
+    for (text3d, global) in &texts {
+        if !text3d.billboard {
+            continue;
+        }
+        let (scale, _, translation) = global.to_scale_rotation_translation();
+        let billboard = GlobalTransform::from(Transform {
+            translation,
+            rotation: camera_rotation,
+            scale,
+        });
+        for part in &text3d.parts {
+            if let Ok(mut part_global) = parts.get_mut(part.entity) {
+                *part_global = billboard;
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SpawnText3d {
+    koto_entity: KotoEntity,
+    text: String,
+    font: Option<String>,
+}
+
+#[derive(Clone, KotoType, KotoCopy)]
+#[koto(type_name = "Text3d")]
+struct KotoText3d {
+    entity: KotoEntityMapping,
+    update_text: KotoEntitySender<UpdateText>,
+    update_text3d: KotoEntitySender<UpdateText3d>,
+    update_entity: KotoEntitySender<UpdateKotoEntity>,
+    update_transform: KotoEntitySender<UpdateTransform>,
+}
+
+impl KotoObject for KotoText3d {}
+
+koto_entity_impl! {
+    impl KotoText3d as "Text3d" with [entity, children, transform_3d] {
+        #[koto_method]
+        fn set_text(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let text = match ctx.args {
+                [KValue::Str(text)] => text.to_string(),
+                _ => return runtime_error!("Text3d.set_text: Expected a string"),
+            };
+
+            let this = ctx.instance()?;
+            this.update_text.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateText::Content(text),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_font(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let font = match ctx.args {
+                [KValue::Str(font)] => font.to_string(),
+                _ => return runtime_error!("Text3d.set_font: Expected a font path as a string"),
+            };
+
+            let this = ctx.instance()?;
+            this.update_text.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateText::SetFontPath(font),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method(alias = "set_colour")]
+        fn set_color(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            use KValue::{Number, Object, Str};
+
+            let color = match ctx.args {
+                [Number(n1), Number(n2), Number(n3)] => {
+                    Color::srgba(f32::from(n1), f32::from(n2), f32::from(n3), 1.0)
+                }
+                [Number(n1), Number(n2), Number(n3), Number(n4)] => {
+                    Color::srgba(f32::from(n1), f32::from(n2), f32::from(n3), f32::from(n4))
+                }
+                [Object(o)] if o.is_a::<KotoColor>() => {
+                    koto_to_bevy_color(&*o.cast::<KotoColor>()?)
+                }
+                [Str(s)] => match parse_color(s) {
+                    Some(color) => color,
+                    None => return runtime_error!("Text3d.set_color: Unknown color '{s}'"),
+                },
+                _ => {
+                    return runtime_error!(
+                        "Text3d.set_color: Expected a Color, a color name or hex String, \
+                         or 3 or 4 numbers"
+                    );
+                }
+            };
+
+            let this = ctx.instance()?;
+            this.entity.set_color_snapshot(Some(color));
+            this.update_text.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateText::Color(color),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method(alias = "get_colour")]
+        fn get_color(&self) -> KValue {
+            self.entity
+                .color()
+                .map_or(KValue::Null, |color| bevy_to_koto_color(color).into())
+        }
+
+        #[koto_method]
+        fn get_alpha(&self) -> KValue {
+            self.entity
+                .color()
+                .map_or(KValue::Null, |color| color.alpha().into())
+        }
+
+        #[koto_method]
+        fn set_alpha(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let alpha = match ctx.args {
+                [KValue::Number(n)] => n.into(),
+                _ => return runtime_error!("Text3d.set_alpha: Expected a number"),
+            };
+
+            let this = ctx.instance()?;
+            this.entity.set_alpha_snapshot(alpha);
+            this.update_text.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateText::Alpha(alpha),
+            ));
+
+            ctx.instance_result()
+        }
+
+        #[koto_method]
+        fn set_billboard(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+            let billboard = match ctx.args {
+                [KValue::Bool(billboard)] => *billboard,
+                _ => return runtime_error!("Text3d.set_billboard: Expected a Bool"),
+            };
+
+            let this = ctx.instance()?;
+            this.update_text3d.send(KotoEntityEvent::new(
+                this.entity.clone(),
+                UpdateText3d::Billboard(billboard),
+            ));
+
+            ctx.instance_result()
+        }
+    }
+}
+
+impl From<KotoText3d> for KValue {
+    fn from(text: KotoText3d) -> Self {
+        KObject::from(text).into()
+    }
+}
@@ -0,0 +1,270 @@
+//! Time as seen by Koto scripts
+
+use crate::prelude::*;
+use bevy::prelude::*;
+use koto::{derive::*, prelude::*, runtime::Result as KotoResult};
+
+/// Time as seen by the running script
+///
+/// The time is reset when a new script is loaded, and is advanced each frame before the script's
+/// `update` function is called.
+///
+/// The time can be paused, resumed, or scaled either by the host app or by the script via the
+/// `Time` object that's passed as the third argument to the script's `update` function.
+/// While the time is paused, the script's `fixed_update` function isn't called.
+#[derive(Resource, Clone, Debug)]
+pub struct KotoTime {
+    delta: f64,
+    elapsed: f64,
+    speed: f64,
+    is_paused: bool,
+    is_advanced: bool,
+}
+
+impl Default for KotoTime {
+    fn default() -> Self {
+        Self {
+            delta: 0.0,
+            elapsed: 0.0,
+            speed: 1.0,
+            is_paused: false,
+            is_advanced: false,
+        }
+    }
+}
+
+impl KotoTime {
+    /// The time in seconds that has passed since the previous update
+    pub fn delta(&self) -> f64 {
+        self.delta
+    }
+
+    /// The time in seconds that has passed since the script was loaded
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    /// Sets the elapsed time, e.g. when restoring a previous session
+    pub fn set_elapsed(&mut self, elapsed: f64) {
+        self.elapsed = elapsed;
+    }
+
+    /// The rate at which time advances, with 1.0 being real time
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Sets the rate at which time advances
+    ///
+    /// Values below 1.0 produce slow motion, and values above 1.0 speed time up.
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed;
+    }
+
+    /// True if the time is currently paused
+    pub fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    /// Pauses the time, subsequent updates will have a delta of zero
+    pub fn pause(&mut self) {
+        self.is_paused = true;
+    }
+
+    /// Resumes the time after it was paused
+    pub fn resume(&mut self) {
+        self.is_paused = false;
+    }
+
+    /// True if the time was advanced for the current update
+    ///
+    /// This is false when stepping is enabled with [KotoFrameControl] and no step was pending, in
+    /// which case the script isn't updated and the delta is zero.
+    pub fn is_advanced(&self) -> bool {
+        self.is_advanced
+    }
+
+    pub(crate) fn advance(&mut self, real_delta: f64) {
+        self.delta = if self.is_paused {
+            0.0
+        } else {
+            real_delta * self.speed
+        };
+        self.elapsed += self.delta;
+        self.is_advanced = true;
+    }
+
+    // Called when an update is skipped while stepping, so that systems following the script's
+    // time don't reuse the delta from the previous step
+    pub(crate) fn skip(&mut self) {
+        self.delta = 0.0;
+        self.is_advanced = false;
+    }
+
+    pub(crate) fn apply(&mut self, update: UpdateKotoTime) {
+        match update {
+            UpdateKotoTime::Pause => self.pause(),
+            UpdateKotoTime::Resume => self.resume(),
+            UpdateKotoTime::SetSpeed(speed) => self.set_speed(speed),
+            UpdateKotoTime::SetElapsed(elapsed) => self.set_elapsed(elapsed),
+        }
+    }
+}
+
+/// Controls how the script's time advances from frame to frame
+///
+/// By default the script's time follows Bevy's [Time], advancing by the frame's delta on each
+/// update. When stepping is enabled, the script's `update` function is only called when a step
+/// has been requested with [KotoFrameControl::step], using the supplied delta rather than the
+/// wall-clock time. This is useful for offline rendering, where each frame is captured after
+/// stepping the script by a fixed amount.
+///
+/// Pausing and time scaling via [KotoTime] are applied to the stepped delta as usual. Entity
+/// `on_update` functions are also only called when a step is performed.
+/// The script's `fixed_update` function continues to follow Bevy's fixed timestep.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct KotoFrameControl {
+    is_stepping: bool,
+    pending_step: Option<f64>,
+}
+
+impl KotoFrameControl {
+    /// Enables stepping, the script will only be updated when a step is requested
+    pub fn enable_stepping(&mut self) {
+        self.is_stepping = true;
+    }
+
+    /// Disables stepping, returning to real-time updates
+    pub fn disable_stepping(&mut self) {
+        self.is_stepping = false;
+        self.pending_step = None;
+    }
+
+    /// True if stepping is enabled
+    pub fn is_stepping(&self) -> bool {
+        self.is_stepping
+    }
+
+    /// Requests that the script should be updated once with the given delta
+    ///
+    /// The step is performed during the next run of the [KotoSchedule], and stepping is enabled
+    /// if it wasn't already. Requesting another step before the pending step has been performed
+    /// replaces the pending step.
+    pub fn step(&mut self, delta: f64) {
+        self.is_stepping = true;
+        self.pending_step = Some(delta);
+    }
+
+    // Returns the delta that should be used for the next update, or None if the update should be
+    // skipped
+    pub(crate) fn next_delta(&mut self, real_delta: f64) -> Option<f64> {
+        if self.is_stepping {
+            self.pending_step.take()
+        } else {
+            Some(real_delta)
+        }
+    }
+}
+
+/// Event for updating the [KotoTime] from a script
+#[derive(Clone, Copy, Debug, Event)]
+pub enum UpdateKotoTime {
+    /// Pauses the time
+    Pause,
+    /// Resumes the time
+    Resume,
+    /// Sets the time's speed
+    SetSpeed(f64),
+    /// Sets the elapsed time
+    SetElapsed(f64),
+}
+
+// The Time object that gets passed to the script's update function
+//
+// The object holds a snapshot of the KotoTime for the current update, changes made by the script
+// are applied to the snapshot immediately, and to the KotoTime resource after the update.
+#[derive(Clone, KotoType, KotoCopy)]
+#[koto(type_name = "Time")]
+pub(crate) struct KotoTimeObject {
+    time: KotoTime,
+    update_time: KotoSender<UpdateKotoTime>,
+}
+
+#[allow(clippy::result_large_err)]
+impl KotoTimeObject {
+    pub(crate) fn new(time: KotoTime, update_time: KotoSender<UpdateKotoTime>) -> Self {
+        Self { time, update_time }
+    }
+
+    fn send(ctx: MethodContext<Self>, update: UpdateKotoTime) -> KotoResult<KValue> {
+        let mut this = ctx.instance_mut()?;
+        this.time.apply(update);
+        this.update_time.send(update);
+        drop(this);
+        ctx.instance_result()
+    }
+}
+
+impl KotoObject for KotoTimeObject {}
+
+#[allow(clippy::result_large_err)]
+#[koto_impl]
+impl KotoTimeObject {
+    #[koto_method]
+    fn delta(&self) -> KValue {
+        self.time.delta().into()
+    }
+
+    #[koto_method]
+    fn elapsed(&self) -> KValue {
+        self.time.elapsed().into()
+    }
+
+    #[koto_method]
+    fn speed(&self) -> KValue {
+        self.time.speed().into()
+    }
+
+    #[koto_method]
+    fn is_paused(&self) -> KValue {
+        self.time.is_paused().into()
+    }
+
+    #[koto_method]
+    fn pause(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+        Self::send(ctx, UpdateKotoTime::Pause)
+    }
+
+    #[koto_method]
+    fn resume(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+        Self::send(ctx, UpdateKotoTime::Resume)
+    }
+
+    #[koto_method]
+    fn set_speed(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+        let speed = match ctx.args {
+            [KValue::Number(n)] => n.into(),
+            _ => return runtime_error!("Time.set_speed: Expected a Number"),
+        };
+        Self::send(ctx, UpdateKotoTime::SetSpeed(speed))
+    }
+
+    #[koto_method]
+    fn set_elapsed(ctx: MethodContext<Self>) -> KotoResult<KValue> {
+        let elapsed = match ctx.args {
+            [KValue::Number(n)] => n.into(),
+            _ => return runtime_error!("Time.set_elapsed: Expected a Number"),
+        };
+        Self::send(ctx, UpdateKotoTime::SetElapsed(elapsed))
+    }
+}
+
+pub(crate) fn update_koto_time(
+    channel: Res<KotoReceiver<UpdateKotoTime>>,
+    mut koto_time: ResMut<KotoTime>,
+) {
+    let _span = info_span!("koto_channel", channel = "UpdateKotoTime").entered();
+    while let Some(update) = channel.receive() {
+        koto_time.apply(update);
+    }
+}
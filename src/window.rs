@@ -1,6 +1,6 @@
 //! Window events for bevy_koto
 
-use crate::prelude::*;
+use crate::{prelude::*, runtime::ScriptHook};
 use bevy::{
     prelude::*,
     window::{PrimaryWindow, WindowResized},
@@ -48,8 +48,8 @@ fn on_window_resized(
 
 fn run_on_window_size(koto: &mut KotoRuntime, width: f32, height: f32) {
     if koto.is_ready() {
-        if let Err(error) = koto.run_exported_function(
-            "on_window_size",
+        if let Err(error) = koto.run_hook(
+            ScriptHook::OnWindowSize,
             &[koto.user_data().clone(), width.into(), height.into()],
         ) {
             error!("Error in 'on_window_size':\n{error}");
@@ -0,0 +1,77 @@
+//! Checks how Koto channels handle values when they're full
+
+use bevy_koto::prelude::*;
+
+fn fill<T: Copy>(sender: &KotoSender<T>, values: &[T]) {
+    for value in values {
+        sender.try_send(*value).unwrap();
+    }
+}
+
+fn drain<T>(receiver: &KotoReceiver<T>) -> Vec<T> {
+    receiver.0.try_iter().collect()
+}
+
+#[test]
+fn drop_oldest_makes_room_for_new_values() {
+    let (sender, receiver) = koto_channel_bounded(2, OverflowPolicy::DropOldest);
+    fill(&sender, &[1, 2]);
+
+    assert_eq!(sender.try_send(3), Ok(()));
+    sender.send(4);
+    assert_eq!(drain(&receiver), [3, 4]);
+}
+
+#[test]
+fn drop_newest_keeps_existing_values() {
+    let (sender, receiver) = koto_channel_bounded(2, OverflowPolicy::DropNewest);
+    fill(&sender, &[1, 2]);
+
+    assert_eq!(sender.try_send(3), Ok(()));
+    sender.send(4);
+    assert_eq!(drain(&receiver), [1, 2]);
+}
+
+#[test]
+fn error_policy_reports_full_channels() {
+    let (sender, receiver) = koto_channel_bounded(2, OverflowPolicy::Error);
+    fill(&sender, &[1, 2]);
+
+    assert_eq!(sender.try_send(3), Err(KotoSendError::Full));
+    // send logs the error rather than panicking
+    sender.send(4);
+    assert_eq!(drain(&receiver), [1, 2]);
+
+    assert_eq!(sender.try_send(5), Ok(()));
+    assert_eq!(drain(&receiver), [5]);
+}
+
+#[test]
+fn zero_capacity_channels_drop_values_with_drop_oldest() {
+    let (sender, receiver) = koto_channel_bounded(0, OverflowPolicy::DropOldest);
+
+    assert_eq!(sender.try_send(1), Ok(()));
+    assert!(drain(&receiver).is_empty());
+}
+
+#[test]
+fn try_send_reports_disconnected_channels() {
+    let (sender, receiver) = koto_channel::<i32>();
+    assert_eq!(sender.try_send(1), Ok(()));
+    assert_eq!(drain(&receiver), [1]);
+
+    drop(receiver);
+    assert_eq!(sender.try_send(2), Err(KotoSendError::Disconnected));
+}
+
+#[test]
+fn wrapped_senders_use_the_public_field() {
+    let (inner, receiver) = crossbeam_channel::bounded(1);
+    let sender = KotoSender::new(inner);
+    fill(&sender, &[1]);
+
+    // Full channels made with KotoSender::new drop new values
+    assert_eq!(sender.try_send(2), Ok(()));
+    assert!(sender.0.try_send(3).is_err());
+    assert_eq!(receiver.try_iter().collect::<Vec<_>>(), [1]);
+}
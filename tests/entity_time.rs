@@ -0,0 +1,99 @@
+//! Checks that entity `on_update` functions follow the script's time
+
+#![cfg(all(feature = "color", feature = "geometry", feature = "text"))]
+
+use bevy::prelude::*;
+use bevy_koto::prelude::*;
+
+const SCRIPT: &str = "
+export setup = ||
+  text = make_text 'ticking'
+  text.on_update |dt| print 'tick'
+  {text}
+";
+
+fn make_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        TransformPlugin,
+        HierarchyPlugin,
+    ))
+    .add_plugins(KotoRuntimePlugin::default())
+    .add_plugins((
+        KotoEntityPlugin::default(),
+        KotoColorPlugin,
+        KotoGeometryPlugin,
+        KotoTextPlugin::default(),
+    ))
+    .init_resource::<ClearColor>()
+    .init_asset::<ColorMaterial>()
+    .init_asset::<Image>()
+    .init_resource::<Ticks>()
+    .add_systems(Last, count_ticks);
+
+    app.world_mut()
+        .send_event(LoadScript::inline("entity_time.koto", SCRIPT));
+    app
+}
+
+#[derive(Resource, Default)]
+struct Ticks(usize);
+
+fn count_ticks(mut output: EventReader<KotoScriptOutput>, mut ticks: ResMut<Ticks>) {
+    ticks.0 += output
+        .read()
+        .filter(|output| output.text.trim() == "tick")
+        .count();
+}
+
+fn ticks(app: &App) -> usize {
+    app.world().resource::<Ticks>().0
+}
+
+#[test]
+fn paused_time_stops_on_update() {
+    let mut app = make_app();
+    for _ in 0..3 {
+        app.update();
+    }
+    let ticks_before_pause = ticks(&app);
+    assert!(ticks_before_pause > 0);
+
+    app.world_mut().resource_mut::<KotoTime>().pause();
+    for _ in 0..3 {
+        app.update();
+    }
+    assert_eq!(ticks(&app), ticks_before_pause);
+
+    app.world_mut().resource_mut::<KotoTime>().resume();
+    app.update();
+    assert!(ticks(&app) > ticks_before_pause);
+}
+
+#[test]
+fn stepping_stops_on_update_until_a_step_is_requested() {
+    let mut app = make_app();
+    for _ in 0..3 {
+        app.update();
+    }
+
+    app.world_mut()
+        .resource_mut::<KotoFrameControl>()
+        .enable_stepping();
+    let ticks_before_stepping = ticks(&app);
+    for _ in 0..3 {
+        app.update();
+    }
+    assert_eq!(ticks(&app), ticks_before_stepping);
+    assert_eq!(app.world().resource::<KotoTime>().delta(), 0.0);
+
+    app.world_mut()
+        .resource_mut::<KotoFrameControl>()
+        .step(0.25);
+    app.update();
+    assert_eq!(ticks(&app), ticks_before_stepping + 1);
+    app.update();
+    assert_eq!(ticks(&app), ticks_before_stepping + 1);
+}
@@ -0,0 +1,61 @@
+//! Checks that modules imported by scripts are resolved by path
+
+use bevy::prelude::*;
+use bevy_koto::prelude::*;
+use std::time::{Duration, Instant};
+
+fn make_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin {
+            file_path: "tests/assets".into(),
+            ..default()
+        },
+    ))
+    .add_plugins(KotoRuntimePlugin::default())
+    .init_resource::<Output>()
+    .add_systems(Last, collect_output);
+    app
+}
+
+#[derive(Resource, Default)]
+struct Output {
+    lines: Vec<String>,
+    errors: Vec<String>,
+}
+
+fn collect_output(
+    mut output: EventReader<KotoScriptOutput>,
+    mut errors: EventReader<KotoScriptError>,
+    mut collected: ResMut<Output>,
+) {
+    collected
+        .lines
+        .extend(output.read().map(|output| output.text.trim().to_string()));
+    collected
+        .errors
+        .extend(errors.read().map(|error| error.to_string()));
+}
+
+#[test]
+fn modules_with_the_same_name_are_resolved_by_path() {
+    let mut app = make_app();
+    app.world_mut()
+        .send_event(LoadScript::from_path("modules/main.koto"));
+
+    // The script and its modules are loaded asynchronously by the asset server
+    let start = Instant::now();
+    while app.world().resource::<Output>().lines.len() < 2 {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "Timed out waiting for the script's output"
+        );
+        app.update();
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    let output = app.world().resource::<Output>();
+    assert!(output.errors.is_empty(), "{:?}", output.errors);
+    assert_eq!(output.lines, ["top", "nested"]);
+}
@@ -0,0 +1,87 @@
+//! Checks that scripts can update allowed resources
+
+#![cfg(feature = "resources")]
+
+use bevy::prelude::*;
+use bevy_koto::prelude::*;
+
+#[derive(Resource, Reflect)]
+struct Settings {
+    speed: f32,
+}
+
+fn make_app(script: &'static str) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(KotoRuntimePlugin::default())
+        .add_plugins(KotoResourcesPlugin::default().allow::<Settings>())
+        .insert_resource(Settings { speed: 1.0 })
+        .init_resource::<Output>()
+        .add_systems(Last, collect_output);
+
+    app.world_mut()
+        .send_event(LoadScript::inline("resources.koto", script));
+    app
+}
+
+#[derive(Resource, Default)]
+struct Output {
+    lines: Vec<String>,
+    errors: Vec<String>,
+}
+
+fn collect_output(
+    mut output: EventReader<KotoScriptOutput>,
+    mut errors: EventReader<KotoScriptError>,
+    mut collected: ResMut<Output>,
+) {
+    collected
+        .lines
+        .extend(output.read().map(|output| output.text.trim().to_string()));
+    collected
+        .errors
+        .extend(errors.read().map(|error| error.message.clone()));
+}
+
+#[test]
+fn set_updates_the_snapshot_and_the_resource() {
+    let mut app = make_app(
+        "
+export setup = || {frame: 0}
+export update = |state, dt|
+  state.frame += 1
+  if state.frame == 1
+    bevy.resources.set 'Settings', 'speed', 2.5
+    print bevy.resources.get 'Settings', 'speed'
+",
+    );
+    for _ in 0..3 {
+        app.update();
+    }
+
+    let output = app.world().resource::<Output>();
+    assert!(output.errors.is_empty(), "{:?}", output.errors);
+    assert_eq!(output.lines, ["2.5"]);
+    assert_eq!(app.world().resource::<Settings>().speed, 2.5);
+}
+
+#[test]
+fn setting_a_missing_path_is_an_error() {
+    let mut app = make_app(
+        "
+export setup = || {frame: 0}
+export update = |state, dt|
+  state.frame += 1
+  if state.frame == 1
+    bevy.resources.set 'Settings', 'missing', 2.5
+",
+    );
+    for _ in 0..3 {
+        app.update();
+    }
+
+    let output = app.world().resource::<Output>();
+    assert_eq!(output.errors.len(), 1);
+    assert!(output.errors[0].contains("'missing' not found in 'Settings'"));
+    assert_eq!(app.world().resource::<Settings>().speed, 1.0);
+}